@@ -0,0 +1,227 @@
+use ::std::env;
+use ::std::ffi::OsString;
+use ::std::fs;
+use ::std::io;
+use ::std::path::PathBuf;
+
+use ::structopt::clap::ErrorKind;
+use ::structopt::StructOpt;
+
+use crate::Opt;
+
+/// `$XDG_CONFIG_HOME/bandwhich/config.toml`, or `~/.config/bandwhich/config.toml` without it
+pub fn default_config_path() -> Option<PathBuf> {
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(config_home) if !config_home.is_empty() => PathBuf::from(config_home),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_home.join("bandwhich").join("config.toml"))
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    // strings and numbers alike, they end up on the command line either way
+    Text(String),
+    Array(Vec<String>),
+}
+
+fn parse_string(quoted: &str, line_number: usize) -> Result<(String, &str), String> {
+    let unterminated = || format!("line {}: unterminated string", line_number);
+    if let Some(literal) = quoted.strip_prefix('\'') {
+        let end = literal.find('\'').ok_or_else(unterminated)?;
+        return Ok((String::from(&literal[..end]), &literal[end + 1..]));
+    }
+    let mut string = String::new();
+    let mut characters = quoted[1..].char_indices();
+    while let Some((index, character)) = characters.next() {
+        match character {
+            '"' => return Ok((string, &quoted[index + 2..])),
+            '\\' => match characters.next() {
+                Some((_, 'n')) => string.push('\n'),
+                Some((_, 't')) => string.push('\t'),
+                Some((_, escaped @ '"')) | Some((_, escaped @ '\\')) => string.push(escaped),
+                _ => return Err(format!("line {}: unsupported escape", line_number)),
+            },
+            _ => string.push(character),
+        }
+    }
+    Err(unterminated())
+}
+
+// a string, number or bare word, and what follows it on the line
+fn parse_scalar(value: &str, line_number: usize) -> Result<(String, &str), String> {
+    if value.starts_with('"') || value.starts_with('\'') {
+        return parse_string(value, line_number);
+    }
+    let end = value.find(&[',', ']', '#'][..]).unwrap_or(value.len());
+    let scalar = value[..end].trim();
+    if scalar.is_empty() {
+        return Err(format!("line {}: missing value", line_number));
+    }
+    Ok((String::from(scalar), &value[end..]))
+}
+
+/// A value of a TOML line, and the comment after it if there is one
+pub fn parse_value(value: &str, line_number: usize) -> Result<Value, String> {
+    let (value, rest) = match value.strip_prefix('[') {
+        Some(mut items) => {
+            let mut array = Vec::new();
+            loop {
+                items = items.trim_start();
+                if let Some(rest) = items.strip_prefix(']') {
+                    break (Value::Array(array), rest);
+                }
+                let (item, rest) = parse_scalar(items, line_number)?;
+                array.push(item);
+                let rest = rest.trim_start();
+                items = rest.strip_prefix(',').unwrap_or(rest);
+                if items.is_empty() {
+                    return Err(format!("line {}: unterminated array", line_number));
+                }
+            }
+        }
+        None => {
+            let quoted = value.starts_with('"') || value.starts_with('\'');
+            let (scalar, rest) = parse_scalar(value, line_number)?;
+            let value = match scalar.as_str() {
+                "true" if !quoted => Value::Bool(true),
+                "false" if !quoted => Value::Bool(false),
+                _ => Value::Text(scalar),
+            };
+            (value, rest)
+        }
+    };
+    let rest = rest.trim();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err(format!("line {}: unexpected '{}'", line_number, rest));
+    }
+    Ok(value)
+}
+
+/// The line number, key and unparsed value of every `key = value` line of a TOML file, leaving
+/// out the blank lines and the comments. Every key has to be in the root table
+pub fn parse_lines(contents: &str) -> Result<Vec<(usize, &str, &str)>, String> {
+    let mut lines = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            return Err(format!("line {}: tables are not supported", line_number));
+        }
+        match line.find('=') {
+            Some(equals) => lines.push((
+                line_number,
+                line[..equals].trim(),
+                line[equals + 1..].trim(),
+            )),
+            None => return Err(format!("line {}: expected key = value", line_number)),
+        }
+    }
+    Ok(lines)
+}
+
+/// The `key = value` pairs of a config file, as the command line arguments they stand for, eg.
+/// `interface = "eth0"` is `--interface=eth0` and `processes = true` is `--processes`.
+///
+/// Only the part of TOML that options need is understood: strings, numbers, booleans and arrays
+/// (an option given more than once), every key in the root table.
+fn parse_config(contents: &str) -> Result<Vec<(String, Vec<OsString>)>, String> {
+    let mut entries = Vec::new();
+    for (line_number, key, value) in parse_lines(contents)? {
+        let name = key.replace('_', "-");
+        let option = |value: &str| OsString::from(format!("--{}={}", name, value));
+        let args = match parse_value(value, line_number)? {
+            Value::Bool(true) => vec![OsString::from(format!("--{}", name))],
+            // a flag cannot be passed as false, leaving it out is the same
+            Value::Bool(false) => vec![],
+            Value::Text(value) => vec![option(&value)],
+            Value::Array(values) => values.iter().map(|value| option(value)).collect(),
+        };
+        entries.push((name, args));
+    }
+    Ok(entries)
+}
+
+/// The command line with the options of the config file in front of it, along with a warning
+/// for every key of the file that is not an option.
+///
+/// Options that are on the command line are left out of the file's, so the command line always
+/// wins. The file is read from `--config`, or from `default_path` if it exists there, unless
+/// `--no-config` is given.
+pub fn args_with_config(
+    args: Vec<OsString>,
+    default_path: Option<PathBuf>,
+) -> Result<(Vec<OsString>, Vec<String>), failure::Error> {
+    let matches = match Opt::clap().get_matches_from_safe(&args) {
+        Ok(matches) => matches,
+        // parsing them again shows the usage, the help or the version as usual
+        Err(_) => return Ok((args, vec![])),
+    };
+    let opts = Opt::from_clap(&matches);
+    if opts.no_config {
+        return Ok((args, vec![]));
+    }
+    let (path, contents) = match (opts.config, default_path) {
+        (Some(path), _) => match fs::read_to_string(&path) {
+            Ok(contents) => (path, contents),
+            Err(err) => failure::bail!("Failed to read {}: {}", path.display(), err),
+        },
+        (None, Some(path)) => match fs::read_to_string(&path) {
+            Ok(contents) => (path, contents),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok((args, vec![])),
+            Err(err) => failure::bail!("Failed to read {}: {}", path.display(), err),
+        },
+        (None, None) => return Ok((args, vec![])),
+    };
+    let entries = match parse_config(&contents) {
+        Ok(entries) => entries,
+        Err(err) => failure::bail!("Failed to parse {}: {}", path.display(), err),
+    };
+    let mut warnings = Vec::new();
+    let mut merged_args = args[..1].to_vec();
+    for (name, entry_args) in entries {
+        let unknown = name == "config" || name == "no-config" || {
+            // a flag turned off has no arguments, its name is still checked
+            let check_args = if entry_args.is_empty() {
+                vec![OsString::from(format!("--{}", name))]
+            } else {
+                entry_args.clone()
+            };
+            let parsed =
+                Opt::clap().get_matches_from_safe(args[..1].iter().chain(check_args.iter()));
+            matches!(parsed, Err(ref err) if err.kind == ErrorKind::UnknownArgument)
+        };
+        if unknown {
+            warnings.push(format!(
+                "warning: unknown key '{}' in {}, ignored",
+                name,
+                path.display()
+            ));
+        } else if matches.occurrences_of(&name) == 0 {
+            merged_args.extend(entry_args);
+        }
+    }
+    merged_args.extend(args[1..].iter().cloned());
+    Ok((merged_args, warnings))
+}
+
+// switches (and the interface) that can also be given in the environment, for when passing flags
+// is awkward (eg. in containers). Other options that take a value use structopt's `env` instead
+pub fn apply_env_flags(opts: &mut Opt, var: impl Fn(&str) -> Option<String>) {
+    let is_set = |name: &str| match var(name) {
+        Some(value) => ["1", "true", "yes"].contains(&value.to_lowercase().as_str()),
+        None => false,
+    };
+    // a flag on the command line always wins, since there is no way to pass it as false
+    opts.raw |= is_set("BANDWHICH_RAW");
+    opts.no_resolve |= is_set("BANDWHICH_NO_RESOLVE");
+    // https://no-color.org: any value but an empty one turns colors off
+    opts.render_opts.no_color |= var("NO_COLOR").is_some_and(|value| !value.is_empty());
+    if opts.interface.is_none() && opts.read_pcap.is_none() {
+        opts.interface = var("BANDWHICH_INTERFACE").filter(|interface| !interface.is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+use ::std::collections::HashSet;
+use ::std::time::Duration;
+
+use crate::display::{BandwidthLimit, DataUnit, DisplayBandwidth, Snapshot, UnitPrefix};
+
+/// Tells when a process goes above `--alert-threshold` (see `--alert-process`).
+///
+/// A process is only alerted on once while it stays above the threshold, it has to drop back
+/// below it to be alerted on again.
+pub struct RateAlert {
+    threshold: BandwidthLimit,
+    /// Lowercase part of the process names to watch, every process if unset
+    process: Option<String>,
+    above_threshold: HashSet<String>,
+    prefix: UnitPrefix,
+}
+
+fn display_rate(bytes_per_second: f64, prefix: UnitPrefix) -> DisplayBandwidth {
+    DisplayBandwidth {
+        bandwidth: bytes_per_second,
+        as_rate: true,
+        exact: false,
+        unit: DataUnit::Bytes,
+        prefix,
+    }
+}
+
+impl RateAlert {
+    pub fn new(threshold: BandwidthLimit, process: Option<String>, prefix: UnitPrefix) -> Self {
+        RateAlert {
+            threshold,
+            process: process.map(|process| process.to_lowercase()),
+            above_threshold: HashSet::new(),
+            prefix,
+        }
+    }
+
+    /// A line for every watched process that went above the threshold since the last snapshot.
+    ///
+    /// The snapshot has to hold rates, so one taken in cumulative mode never alerts.
+    pub fn check(&mut self, snapshot: &Snapshot) -> Vec<String> {
+        let mut alerts = Vec::new();
+        let mut above_threshold = HashSet::new();
+        if snapshot.cumulative {
+            self.above_threshold = above_threshold;
+            return alerts;
+        }
+        for process in &snapshot.processes {
+            let watched = match &self.process {
+                Some(watched) => process.name.to_lowercase().contains(watched.as_str()),
+                None => true,
+            };
+            let rate = process.total_bytes_uploaded + process.total_bytes_downloaded;
+            if !watched || !self.threshold.is_exceeded_by(rate, Duration::from_secs(1)) {
+                continue;
+            }
+            if !self.above_threshold.contains(&process.name) {
+                alerts.push(format!(
+                    "alert: <{}> \"{}\" went above {} with {}",
+                    snapshot.timestamp.timestamp(),
+                    process.name,
+                    display_rate(self.threshold.bytes_per_second, self.prefix),
+                    display_rate(rate as f64, self.prefix)
+                ));
+            }
+            above_threshold.insert(process.name.clone());
+        }
+        self.above_threshold = above_threshold;
+        alerts
+    }
+}
@@ -0,0 +1,185 @@
+use ::std::env;
+use ::std::fs;
+use ::std::io;
+use ::std::path::{Path, PathBuf};
+
+use chrono::prelude::*;
+
+/// `$XDG_STATE_HOME/bandwhich/budget`, or `~/.local/state/bandwhich/budget` without it
+pub fn default_budget_path() -> Option<PathBuf> {
+    let state_home = match env::var_os("XDG_STATE_HOME") {
+        Some(state_home) if !state_home.is_empty() => PathBuf::from(state_home),
+        _ => PathBuf::from(env::var_os("HOME")?)
+            .join(".local")
+            .join("state"),
+    };
+    Some(state_home.join("bandwhich").join("budget"))
+}
+
+/// A number of bytes, eg. 50GB or 500MB (powers of 1000, like the ISPs count them)
+pub fn parse_budget(budget: &str) -> Result<u128, String> {
+    let invalid = || {
+        format!(
+            "invalid budget '{}', expected a number of bytes like 50GB or 500MB",
+            budget
+        )
+    };
+    let unit_start = budget
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(budget.len());
+    let (amount, unit) = budget.split_at(unit_start);
+    let amount: f64 = amount.trim().parse().map_err(|_| invalid())?;
+    let multiplier = match unit {
+        "" | "B" => 1.0,
+        "K" | "k" | "KB" | "kB" => 1e3,
+        "M" | "MB" => 1e6,
+        "G" | "GB" => 1e9,
+        "T" | "TB" => 1e12,
+        _ => return Err(invalid()),
+    };
+    if amount <= 0.0 || !amount.is_finite() {
+        return Err(invalid());
+    }
+    Ok((amount * multiplier) as u128)
+}
+
+/// The traffic of this and the earlier runs against a cap that starts over every period, eg.
+/// a monthly allowance of a metered connection (see `--budget`).
+///
+/// What was used is kept in a file between runs, as a line with when the period started (in unix
+/// seconds) and a line with the bytes used since then, eg. `period_start 1588000000`.
+#[derive(Clone, Debug)]
+pub struct Budget {
+    path: PathBuf,
+    pub limit: u128,
+    period: chrono::Duration,
+    pub period_start: DateTime<Local>,
+    /// The bytes used in the period, by this run and the ones before it
+    pub used: u128,
+    // the lifetime counters of the capture as of the last count
+    counted: u128,
+}
+
+impl Budget {
+    /// What the file at `path` remembers, or a period starting `now` if there is no such file.
+    /// A period that is over by `now` is started over
+    pub fn load(
+        path: PathBuf,
+        limit: u128,
+        period_days: u32,
+        now: DateTime<Local>,
+    ) -> io::Result<Self> {
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        let mut budget = Budget {
+            path,
+            limit,
+            period: chrono::Duration::days(i64::from(period_days.max(1))),
+            period_start: now,
+            used: 0,
+            counted: 0,
+        };
+        for line in contents.lines() {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next()) {
+                (Some("period_start"), Some(value)) => {
+                    let period_start = value
+                        .parse()
+                        .ok()
+                        .and_then(|seconds| Local.timestamp_opt(seconds, 0).single());
+                    if let Some(period_start) = period_start {
+                        budget.period_start = period_start;
+                    }
+                }
+                (Some("used"), Some(value)) => {
+                    if let Ok(used) = value.parse() {
+                        budget.used = used;
+                    }
+                }
+                _ => {}
+            }
+        }
+        budget.roll_over(now);
+        Ok(budget)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn period_end(&self) -> DateTime<Local> {
+        self.period_start + self.period
+    }
+
+    // the periods that are over by `now` are skipped, the next one starts where they ended. A
+    // period that starts later than `now` (eg. the clock was turned back) starts `now` instead
+    fn roll_over(&mut self, now: DateTime<Local>) {
+        if self.period_start > now {
+            self.period_start = now;
+        }
+        let periods_over = (now - self.period_start).num_seconds() / self.period.num_seconds();
+        if periods_over > 0 {
+            self.period_start = self.period_start + self.period * periods_over as i32;
+            self.used = 0;
+        }
+    }
+
+    /// Adds what the capture counted since the last call, from its lifetime counters
+    pub fn count(&mut self, lifetime_bytes: u128, now: DateTime<Local>) {
+        self.roll_over(now);
+        self.used += lifetime_bytes.saturating_sub(self.counted);
+        self.counted = lifetime_bytes;
+    }
+
+    /// Counts from 0 again, for when the lifetime counters started over (with a session reset)
+    pub fn restart_count(&mut self) {
+        self.counted = 0;
+    }
+
+    pub fn remaining(&self) -> u128 {
+        self.limit.saturating_sub(self.used)
+    }
+
+    /// Whether at least 90% of the budget is used
+    pub fn running_low(&self) -> bool {
+        self.used * 10 >= self.limit * 9
+    }
+
+    /// When the budget runs out if the traffic goes on at the average of the period so far, None
+    /// if it lasts until the period is over
+    pub fn projected_exhaustion(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        if self.used >= self.limit {
+            return Some(now);
+        }
+        let elapsed = (now - self.period_start).num_seconds().max(1) as f64;
+        let bytes_per_second = self.used as f64 / elapsed;
+        if bytes_per_second == 0.0 {
+            return None;
+        }
+        let seconds_left = self.remaining() as f64 / bytes_per_second;
+        let exhaustion = now + chrono::Duration::seconds(seconds_left.min(i32::MAX as f64) as i64);
+        if exhaustion < self.period_end() {
+            Some(exhaustion)
+        } else {
+            None
+        }
+    }
+
+    /// Writes what was used over the file, creating the directory it is in if needed
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(
+            &self.path,
+            format!(
+                "period_start {}\nused {}\n",
+                self.period_start.timestamp(),
+                self.used
+            ),
+        )
+    }
+}
@@ -0,0 +1,64 @@
+use crate::display::RateHistory;
+
+// what a rate limit is usually set to, times a power of ten
+const ROUND_MANTISSAS: [f64; 5] = [1.0, 2.0, 2.5, 5.0, 10.0];
+// below this many bytes per second a steady rate is more likely a keepalive than a limit
+const MIN_CAPPED_RATE: f64 = 1024.0;
+
+/// Tells the connections that look held back by a rate limit, see `--detect-caps`.
+///
+/// A connection looks capped once its download or upload stayed within `tolerance` of its
+/// average over each of the last `ticks` ticks, and that average is at least 1KiB per second and
+/// within `tolerance` of a round rate: 1, 2, 2.5 or 5 times a power of ten in bits or in bytes
+/// per second (eg. 10Mbps or 500kBps), or a power of two in bytes per second (eg. 512KiBps).
+/// This is a guess, a connection can just as well be held back by the other end.
+pub struct CapDetection {
+    ticks: usize,
+    tolerance: f64,
+}
+
+impl CapDetection {
+    /// `tolerance_percent` of the rate either way, eg. 2 for 2%
+    pub fn new(ticks: usize, tolerance_percent: f64) -> Self {
+        CapDetection {
+            ticks: ticks.max(2),
+            tolerance: tolerance_percent / 100.0,
+        }
+    }
+
+    /// How many ticks of history `looks_capped` needs
+    pub fn ticks(&self) -> usize {
+        self.ticks
+    }
+
+    pub fn looks_capped(&self, history: &RateHistory) -> bool {
+        if history.len() < self.ticks {
+            return false;
+        }
+        let recent = history.iter().rev().take(self.ticks);
+        let uploaded = recent.clone().map(|&(uploaded, _)| uploaded as f64);
+        let downloaded = recent.map(|&(_, downloaded)| downloaded as f64);
+        self.is_steady_near_round_rate(uploaded.collect())
+            || self.is_steady_near_round_rate(downloaded.collect())
+    }
+
+    fn is_steady_near_round_rate(&self, rates: Vec<f64>) -> bool {
+        let average = rates.iter().sum::<f64>() / rates.len() as f64;
+        average >= MIN_CAPPED_RATE
+            && rates.iter().all(|rate| self.is_near(*rate, average))
+            && (self.is_near_round_decimal(average)
+                || self.is_near_round_decimal(average * 8.0)
+                || self.is_near(average, 2f64.powf(average.log2().round())))
+    }
+
+    fn is_near_round_decimal(&self, rate: f64) -> bool {
+        let decade = 10f64.powf(rate.log10().floor());
+        ROUND_MANTISSAS
+            .iter()
+            .any(|mantissa| self.is_near(rate, mantissa * decade))
+    }
+
+    fn is_near(&self, rate: f64, round: f64) -> bool {
+        (rate - round).abs() <= round * self.tolerance
+    }
+}
@@ -0,0 +1,93 @@
+use ::std::str::FromStr;
+
+use ::tui::buffer::Buffer;
+use ::tui::layout::Rect;
+use ::tui::symbols::line;
+use ::tui::widgets::{Borders, Widget};
+
+/// How the borders of the tables are drawn, see --border
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BorderStyle {
+    /// The box-drawing lines
+    #[default]
+    Plain,
+    /// `+`, `-` and `|`, for fonts (eg. over SSH) without the box-drawing characters
+    Ascii,
+    /// The box-drawing lines with round corners
+    Rounded,
+    /// No borders at all, the title stays on top, so a table fits a row more and two columns
+    None,
+}
+
+impl BorderStyle {
+    pub fn borders(self) -> Borders {
+        match self {
+            BorderStyle::None => Borders::NONE,
+            _ => Borders::ALL,
+        }
+    }
+
+    // the symbols the lines tui draws are replaced with, in its order: horizontal, vertical, then
+    // the top left, top right, bottom left and bottom right corners
+    fn symbols(self) -> Option<[&'static str; 6]> {
+        match self {
+            BorderStyle::Plain | BorderStyle::None => None,
+            BorderStyle::Ascii => Some(["-", "|", "+", "+", "+", "+"]),
+            BorderStyle::Rounded => Some([line::HORIZONTAL, line::VERTICAL, "╭", "╮", "╰", "╯"]),
+        }
+    }
+}
+
+impl FromStr for BorderStyle {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(BorderStyle::Plain),
+            "ascii" => Ok(BorderStyle::Ascii),
+            "rounded" => Ok(BorderStyle::Rounded),
+            "none" => Ok(BorderStyle::None),
+            _ => Err(format!(
+                "invalid border '{}', expected plain, ascii, rounded or none",
+                s
+            )),
+        }
+    }
+}
+
+/// Redraws the lines of a block drawn with all of its borders in the `BorderStyle`, rendered
+/// over it in the same area. The title on the top border is left as it is.
+pub struct BorderLines(pub BorderStyle);
+
+impl Widget for BorderLines {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let symbols = match self.0.symbols() {
+            Some(symbols) => symbols,
+            None => return,
+        };
+        if area.width < 2 || area.height < 2 {
+            return;
+        }
+        let drawn = [
+            line::HORIZONTAL,
+            line::VERTICAL,
+            line::TOP_LEFT,
+            line::TOP_RIGHT,
+            line::BOTTOM_LEFT,
+            line::BOTTOM_RIGHT,
+        ];
+        let mut redraw = |x: u16, y: u16| {
+            let cell = buf.get_mut(x, y);
+            if let Some(index) = drawn.iter().position(|&symbol| cell.symbol == symbol) {
+                cell.set_symbol(symbols[index]);
+            }
+        };
+        for x in area.left()..area.right() {
+            redraw(x, area.top());
+            redraw(x, area.bottom() - 1);
+        }
+        for y in area.top() + 1..area.bottom() - 1 {
+            redraw(area.left(), y);
+            redraw(area.right() - 1, y);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use ::chrono::prelude::*;
+use ::tui::backend::Backend;
+use ::tui::layout::{Alignment, Rect};
+use ::tui::style::Style;
+use ::tui::terminal::Frame;
+use ::tui::widgets::{Paragraph, Text, Widget};
+
+use crate::display::{Budget, DataUnit, DisplayBandwidth, UnitPrefix};
+use crate::RenderOpts;
+
+/// What is left of the --budget and when it runs out at the pace of the period so far
+pub struct BudgetLine<'a> {
+    pub budget: &'a Budget,
+    pub opts: &'a RenderOpts,
+}
+
+impl<'a> BudgetLine<'a> {
+    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect) {
+        let budget = self.budget;
+        // in bytes and powers of 1000 whatever the tables show, as the budget was given
+        let display_bytes = |bytes: u128| DisplayBandwidth {
+            bandwidth: bytes as f64,
+            as_rate: false,
+            exact: false,
+            unit: DataUnit::Bytes,
+            prefix: UnitPrefix::Si,
+        };
+        let now = Local::now();
+        let pace = match budget.projected_exhaustion(now) {
+            Some(_) if budget.remaining() == 0 => String::from("used up"),
+            Some(exhaustion) => format!(
+                "runs out around {} at this pace",
+                exhaustion.format("%Y-%m-%d %H:%M")
+            ),
+            None => String::from("lasts the period at this pace"),
+        };
+        let line = format!(
+            " Budget: {} of {} used, {} left until {}, {}",
+            display_bytes(budget.used),
+            display_bytes(budget.limit),
+            display_bytes(budget.remaining()),
+            budget.period_end().format("%Y-%m-%d"),
+            pace
+        );
+        let style = if budget.running_low() && !self.opts.no_color {
+            Style::default().fg(self.opts.colors.high)
+        } else {
+            Style::default()
+        };
+        let text = [Text::styled(line, style)];
+        Paragraph::new(text.iter())
+            .alignment(Alignment::Left)
+            .render(frame, rect);
+    }
+}
@@ -0,0 +1,30 @@
+use ::tui::backend::Backend;
+use ::tui::layout::{Alignment, Rect};
+use ::tui::terminal::Frame;
+use ::tui::widgets::{Paragraph, Text, Widget};
+
+use crate::network::CaptureStats;
+
+pub struct CaptureStatsLine {
+    pub stats: CaptureStats,
+}
+
+impl CaptureStatsLine {
+    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect) {
+        let overflowed = if self.stats.overflowed == 0 {
+            String::new()
+        } else {
+            format!(
+                ", {} left out as counting fell behind",
+                self.stats.overflowed
+            )
+        };
+        let text = [Text::raw(format!(
+            " Captured {} packets, {} of them not TCP/UDP over IP or malformed{}",
+            self.stats.packets, self.stats.unparsed, overflowed
+        ))];
+        Paragraph::new(text.iter())
+            .alignment(Alignment::Left)
+            .render(frame, rect);
+    }
+}
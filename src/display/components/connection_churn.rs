@@ -0,0 +1,27 @@
+use ::tui::backend::Backend;
+use ::tui::layout::{Alignment, Rect};
+use ::tui::terminal::Frame;
+use ::tui::widgets::{Paragraph, Text, Widget};
+
+/// How many connections showed up and how many went away since the last tick (see `--churn`)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ConnectionChurn {
+    pub opened: usize,
+    pub closed: usize,
+}
+
+pub struct ChurnLine {
+    pub churn: ConnectionChurn,
+}
+
+impl ChurnLine {
+    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect) {
+        let text = [Text::raw(format!(
+            " {} connections opened and {} closed since the last tick",
+            self.churn.opened, self.churn.closed
+        ))];
+        Paragraph::new(text.iter())
+            .alignment(Alignment::Left)
+            .render(frame, rect);
+    }
+}
@@ -1,21 +1,338 @@
 use ::std::fmt;
+use ::std::str::FromStr;
+use ::std::time::Duration;
+
+use crate::network::CaptureDirection;
+use crate::RenderOpts;
+
+/// What byte counts are shown in, the same for the tables, the raw output and the JSON output
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DataUnit {
+    Bytes,
+    Bits,
+    /// The counts are packets to begin with (see --packets)
+    Packets,
+}
+
+impl DataUnit {
+    pub fn from_opts(opts: &RenderOpts) -> Self {
+        if opts.packets {
+            DataUnit::Packets
+        } else if opts.bits {
+            DataUnit::Bits
+        } else {
+            DataUnit::Bytes
+        }
+    }
+    pub fn convert(self, bytes: u128) -> u128 {
+        match self {
+            DataUnit::Bytes | DataUnit::Packets => bytes,
+            DataUnit::Bits => bytes * 8,
+        }
+    }
+    fn symbol(self) -> &'static str {
+        match self {
+            DataUnit::Bytes => "B",
+            DataUnit::Bits => "bit",
+            DataUnit::Packets => "pkt",
+        }
+    }
+    fn rate_suffix(self) -> &'static str {
+        match self {
+            DataUnit::Bytes => "ps",
+            DataUnit::Bits | DataUnit::Packets => "/s",
+        }
+    }
+    /// The unit of the rates in the raw output, eg. `up/down Bps`
+    pub fn raw_rate_label(self) -> &'static str {
+        match self {
+            DataUnit::Bytes => "Bps",
+            DataUnit::Bits => "bps",
+            DataUnit::Packets => "pps",
+        }
+    }
+    /// The suffix of the JSON keys holding counts, eg. `up_bytes`
+    pub fn json_name(self) -> &'static str {
+        match self {
+            DataUnit::Bytes => "bytes",
+            DataUnit::Bits => "bits",
+            DataUnit::Packets => "packets",
+        }
+    }
+}
+
+/// Whether scaled sizes go up in powers of 1024 (KiB, MiB...) or of 1000 (kB, MB...), see `--si-units`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnitPrefix {
+    Binary,
+    Si,
+}
+
+impl UnitPrefix {
+    pub fn from_opts(opts: &RenderOpts) -> Self {
+        // a thousand packets are a thousand whatever they weigh
+        if opts.si_units || opts.packets {
+            UnitPrefix::Si
+        } else {
+            UnitPrefix::Binary
+        }
+    }
+    fn base(self) -> f64 {
+        match self {
+            UnitPrefix::Binary => 1024.0,
+            UnitPrefix::Si => 1000.0,
+        }
+    }
+    fn prefixes(self) -> &'static [&'static str] {
+        match self {
+            UnitPrefix::Binary => &["Ki", "Mi", "Gi"],
+            UnitPrefix::Si => &["k", "M", "G"],
+        }
+    }
+}
 
 pub struct DisplayBandwidth {
     pub bandwidth: f64,
     pub as_rate: bool,
+    pub exact: bool,
+    pub unit: DataUnit,
+    pub prefix: UnitPrefix,
+}
+
+pub fn display_exact_bytes(bytes: u128) -> String {
+    let digits = bytes.to_string();
+    let groups: Vec<&str> = digits
+        .as_bytes()
+        .rchunks(3)
+        .rev()
+        .map(|group| ::std::str::from_utf8(group).unwrap())
+        .collect();
+    groups.join(",")
 }
 
 impl fmt::Display for DisplayBandwidth {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let suffix = if self.as_rate { "ps" } else { "" };
-        if self.bandwidth > 999_999_999.0 {
-            write!(f, "{:.2}GB{}", self.bandwidth / 1_000_000_000.0, suffix)
-        } else if self.bandwidth > 999_999.0 {
-            write!(f, "{:.2}MB{}", self.bandwidth / 1_000_000.0, suffix)
-        } else if self.bandwidth > 999.0 {
-            write!(f, "{:.2}KB{}", self.bandwidth / 1000.0, suffix)
+        let symbol = self.unit.symbol();
+        let suffix = if self.as_rate {
+            self.unit.rate_suffix()
+        } else {
+            ""
+        };
+        let bandwidth = self.bandwidth * self.unit.convert(1) as f64;
+        if self.exact {
+            write!(
+                f,
+                "{}{}{}",
+                display_exact_bytes(bandwidth as u128),
+                symbol,
+                suffix
+            )
         } else {
-            write!(f, "{}B{}", self.bandwidth, suffix)
+            // the next prefix as soon as there would be four digits, so the widths stay the same
+            let mut scaled = bandwidth;
+            let mut prefix = None;
+            for next_prefix in self.prefix.prefixes().iter() {
+                if scaled <= 999.0 {
+                    break;
+                }
+                scaled /= self.prefix.base();
+                prefix = Some(next_prefix);
+            }
+            match prefix {
+                Some(prefix) => write!(f, "{:.2}{}{}{}", scaled, prefix, symbol, suffix),
+                None => write!(f, "{}{}{}", bandwidth, symbol, suffix),
+            }
         }
     }
 }
+
+pub fn display_upload_and_download(
+    uploaded: u128,
+    downloaded: u128,
+    total: bool,
+    opts: &RenderOpts,
+) -> String {
+    let display_bandwidth = |bandwidth: u128| DisplayBandwidth {
+        bandwidth: bandwidth as f64,
+        as_rate: !total,
+        exact: opts.exact_bytes,
+        unit: DataUnit::from_opts(opts),
+        prefix: UnitPrefix::from_opts(opts),
+    };
+    match opts.capture_direction {
+        CaptureDirection::Both => format!(
+            "{} / {}",
+            display_bandwidth(uploaded),
+            display_bandwidth(downloaded)
+        ),
+        CaptureDirection::Egress => display_bandwidth(uploaded).to_string(),
+        CaptureDirection::Ingress => display_bandwidth(downloaded).to_string(),
+    }
+}
+
+/// A rate given on the command line, either in bits (eg. 100Mbps) or in bytes (eg. 5MBps, or just 5MB)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BandwidthLimit {
+    pub bytes_per_second: f64,
+}
+
+impl BandwidthLimit {
+    pub fn is_exceeded_by(&self, bytes: u128, window: Duration) -> bool {
+        let seconds = window.as_secs_f64();
+        seconds > 0.0 && bytes as f64 / seconds > self.bytes_per_second
+    }
+}
+
+impl FromStr for BandwidthLimit {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid rate '{}', expected something like 100Mbps (bits) or 5MBps (bytes)",
+                s
+            )
+        };
+        // nothing needs no unit
+        if s.trim() == "0" {
+            return Ok(BandwidthLimit::default());
+        }
+        let unit_start = s
+            .find(|c: char| c.is_ascii_alphabetic())
+            .ok_or_else(invalid)?;
+        let (amount, unit) = s.split_at(unit_start);
+        let amount: f64 = amount.trim().parse().map_err(|_| invalid())?;
+        let (multiplier, unit) = match unit.chars().next() {
+            Some('K') | Some('k') => (1_000.0, &unit[1..]),
+            Some('M') => (1_000_000.0, &unit[1..]),
+            Some('G') => (1_000_000_000.0, &unit[1..]),
+            _ => (1.0, unit),
+        };
+        let bytes_per_unit = match unit {
+            // a bare size is taken to be per second, since a rate is all that makes sense here
+            "Bps" | "B" => 1.0,
+            "bps" => 1.0 / 8.0,
+            _ => return Err(invalid()),
+        };
+        if amount < 0.0 {
+            return Err(invalid());
+        }
+        Ok(BandwidthLimit {
+            bytes_per_second: amount * multiplier * bytes_per_unit,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn display(bandwidth: f64, as_rate: bool, exact: bool, unit: DataUnit) -> String {
+        DisplayBandwidth {
+            bandwidth,
+            as_rate,
+            exact,
+            unit,
+            prefix: UnitPrefix::Si,
+        }
+        .to_string()
+    }
+
+    fn display_binary(bandwidth: f64, as_rate: bool, unit: DataUnit) -> String {
+        DisplayBandwidth {
+            bandwidth,
+            as_rate,
+            exact: false,
+            unit,
+            prefix: UnitPrefix::Binary,
+        }
+        .to_string()
+    }
+
+    #[test]
+    fn test_display_bandwidth_in_bytes() {
+        assert_eq!(display(21.0, true, false, DataUnit::Bytes), "21Bps");
+        assert_eq!(
+            display(1_500_000.0, false, false, DataUnit::Bytes),
+            "1.50MB"
+        );
+        assert_eq!(display(1_500.0, true, false, DataUnit::Bytes), "1.50kBps");
+    }
+
+    #[test]
+    fn test_display_bandwidth_in_bits() {
+        assert_eq!(display(21.0, true, false, DataUnit::Bits), "168bit/s");
+        assert_eq!(
+            display(1_500_000.0, true, false, DataUnit::Bits),
+            "12.00Mbit/s"
+        );
+        assert_eq!(display(1_500.0, false, true, DataUnit::Bits), "12,000bit");
+    }
+
+    #[test]
+    fn test_display_bandwidth_in_packets() {
+        assert_eq!(display(21.0, true, false, DataUnit::Packets), "21pkt/s");
+        assert_eq!(
+            display(1_500.0, true, false, DataUnit::Packets),
+            "1.50kpkt/s"
+        );
+        assert_eq!(display(1_500.0, false, true, DataUnit::Packets), "1,500pkt");
+    }
+
+    #[test]
+    fn test_display_bandwidth_in_binary_units() {
+        assert_eq!(display_binary(999.0, true, DataUnit::Bytes), "999Bps");
+        assert_eq!(display_binary(1_536.0, true, DataUnit::Bytes), "1.50KiBps");
+        assert_eq!(
+            display_binary(1_048_576.0, false, DataUnit::Bytes),
+            "1.00MiB"
+        );
+        assert_eq!(display_binary(128.0, true, DataUnit::Bits), "1.00Kibit/s");
+        // four digits would not fit where three do
+        assert_eq!(display_binary(1_000.0, false, DataUnit::Bytes), "0.98KiB");
+    }
+
+    #[test]
+    fn test_bandwidth_limit_in_bits() {
+        let limit: BandwidthLimit = "100Mbps".parse().unwrap();
+        assert_eq!(limit.bytes_per_second, 12_500_000.0);
+    }
+
+    #[test]
+    fn test_bandwidth_limit_in_bytes() {
+        let limit: BandwidthLimit = "1.5KBps".parse().unwrap();
+        assert_eq!(limit.bytes_per_second, 1_500.0);
+    }
+
+    #[test]
+    fn test_bandwidth_limit_in_bytes_without_per_second() {
+        let limit: BandwidthLimit = "5MB".parse().unwrap();
+        assert_eq!(limit.bytes_per_second, 5_000_000.0);
+    }
+
+    #[test]
+    fn test_bandwidth_limit_without_prefix() {
+        let limit: BandwidthLimit = "800bps".parse().unwrap();
+        assert_eq!(limit.bytes_per_second, 100.0);
+    }
+
+    #[test]
+    fn test_bandwidth_limit_of_zero_without_unit() {
+        let limit: BandwidthLimit = "0".parse().unwrap();
+        assert_eq!(limit.bytes_per_second, 0.0);
+    }
+
+    #[test]
+    fn test_bandwidth_limit_rejects_unknown_units() {
+        assert!("100".parse::<BandwidthLimit>().is_err());
+        assert!("100Mbit".parse::<BandwidthLimit>().is_err());
+        assert!("fastps".parse::<BandwidthLimit>().is_err());
+    }
+
+    #[test]
+    fn test_bandwidth_limit_is_exceeded_per_second() {
+        let limit: BandwidthLimit = "1KBps".parse().unwrap();
+        assert!(!limit.is_exceeded_by(1_500, Duration::from_secs(2)));
+        assert!(limit.is_exceeded_by(1_500, Duration::from_secs(1)));
+        assert!(!limit.is_exceeded_by(1_500, Duration::from_secs(0)));
+    }
+}
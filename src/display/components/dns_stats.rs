@@ -0,0 +1,31 @@
+use ::tui::backend::Backend;
+use ::tui::layout::{Alignment, Rect};
+use ::tui::terminal::Frame;
+use ::tui::widgets::{Paragraph, Text, Widget};
+
+use crate::network::dns::DnsStats;
+
+pub struct DnsStatsLine {
+    pub stats: DnsStats,
+}
+
+impl DnsStatsLine {
+    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect) {
+        let resolved = match (self.stats.average, self.stats.max) {
+            (Some(average), Some(max)) => format!(
+                "Resolved {} addresses since the last tick in {}ms on average and {}ms at most",
+                self.stats.lookups,
+                average.as_millis(),
+                max.as_millis()
+            ),
+            _ => String::from("Resolved no addresses since the last tick"),
+        };
+        let text = [Text::raw(format!(
+            " {}, {} still pending",
+            resolved, self.stats.pending
+        ))];
+        Paragraph::new(text.iter())
+            .alignment(Alignment::Left)
+            .render(frame, rect);
+    }
+}
@@ -0,0 +1,122 @@
+use ::std::cmp;
+use ::std::str::FromStr;
+
+use ::termion::event::Key;
+
+use ::tui::backend::Backend;
+use ::tui::layout::Rect;
+use ::tui::terminal::Frame;
+use ::tui::widgets::{Block, Borders, Paragraph, Text, Widget};
+
+/// Which keys quit, see --quit-key
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum QuitKeys {
+    CtrlC,
+    Q,
+    #[default]
+    Both,
+}
+
+impl QuitKeys {
+    pub fn quits(self, key: Key) -> bool {
+        match key {
+            Key::Ctrl('c') => self != QuitKeys::Q,
+            Key::Char('q') => self != QuitKeys::CtrlC,
+            _ => false,
+        }
+    }
+    fn label(self) -> &'static str {
+        match self {
+            QuitKeys::CtrlC => "<CTRL-C>",
+            QuitKeys::Q => "q",
+            QuitKeys::Both => "q, <CTRL-C>",
+        }
+    }
+}
+
+impl FromStr for QuitKeys {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ctrl-c" => Ok(QuitKeys::CtrlC),
+            "q" => Ok(QuitKeys::Q),
+            "both" => Ok(QuitKeys::Both),
+            _ => Err(format!(
+                "invalid quit key '{}', expected ctrl-c, q or both",
+                s
+            )),
+        }
+    }
+}
+
+/// Every key the tables listen to and what it does, in the order they are shown with `?` (after
+/// the ones that quit)
+pub const KEY_BINDINGS: &[(&str, &str)] = &[
+    ("<SPACE>", "pause or resume"),
+    ("f", "freeze or unfreeze the tables, capturing goes on"),
+    ("<TAB>", "focus the next table"),
+    ("<SHIFT-TAB>", "focus the previous table"),
+    ("<UP>, <DOWN>", "scroll the focused table"),
+    ("<PGUP>, <PGDN>", "scroll it a page at a time"),
+    ("s", "sort by the next column"),
+    ("/", "search (<ENTER> to keep, <ESC> to clear)"),
+    ("b", "jump to the top talker"),
+    ("p", "pin the clicked row to the top, or unpin it"),
+    ("y", "copy the clicked row to the clipboard"),
+    ("r", "resolve hostnames or stop resolving them"),
+    ("l", "show or hide the event log"),
+    ("h", "show or hide the connection history"),
+    ("t", "show or hide the session bytes"),
+    ("z", "reset the session counters"),
+    ("?", "show or hide this help"),
+];
+
+/// The key bindings in a box over the middle of the screen, until any key is pressed
+pub struct HelpOverlay {
+    pub quit_keys: QuitKeys,
+}
+
+impl HelpOverlay {
+    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect) {
+        let key_bindings = [(self.quit_keys.label(), "quit")];
+        let key_bindings = key_bindings.iter().chain(KEY_BINDINGS.iter());
+        let key_width = key_bindings
+            .clone()
+            .map(|(key, _)| key.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut lines: Vec<String> = key_bindings
+            .map(|(key, action)| format!(" {:width$}  {}", key, action, width = key_width))
+            .collect();
+        lines.push(String::new());
+        lines.push(String::from(" Press any key to close this."));
+        let text_width = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let width = cmp::min(text_width as u16 + 3, rect.width);
+        let height = cmp::min(lines.len() as u16 + 2, rect.height);
+        let area = Rect {
+            x: rect.x + (rect.width - width) / 2,
+            y: rect.y + (rect.height - height) / 2,
+            width,
+            height,
+        };
+        // the paragraph only writes where there is text, so every line is padded to hide what
+        // is drawn below it
+        let text: Vec<Text> = lines
+            .iter()
+            .map(|line| {
+                Text::raw(format!(
+                    "{:width$}\n",
+                    line,
+                    width = width.saturating_sub(2) as usize
+                ))
+            })
+            .collect();
+        Paragraph::new(text.iter())
+            .block(Block::default().title("Keys").borders(Borders::ALL))
+            .render(frame, area);
+    }
+}
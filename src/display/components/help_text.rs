@@ -6,6 +6,10 @@ use ::tui::widgets::{Paragraph, Text, Widget};
 
 pub struct HelpText {
     pub paused: bool,
+    pub search_query: Option<String>,
+    pub typing_search_query: bool,
+    /// How the last `y` went, eg. `copied`
+    pub clipboard_notice: Option<&'static str>,
 }
 
 const TEXT_WHEN_PAUSED: &str = " Press <SPACE> to resume.";
@@ -20,6 +24,19 @@ impl HelpText {
                 TEXT_WHEN_NOT_PAUSED
             };
 
+            let content = match &self.search_query {
+                Some(query) if self.typing_search_query => format!(
+                    "{} Search: {}_ (<ENTER> to keep, <ESC> to clear)",
+                    content, query
+                ),
+                Some(query) => format!("{} Search: {} (</> to edit)", content, query),
+                None => String::from(content),
+            };
+            let content = match self.clipboard_notice {
+                Some(notice) => format!("{} [{}]", content, notice),
+                None => content,
+            };
+
             [Text::styled(
                 content,
                 Style::default().modifier(Modifier::BOLD),
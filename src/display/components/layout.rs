@@ -2,12 +2,27 @@ use ::tui::backend::Backend;
 use ::tui::layout::{Constraint, Direction, Rect};
 use ::tui::terminal::Frame;
 
+use super::BudgetLine;
+use super::CaptureStatsLine;
+use super::ChurnLine;
+use super::DnsStatsLine;
+use super::HelpOverlay;
 use super::HelpText;
+use super::LogPane;
+use super::SessionSummary;
 use super::Table;
+use super::TableArea;
+use super::ThroughputGraph;
 use super::TotalBandwidth;
+use super::THROUGHPUT_GRAPH_HEIGHT;
 
 const FIRST_HEIGHT_BREAKPOINT: u16 = 30;
 const FIRST_WIDTH_BREAKPOINT: u16 = 120;
+// with --wide, from this width the connections table has room for its columns next to another
+const WIDE_WIDTH_BREAKPOINT: u16 = 180;
+const LOG_PANE_HEIGHT: u16 = 8;
+// the graph only takes up room the tables can spare
+const MIN_TABLES_HEIGHT_WITH_GRAPH: u16 = 10;
 
 fn top_app_and_bottom_split(rect: Rect) -> (Rect, Rect, Rect) {
     let parts = ::tui::layout::Layout::default()
@@ -25,10 +40,68 @@ fn top_app_and_bottom_split(rect: Rect) -> (Rect, Rect, Rect) {
     (parts[0], parts[1], parts[2])
 }
 
+fn tables_and_log_split(rect: Rect) -> (Rect, Rect) {
+    let log_height = ::std::cmp::min(LOG_PANE_HEIGHT, rect.height / 2);
+    let parts = ::tui::layout::Layout::default()
+        .direction(Direction::Vertical)
+        .margin(0)
+        .constraints(
+            [
+                Constraint::Length(rect.height - log_height),
+                Constraint::Length(log_height),
+            ]
+            .as_ref(),
+        )
+        .split(rect);
+    (parts[0], parts[1])
+}
+
+fn tables_and_stats_split(rect: Rect) -> (Rect, Rect) {
+    let parts = ::tui::layout::Layout::default()
+        .direction(Direction::Vertical)
+        .margin(0)
+        .constraints([Constraint::Length(rect.height - 1), Constraint::Length(1)].as_ref())
+        .split(rect);
+    (parts[0], parts[1])
+}
+
+fn graph_and_tables_split(rect: Rect) -> (Rect, Rect) {
+    let parts = ::tui::layout::Layout::default()
+        .direction(Direction::Vertical)
+        .margin(0)
+        .constraints(
+            [
+                Constraint::Length(THROUGHPUT_GRAPH_HEIGHT),
+                Constraint::Length(rect.height - THROUGHPUT_GRAPH_HEIGHT),
+            ]
+            .as_ref(),
+        )
+        .split(rect);
+    (parts[0], parts[1])
+}
+
+fn footer_split(rect: Rect) -> (Rect, Rect) {
+    let parts = ::tui::layout::Layout::default()
+        .direction(Direction::Horizontal)
+        .margin(0)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(rect);
+    (parts[0], parts[1])
+}
+
 pub struct Layout<'a> {
     pub header: TotalBandwidth<'a>,
-    pub children: Vec<Table<'a>>,
+    pub children: Vec<Table>,
+    pub wide: bool,
     pub footer: HelpText,
+    pub summary: SessionSummary<'a>,
+    pub log: Option<LogPane<'a>>,
+    pub stats: Option<CaptureStatsLine>,
+    pub churn: Option<ChurnLine>,
+    pub dns_stats: Option<DnsStatsLine>,
+    pub budget: Option<BudgetLine<'a>>,
+    pub graph: Option<ThroughputGraph<'a>>,
+    pub help: Option<HelpOverlay>,
 }
 
 impl<'a> Layout<'a> {
@@ -89,27 +162,124 @@ impl<'a> Layout<'a> {
         }
     }
 
+    fn build_four_children_layout(&self, rect: Rect) -> Vec<Rect> {
+        // if there are four elements
+        if rect.height < FIRST_HEIGHT_BREAKPOINT || rect.width < FIRST_WIDTH_BREAKPOINT {
+            // if the space is not enough, we drop the last element
+            self.build_three_children_layout(rect)
+        } else {
+            // we display the four elements in quarters
+            self.progressive_split(rect, vec![Direction::Vertical])
+                .into_iter()
+                .flat_map(|half| self.progressive_split(half, vec![Direction::Horizontal]))
+                .collect()
+        }
+    }
+
+    fn build_wide_layout(&self, rect: Rect) -> Vec<Rect> {
+        // the first and third elements (the processes and connections) side by side, the second
+        // (the remote addresses) and the fourth below them
+        let halves = self.progressive_split(rect, vec![Direction::Vertical]);
+        let top_halves = self.progressive_split(halves[0], vec![Direction::Horizontal]);
+        if self.children.len() == 3 {
+            vec![top_halves[0], halves[1], top_halves[1]]
+        } else {
+            let bottom_halves = self.progressive_split(halves[1], vec![Direction::Horizontal]);
+            vec![
+                top_halves[0],
+                bottom_halves[0],
+                top_halves[1],
+                bottom_halves[1],
+            ]
+        }
+    }
+
     fn build_layout(&self, rect: Rect) -> Vec<Rect> {
-        if self.children.len() == 1 {
+        if self.wide
+            && self.children.len() >= 3
+            && rect.width >= WIDE_WIDTH_BREAKPOINT
+            && rect.height >= FIRST_HEIGHT_BREAKPOINT
+        {
+            // checked on every draw, so a terminal resized below it goes back to the default
+            self.build_wide_layout(rect)
+        } else if self.children.len() == 1 {
             // if there's only one element to render, it can take the whole frame
             vec![rect]
         } else if self.children.len() == 2 {
             self.build_two_children_layout(rect)
-        } else {
+        } else if self.children.len() == 3 {
             self.build_three_children_layout(rect)
+        } else {
+            self.build_four_children_layout(rect)
         }
     }
-    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect) {
+    /// Returns where the tables that fit on the screen were drawn, in the order of the children
+    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect) -> Vec<TableArea> {
         let (top, app, bottom) = top_app_and_bottom_split(rect);
+        let app = match &self.graph {
+            Some(graph) if app.height >= THROUGHPUT_GRAPH_HEIGHT + MIN_TABLES_HEIGHT_WITH_GRAPH => {
+                let (graph_rect, tables) = graph_and_tables_split(app);
+                graph.render(frame, graph_rect);
+                tables
+            }
+            _ => app,
+        };
+        let app = match &self.stats {
+            Some(stats) => {
+                let (tables, stats_rect) = tables_and_stats_split(app);
+                stats.render(frame, stats_rect);
+                tables
+            }
+            None => app,
+        };
+        let app = match &self.churn {
+            Some(churn) => {
+                let (tables, churn_rect) = tables_and_stats_split(app);
+                churn.render(frame, churn_rect);
+                tables
+            }
+            None => app,
+        };
+        let app = match &self.dns_stats {
+            Some(dns_stats) => {
+                let (tables, dns_stats_rect) = tables_and_stats_split(app);
+                dns_stats.render(frame, dns_stats_rect);
+                tables
+            }
+            None => app,
+        };
+        let app = match &self.budget {
+            Some(budget) => {
+                let (tables, budget_rect) = tables_and_stats_split(app);
+                budget.render(frame, budget_rect);
+                tables
+            }
+            None => app,
+        };
+        let app = match &self.log {
+            Some(log) => {
+                let (tables, log_rect) = tables_and_log_split(app);
+                log.render(frame, log_rect);
+                tables
+            }
+            None => app,
+        };
         let layout_slots = self.build_layout(app);
+        let mut table_areas = vec![];
         for i in 0..layout_slots.len() {
             if let Some(rect) = layout_slots.get(i) {
                 if let Some(child) = self.children.get(i) {
-                    child.render(frame, *rect);
+                    table_areas.push(child.render(frame, *rect));
                 }
             }
         }
         self.header.render(frame, top);
-        self.footer.render(frame, bottom);
+        let (help, summary) = footer_split(bottom);
+        self.footer.render(frame, help);
+        self.summary.render(frame, summary);
+        if let Some(help) = &self.help {
+            help.render(frame, rect);
+        }
+        table_areas
     }
 }
@@ -0,0 +1,31 @@
+use ::tui::backend::Backend;
+use ::tui::layout::Rect;
+use ::tui::terminal::Frame;
+use ::tui::widgets::{Block, Borders, Paragraph, Text, Widget};
+
+use crate::display::EventLog;
+
+pub struct LogPane<'a> {
+    pub event_log: &'a EventLog,
+}
+
+impl<'a> LogPane<'a> {
+    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect) {
+        // newest events are at the bottom, so only show as many as fit inside the borders
+        let visible_lines = rect.height.saturating_sub(2) as usize;
+        let lines: Vec<Text> = self
+            .event_log
+            .latest(visible_lines)
+            .map(|event| {
+                Text::raw(format!(
+                    "{} {}\n",
+                    event.time.format("%H:%M:%S"),
+                    event.message
+                ))
+            })
+            .collect();
+        Paragraph::new(lines.iter())
+            .block(Block::default().title("Event log").borders(Borders::ALL))
+            .render(frame, rect);
+    }
+}
@@ -1,11 +1,29 @@
+mod border;
+mod budget_line;
+mod capture_stats;
+mod connection_churn;
 mod display_bandwidth;
+mod dns_stats;
+mod help_overlay;
 mod help_text;
 mod layout;
+mod log_pane;
+mod session_summary;
 mod table;
+mod throughput_graph;
 mod total_bandwidth;
 
+pub use border::*;
+pub use budget_line::*;
+pub use capture_stats::*;
+pub use connection_churn::*;
 pub use display_bandwidth::*;
+pub use dns_stats::*;
+pub use help_overlay::*;
 pub use help_text::*;
 pub use layout::*;
+pub use log_pane::*;
+pub use session_summary::*;
 pub use table::*;
+pub use throughput_graph::*;
 pub use total_bandwidth::*;
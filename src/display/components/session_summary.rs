@@ -0,0 +1,53 @@
+use ::std::time::Duration;
+
+use ::tui::backend::Backend;
+use ::tui::layout::{Alignment, Rect};
+use ::tui::style::{Modifier, Style};
+use ::tui::terminal::Frame;
+use ::tui::widgets::{Paragraph, Text, Widget};
+
+use crate::display::{display_upload_and_download, SessionTotals};
+use crate::RenderOpts;
+
+pub struct SessionSummary<'a> {
+    pub totals: &'a SessionTotals,
+    pub opts: &'a RenderOpts,
+}
+
+pub fn display_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+impl<'a> SessionSummary<'a> {
+    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect) {
+        let totals = self.totals;
+        let text = [Text::styled(
+            format!(
+                "Session: {} in {}, average {} ",
+                display_upload_and_download(
+                    totals.bytes_uploaded,
+                    totals.bytes_downloaded,
+                    true,
+                    self.opts
+                ),
+                display_elapsed(totals.elapsed),
+                display_upload_and_download(
+                    totals.average(totals.bytes_uploaded),
+                    totals.average(totals.bytes_downloaded),
+                    false,
+                    self.opts
+                ),
+            ),
+            Style::default().modifier(Modifier::BOLD),
+        )];
+        Paragraph::new(text.iter())
+            .alignment(Alignment::Right)
+            .render(frame, rect);
+    }
+}
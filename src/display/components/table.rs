@@ -1,33 +1,92 @@
-use ::std::collections::{BTreeMap, HashMap};
+use ::std::cmp;
+use ::std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use ::std::net::{IpAddr, SocketAddr};
 
 use ::tui::backend::Backend;
 use ::tui::layout::Rect;
-use ::tui::style::{Color, Style};
+use ::tui::style::{Color, Modifier, Style};
 use ::tui::terminal::Frame;
-use ::tui::widgets::{Block, Borders, Row, Widget};
+use ::tui::widgets::{Block, Row, Widget};
 
-use crate::display::{Bandwidth, DisplayBandwidth, UIState};
-use crate::network::{display_connection_string, display_ip_or_host};
+use crate::display::{
+    display_elapsed, display_upload_and_download, tag_of, BorderLines, BorderStyle, DataUnit,
+    DisplayBandwidth, GroupBy, GroupEntry, Snapshot, SortBy, Theme, UIState, UnitPrefix,
+};
+use crate::network::{
+    display_connection_string, display_ip_or_host, Connection, Encryption, Protocol,
+};
+use crate::RenderOpts;
 
-use ::std::net::IpAddr;
+fn display_connection_count_history(history: Option<&VecDeque<u128>>, current: u128) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let history = match history {
+        Some(history) => history,
+        None => return current.to_string(),
+    };
+    let max = history.iter().max().cloned().unwrap_or(0).max(1);
+    let sparkline: String = history
+        .iter()
+        .map(|count| BARS[(count * (BARS.len() as u128 - 1) / max) as usize])
+        .collect();
+    format!("{} {}", current, sparkline)
+}
 
-fn display_upload_and_download(bandwidth: &impl Bandwidth, total: bool) -> String {
+fn display_up_down_ratio(uploaded: u128, downloaded: u128) -> String {
+    match (uploaded, downloaded) {
+        (0, 0) => String::from("-"),
+        (_, 0) => String::from("up only"),
+        _ => format!("{:.2}", uploaded as f64 / downloaded as f64),
+    }
+}
+
+fn display_packets(packets: u128, total: bool) -> String {
+    if total {
+        packets.to_string()
+    } else {
+        format!("{}pps", packets)
+    }
+}
+
+fn display_retransmissions(
+    retransmitted_packets: u128,
+    packets: u128,
+    protocol: Protocol,
+) -> String {
+    if protocol != Protocol::Tcp || packets == 0 {
+        return String::from("-");
+    }
     format!(
-        "{} / {}",
-        DisplayBandwidth {
-            bandwidth: bandwidth.get_total_bytes_uploaded() as f64,
-            as_rate: !total,
-        },
-        DisplayBandwidth {
-            bandwidth: bandwidth.get_total_bytes_downloaded() as f64,
-            as_rate: !total,
-        },
+        "{:.1}%",
+        retransmitted_packets as f64 * 100.0 / packets as f64
     )
 }
 
+fn display_average_packet_size(
+    bytes: u128,
+    packets: u128,
+    exact: bool,
+    prefix: UnitPrefix,
+) -> String {
+    match bytes.checked_div(packets) {
+        Some(average) => DisplayBandwidth {
+            bandwidth: average as f64,
+            as_rate: false,
+            exact,
+            // packet sizes are sizes rather than rates, so they stay in bytes
+            unit: DataUnit::Bytes,
+            prefix,
+        }
+        .to_string(),
+        None => String::from("-"),
+    }
+}
+
 pub enum ColumnCount {
     Two,
     Three,
+    Four,
+    /// Five columns or more, as many as the optional ones add up to
+    More(u16),
 }
 
 impl ColumnCount {
@@ -35,51 +94,343 @@ impl ColumnCount {
         match &self {
             ColumnCount::Two => 2,
             ColumnCount::Three => 3,
+            ColumnCount::Four => 4,
+            ColumnCount::More(count) => *count,
+        }
+    }
+    fn from_u16(count: u16) -> Self {
+        match count {
+            2 => ColumnCount::Two,
+            3 => ColumnCount::Three,
+            4 => ColumnCount::Four,
+            _ => ColumnCount::More(count),
+        }
+    }
+    fn column_indices(&self) -> Vec<usize> {
+        match &self {
+            ColumnCount::Two => vec![0, 2], // always lose the middle column when needed
+            _ => (0..self.as_u16() as usize).collect(),
         }
     }
 }
 
+pub struct HostAddresses<'a> {
+    pub ips: &'a HashSet<IpAddr>,
+    pub use_color: bool,
+}
+
+impl<'a> HostAddresses<'a> {
+    // returns whether the row should be colored, annotating the cell instead when colors are off
+    fn mark(&self, ip: IpAddr, cell: &mut String) -> bool {
+        if !self.ips.contains(&ip) {
+            false
+        } else if self.use_color {
+            true
+        } else {
+            cell.push_str(" (this host)");
+            false
+        }
+    }
+}
+
+/// How a row differs from the tick before, see `--highlight-changes`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RowChange {
+    Added,
+    Changed,
+}
+
 pub struct ColumnData {
     column_count: ColumnCount,
     column_widths: Vec<u16>,
 }
 
-pub struct Table<'a> {
-    title: &'a str,
-    column_names: &'a [&'a str],
+pub struct Table {
+    title: String,
+    column_names: Vec<String>,
     rows: Vec<Vec<String>>,
+    highlighted_rows: HashSet<usize>,
+    /// The rows to the remote addresses of a --tag, in its color
+    tagged_rows: HashMap<usize, Color>,
+    /// Upload and download of every row, to color them by
+    row_bandwidths: Vec<u128>,
+    /// Color the header and the rows, everything is in the default colors otherwise (see `--no-color`)
+    use_color: bool,
+    /// The colors it is drawn in with colors, see `--theme`
+    theme: Theme,
     breakpoints: BTreeMap<u16, ColumnData>,
+    focused: bool,
+    scroll_offset: usize,
+    /// The column cut on the left rather than in the middle when it does not fit (see `--full-cmdline`)
+    left_truncated_column: Option<usize>,
+    /// The first row was moved up there and is shown in reverse (see `Table::pin_row`)
+    pinned_row: bool,
+    /// The row clicked on, shown in reverse too (see `Table::select_row`)
+    selected_row: Option<usize>,
+    /// What the tables get sorted by when the header of a column is clicked, see `SortBy::clicked`
+    sort_columns: HashMap<usize, SortBy>,
+    /// The rows that are new or whose rate changed since the last tick (see `Table::mark_changes`)
+    row_changes: HashMap<usize, RowChange>,
+    /// How many of the first columns tell a row apart, see `Table::row_keys`
+    key_columns: usize,
+    border: BorderStyle,
+    /// A bar of each row's rate against the busiest row's in the columns left over, see
+    /// `Table::add_bars`
+    bars: bool,
+}
+
+/// Where a table was drawn, to tell what a click landed on
+pub struct TableArea {
+    rect: Rect,
+    /// The index, first x and width of each column shown
+    columns: Vec<(usize, u16, u16)>,
+    /// How many rows down the table is shown, less than asked for once the last row is in sight
+    pub scroll_offset: usize,
+    row_count: usize,
+    /// Drawn with borders, see --border
+    bordered: bool,
+}
+
+pub enum TableClick {
+    Header(usize),
+    Row(usize),
+    Elsewhere,
+}
+
+impl TableArea {
+    pub fn contains(&self, x: u16, y: u16) -> bool {
+        x >= self.rect.x
+            && x < self.rect.x + self.rect.width
+            && y >= self.rect.y
+            && y < self.rect.y + self.rect.height
+    }
+    /// The header of the column or the row at `x`, `y` (which is inside the table)
+    pub fn click(&self, x: u16, y: u16) -> TableClick {
+        // below the top border (or the title), then the header and a gap
+        let header_y = self.rect.y + 1;
+        let first_row_y = header_y + 2;
+        let bottom_border = if self.bordered { 1 } else { 0 };
+        let last_row_y = self.rect.y + self.rect.height.saturating_sub(1 + bottom_border);
+        if y == header_y {
+            match self
+                .columns
+                .iter()
+                .find(|&&(_, column_x, width)| x >= column_x && x < column_x + width)
+            {
+                Some(&(index, _, _)) => TableClick::Header(index),
+                None => TableClick::Elsewhere,
+            }
+        } else if y >= first_row_y && y <= last_row_y {
+            let row = self.scroll_offset + (y - first_row_y) as usize;
+            if row < self.row_count {
+                TableClick::Row(row)
+            } else {
+                TableClick::Elsewhere
+            }
+        } else {
+            TableClick::Elsewhere
+        }
+    }
+}
+
+// the busiest row is red, the ones with less than two thirds of its bandwidth yellow and those
+// with less than a third green
+fn throughput_color(bandwidth: u128, busiest: u128, theme: &Theme) -> Option<Color> {
+    if bandwidth == 0 {
+        return None;
+    }
+    match bandwidth * 3 / busiest {
+        0 => Some(theme.low),
+        1 => Some(theme.middle),
+        _ => Some(theme.high),
+    }
+}
+
+const PERCENT_COLUMN_NAME: &str = "%";
+// the share of the table's traffic a row has, eg. "80.0%"
+const PERCENT_COLUMN_WIDTH: u16 = 6;
+const LINK_UTILIZATION_COLUMN_NAME: &str = "Link Up / Down";
+// eg. "12.5% / 100.0%"
+const LINK_UTILIZATION_COLUMN_WIDTH: u16 = 14;
+// what the first column is left with at least when it makes room for another column
+const MIN_FIRST_COLUMN_WIDTH: u16 = 12;
+// the narrowest the bars are drawn, below that they are left out
+const MIN_BAR_WIDTH: u16 = 4;
+// the eighths of a cell a bar ends with, from none to a full block
+const BAR_EIGHTHS: [char; 9] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
+// a bar of up to `width` cells as long as `bandwidth` is against `busiest`, in eighths of a cell
+fn display_bar(bandwidth: u128, busiest: u128, width: u16) -> String {
+    if busiest == 0 {
+        return String::new();
+    }
+    let eighths = (bandwidth * u128::from(width) * 8 / busiest) as usize;
+    let mut bar = BAR_EIGHTHS[8].to_string().repeat(eighths / 8);
+    if eighths % 8 > 0 {
+        bar.push(BAR_EIGHTHS[eighths % 8]);
+    }
+    bar
+}
+
+/// The name first, the bandwidth third, as most tables have them
+fn name_and_bandwidth_sort_columns() -> HashMap<usize, SortBy> {
+    [(0, SortBy::Name), (2, SortBy::Total)]
+        .iter()
+        .copied()
+        .collect()
 }
 
 fn truncate_middle(row: &str, max_length: u16) -> String {
-    if row.len() as u16 > max_length {
-        let first_slice = &row[0..(max_length as usize / 2) - 2];
-        let second_slice = &row[(row.len() - (max_length / 2) as usize + 2)..row.len()];
+    let row_length = row.chars().count();
+    if row_length as u16 > max_length {
+        let first_slice: String = row.chars().take((max_length as usize / 2) - 2).collect();
+        let second_slice: String = row
+            .chars()
+            .skip(row_length - (max_length / 2) as usize + 2)
+            .collect();
         format!("{}[..]{}", first_slice, second_slice)
     } else {
         row.to_string()
     }
 }
 
-impl<'a> Table<'a> {
-    pub fn create_connections_table(state: &UIState, ip_to_host: &HashMap<IpAddr, String>) -> Self {
-        let connections_rows = state
+// the end of a command line or path is what tells it apart, so that is what is kept
+fn truncate_left(row: &str, max_length: u16) -> String {
+    let row_length = row.chars().count();
+    if row_length as u16 > max_length {
+        let kept: String = row
+            .chars()
+            .skip(row_length - (max_length as usize).saturating_sub(4))
+            .collect();
+        format!("[..]{}", kept)
+    } else {
+        row.to_string()
+    }
+}
+
+impl Table {
+    pub fn create_connections_table(
+        snapshot: &Snapshot,
+        opts: &RenderOpts,
+        host_addresses: Option<&HostAddresses>,
+        capped_connections: &HashSet<Connection>,
+    ) -> Self {
+        let mut highlighted_rows = HashSet::new();
+        let mut tagged_rows = HashMap::new();
+        let connections_rows = snapshot
             .connections
             .iter()
-            .map(|(connection, connection_data)| {
-                vec![
-                    display_connection_string(
-                        &connection,
-                        &ip_to_host,
-                        &connection_data.interface_name,
+            .enumerate()
+            .map(|(index, entry)| {
+                let mut connection_string = display_connection_string(
+                    &entry.connection,
+                    &snapshot.ip_to_host,
+                    &entry.interface_name,
+                    !opts.numeric_ports,
+                );
+                if let Some(host_addresses) = host_addresses {
+                    if host_addresses
+                        .mark(entry.connection.remote_socket.ip, &mut connection_string)
+                    {
+                        highlighted_rows.insert(index);
+                    }
+                }
+                if let Some((label, color)) = tag_of(entry.connection.remote_socket.ip, &opts.tag) {
+                    connection_string.push_str(&format!(" [{}]", label));
+                    tagged_rows.insert(index, color);
+                }
+                // see --detect-caps
+                if capped_connections.contains(&entry.connection) {
+                    connection_string.push_str(" [capped?]");
+                }
+                let mut row = vec![
+                    connection_string,
+                    entry.process_name.to_string(),
+                    display_upload_and_download(
+                        entry.total_bytes_uploaded,
+                        entry.total_bytes_downloaded,
+                        snapshot.cumulative,
+                        opts,
                     ),
-                    connection_data.process_name.to_string(),
-                    display_upload_and_download(connection_data, state.cumulative_mode),
-                ]
+                ];
+                if opts.protocol.is_none() {
+                    row.push(entry.connection.local_socket.protocol.to_string());
+                }
+                row.push(display_elapsed(entry.age));
+                if opts.session_bytes {
+                    row.push(
+                        DisplayBandwidth {
+                            bandwidth: entry.session_bytes as f64,
+                            as_rate: false,
+                            exact: opts.exact_bytes,
+                            unit: DataUnit::from_opts(opts),
+                            prefix: UnitPrefix::from_opts(opts),
+                        }
+                        .to_string(),
+                    );
+                }
+                if opts.up_down_ratio {
+                    row.push(display_up_down_ratio(
+                        entry.total_bytes_uploaded,
+                        entry.total_bytes_downloaded,
+                    ));
+                }
+                if opts.packet_stats {
+                    row.push(display_packets(entry.total_packets, snapshot.cumulative));
+                    row.push(display_average_packet_size(
+                        entry.total_bytes_uploaded + entry.total_bytes_downloaded,
+                        entry.total_packets,
+                        opts.exact_bytes,
+                        UnitPrefix::from_opts(opts),
+                    ));
+                }
+                if opts.tcp_stats {
+                    row.push(display_retransmissions(
+                        entry.retransmitted_packets,
+                        entry.total_packets,
+                        entry.connection.local_socket.protocol,
+                    ));
+                }
+                if opts.security {
+                    let encryption = Encryption::guess(
+                        &entry.connection,
+                        &opts.plaintext_port,
+                        &opts.encrypted_port,
+                    );
+                    row.push(String::from(encryption.glyph()));
+                }
+                row
             })
             .collect();
         let connections_title = "Utilization by connection";
-        let connections_column_names = &["Connection", "Process", "Up / Down"];
+        let mut connections_column_names = vec![
+            String::from("Connection"),
+            String::from("Process"),
+            String::from(opts.capture_direction.bandwidth_label()),
+        ];
+        if opts.protocol.is_none() {
+            connections_column_names.push(String::from("Protocol"));
+        }
+        let age_column = connections_column_names.len();
+        let mut age_column_name = String::from("Age");
+        opts.sort_by.mark_age_column(&mut age_column_name);
+        connections_column_names.push(age_column_name);
+        if opts.session_bytes {
+            connections_column_names.push(String::from("Session"));
+        }
+        if opts.up_down_ratio {
+            connections_column_names.push(String::from("Up:Down"));
+        }
+        if opts.packet_stats {
+            connections_column_names.push(String::from("Packets"));
+            connections_column_names.push(String::from("Avg Pkt"));
+        }
+        if opts.tcp_stats {
+            connections_column_names.push(String::from("Retrans"));
+        }
+        if opts.security {
+            connections_column_names.push(String::from("Enc"));
+        }
         let mut breakpoints = BTreeMap::new();
         breakpoints.insert(
             0,
@@ -109,27 +460,96 @@ impl<'a> Table<'a> {
                 column_widths: vec![100, 12, 23],
             },
         );
+        if connections_column_names.len() > 3 {
+            // the extra columns are nice-to-haves, so they only show up once everything else fits
+            let mut column_widths = vec![100, 12, 23];
+            for _ in 3..connections_column_names.len() {
+                column_widths[0] -= 10;
+                column_widths.push(10);
+            }
+            breakpoints.insert(
+                140,
+                ColumnData {
+                    column_count: ColumnCount::from_u16(column_widths.len() as u16),
+                    column_widths,
+                },
+            );
+        }
+        opts.sort_by
+            .mark_columns(&mut connections_column_names, 0, 2);
         Table {
-            title: connections_title,
+            title: String::from(connections_title),
             column_names: connections_column_names,
             rows: connections_rows,
+            highlighted_rows,
+            tagged_rows,
+            row_bandwidths: snapshot
+                .connections
+                .iter()
+                .map(|entry| entry.total_bytes_uploaded + entry.total_bytes_downloaded)
+                .collect(),
+            use_color: !opts.no_color,
+            theme: opts.colors,
+            border: opts.border,
+            bars: false,
             breakpoints,
+            focused: false,
+            scroll_offset: 0,
+            left_truncated_column: if opts.full_cmdline { Some(1) } else { None },
+            pinned_row: false,
+            selected_row: None,
+            row_changes: HashMap::new(),
+            key_columns: 2,
+            sort_columns: [
+                (0, SortBy::Name),
+                (2, SortBy::Total),
+                (age_column, SortBy::Age),
+            ]
+            .iter()
+            .copied()
+            .collect(),
         }
     }
-    pub fn create_processes_table(state: &UIState) -> Self {
-        let processes_rows = state
+    pub fn create_processes_table(
+        snapshot: &Snapshot,
+        connection_history: Option<&HashMap<String, VecDeque<u128>>>,
+        process_users: Option<&HashMap<String, String>>,
+        opts: &RenderOpts,
+    ) -> Self {
+        let processes_rows = snapshot
             .processes
             .iter()
-            .map(|(process_name, data_for_process)| {
-                vec![
-                    (*process_name).to_string(),
-                    data_for_process.connection_count.to_string(),
-                    display_upload_and_download(data_for_process, state.cumulative_mode),
-                ]
+            .map(|entry| {
+                let connection_count = match connection_history {
+                    Some(connection_history) => display_connection_count_history(
+                        connection_history.get(&entry.name),
+                        entry.connection_count,
+                    ),
+                    None => entry.connection_count.to_string(),
+                };
+                let mut row = vec![
+                    entry.display_name(),
+                    connection_count,
+                    display_upload_and_download(
+                        entry.total_bytes_uploaded,
+                        entry.total_bytes_downloaded,
+                        snapshot.cumulative,
+                        opts,
+                    ),
+                ];
+                if let Some(process_users) = process_users {
+                    // blank for the processes whose owner could not be read
+                    row.push(process_users.get(&entry.name).cloned().unwrap_or_default());
+                }
+                row
             })
             .collect();
         let processes_title = "Utilization by process name";
-        let processes_column_names = &["Process", "Connections", "Up / Down"];
+        let mut processes_column_names = vec![
+            String::from("Process"),
+            String::from("Connections"),
+            String::from(opts.capture_direction.bandwidth_label()),
+        ];
         let mut breakpoints = BTreeMap::new();
         breakpoints.insert(
             0,
@@ -159,31 +579,96 @@ impl<'a> Table<'a> {
                 column_widths: vec![40, 12, 23],
             },
         );
+        if process_users.is_some() {
+            processes_column_names.push(String::from("User"));
+            breakpoints.insert(
+                100,
+                ColumnData {
+                    column_count: ColumnCount::Four,
+                    column_widths: vec![28, 12, 23, 12],
+                },
+            );
+            breakpoints.insert(
+                140,
+                ColumnData {
+                    column_count: ColumnCount::Four,
+                    column_widths: vec![40, 12, 23, 16],
+                },
+            );
+        }
+        opts.sort_by.mark_columns(&mut processes_column_names, 0, 2);
         Table {
-            title: processes_title,
+            title: String::from(processes_title),
             column_names: processes_column_names,
             rows: processes_rows,
+            highlighted_rows: HashSet::new(),
+            tagged_rows: HashMap::new(),
+            row_bandwidths: snapshot
+                .processes
+                .iter()
+                .map(|entry| entry.total_bytes_uploaded + entry.total_bytes_downloaded)
+                .collect(),
+            use_color: !opts.no_color,
+            theme: opts.colors,
+            border: opts.border,
+            bars: false,
             breakpoints,
+            focused: false,
+            scroll_offset: 0,
+            left_truncated_column: if opts.full_cmdline { Some(0) } else { None },
+            pinned_row: false,
+            selected_row: None,
+            row_changes: HashMap::new(),
+            key_columns: 1,
+            sort_columns: name_and_bandwidth_sort_columns(),
         }
     }
     pub fn create_remote_addresses_table(
-        state: &UIState,
-        ip_to_host: &HashMap<IpAddr, String>,
+        snapshot: &Snapshot,
+        opts: &RenderOpts,
+        host_addresses: Option<&HostAddresses>,
+        ip_to_geo: Option<&HashMap<IpAddr, String>>,
     ) -> Self {
-        let remote_addresses_rows = state
+        let mut highlighted_rows = HashSet::new();
+        let mut tagged_rows = HashMap::new();
+        let remote_addresses_rows = snapshot
             .remote_addresses
             .iter()
-            .map(|(remote_address, data_for_remote_address)| {
-                let remote_address = display_ip_or_host(*remote_address, &ip_to_host);
-                vec![
+            .enumerate()
+            .map(|(index, entry)| {
+                let mut remote_address = display_ip_or_host(entry.ip, &snapshot.ip_to_host);
+                if let Some(host_addresses) = host_addresses {
+                    if host_addresses.mark(entry.ip, &mut remote_address) {
+                        highlighted_rows.insert(index);
+                    }
+                }
+                if let Some((label, color)) = tag_of(entry.ip, &opts.tag) {
+                    remote_address.push_str(&format!(" [{}]", label));
+                    tagged_rows.insert(index, color);
+                }
+                let mut row = vec![
                     remote_address,
-                    data_for_remote_address.connection_count.to_string(),
-                    display_upload_and_download(data_for_remote_address, state.cumulative_mode),
-                ]
+                    entry.connection_count.to_string(),
+                    display_upload_and_download(
+                        entry.total_bytes_uploaded,
+                        entry.total_bytes_downloaded,
+                        snapshot.cumulative,
+                        opts,
+                    ),
+                ];
+                if let Some(ip_to_geo) = ip_to_geo {
+                    // blank for the addresses none of the --geoip files has
+                    row.push(ip_to_geo.get(&entry.ip).cloned().unwrap_or_default());
+                }
+                row
             })
             .collect();
         let remote_addresses_title = "Utilization by remote address";
-        let remote_addresses_column_names = &["Remote Address", "Connections", "Up / Down"];
+        let mut remote_addresses_column_names = vec![
+            String::from("Remote Address"),
+            String::from("Connections"),
+            String::from(opts.capture_direction.bandwidth_label()),
+        ];
         let mut breakpoints = BTreeMap::new();
         breakpoints.insert(
             0,
@@ -213,15 +698,557 @@ impl<'a> Table<'a> {
                 column_widths: vec![100, 12, 23],
             },
         );
+        if ip_to_geo.is_some() {
+            remote_addresses_column_names.push(String::from("Location"));
+            breakpoints.insert(
+                70,
+                ColumnData {
+                    column_count: ColumnCount::Four,
+                    column_widths: vec![18, 12, 23, 11],
+                },
+            );
+            breakpoints.insert(
+                100,
+                ColumnData {
+                    column_count: ColumnCount::Four,
+                    column_widths: vec![44, 12, 23, 16],
+                },
+            );
+            breakpoints.insert(
+                140,
+                ColumnData {
+                    column_count: ColumnCount::Four,
+                    column_widths: vec![84, 12, 23, 16],
+                },
+            );
+        }
+        opts.sort_by
+            .mark_columns(&mut remote_addresses_column_names, 0, 2);
         Table {
-            title: remote_addresses_title,
+            title: String::from(remote_addresses_title),
             column_names: remote_addresses_column_names,
             rows: remote_addresses_rows,
+            highlighted_rows,
+            tagged_rows,
+            row_bandwidths: snapshot
+                .remote_addresses
+                .iter()
+                .map(|entry| entry.total_bytes_uploaded + entry.total_bytes_downloaded)
+                .collect(),
+            use_color: !opts.no_color,
+            theme: opts.colors,
+            border: opts.border,
+            bars: false,
+            breakpoints,
+            focused: false,
+            scroll_offset: 0,
+            left_truncated_column: None,
+            pinned_row: false,
+            selected_row: None,
+            row_changes: HashMap::new(),
+            key_columns: 1,
+            sort_columns: name_and_bandwidth_sort_columns(),
+        }
+    }
+    pub fn create_grouped_table(
+        groups: &[GroupEntry],
+        group_by: &GroupBy,
+        cumulative: bool,
+        opts: &RenderOpts,
+    ) -> Self {
+        let grouped_rows = groups
+            .iter()
+            .map(|entry| {
+                vec![
+                    entry.key.join(" / "),
+                    entry.connection_count.to_string(),
+                    display_upload_and_download(
+                        entry.total_bytes_uploaded,
+                        entry.total_bytes_downloaded,
+                        cumulative,
+                        opts,
+                    ),
+                ]
+            })
+            .collect();
+        let mut grouped_column_names = vec![
+            group_by.column_name(),
+            String::from("Connections"),
+            String::from(opts.capture_direction.bandwidth_label()),
+        ];
+        let mut breakpoints = BTreeMap::new();
+        breakpoints.insert(
+            0,
+            ColumnData {
+                column_count: ColumnCount::Two,
+                column_widths: vec![20, 23],
+            },
+        );
+        breakpoints.insert(
+            70,
+            ColumnData {
+                column_count: ColumnCount::Three,
+                column_widths: vec![30, 12, 23],
+            },
+        );
+        breakpoints.insert(
+            100,
+            ColumnData {
+                column_count: ColumnCount::Three,
+                column_widths: vec![60, 12, 23],
+            },
+        );
+        breakpoints.insert(
+            140,
+            ColumnData {
+                column_count: ColumnCount::Three,
+                column_widths: vec![100, 12, 23],
+            },
+        );
+        opts.sort_by.mark_columns(&mut grouped_column_names, 0, 2);
+        Table {
+            title: group_by.title(),
+            column_names: grouped_column_names,
+            rows: grouped_rows,
+            highlighted_rows: HashSet::new(),
+            tagged_rows: HashMap::new(),
+            row_bandwidths: groups
+                .iter()
+                .map(|entry| entry.total_bytes_uploaded + entry.total_bytes_downloaded)
+                .collect(),
+            use_color: !opts.no_color,
+            theme: opts.colors,
+            border: opts.border,
+            bars: false,
+            breakpoints,
+            focused: false,
+            scroll_offset: 0,
+            left_truncated_column: None,
+            pinned_row: false,
+            selected_row: None,
+            row_changes: HashMap::new(),
+            key_columns: 1,
+            sort_columns: name_and_bandwidth_sort_columns(),
+        }
+    }
+    pub fn create_listening_sockets_table(state: &UIState, opts: &RenderOpts) -> Self {
+        let listening_sockets = state.listening_socket_rows();
+        let listening_sockets_rows = listening_sockets
+            .iter()
+            .map(|(listening_socket, data)| {
+                vec![
+                    format!(
+                        "{} ({})",
+                        SocketAddr::new(listening_socket.ip, listening_socket.port),
+                        listening_socket.protocol
+                    ),
+                    data.connection_count.to_string(),
+                    display_upload_and_download(
+                        data.total_bytes_uploaded,
+                        data.total_bytes_downloaded,
+                        state.cumulative_mode,
+                        opts,
+                    ),
+                    data.process_name.clone(),
+                ]
+            })
+            .collect();
+        let listening_sockets_title = "Listening sockets";
+        let listening_sockets_column_names = vec![
+            String::from("Listening On"),
+            String::from("Connections"),
+            String::from(opts.capture_direction.bandwidth_label()),
+            String::from("Process"),
+        ];
+        let mut breakpoints = BTreeMap::new();
+        breakpoints.insert(
+            0,
+            ColumnData {
+                column_count: ColumnCount::Two,
+                column_widths: vec![20, 23],
+            },
+        );
+        breakpoints.insert(
+            70,
+            ColumnData {
+                column_count: ColumnCount::Three,
+                column_widths: vec![30, 12, 23],
+            },
+        );
+        breakpoints.insert(
+            100,
+            ColumnData {
+                column_count: ColumnCount::Four,
+                column_widths: vec![30, 12, 23, 20],
+            },
+        );
+        breakpoints.insert(
+            140,
+            ColumnData {
+                column_count: ColumnCount::Four,
+                column_widths: vec![50, 12, 23, 40],
+            },
+        );
+        Table {
+            title: String::from(listening_sockets_title),
+            column_names: listening_sockets_column_names,
+            rows: listening_sockets_rows,
+            highlighted_rows: HashSet::new(),
+            tagged_rows: HashMap::new(),
+            row_bandwidths: listening_sockets
+                .iter()
+                .map(|(_, data)| data.total_bytes_uploaded + data.total_bytes_downloaded)
+                .collect(),
+            use_color: !opts.no_color,
+            theme: opts.colors,
+            border: opts.border,
+            bars: false,
             breakpoints,
+            focused: false,
+            scroll_offset: 0,
+            left_truncated_column: None,
+            pinned_row: false,
+            selected_row: None,
+            row_changes: HashMap::new(),
+            key_columns: 1,
+            sort_columns: HashMap::new(),
+        }
+    }
+    pub fn create_unix_sockets_table(state: &UIState, opts: &RenderOpts) -> Self {
+        let unix_sockets_rows = state
+            .unix_sockets
+            .iter()
+            .map(|(path, data_for_path)| {
+                vec![
+                    path.to_string(),
+                    data_for_path.socket_count.to_string(),
+                    data_for_path
+                        .process_names
+                        .iter()
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ]
+            })
+            .collect();
+        let unix_sockets_title = "UNIX domain sockets (rates unavailable)";
+        let unix_sockets_column_names = vec![
+            String::from("Path"),
+            String::from("Sockets"),
+            String::from("Processes"),
+        ];
+        let mut breakpoints = BTreeMap::new();
+        breakpoints.insert(
+            0,
+            ColumnData {
+                column_count: ColumnCount::Two,
+                column_widths: vec![20, 23],
+            },
+        );
+        breakpoints.insert(
+            70,
+            ColumnData {
+                column_count: ColumnCount::Three,
+                column_widths: vec![30, 12, 23],
+            },
+        );
+        breakpoints.insert(
+            100,
+            ColumnData {
+                column_count: ColumnCount::Three,
+                column_widths: vec![50, 12, 33],
+            },
+        );
+        breakpoints.insert(
+            140,
+            ColumnData {
+                column_count: ColumnCount::Three,
+                column_widths: vec![70, 12, 53],
+            },
+        );
+        Table {
+            title: String::from(unix_sockets_title),
+            column_names: unix_sockets_column_names,
+            rows: unix_sockets_rows,
+            highlighted_rows: HashSet::new(),
+            tagged_rows: HashMap::new(),
+            row_bandwidths: Vec::new(),
+            use_color: !opts.no_color,
+            theme: opts.colors,
+            border: opts.border,
+            bars: false,
+            breakpoints,
+            focused: false,
+            scroll_offset: 0,
+            left_truncated_column: None,
+            pinned_row: false,
+            selected_row: None,
+            row_changes: HashMap::new(),
+            key_columns: 1,
+            sort_columns: HashMap::new(),
+        }
+    }
+    /// Adds a column of the share of the table's traffic each row has (see `--percent`), to the
+    /// tables with a rate, in all but the narrowest layout. The first column makes room for it
+    pub fn add_percentages(&mut self) {
+        if !self
+            .sort_columns
+            .values()
+            .any(|&sort_by| sort_by == SortBy::Total)
+        {
+            return;
+        }
+        let total: u128 = self.row_bandwidths.iter().sum();
+        let percentages = self
+            .row_bandwidths
+            .iter()
+            .map(|&bandwidth| {
+                let percent = if total == 0 {
+                    0.0
+                } else {
+                    bandwidth as f64 * 100.0 / total as f64
+                };
+                format!("{:.1}%", percent)
+            })
+            .collect();
+        self.add_column(PERCENT_COLUMN_NAME, percentages, PERCENT_COLUMN_WIDTH);
+    }
+    /// Shows "collecting..." instead of the rate of every row, as the header does on the first
+    /// tick (the tables without a rate stay as they are)
+    pub fn hide_rates(&mut self) {
+        let rate_columns: Vec<usize> = self
+            .sort_columns
+            .iter()
+            .filter(|(_, &sort_by)| sort_by == SortBy::Total)
+            .map(|(&index, _)| index)
+            .collect();
+        if rate_columns.is_empty() {
+            return;
+        }
+        for row in self.rows.iter_mut() {
+            for &index in rate_columns.iter() {
+                if let Some(cell) = row.get_mut(index) {
+                    *cell = String::from("collecting...");
+                }
+            }
+        }
+        self.bars = false;
+    }
+    /// Draws a bar of each row's rate after the last column (see `--bars`), in the tables with a
+    /// rate, as wide as what is left of the terminal then
+    pub fn add_bars(&mut self) {
+        self.bars = self
+            .sort_columns
+            .values()
+            .any(|&sort_by| sort_by == SortBy::Total);
+    }
+    /// Adds the share of its link's speed each interface uses up and down (see
+    /// `--interface-breakdown`), in all but the narrowest layout
+    pub fn add_link_utilization(&mut self, link_utilization: Vec<String>) {
+        self.add_column(
+            LINK_UTILIZATION_COLUMN_NAME,
+            link_utilization,
+            LINK_UTILIZATION_COLUMN_WIDTH,
+        );
+    }
+    // one cell per row, the first column makes room for it
+    fn add_column(&mut self, name: &str, cells: Vec<String>, width: u16) {
+        for (row, cell) in self.rows.iter_mut().zip(cells) {
+            row.push(cell);
+        }
+        self.column_names.push(String::from(name));
+        for column_data in self.breakpoints.values_mut() {
+            if let ColumnCount::Two = column_data.column_count {
+                continue;
+            }
+            let column_widths = &mut column_data.column_widths;
+            // the width and the gap after it
+            column_widths[0] = cmp::max(
+                column_widths[0].saturating_sub(width + 1),
+                MIN_FIRST_COLUMN_WIDTH,
+            );
+            column_widths.push(width);
+            column_data.column_count = ColumnCount::from_u16(column_widths.len() as u16);
+        }
+    }
+    /// Keeps only the first `count` rows (all of them if 0), with a last one telling how many
+    /// were left out
+    pub fn limit_rows(&mut self, count: usize) {
+        if count == 0 || self.rows.len() <= count {
+            return;
+        }
+        let left_out = self.rows.len() - count;
+        self.rows.truncate(count);
+        self.row_bandwidths.truncate(count);
+        let mut row = vec![String::new(); self.column_names.len()];
+        row[0] = format!("… and {} more", left_out);
+        self.rows.push(row);
+    }
+    /// The first cell of the row with the most traffic, None if there is no traffic at all
+    pub fn busiest_row(&self) -> Option<String> {
+        let (index, _) = self
+            .row_bandwidths
+            .iter()
+            .enumerate()
+            .filter(|&(_, &bandwidth)| bandwidth > 0)
+            // the first of the busiest rows, as they are shown
+            .min_by_key(|&(index, &bandwidth)| (cmp::Reverse(bandwidth), index))?;
+        Some(self.rows[index][0].clone())
+    }
+    /// Moves the row whose first cell is `first_cell` to the top and shows it in reverse, if it
+    /// is still there
+    pub fn pin_row(&mut self, first_cell: &str) {
+        let index = match self.rows.iter().position(|row| row[0] == first_cell) {
+            Some(index) => index,
+            None => return,
+        };
+        let row = self.rows.remove(index);
+        self.rows.insert(0, row);
+        if index < self.row_bandwidths.len() {
+            let bandwidth = self.row_bandwidths.remove(index);
+            self.row_bandwidths.insert(0, bandwidth);
+        }
+        self.highlighted_rows = self
+            .highlighted_rows
+            .iter()
+            .map(|&highlighted| match highlighted {
+                highlighted if highlighted == index => 0,
+                highlighted if highlighted < index => highlighted + 1,
+                highlighted => highlighted,
+            })
+            .collect();
+        self.tagged_rows = self
+            .tagged_rows
+            .iter()
+            .map(|(&tagged, &color)| match tagged {
+                tagged if tagged == index => (0, color),
+                tagged if tagged < index => (tagged + 1, color),
+                tagged => (tagged, color),
+            })
+            .collect();
+        self.pinned_row = true;
+    }
+    /// Moves the `pinned` rows (told apart by their first cell) to the top in that order, with a
+    /// blank row below them. The pinned rows that are not there anymore are shown as they were
+    /// last, with `idle_rate` for their rate
+    pub fn keep_on_top(&mut self, pinned: &[Vec<String>], idle_rate: &str) {
+        if pinned.is_empty() {
+            return;
+        }
+        let rate_column = self
+            .sort_columns
+            .iter()
+            .find(|&(_, &sort_by)| sort_by == SortBy::Total)
+            .map(|(&column, _)| column);
+        let row_bandwidths = &self.row_bandwidths;
+        let mut rows: Vec<(Vec<String>, u128)> = self
+            .rows
+            .drain(..)
+            .enumerate()
+            .map(|(index, row)| (row, row_bandwidths.get(index).copied().unwrap_or(0)))
+            .collect();
+        let percent_column = self
+            .column_names
+            .iter()
+            .position(|name| name == PERCENT_COLUMN_NAME);
+        let mut order = Vec::new();
+        for pinned_row in pinned {
+            let index = match rows.iter().position(|(row, _)| row[0] == pinned_row[0]) {
+                Some(index) => index,
+                None => {
+                    let mut idle_row = pinned_row.clone();
+                    // the columns may have changed since, eg. with the `t` key
+                    idle_row.resize(self.column_names.len(), String::new());
+                    if let Some(rate_column) = rate_column {
+                        idle_row[rate_column] = String::from(idle_rate);
+                    }
+                    if let Some(percent_column) = percent_column {
+                        idle_row[percent_column] = String::from("0.0%");
+                    }
+                    rows.push((idle_row, 0));
+                    rows.len() - 1
+                }
+            };
+            order.push(index);
         }
+        rows.push((vec![String::new(); self.column_names.len()], 0));
+        order.push(rows.len() - 1);
+        let unpinned = (0..rows.len())
+            .filter(|index| !order.contains(index))
+            .collect::<Vec<_>>();
+        order.extend(unpinned);
+        self.highlighted_rows = self
+            .highlighted_rows
+            .iter()
+            .filter_map(|&highlighted| order.iter().position(|&index| index == highlighted))
+            .collect();
+        self.tagged_rows = self
+            .tagged_rows
+            .iter()
+            .filter_map(|(&tagged, &color)| {
+                order
+                    .iter()
+                    .position(|&index| index == tagged)
+                    .map(|position| (position, color))
+            })
+            .collect();
+        self.row_bandwidths = order.iter().map(|&index| rows[index].1).collect();
+        self.rows = order.iter().map(|&index| rows[index].0.clone()).collect();
+    }
+    /// The row whose first cell is `first_cell`, if it is there
+    pub fn row(&self, first_cell: &str) -> Option<&Vec<String>> {
+        self.rows.iter().find(|row| row[0] == first_cell)
+    }
+    /// What tells the row whose first cell is `first_cell` apart, without its rates, eg. the
+    /// connection and its process
+    pub fn row_keys(&self, first_cell: &str) -> Option<String> {
+        self.row(first_cell)
+            .map(|row| row[..self.key_columns.min(row.len())].join("  "))
     }
-    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect) {
-        let mut column_spacing: u16 = 0;
+    /// Shows the row whose first cell is `first_cell` in reverse, if it is still there
+    pub fn select_row(&mut self, first_cell: &str) {
+        self.selected_row = self.rows.iter().position(|row| row[0] == first_cell);
+    }
+    /// The upload and download of every row with a rate, by its first cell
+    pub fn row_rates(&self) -> HashMap<String, u128> {
+        self.rows
+            .iter()
+            .zip(self.row_bandwidths.iter())
+            .map(|(row, &bandwidth)| (row[0].clone(), bandwidth))
+            .collect()
+    }
+    /// Marks the rows that were not in `previous_rates` (see `Table::row_rates`) and those whose
+    /// rate is not the same anymore
+    pub fn mark_changes(&mut self, previous_rates: &HashMap<String, u128>) {
+        self.row_changes = self
+            .rows
+            .iter()
+            .zip(self.row_bandwidths.iter())
+            .enumerate()
+            .filter_map(
+                |(index, (row, bandwidth))| match previous_rates.get(&row[0]) {
+                    None => Some((index, RowChange::Added)),
+                    Some(previous) if previous != bandwidth => Some((index, RowChange::Changed)),
+                    Some(_) => None,
+                },
+            )
+            .collect();
+    }
+    /// The first cell of the `row`th row, None past the last one
+    pub fn first_cell(&self, row: usize) -> Option<&String> {
+        self.rows.get(row).map(|row| &row[0])
+    }
+    /// What clicking the header of the `column`th column sorts by, None if it does not sort
+    pub fn sort_column(&self, column: usize) -> Option<SortBy> {
+        self.sort_columns.get(&column).copied()
+    }
+    /// Makes this the table the arrow keys scroll, starting `scroll_offset` rows down
+    pub fn focus(&mut self, scroll_offset: usize) {
+        self.focused = true;
+        self.scroll_offset = scroll_offset;
+    }
+    /// Returns where the table was drawn, see `TableArea`
+    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect) -> TableArea {
         let mut widths = &vec![];
         let mut column_count: &ColumnCount = &ColumnCount::Three;
 
@@ -229,47 +1256,175 @@ impl<'a> Table<'a> {
             if *width_breakpoint < rect.width {
                 widths = &column_data.column_widths;
                 column_count = &column_data.column_count;
-
-                let total_column_width: u16 = widths.iter().sum();
-                if rect.width < total_column_width - column_count.as_u16() {
-                    column_spacing = 0;
-                } else {
-                    column_spacing = (rect.width - total_column_width) / column_count.as_u16();
-                }
             }
         }
 
-        let column_names = match column_count {
-            ColumnCount::Two => {
-                vec![self.column_names[0], self.column_names[2]] // always lose the middle column when needed
-            }
-            ColumnCount::Three => vec![
-                self.column_names[0],
-                self.column_names[1],
-                self.column_names[2],
-            ],
+        let column_indices = column_count.column_indices();
+        let bordered = self.border != BorderStyle::None;
+        let (border_width, bottom_border) = if bordered { (2, 1) } else { (0, 0) };
+        // the first column holds the addresses, a long one (eg. IPv6) takes the room that would
+        // otherwise only go to spacing rather than being truncated
+        let mut widths = widths.clone();
+        let longest_first_cell = self
+            .rows
+            .iter()
+            .map(|row| row[column_indices[0]].chars().count() as u16)
+            .max()
+            .unwrap_or(0);
+        let spare_width = rect.width.saturating_sub(
+            // the borders and a gap after every column
+            widths.iter().sum::<u16>() + border_width + column_count.as_u16(),
+        );
+        if let Some(first_width) = widths.first_mut() {
+            *first_width += cmp::min(longest_first_cell.saturating_sub(*first_width), spare_width);
+        }
+        // what the columns left over, after a gap
+        let bar_width = rect
+            .width
+            .saturating_sub(widths.iter().sum::<u16>() + border_width + column_count.as_u16());
+        let bar_width = if self.bars && bar_width >= MIN_BAR_WIDTH {
+            widths.push(bar_width);
+            Some(bar_width)
+        } else {
+            None
+        };
+        let shown_columns = column_count.as_u16() + if bar_width.is_some() { 1 } else { 0 };
+        let total_column_width: u16 = widths.iter().sum();
+        let column_spacing = if rect.width < total_column_width - shown_columns {
+            0
+        } else {
+            rect.width.saturating_sub(total_column_width) / shown_columns
+        };
+        let bar_column_name = String::new();
+        let mut column_names: Vec<&String> = column_indices
+            .iter()
+            .map(|&index| &self.column_names[index])
+            .collect();
+        if bar_width.is_some() {
+            column_names.push(&bar_column_name);
+        }
+
+        // the top border (or the title), the header, the gap below it and the bottom border
+        let visible_rows = rect.height.saturating_sub(3 + bottom_border) as usize;
+        let scroll_offset = cmp::min(
+            self.scroll_offset,
+            self.rows.len().saturating_sub(visible_rows),
+        );
+        let title = if self.rows.len() > visible_rows {
+            format!(
+                "{} [{}-{} of {}]",
+                self.title,
+                scroll_offset + 1,
+                cmp::min(scroll_offset + visible_rows, self.rows.len()),
+                self.rows.len()
+            )
+        } else {
+            self.title.clone()
+        };
+        let title_style = if self.focused {
+            Style::default().modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+
+        let header_style = if self.use_color {
+            Style::default().fg(self.theme.header)
+        } else {
+            Style::default()
+        };
+        let border_style = if self.use_color {
+            Style::default().fg(self.theme.border)
+        } else {
+            Style::default()
         };
 
-        let rows = self.rows.iter().map(|row| match column_count {
-            ColumnCount::Two => vec![
-                truncate_middle(&row[0], widths[0]),
-                truncate_middle(&row[2], widths[1]),
-            ],
-            ColumnCount::Three => vec![
-                truncate_middle(&row[0], widths[0]),
-                truncate_middle(&row[1], widths[1]),
-                truncate_middle(&row[2], widths[2]),
-            ],
+        // colors are relative to the busiest row, so they scale with whatever the traffic is
+        let busiest_row_bandwidth = self.row_bandwidths.iter().copied().max().unwrap_or(0);
+        let rows = self.rows.iter().enumerate().map(|(row_index, row)| {
+            let mut cells = column_indices
+                .iter()
+                .zip(widths.iter())
+                .map(|(&index, &width)| {
+                    if self.left_truncated_column == Some(index) {
+                        truncate_left(&row[index], width)
+                    } else {
+                        truncate_middle(&row[index], width)
+                    }
+                })
+                .collect::<Vec<String>>();
+            if let Some(bar_width) = bar_width {
+                let bandwidth = self.row_bandwidths.get(row_index).copied().unwrap_or(0);
+                cells.push(display_bar(bandwidth, busiest_row_bandwidth, bar_width));
+            }
+            cells
         });
 
-        let table_rows = rows.map(|row| Row::StyledData(row.into_iter(), Style::default()));
+        let table_rows = rows.enumerate().skip(scroll_offset).map(|(index, row)| {
+            let throughput_color = match self.row_bandwidths.get(index) {
+                Some(&bandwidth) if self.use_color => {
+                    throughput_color(bandwidth, busiest_row_bandwidth, &self.theme)
+                }
+                _ => None,
+            };
+            let tag_color = self.tagged_rows.get(&index).filter(|_| self.use_color);
+            let style = if self.highlighted_rows.contains(&index) {
+                Style::default().fg(Color::Cyan).modifier(Modifier::BOLD)
+            } else if let Some(&color) = tag_color {
+                Style::default().fg(color)
+            } else if let Some(color) = throughput_color {
+                Style::default().fg(color)
+            } else {
+                Style::default()
+            };
+            let style = if (self.pinned_row && index == 0) || self.selected_row == Some(index) {
+                match self.theme.selected {
+                    Some(background) if self.use_color => style.bg(background),
+                    _ => style.modifier(Modifier::REVERSED),
+                }
+            } else {
+                style
+            };
+            let style = match self.row_changes.get(&index) {
+                Some(RowChange::Added) => {
+                    style.modifier(style.modifier | Modifier::BOLD | Modifier::UNDERLINED)
+                }
+                Some(RowChange::Changed) => style.modifier(style.modifier | Modifier::BOLD),
+                None => style,
+            };
+            Row::StyledData(row.into_iter(), style)
+        });
 
         ::tui::widgets::Table::new(column_names.into_iter(), table_rows)
-            .block(Block::default().title(self.title).borders(Borders::ALL))
-            .header_style(Style::default().fg(Color::Yellow))
+            .block(
+                Block::default()
+                    .title(&title)
+                    .title_style(title_style)
+                    .border_style(border_style)
+                    .borders(self.border.borders()),
+            )
+            .header_style(header_style)
             .widths(&widths[..])
             .style(Style::default())
             .column_spacing(column_spacing)
             .render(frame, rect);
+        BorderLines(self.border).render(frame, rect);
+        // the columns that fit inside the borders, as tui lays them out
+        let inner_width = rect.width.saturating_sub(border_width);
+        let mut columns = vec![];
+        let mut x = 0;
+        for (&index, &width) in column_indices.iter().zip(widths.iter()) {
+            if x + width >= inner_width {
+                break;
+            }
+            columns.push((index, rect.x + border_width / 2 + x, width));
+            x += width + column_spacing;
+        }
+        TableArea {
+            rect,
+            columns,
+            scroll_offset,
+            row_count: self.rows.len(),
+            bordered,
+        }
     }
 }
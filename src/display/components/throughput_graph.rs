@@ -0,0 +1,74 @@
+use ::std::collections::VecDeque;
+
+use ::tui::backend::Backend;
+use ::tui::layout::{Constraint, Direction, Rect};
+use ::tui::style::Style;
+use ::tui::terminal::Frame;
+use ::tui::widgets::{Block, Borders, Sparkline, Widget};
+
+use crate::display::{DataUnit, DisplayBandwidth, UnitPrefix};
+use crate::RenderOpts;
+
+/// How many ticks of total throughput are kept for the graph, a minute at the default interval
+pub const THROUGHPUT_HISTORY_LENGTH: usize = 60;
+/// The rows the graph takes, the borders and two rows of bars
+pub const THROUGHPUT_GRAPH_HEIGHT: u16 = 4;
+
+/// The total upload and download per second of the recent ticks (see `--graph`), one bar each
+pub struct ThroughputGraph<'a> {
+    /// Upload and download per second, oldest first
+    pub history: &'a VecDeque<(u128, u128)>,
+    pub opts: &'a RenderOpts,
+}
+
+impl<'a> ThroughputGraph<'a> {
+    pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect) {
+        let halves = ::tui::layout::Layout::default()
+            .direction(Direction::Horizontal)
+            .margin(0)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(rect);
+        // both directions share a scale, so they can be compared at a glance
+        let max = self
+            .history
+            .iter()
+            .map(|&(uploaded, downloaded)| uploaded.max(downloaded))
+            .max()
+            .unwrap_or(0)
+            .max(1) as u64;
+        let uploads: Vec<u64> = self.history.iter().map(|&(up, _)| up as u64).collect();
+        let downloads: Vec<u64> = self.history.iter().map(|&(_, down)| down as u64).collect();
+        let graphs = [
+            ("Up", uploads, self.opts.colors.middle, halves[0]),
+            ("Down", downloads, self.opts.colors.low, halves[1]),
+        ];
+        for (name, data, color, half) in graphs.iter() {
+            // as many of the most recent ticks as fit between the borders
+            let shown = (half.width.saturating_sub(2) as usize).min(data.len());
+            let data = &data[data.len() - shown..];
+            let current = data.last().cloned().unwrap_or(0);
+            let title = format!(
+                "{} {}",
+                name,
+                DisplayBandwidth {
+                    bandwidth: current as f64,
+                    as_rate: true,
+                    exact: self.opts.exact_bytes,
+                    unit: DataUnit::from_opts(self.opts),
+                    prefix: UnitPrefix::from_opts(self.opts),
+                }
+            );
+            let style = if self.opts.no_color {
+                Style::default()
+            } else {
+                Style::default().fg(*color)
+            };
+            Sparkline::default()
+                .block(Block::default().title(&title).borders(Borders::ALL))
+                .data(data)
+                .max(max)
+                .style(style)
+                .render(frame, *half);
+        }
+    }
+}
@@ -1,41 +1,89 @@
 use ::tui::backend::Backend;
 use ::tui::layout::{Alignment, Rect};
-use ::tui::style::{Color, Modifier, Style};
+use ::tui::style::{Modifier, Style};
 use ::tui::terminal::Frame;
 use ::tui::widgets::{Paragraph, Text, Widget};
 
-use crate::display::{DisplayBandwidth, UIState};
+use crate::display::{display_upload_and_download, Snapshot};
+use crate::RenderOpts;
 
 pub struct TotalBandwidth<'a> {
-    pub state: &'a UIState,
+    pub snapshot: &'a Snapshot,
     pub paused: bool,
+    /// The tables stay as they are while the capture goes on, see the `f` key
+    pub frozen: bool,
+    pub warming_up: bool,
+    /// Whether new addresses get resolved to hostnames, see the `r` key
+    pub resolving: bool,
+    pub host_only_interfaces: &'a [String],
+    /// How many rows the tables leave out, see --min-rate
+    pub hidden_rows: usize,
+    pub opts: &'a RenderOpts,
 }
 
 impl<'a> TotalBandwidth<'a> {
     pub fn render(&self, frame: &mut Frame<impl Backend>, rect: Rect) {
-        let c_mode = self.state.cumulative_mode;
+        let c_mode = self.snapshot.cumulative;
         let title_text = {
-            let paused_str = if self.paused { "[PAUSED]" } else { "" };
-            let color = if self.paused {
-                Color::Yellow
+            let paused_str = if self.paused {
+                "[PAUSED]"
+            } else if self.frozen {
+                "[FROZEN]"
             } else {
-                Color::Green
+                ""
+            };
+            let style = if self.opts.no_color {
+                Style::default()
+            } else if self.paused || self.frozen {
+                Style::default().fg(self.opts.colors.middle)
+            } else {
+                Style::default().fg(self.opts.colors.low)
+            };
+
+            let totals = if self.warming_up {
+                // the first interval only covers the time since launch, its rates mean nothing
+                String::from("collecting...")
+            } else {
+                display_upload_and_download(
+                    self.snapshot.total_bytes_uploaded,
+                    self.snapshot.total_bytes_downloaded,
+                    c_mode,
+                    self.opts,
+                )
+            };
+
+            let capture_mode = if self.host_only_interfaces.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    " [host traffic only: {}]",
+                    self.host_only_interfaces.join(", ")
+                )
+            };
+
+            let resolving = if self.resolving {
+                ""
+            } else {
+                " [not resolving hostnames]"
+            };
+
+            let hidden_rows = if self.hidden_rows == 0 {
+                String::new()
+            } else {
+                format!(" [rows hidden below --min-rate: {}]", self.hidden_rows)
             };
 
             [Text::styled(
                 format!(
-                    " Total Up / Down: {} / {} {}",
-                    DisplayBandwidth {
-                        bandwidth: self.state.total_bytes_uploaded as f64,
-                        as_rate: !c_mode,
-                    },
-                    DisplayBandwidth {
-                        bandwidth: self.state.total_bytes_downloaded as f64,
-                        as_rate: !c_mode,
-                    },
-                    paused_str
+                    " Total {}: {} {}{}{}{}",
+                    self.opts.capture_direction.bandwidth_label(),
+                    totals,
+                    paused_str,
+                    capture_mode,
+                    resolving,
+                    hidden_rows
                 ),
-                Style::default().fg(color).modifier(Modifier::BOLD),
+                style.modifier(Modifier::BOLD),
             )]
         };
         Paragraph::new(title_text.iter())
@@ -0,0 +1,70 @@
+use ::std::fs::File;
+use ::std::io::{self, BufWriter, Write};
+use ::std::path::{Path, PathBuf};
+
+use crate::display::Snapshot;
+
+/// The rates of every tick as CSV rows, for a spreadsheet (see `--csv-history`).
+///
+/// A row of the total upload and download per tick, or with `per_process` a row per process per
+/// tick (the long format, since the processes come and go). The file starts over with its header
+/// at every run and is flushed after every tick, so it is complete up to the last one.
+pub struct CsvHistory {
+    path: PathBuf,
+    per_process: bool,
+    file: BufWriter<File>,
+}
+
+// a field with a comma, a quote or a line break in it is quoted, the quotes in it doubled
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+impl CsvHistory {
+    pub fn create(path: PathBuf, per_process: bool) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(&path)?);
+        if per_process {
+            writeln!(file, "timestamp,process,up,down")?;
+        } else {
+            writeln!(file, "timestamp,up,down")?;
+        }
+        file.flush()?;
+        Ok(CsvHistory {
+            path,
+            per_process,
+            file,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The rows of one tick, timestamped in RFC3339
+    pub fn write_tick(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        let timestamp = snapshot.timestamp.to_rfc3339();
+        if self.per_process {
+            for process in &snapshot.processes {
+                writeln!(
+                    self.file,
+                    "{},{},{},{}",
+                    timestamp,
+                    csv_field(&process.name),
+                    process.total_bytes_uploaded,
+                    process.total_bytes_downloaded
+                )?;
+            }
+        } else {
+            writeln!(
+                self.file,
+                "{},{},{}",
+                timestamp, snapshot.total_bytes_uploaded, snapshot.total_bytes_downloaded
+            )?;
+        }
+        self.file.flush()
+    }
+}
@@ -0,0 +1,32 @@
+use ::std::collections::VecDeque;
+
+use chrono::prelude::*;
+
+static MAX_LOG_EVENTS: usize = 100;
+
+pub struct LogEvent {
+    pub time: DateTime<Local>,
+    pub message: String,
+}
+
+#[derive(Default)]
+pub struct EventLog {
+    events: VecDeque<LogEvent>,
+}
+
+impl EventLog {
+    pub fn push(&mut self, message: String) {
+        self.events.push_back(LogEvent {
+            time: Local::now(),
+            message,
+        });
+        if self.events.len() > MAX_LOG_EVENTS {
+            self.events.pop_front();
+        }
+    }
+    pub fn latest(&self, count: usize) -> impl Iterator<Item = &LogEvent> {
+        self.events
+            .iter()
+            .skip(self.events.len().saturating_sub(count))
+    }
+}
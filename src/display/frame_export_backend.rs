@@ -0,0 +1,233 @@
+// a backend that renders into a file instead of a terminal
+//
+// every flush overwrites the file with the current frame, so once the app is done
+// the file holds the last frame that was drawn - either as an SVG image (if the path
+// ends with .svg) or as text with ANSI color codes that can be shown with eg. `cat`
+use ::std::fs::File;
+use ::std::io::{self, Write};
+use ::std::path::PathBuf;
+
+use ::tui::backend::Backend;
+use ::tui::buffer::Cell;
+use ::tui::layout::Rect;
+use ::tui::style::{Color, Modifier, Style};
+
+const SVG_CELL_WIDTH: usize = 9;
+const SVG_CELL_HEIGHT: usize = 18;
+const SVG_FONT_SIZE: usize = 15;
+const SVG_BACKGROUND: &str = "#1d1f21";
+const SVG_FOREGROUND: &str = "#c5c8c6";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrameFormat {
+    Svg,
+    Ansi,
+}
+
+pub struct FrameExportBackend {
+    path: PathBuf,
+    format: FrameFormat,
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+}
+
+impl FrameExportBackend {
+    pub fn new(path: PathBuf, width: u16, height: u16) -> io::Result<Self> {
+        // fail early rather than on the first frame if the path is not writable
+        File::create(&path)?;
+        let format = match path.extension() {
+            Some(extension) if extension == "svg" => FrameFormat::Svg,
+            _ => FrameFormat::Ansi,
+        };
+        Ok(FrameExportBackend {
+            path,
+            format,
+            width,
+            height,
+            cells: vec![Cell::default(); width as usize * height as usize],
+        })
+    }
+    fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.cells.chunks(self.width.max(1) as usize)
+    }
+    fn style_runs(row: &[Cell]) -> Vec<(Style, String)> {
+        let mut runs: Vec<(Style, String)> = Vec::new();
+        for cell in row {
+            match runs.last_mut() {
+                Some((style, text)) if *style == cell.style => text.push_str(&cell.symbol),
+                _ => runs.push((cell.style, cell.symbol.clone())),
+            }
+        }
+        runs
+    }
+    pub fn to_svg(&self) -> String {
+        let width = self.width as usize * SVG_CELL_WIDTH;
+        let height = self.height as usize * SVG_CELL_HEIGHT;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\" font-size=\"{}\" xml:space=\"preserve\">\n",
+            width, height, SVG_FONT_SIZE
+        );
+        svg.push_str(&format!(
+            "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+            SVG_BACKGROUND
+        ));
+        for (y, row) in self.rows().enumerate() {
+            svg.push_str(&format!(
+                "<text x=\"0\" y=\"{}\" textLength=\"{}\" fill=\"{}\">",
+                (y + 1) * SVG_CELL_HEIGHT - SVG_CELL_HEIGHT / 4,
+                width,
+                SVG_FOREGROUND
+            ));
+            for (style, text) in FrameExportBackend::style_runs(row) {
+                let mut attributes = String::new();
+                if let Some(color) = svg_color(style.fg) {
+                    attributes.push_str(&format!(" fill=\"{}\"", color));
+                }
+                if style.modifier.contains(Modifier::BOLD) {
+                    attributes.push_str(" font-weight=\"bold\"");
+                }
+                if style.modifier.contains(Modifier::UNDERLINED) {
+                    attributes.push_str(" text-decoration=\"underline\"");
+                }
+                svg.push_str(&format!(
+                    "<tspan{}>{}</tspan>",
+                    attributes,
+                    escape_xml(&text)
+                ));
+            }
+            svg.push_str("</text>\n");
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+    pub fn to_ansi(&self) -> String {
+        let mut ansi = String::new();
+        for row in self.rows() {
+            for (style, text) in FrameExportBackend::style_runs(row) {
+                let mut codes = Vec::new();
+                if style.modifier.contains(Modifier::BOLD) {
+                    codes.push(String::from("1"));
+                }
+                if style.modifier.contains(Modifier::UNDERLINED) {
+                    codes.push(String::from("4"));
+                }
+                if let Some(code) = ansi_color(style.fg) {
+                    codes.push(code);
+                }
+                if codes.is_empty() {
+                    ansi.push_str(&text);
+                } else {
+                    ansi.push_str(&format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text));
+                }
+            }
+            ansi.push('\n');
+        }
+        ansi
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn svg_color(color: Color) -> Option<String> {
+    let color = match color {
+        Color::Reset => return None,
+        Color::Black => "#1d1f21",
+        Color::Red => "#cc6666",
+        Color::Green => "#b5bd68",
+        Color::Yellow => "#f0c674",
+        Color::Blue => "#81a2be",
+        Color::Magenta => "#b294bb",
+        Color::Cyan => "#8abeb7",
+        Color::Gray => "#c5c8c6",
+        Color::DarkGray => "#969896",
+        Color::LightRed => "#ff3334",
+        Color::LightGreen => "#9ec400",
+        Color::LightYellow => "#ffff66",
+        Color::LightBlue => "#6fa8dc",
+        Color::LightMagenta => "#d291e4",
+        Color::LightCyan => "#70c0b1",
+        Color::White => "#ffffff",
+        Color::Rgb(r, g, b) => return Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+        Color::Indexed(_) => return None,
+    };
+    Some(String::from(color))
+}
+
+fn ansi_color(color: Color) -> Option<String> {
+    let code = match color {
+        Color::Reset => return None,
+        Color::Black => 30,
+        Color::Red => 31,
+        Color::Green => 32,
+        Color::Yellow => 33,
+        Color::Blue => 34,
+        Color::Magenta => 35,
+        Color::Cyan => 36,
+        Color::Gray => 37,
+        Color::DarkGray => 90,
+        Color::LightRed => 91,
+        Color::LightGreen => 92,
+        Color::LightYellow => 93,
+        Color::LightBlue => 94,
+        Color::LightMagenta => 95,
+        Color::LightCyan => 96,
+        Color::White => 97,
+        Color::Rgb(r, g, b) => return Some(format!("38;2;{};{};{}", r, g, b)),
+        Color::Indexed(index) => return Some(format!("38;5;{}", index)),
+    };
+    Some(code.to_string())
+}
+
+impl Backend for FrameExportBackend {
+    fn clear(&mut self) -> io::Result<()> {
+        for cell in self.cells.iter_mut() {
+            cell.reset();
+        }
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn get_cursor(&mut self) -> io::Result<(u16, u16)> {
+        Ok((0, 0))
+    }
+
+    fn set_cursor(&mut self, _x: u16, _y: u16) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        for (x, y, cell) in content {
+            if x < self.width && y < self.height {
+                self.cells[y as usize * self.width as usize + x as usize] = cell.clone();
+            }
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> io::Result<Rect> {
+        Ok(Rect::new(0, 0, self.width, self.height))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let frame = match self.format {
+            FrameFormat::Svg => self.to_svg(),
+            FrameFormat::Ansi => self.to_ansi(),
+        };
+        File::create(&self.path)?.write_all(frame.as_bytes())
+    }
+}
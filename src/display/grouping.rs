@@ -0,0 +1,161 @@
+use ::std::collections::HashMap;
+use ::std::str::FromStr;
+
+use crate::display::{ConnectionEntry, Snapshot, SortBy};
+use crate::network::display_ip_or_host;
+use crate::network::dns::registrable_domain;
+
+// what the addresses that were never resolved (eg. with --no-resolve) are grouped under
+const UNRESOLVED_DOMAIN: &str = "unresolved";
+// what the sockets no user was found for (eg. of a merged connection) are grouped under
+const UNKNOWN_USER: &str = "<UNKNOWN>";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupDimension {
+    Interface,
+    Process,
+    RemoteAddress,
+    RemoteDomain,
+    RemotePort,
+    Protocol,
+    User,
+}
+
+impl GroupDimension {
+    pub fn name(self) -> &'static str {
+        match self {
+            GroupDimension::Interface => "Interface",
+            GroupDimension::Process => "Process",
+            GroupDimension::RemoteAddress => "Remote Address",
+            GroupDimension::RemoteDomain => "Remote Domain",
+            GroupDimension::RemotePort => "Remote Port",
+            GroupDimension::Protocol => "Protocol",
+            GroupDimension::User => "User",
+        }
+    }
+    fn key_of(self, entry: &ConnectionEntry, snapshot: &Snapshot) -> String {
+        match self {
+            GroupDimension::Interface => entry.interface_name.clone(),
+            GroupDimension::Process => entry.process_name.clone(),
+            GroupDimension::RemoteAddress => {
+                display_ip_or_host(entry.connection.remote_socket.ip, &snapshot.ip_to_host)
+            }
+            GroupDimension::RemoteDomain => {
+                match snapshot.ip_to_host.get(&entry.connection.remote_socket.ip) {
+                    Some(host) => registrable_domain(host),
+                    None => String::from(UNRESOLVED_DOMAIN),
+                }
+            }
+            GroupDimension::RemotePort => entry.connection.remote_socket.port.to_string(),
+            GroupDimension::Protocol => entry.connection.local_socket.protocol.to_string(),
+            GroupDimension::User => entry
+                .user
+                .clone()
+                .unwrap_or_else(|| String::from(UNKNOWN_USER)),
+        }
+    }
+}
+
+impl FromStr for GroupDimension {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "interface" => Ok(GroupDimension::Interface),
+            "process" => Ok(GroupDimension::Process),
+            "remote-address" => Ok(GroupDimension::RemoteAddress),
+            "remote-domain" => Ok(GroupDimension::RemoteDomain),
+            "remote-port" => Ok(GroupDimension::RemotePort),
+            "protocol" => Ok(GroupDimension::Protocol),
+            "user" => Ok(GroupDimension::User),
+            other => Err(format!(
+                "unknown grouping '{}', expected interface, process, remote-address, remote-domain, remote-port, protocol or user",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupEntry {
+    pub key: Vec<String>,
+    pub connection_count: u128,
+    pub total_bytes_uploaded: u128,
+    pub total_bytes_downloaded: u128,
+}
+
+/// The composite key the connections of a snapshot are aggregated by, eg. `interface,process`.
+///
+/// Without it the usual process, remote address and connection tables are shown.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupBy {
+    pub dimensions: Vec<GroupDimension>,
+}
+
+impl GroupBy {
+    pub fn title(&self) -> String {
+        let names: Vec<String> = self
+            .dimensions
+            .iter()
+            .map(|dimension| dimension.name().to_lowercase())
+            .collect();
+        format!("Utilization by {}", names.join(", "))
+    }
+    pub fn column_name(&self) -> String {
+        let names: Vec<&str> = self
+            .dimensions
+            .iter()
+            .map(|dimension| dimension.name())
+            .collect();
+        names.join(" / ")
+    }
+    pub fn group(&self, snapshot: &Snapshot, sort_by: SortBy) -> Vec<GroupEntry> {
+        let mut groups: HashMap<Vec<String>, GroupEntry> = HashMap::new();
+        for entry in &snapshot.connections {
+            let key: Vec<String> = self
+                .dimensions
+                .iter()
+                .map(|dimension| dimension.key_of(entry, snapshot))
+                .collect();
+            let group = groups.entry(key.clone()).or_insert_with(|| GroupEntry {
+                key,
+                connection_count: 0,
+                total_bytes_uploaded: 0,
+                total_bytes_downloaded: 0,
+            });
+            group.connection_count += 1;
+            group.total_bytes_uploaded += entry.total_bytes_uploaded;
+            group.total_bytes_downloaded += entry.total_bytes_downloaded;
+        }
+        let mut groups: Vec<GroupEntry> = groups.into_values().collect();
+        groups.sort_by(|a, b| {
+            let a_bandwidth = a.total_bytes_uploaded + a.total_bytes_downloaded;
+            let b_bandwidth = b.total_bytes_uploaded + b.total_bytes_downloaded;
+            sort_by
+                .compare(
+                    (&a.key, a.total_bytes_uploaded, a.total_bytes_downloaded),
+                    (&b.key, b.total_bytes_uploaded, b.total_bytes_downloaded),
+                )
+                .then_with(|| b_bandwidth.cmp(&a_bandwidth))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        groups
+    }
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut dimensions = Vec::new();
+        for dimension in s.split(',') {
+            let dimension: GroupDimension = dimension.parse()?;
+            if dimensions.contains(&dimension) {
+                return Err(format!(
+                    "'{}' is grouped by more than once",
+                    dimension.name()
+                ));
+            }
+            dimensions.push(dimension);
+        }
+        Ok(GroupBy { dimensions })
+    }
+}
@@ -0,0 +1,34 @@
+use ::std::str::FromStr;
+
+/// A name to show an interface by instead of its own, eg. `enp0s31f6=lan` (see --alias)
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterfaceAlias {
+    pub name: String,
+    pub alias: String,
+}
+
+impl FromStr for InterfaceAlias {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.find('=') {
+            Some(equals) if equals > 0 && equals + 1 < s.len() => Ok(InterfaceAlias {
+                name: String::from(&s[..equals]),
+                alias: String::from(&s[equals + 1..]),
+            }),
+            _ => Err(format!(
+                "invalid alias '{}', expected an interface and the name to show it by, eg. enp0s31f6=lan",
+                s
+            )),
+        }
+    }
+}
+
+/// What the interface called `name` is shown as, its own name unless it has an alias
+pub fn display_interface_name(name: &str, aliases: &[InterfaceAlias]) -> String {
+    aliases
+        .iter()
+        .rev()
+        .find(|alias| alias.name == name)
+        .map_or(name, |alias| alias.alias.as_str())
+        .to_string()
+}
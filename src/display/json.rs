@@ -0,0 +1,389 @@
+use ::std::net::IpAddr;
+
+use ::serde::Serialize;
+
+use crate::display::{DataUnit, SessionTotals, Snapshot};
+use crate::network::{Connection, LocalSocket};
+use crate::os::StartupError;
+
+/// The version of the schema of the JSON output (see --json). Fields are only ever added within a
+/// version, renaming or removing one (or changing what it means) makes a new one
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Serializes a line of the JSON output, which only holds strings, numbers and booleans
+pub fn json_line(line: &impl Serialize) -> String {
+    ::serde_json::to_string(line).expect("a line of the JSON output cannot fail to serialize")
+}
+
+/// Upload and download in the unit of the output: `up_bytes` and `down_bytes`, `up_bits` and
+/// `down_bits` with --bits or `up_packets` and `down_packets` with --packets. None of them are
+/// there where there is no traffic to tell of
+#[derive(Default, Serialize)]
+pub struct JsonTraffic {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    up_bytes: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    down_bytes: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    up_bits: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    down_bits: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    up_packets: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    down_packets: Option<u128>,
+}
+
+impl JsonTraffic {
+    pub fn new(unit: DataUnit, uploaded: u128, downloaded: u128) -> Self {
+        let (up, down) = (Some(unit.convert(uploaded)), Some(unit.convert(downloaded)));
+        match unit {
+            DataUnit::Bytes => JsonTraffic {
+                up_bytes: up,
+                down_bytes: down,
+                ..Default::default()
+            },
+            DataUnit::Bits => JsonTraffic {
+                up_bits: up,
+                down_bits: down,
+                ..Default::default()
+            },
+            DataUnit::Packets => JsonTraffic {
+                up_packets: up,
+                down_packets: down,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct JsonLocalSocket {
+    pub ip: IpAddr,
+    pub port: u16,
+    pub protocol: String,
+}
+
+impl JsonLocalSocket {
+    pub fn new(local_socket: &LocalSocket) -> Self {
+        JsonLocalSocket {
+            ip: local_socket.ip,
+            port: local_socket.port,
+            protocol: local_socket.protocol.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct JsonRemoteSocket<'a> {
+    pub ip: IpAddr,
+    pub port: u16,
+    pub host: Option<&'a str>,
+}
+
+/// A connection of the JSON output, in the ticks and in --once alike
+#[derive(Serialize)]
+pub struct JsonConnection<'a> {
+    /// None where the interface is not known, eg. of the connections --once lists
+    pub interface: Option<&'a str>,
+    pub local_socket: JsonLocalSocket,
+    pub remote_socket: JsonRemoteSocket<'a>,
+    pub process: &'a str,
+    #[serde(flatten)]
+    pub traffic: JsonTraffic,
+}
+
+impl<'a> JsonConnection<'a> {
+    pub fn new(
+        connection: &Connection,
+        interface: Option<&'a str>,
+        host: Option<&'a str>,
+        process: &'a str,
+        traffic: JsonTraffic,
+    ) -> Self {
+        JsonConnection {
+            interface,
+            local_socket: JsonLocalSocket::new(&connection.local_socket),
+            remote_socket: JsonRemoteSocket {
+                ip: connection.remote_socket.ip,
+                port: connection.remote_socket.port,
+                host,
+            },
+            process,
+            traffic,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonSession {
+    #[serde(flatten)]
+    traffic: JsonTraffic,
+    seconds: u64,
+}
+
+#[derive(Serialize)]
+struct JsonProcess<'a> {
+    name: &'a str,
+    #[serde(flatten)]
+    traffic: JsonTraffic,
+    connections: u128,
+}
+
+#[derive(Serialize)]
+struct JsonRemoteAddress<'a> {
+    ip: IpAddr,
+    host: Option<&'a str>,
+    #[serde(flatten)]
+    traffic: JsonTraffic,
+    connections: u128,
+}
+
+#[derive(Serialize)]
+struct JsonTickLine<'a> {
+    schema_version: u32,
+    elapsed_seconds: u64,
+    cumulative: bool,
+    session: JsonSession,
+    processes: Vec<JsonProcess<'a>>,
+    connections: Vec<JsonConnection<'a>>,
+    remote_addresses: Vec<JsonRemoteAddress<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonListeningSocket<'a> {
+    #[serde(flatten)]
+    socket: JsonLocalSocket,
+    process: &'a str,
+}
+
+#[derive(Serialize)]
+struct JsonOpenConnectionsLine<'a> {
+    schema_version: u32,
+    connections: Vec<JsonConnection<'a>>,
+    listening_sockets: Vec<JsonListeningSocket<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonInterface<'a> {
+    name: &'a str,
+    captured: bool,
+    promiscuous: bool,
+    reason: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonCapabilitiesLine<'a> {
+    schema_version: u32,
+    version: &'a str,
+    platform: &'a str,
+    open_sockets: &'a str,
+    dns: bool,
+    interfaces: Vec<JsonInterface<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonError<'a> {
+    code: &'a str,
+    message: &'a str,
+    hint: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonErrorLine<'a> {
+    schema_version: u32,
+    error: JsonError<'a>,
+}
+
+// a tick of --no-tables
+#[derive(Serialize)]
+struct JsonTotalsLine {
+    schema_version: u32,
+    elapsed_seconds: u64,
+    cumulative: bool,
+    #[serde(flatten)]
+    traffic: JsonTraffic,
+}
+
+/// A line of the JSON output, written straight from the snapshot the tables are drawn from so the
+/// two cannot drift apart. Every key of it is always there, so consumers do not have to tell an
+/// empty table from a missing one
+pub struct JsonTick<'a> {
+    pub snapshot: &'a Snapshot,
+    pub session_totals: &'a SessionTotals,
+    pub elapsed_seconds: u64,
+    pub unit: DataUnit,
+    /// How many rows of each table are written, see --count
+    pub count: usize,
+    /// Only the totals are written, see --no-tables
+    pub totals_only: bool,
+}
+
+impl<'a> JsonTick<'a> {
+    pub fn to_json(&self) -> String {
+        let snapshot = self.snapshot;
+        let unit = self.unit;
+        let host = |ip: &IpAddr| snapshot.ip_to_host.get(ip).map(String::as_str);
+        if self.totals_only {
+            return json_line(&JsonTotalsLine {
+                schema_version: JSON_SCHEMA_VERSION,
+                elapsed_seconds: self.elapsed_seconds,
+                cumulative: snapshot.cumulative,
+                traffic: JsonTraffic::new(
+                    unit,
+                    snapshot.total_bytes_uploaded,
+                    snapshot.total_bytes_downloaded,
+                ),
+            });
+        }
+        let processes = snapshot
+            .processes
+            .iter()
+            .take(self.count)
+            .map(|process| JsonProcess {
+                name: &process.name,
+                traffic: JsonTraffic::new(
+                    unit,
+                    process.total_bytes_uploaded,
+                    process.total_bytes_downloaded,
+                ),
+                connections: process.connection_count,
+            })
+            .collect();
+        let connections = snapshot
+            .connections
+            .iter()
+            .take(self.count)
+            .map(|entry| {
+                JsonConnection::new(
+                    &entry.connection,
+                    Some(entry.interface_name.as_str()),
+                    host(&entry.connection.remote_socket.ip),
+                    &entry.process_name,
+                    JsonTraffic::new(
+                        unit,
+                        entry.total_bytes_uploaded,
+                        entry.total_bytes_downloaded,
+                    ),
+                )
+            })
+            .collect();
+        let remote_addresses = snapshot
+            .remote_addresses
+            .iter()
+            .take(self.count)
+            .map(|entry| JsonRemoteAddress {
+                ip: entry.ip,
+                host: host(&entry.ip),
+                traffic: JsonTraffic::new(
+                    unit,
+                    entry.total_bytes_uploaded,
+                    entry.total_bytes_downloaded,
+                ),
+                connections: entry.connection_count,
+            })
+            .collect();
+        json_line(&JsonTickLine {
+            schema_version: JSON_SCHEMA_VERSION,
+            elapsed_seconds: self.elapsed_seconds,
+            cumulative: snapshot.cumulative,
+            session: JsonSession {
+                traffic: JsonTraffic::new(
+                    unit,
+                    self.session_totals.bytes_uploaded,
+                    self.session_totals.bytes_downloaded,
+                ),
+                seconds: self.session_totals.elapsed.as_secs(),
+            },
+            processes,
+            connections,
+            remote_addresses,
+        })
+    }
+}
+
+/// The line --once writes with --json. Its connections are those of the ticks, only there is no
+/// traffic to tell of yet and neither the interface nor the hostname are looked up
+pub struct JsonOpenConnections<'a> {
+    pub connections: &'a [(Connection, String)],
+    pub listening_sockets: &'a [(LocalSocket, String)],
+}
+
+impl<'a> JsonOpenConnections<'a> {
+    pub fn to_json(&self) -> String {
+        json_line(&JsonOpenConnectionsLine {
+            schema_version: JSON_SCHEMA_VERSION,
+            connections: self
+                .connections
+                .iter()
+                .map(|(connection, process_name)| {
+                    JsonConnection::new(
+                        connection,
+                        None,
+                        None,
+                        process_name,
+                        JsonTraffic::default(),
+                    )
+                })
+                .collect(),
+            listening_sockets: self
+                .listening_sockets
+                .iter()
+                .map(|(listening_socket, process_name)| JsonListeningSocket {
+                    socket: JsonLocalSocket::new(listening_socket),
+                    process: process_name,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// The line --print-capabilities writes with --json
+pub struct JsonCapabilities<'a> {
+    pub version: &'a str,
+    pub platform: &'a str,
+    pub open_sockets: &'a str,
+    pub dns: bool,
+    /// Per interface: its name, whether it is captured, whether in promiscuous mode and why it is
+    /// not captured
+    pub interfaces: &'a [(&'a str, bool, bool, Option<&'a str>)],
+}
+
+impl<'a> JsonCapabilities<'a> {
+    pub fn to_json(&self) -> String {
+        json_line(&JsonCapabilitiesLine {
+            schema_version: JSON_SCHEMA_VERSION,
+            version: self.version,
+            platform: self.platform,
+            open_sockets: self.open_sockets,
+            dns: self.dns,
+            interfaces: self
+                .interfaces
+                .iter()
+                .map(|&(name, captured, promiscuous, reason)| JsonInterface {
+                    name,
+                    captured,
+                    promiscuous,
+                    reason,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// The line an error ending the run is printed as with --json, instead of the ticks
+pub fn display_json_error(err: &failure::Error) -> String {
+    let message = err.to_string();
+    let (code, hint) = match err.downcast_ref::<StartupError>() {
+        Some(startup_error) => (startup_error.code(), Some(startup_error.hint())),
+        None => ("other", None),
+    };
+    json_line(&JsonErrorLine {
+        schema_version: JSON_SCHEMA_VERSION,
+        error: JsonError {
+            code,
+            message: message.trim(),
+            hint,
+        },
+    })
+}
@@ -0,0 +1,94 @@
+use ::std::env;
+use ::std::fs;
+use ::std::io;
+use ::std::path::{Path, PathBuf};
+
+use crate::display::SortBy;
+
+/// `$XDG_STATE_HOME/bandwhich/layout`, or `~/.local/state/bandwhich/layout` without it
+pub fn default_layout_path() -> Option<PathBuf> {
+    let state_home = match env::var_os("XDG_STATE_HOME") {
+        Some(state_home) if !state_home.is_empty() => PathBuf::from(state_home),
+        _ => PathBuf::from(env::var_os("HOME")?)
+            .join(".local")
+            .join("state"),
+    };
+    Some(state_home.join("bandwhich").join("layout"))
+}
+
+/// How the tables were left at the end of the last run, kept in a file between runs (see
+/// `--layout-file`). Unlike the config file this is not options, but what the keys changed.
+///
+/// Every line holds a name and a value, eg. `sort_by download`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayoutState {
+    pub sort_by: SortBy,
+    pub focused_table: usize,
+    pub session_bytes: bool,
+    pub connection_history: bool,
+}
+
+impl LayoutState {
+    /// `layout` with what the file at `path` remembers, all of it if there is no such file. The
+    /// lines that cannot be read are left out, they are written again on the next save
+    pub fn load(path: &Path, layout: LayoutState) -> io::Result<LayoutState> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(layout),
+            Err(err) => return Err(err),
+        };
+        let mut layout = layout;
+        for line in contents.lines() {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next()) {
+                (Some("sort_by"), Some(value)) => {
+                    if let Ok(sort_by) = value.parse() {
+                        layout.sort_by = sort_by;
+                    }
+                }
+                (Some("focused_table"), Some(value)) => {
+                    if let Ok(focused_table) = value.parse() {
+                        layout.focused_table = focused_table;
+                    }
+                }
+                (Some("session_bytes"), Some(value)) => {
+                    if let Ok(session_bytes) = value.parse() {
+                        layout.session_bytes = session_bytes;
+                    }
+                }
+                (Some("connection_history"), Some(value)) => {
+                    if let Ok(connection_history) = value.parse() {
+                        layout.connection_history = connection_history;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(layout)
+    }
+
+    /// Writes the layout over the file at `path`, creating the directory it is in if needed
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(
+            path,
+            format!(
+                "sort_by {}\nfocused_table {}\nsession_bytes {}\nconnection_history {}\n",
+                self.sort_by.name(),
+                self.focused_table,
+                self.session_bytes,
+                self.connection_history
+            ),
+        )
+    }
+
+    /// Forgets what was remembered, the next run starts from the options again
+    pub fn reset(path: &Path) -> io::Result<()> {
+        match fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
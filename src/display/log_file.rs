@@ -0,0 +1,68 @@
+use ::std::ffi::OsString;
+use ::std::fs::{self, File, OpenOptions};
+use ::std::io::{self, Write};
+use ::std::path::{Path, PathBuf};
+
+/// The raw output of every tick, appended to a file (see `--log-file`).
+///
+/// Once the file would grow past `max_size` it is moved to `<path>.1` and a new one is started,
+/// the ones rotated before move up a number (`<path>.1` to `<path>.2` and so on).
+pub struct LogFile {
+    path: PathBuf,
+    max_size: u64,
+    file: File,
+    size: u64,
+}
+
+fn open_for_appending(path: &PathBuf) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+impl LogFile {
+    /// Appends to the file if it is already there, it is only rotated once it gets too big
+    pub fn open(path: PathBuf, max_size: u64) -> io::Result<Self> {
+        let file = open_for_appending(&path)?;
+        let size = file.metadata()?.len();
+        Ok(LogFile {
+            path,
+            max_size,
+            file,
+            size,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let length = line.len() as u64 + 1;
+        // a line longer than max_size still gets written, to a file of its own
+        if self.size > 0 && self.size + length > self.max_size {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += length;
+        Ok(())
+    }
+
+    fn rotated_path(&self, number: usize) -> PathBuf {
+        let mut path = OsString::from(&self.path);
+        path.push(format!(".{}", number));
+        PathBuf::from(path)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let mut last = 0;
+        while self.rotated_path(last + 1).exists() {
+            last += 1;
+        }
+        for number in (1..=last).rev() {
+            fs::rename(self.rotated_path(number), self.rotated_path(number + 1))?;
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+        self.file = open_for_appending(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
@@ -0,0 +1,254 @@
+// the Prometheus text format (see --metrics-port), served by a tiny HTTP listener
+// so no web framework is needed for the one endpoint there is
+use ::std::collections::HashMap;
+use ::std::fmt::Write as _;
+use ::std::io::{self, Read, Write};
+use ::std::net::{IpAddr, TcpListener, TcpStream};
+use ::std::sync::atomic::{AtomicBool, Ordering};
+use ::std::thread;
+use ::std::time::Duration;
+
+use crate::display::{LaunchTotals, SessionTotals, Snapshot};
+use crate::network::{display_ip_or_host, Connection};
+
+// how often the listener checks if it should stop
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const REQUEST_READ_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_REQUEST_HEAD_LENGTH: usize = 8192;
+
+fn label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_family(metrics: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(metrics, "# HELP {} {}", name, help).unwrap();
+    writeln!(metrics, "# TYPE {} {}", name, kind).unwrap();
+}
+
+fn write_sample(metrics: &mut String, name: &str, labels: &[(&str, &str)], value: u128) {
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(label, value)| format!("{}=\"{}\"", label, label_value(value)))
+        .collect();
+    writeln!(metrics, "{}{{{}}} {}", name, labels.join(","), value).unwrap();
+}
+
+fn write_connection_sample(
+    metrics: &mut String,
+    name: &str,
+    connection: &Connection,
+    process_name: &str,
+    ip_to_host: &HashMap<IpAddr, String>,
+    direction: &str,
+    value: u128,
+) {
+    let remote_host = display_ip_or_host(connection.remote_socket.ip, ip_to_host);
+    let remote_port = connection.remote_socket.port.to_string();
+    let local_port = connection.local_socket.port.to_string();
+    let protocol = connection.local_socket.protocol.to_string();
+    // the ports and protocol keep two connections to the same host apart
+    write_sample(
+        metrics,
+        name,
+        &[
+            ("process", process_name),
+            ("remote_host", &remote_host),
+            ("remote_port", &remote_port),
+            ("local_port", &local_port),
+            ("protocol", &protocol),
+            ("direction", direction),
+        ],
+        value,
+    );
+}
+
+/// Renders the bytes since launch, the rates of a snapshot and the session totals in the
+/// Prometheus text format.
+///
+/// The `_bytes_total` counters only ever go up, whatever the tables show. The `_bytes_per_second`
+/// gauges are left out without `rates`, eg. when the snapshot holds totals since launch
+/// (--total-utilization) or packets (--packets) instead.
+pub fn prometheus_metrics(
+    rates: Option<&Snapshot>,
+    launch_totals: &LaunchTotals,
+    ip_to_host: &HashMap<IpAddr, String>,
+    session_totals: &SessionTotals,
+) -> String {
+    let mut metrics = String::new();
+    write_family(
+        &mut metrics,
+        "bandwhich_process_bytes_total",
+        "counter",
+        "Bytes moved by a process since launch",
+    );
+    let mut processes: Vec<_> = launch_totals.processes.iter().collect();
+    processes.sort();
+    for (process_name, &(uploaded, downloaded)) in processes {
+        for &(direction, bytes) in &[("up", uploaded), ("down", downloaded)] {
+            write_sample(
+                &mut metrics,
+                "bandwhich_process_bytes_total",
+                &[("process", process_name), ("direction", direction)],
+                bytes,
+            );
+        }
+    }
+    write_family(
+        &mut metrics,
+        "bandwhich_connection_bytes_total",
+        "counter",
+        "Bytes moved over a connection since launch",
+    );
+    let mut connections: Vec<_> = launch_totals.connections.iter().collect();
+    connections.sort_by_key(|&(connection, _)| connection);
+    for (connection, (process_name, uploaded, downloaded)) in connections {
+        for &(direction, bytes) in &[("up", *uploaded), ("down", *downloaded)] {
+            write_connection_sample(
+                &mut metrics,
+                "bandwhich_connection_bytes_total",
+                connection,
+                process_name,
+                ip_to_host,
+                direction,
+                bytes,
+            );
+        }
+    }
+    if let Some(snapshot) = rates {
+        write_family(
+            &mut metrics,
+            "bandwhich_process_bytes_per_second",
+            "gauge",
+            "Bytes per second of a process, averaged like in the tables",
+        );
+        for process in &snapshot.processes {
+            for &(direction, bytes) in &[
+                ("up", process.total_bytes_uploaded),
+                ("down", process.total_bytes_downloaded),
+            ] {
+                write_sample(
+                    &mut metrics,
+                    "bandwhich_process_bytes_per_second",
+                    &[("process", &process.name), ("direction", direction)],
+                    bytes,
+                );
+            }
+        }
+        write_family(
+            &mut metrics,
+            "bandwhich_connection_bytes_per_second",
+            "gauge",
+            "Bytes per second of a connection, averaged like in the tables",
+        );
+        for entry in &snapshot.connections {
+            for &(direction, bytes) in &[
+                ("up", entry.total_bytes_uploaded),
+                ("down", entry.total_bytes_downloaded),
+            ] {
+                write_connection_sample(
+                    &mut metrics,
+                    "bandwhich_connection_bytes_per_second",
+                    &entry.connection,
+                    &entry.process_name,
+                    &snapshot.ip_to_host,
+                    direction,
+                    bytes,
+                );
+            }
+        }
+    }
+    write_family(
+        &mut metrics,
+        "bandwhich_bytes_total",
+        "counter",
+        "Bytes captured since launch",
+    );
+    for &(direction, bytes) in &[
+        ("up", session_totals.bytes_uploaded),
+        ("down", session_totals.bytes_downloaded),
+    ] {
+        write_sample(
+            &mut metrics,
+            "bandwhich_bytes_total",
+            &[("direction", direction)],
+            bytes,
+        );
+    }
+    metrics
+}
+
+/// Serves `/metrics` until `running` is unset, one request at a time.
+pub struct MetricsServer {
+    listener: TcpListener,
+}
+
+impl MetricsServer {
+    pub fn new(listener: TcpListener) -> io::Result<Self> {
+        // accepting does not block, so a quit is noticed even if nobody scrapes
+        listener.set_nonblocking(true)?;
+        Ok(MetricsServer { listener })
+    }
+    pub fn serve(&self, running: &AtomicBool, metrics: impl Fn() -> String) {
+        while running.load(Ordering::Acquire) {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    // a client that goes away mid request is its own problem
+                    let _ = respond(stream, &metrics);
+                }
+                // mostly WouldBlock, other errors only concern the client that caused them
+                Err(_) => thread::sleep(ACCEPT_POLL_INTERVAL),
+            }
+        }
+    }
+}
+
+fn read_request_head(stream: &mut TcpStream) -> io::Result<String> {
+    let mut head = Vec::new();
+    let mut buffer = [0; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n")
+        && head.len() < MAX_REQUEST_HEAD_LENGTH
+    {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(&buffer[..read]);
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+fn respond(mut stream: TcpStream, metrics: &impl Fn() -> String) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(REQUEST_READ_TIMEOUT))?;
+    let head = read_request_head(&mut stream)?;
+    let mut request_line = head.lines().next().unwrap_or("").split_whitespace();
+    let (status, content_type, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/metrics")) => (
+            "200 OK",
+            "text/plain; version=0.0.4; charset=utf-8",
+            metrics(),
+        ),
+        (Some("GET"), Some(_)) => (
+            "404 Not Found",
+            "text/plain; charset=utf-8",
+            String::from("only /metrics is served\n"),
+        ),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain; charset=utf-8",
+            String::from("only GET is supported\n"),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
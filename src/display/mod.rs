@@ -1,9 +1,42 @@
+mod alert;
+mod budget;
+mod cap_detection;
 mod components;
+mod csv_history;
+mod event_log;
+mod frame_export_backend;
+mod grouping;
+mod interface_alias;
+mod json;
+mod layout_state;
+mod log_file;
+mod metrics;
+mod network_tag;
+mod output_sink;
 mod raw_terminal_backend;
+mod smoothing;
+mod socket_sink;
+mod theme;
 mod ui;
-mod ui_state;
 
+pub use alert::*;
+pub use bandwhich::model::*;
+pub use budget::*;
+pub use cap_detection::*;
 pub use components::*;
+pub use csv_history::*;
+pub use event_log::*;
+pub use frame_export_backend::*;
+pub use grouping::*;
+pub use interface_alias::*;
+pub use json::*;
+pub use layout_state::*;
+pub use log_file::*;
+pub use metrics::*;
+pub use network_tag::*;
+pub use output_sink::*;
 pub use raw_terminal_backend::*;
+pub use smoothing::*;
+pub use socket_sink::*;
+pub use theme::*;
 pub use ui::*;
-pub use ui_state::*;
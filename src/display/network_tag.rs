@@ -0,0 +1,52 @@
+use ::ipnetwork::IpNetwork;
+use ::std::net::IpAddr;
+use ::std::str::FromStr;
+use ::tui::style::Color;
+
+// the colors of the tags in the order they were given, none of them one a row already gets for
+// its rate or for --highlight-host
+const TAG_COLORS: [Color; 4] = [
+    Color::Magenta,
+    Color::Blue,
+    Color::LightMagenta,
+    Color::LightBlue,
+];
+
+/// A label for the remote addresses in a network, eg. 192.168.1.0/24=nas (see `--tag`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkTag {
+    pub network: IpNetwork,
+    pub label: String,
+}
+
+impl FromStr for NetworkTag {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid tag '{}', expected a network and the label to show it by, eg. 192.168.1.0/24=nas",
+                s
+            )
+        };
+        let equals = s.find('=').ok_or_else(invalid)?;
+        let label = &s[equals + 1..];
+        if label.is_empty() {
+            return Err(invalid());
+        }
+        let network = s[..equals].parse().map_err(|_| invalid())?;
+        Ok(NetworkTag {
+            network,
+            label: String::from(label),
+        })
+    }
+}
+
+/// The label of the most specific of the `tags` `ip` is in (the last one given of those as
+/// specific), and the color it is shown in
+pub fn tag_of(ip: IpAddr, tags: &[NetworkTag]) -> Option<(&str, Color)> {
+    tags.iter()
+        .enumerate()
+        .filter(|(_, tag)| tag.network.contains(ip))
+        .max_by_key(|(index, tag)| (tag.network.prefix(), *index))
+        .map(|(index, tag)| (tag.label.as_str(), TAG_COLORS[index % TAG_COLORS.len()]))
+}
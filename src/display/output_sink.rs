@@ -0,0 +1,119 @@
+use ::std::io;
+use ::std::path::PathBuf;
+use ::std::str::FromStr;
+
+use ::tui::backend::Backend;
+
+use crate::display::{CsvHistory, LogFile, SocketSink, Ui};
+
+/// Where the output of every tick goes, eg. `file:/tmp/bandwhich.json` (see --output)
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputTarget {
+    /// The raw output on stdout, same as --raw
+    StdoutRaw,
+    /// The JSON output on stdout, same as --json
+    StdoutJson,
+    /// Appended to the file and rotated like the --log-file, JSON if it ends with .json
+    File(PathBuf),
+    /// The JSON output streamed like to the --socket
+    Socket(PathBuf),
+    /// Drops the outputs given before it, eg. in the config file
+    None,
+}
+
+impl FromStr for OutputTarget {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stdout-raw" => Ok(OutputTarget::StdoutRaw),
+            "stdout-json" => Ok(OutputTarget::StdoutJson),
+            "none" => Ok(OutputTarget::None),
+            _ => match s.split_once(':') {
+                Some(("file", path)) if !path.is_empty() => Ok(OutputTarget::File(path.into())),
+                Some(("socket", path)) if !path.is_empty() => {
+                    Ok(OutputTarget::Socket(path.into()))
+                }
+                _ => Err(format!(
+                    "invalid output '{}', expected stdout-raw, stdout-json, file:<path>, socket:<path> or none",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+/// The outputs that count, the ones after the last `none`
+pub fn configured_outputs(outputs: &[OutputTarget]) -> &[OutputTarget] {
+    match outputs
+        .iter()
+        .rposition(|target| *target == OutputTarget::None)
+    {
+        Some(none) => &outputs[none + 1..],
+        None => outputs,
+    }
+}
+
+/// One of the places besides stdout the output of every tick is written to, see
+/// `OutputTarget`. Stdout is left to the tables or the raw output, whichever it shows.
+pub enum OutputSink {
+    File { log_file: LogFile, json: bool },
+    Socket(SocketSink),
+    Csv(CsvHistory),
+}
+
+impl OutputSink {
+    pub fn file(path: PathBuf, max_size: u64, json: bool) -> io::Result<Self> {
+        Ok(OutputSink::File {
+            log_file: LogFile::open(path, max_size)?,
+            json,
+        })
+    }
+
+    pub fn socket(path: PathBuf) -> io::Result<Self> {
+        Ok(OutputSink::Socket(SocketSink::open(path)?))
+    }
+
+    pub fn csv(path: PathBuf, per_process: bool) -> io::Result<Self> {
+        Ok(OutputSink::Csv(CsvHistory::create(path, per_process)?))
+    }
+
+    /// What the error of a failed write is about, eg. `the file /tmp/bandwhich.log`
+    pub fn name(&self) -> String {
+        match self {
+            OutputSink::File { log_file, .. } => format!("the file {}", log_file.path().display()),
+            OutputSink::Socket(socket_sink) => {
+                format!("the socket {}", socket_sink.path().display())
+            }
+            OutputSink::Csv(csv_history) => {
+                format!("the file {}", csv_history.path().display())
+            }
+        }
+    }
+
+    /// Only a file can fail, a socket consumer is free to come and go
+    pub fn write<B: Backend>(&mut self, ui: &mut Ui<B>) -> io::Result<()> {
+        match self {
+            OutputSink::File {
+                log_file,
+                json: false,
+            } => ui.output_to_log_file(log_file),
+            OutputSink::File {
+                log_file,
+                json: true,
+            } => {
+                let mut result = Ok(());
+                ui.output_json(&mut |line| {
+                    if result.is_ok() {
+                        result = log_file.write_line(&line);
+                    }
+                });
+                result
+            }
+            OutputSink::Socket(socket_sink) => {
+                ui.output_json(&mut |line| socket_sink.write_line(&line));
+                Ok(())
+            }
+            OutputSink::Csv(csv_history) => ui.output_to_csv_history(csv_history),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use ::std::collections::{HashMap, VecDeque};
+use ::std::hash::Hash;
+use ::std::mem;
+use ::std::net::IpAddr;
+
+use crate::display::{Snapshot, SortBy};
+use crate::network::Connection;
+
+/// The uploaded and downloaded bytes per second of a row in the recent ticks, oldest first
+pub type RateHistory = VecDeque<(u128, u128)>;
+
+/// The rates of the last few ticks of every row, to show their average rather than the jumpier
+/// rates of a single tick (see `--smooth`).
+///
+/// A row that is gone from a tick starts from scratch if it comes back, so it never carries a
+/// stale average along. More ticks than are averaged can be kept, for whatever else looks at the
+/// history of the connections (see `CapDetection`).
+pub struct RateSmoothing {
+    ticks: usize,
+    kept_ticks: usize,
+    totals: RateHistory,
+    processes: HashMap<String, RateHistory>,
+    remote_addresses: HashMap<IpAddr, RateHistory>,
+    connections: HashMap<Connection, RateHistory>,
+}
+
+impl RateSmoothing {
+    pub fn new(ticks: usize, kept_ticks: usize) -> Self {
+        RateSmoothing {
+            ticks,
+            kept_ticks: kept_ticks.max(ticks),
+            totals: VecDeque::new(),
+            processes: HashMap::new(),
+            remote_addresses: HashMap::new(),
+            connections: HashMap::new(),
+        }
+    }
+    /// A copy of `snapshot` with every rate averaged over the last ticks, sorted anew by them.
+    /// Totals since launch (see `--total-utilization`) are left as they are
+    pub fn smooth(&mut self, snapshot: &Snapshot, sort_by: SortBy) -> Snapshot {
+        let mut smoothed = snapshot.clone();
+        if snapshot.cumulative {
+            return smoothed;
+        }
+        let ticks = (self.ticks, self.kept_ticks);
+        let (uploaded, downloaded) = average(
+            &mut self.totals,
+            ticks,
+            (
+                smoothed.total_bytes_uploaded,
+                smoothed.total_bytes_downloaded,
+            ),
+        );
+        smoothed.total_bytes_uploaded = uploaded;
+        smoothed.total_bytes_downloaded = downloaded;
+        smooth_rows(
+            &mut self.processes,
+            ticks,
+            smoothed.processes.iter_mut().map(|entry| {
+                (
+                    entry.name.clone(),
+                    &mut entry.total_bytes_uploaded,
+                    &mut entry.total_bytes_downloaded,
+                )
+            }),
+        );
+        smooth_rows(
+            &mut self.remote_addresses,
+            ticks,
+            smoothed.remote_addresses.iter_mut().map(|entry| {
+                (
+                    entry.ip,
+                    &mut entry.total_bytes_uploaded,
+                    &mut entry.total_bytes_downloaded,
+                )
+            }),
+        );
+        smooth_rows(
+            &mut self.connections,
+            ticks,
+            smoothed.connections.iter_mut().map(|entry| {
+                (
+                    entry.connection,
+                    &mut entry.total_bytes_uploaded,
+                    &mut entry.total_bytes_downloaded,
+                )
+            }),
+        );
+        // names and ages do not change with the rates, so neither does what they sort
+        if sort_by != SortBy::Name && sort_by != SortBy::Age {
+            smoothed.processes.sort_by(|a, b| {
+                sort_by.compare(
+                    (&a.name, a.total_bytes_uploaded, a.total_bytes_downloaded),
+                    (&b.name, b.total_bytes_uploaded, b.total_bytes_downloaded),
+                )
+            });
+            smoothed.remote_addresses.sort_by(|a, b| {
+                sort_by.compare(
+                    (&a.ip, a.total_bytes_uploaded, a.total_bytes_downloaded),
+                    (&b.ip, b.total_bytes_uploaded, b.total_bytes_downloaded),
+                )
+            });
+            smoothed.connections.sort_by(|a, b| {
+                sort_by.compare(
+                    (
+                        &a.connection,
+                        a.total_bytes_uploaded,
+                        a.total_bytes_downloaded,
+                    ),
+                    (
+                        &b.connection,
+                        b.total_bytes_uploaded,
+                        b.total_bytes_downloaded,
+                    ),
+                )
+            });
+        }
+        smoothed
+    }
+    /// The rates of every connection of the last `smooth` call in the ticks before it, as many
+    /// as are kept
+    pub fn connection_histories(&self) -> impl Iterator<Item = (&Connection, &RateHistory)> {
+        self.connections.iter()
+    }
+}
+
+fn smooth_rows<'a, K: Eq + Hash>(
+    histories: &mut HashMap<K, RateHistory>,
+    ticks: (usize, usize),
+    rows: impl Iterator<Item = (K, &'a mut u128, &'a mut u128)>,
+) {
+    let mut previous_histories = mem::take(histories);
+    for (key, uploaded, downloaded) in rows {
+        let mut history = previous_histories.remove(&key).unwrap_or_default();
+        let (average_uploaded, average_downloaded) =
+            average(&mut history, ticks, (*uploaded, *downloaded));
+        *uploaded = average_uploaded;
+        *downloaded = average_downloaded;
+        histories.insert(key, history);
+    }
+}
+
+// the average of the last `ticks` of the history, which keeps the last `kept_ticks`
+fn average(
+    history: &mut RateHistory,
+    (ticks, kept_ticks): (usize, usize),
+    rates: (u128, u128),
+) -> (u128, u128) {
+    history.push_back(rates);
+    while history.len() > kept_ticks {
+        history.pop_front();
+    }
+    let (uploaded, downloaded) = history
+        .iter()
+        .rev()
+        .take(ticks)
+        .fold((0, 0), |(uploaded, downloaded), &(up, down)| {
+            (uploaded + up, downloaded + down)
+        });
+    let count = history.len().min(ticks) as u128;
+    (uploaded / count, downloaded / count)
+}
@@ -0,0 +1,81 @@
+use ::std::fs;
+use ::std::io::{self, Write};
+use ::std::os::unix::net::{UnixListener, UnixStream};
+use ::std::path::{Path, PathBuf};
+use ::std::time::Duration;
+
+// a consumer that stops reading must not hold up the tables
+const WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The JSON output of every tick, streamed to a Unix socket (see `--socket`).
+///
+/// If something is listening on the socket it is connected to, otherwise the socket is created
+/// and the first consumer to connect to it gets the lines. A consumer that goes away is dropped
+/// and connected to (or waited for) again on the next line, the lines in between are lost.
+pub struct SocketSink {
+    path: PathBuf,
+    listener: Option<UnixListener>,
+    stream: Option<UnixStream>,
+}
+
+impl SocketSink {
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        if let Ok(stream) = UnixStream::connect(&path) {
+            return Ok(SocketSink {
+                path,
+                listener: None,
+                stream: Some(stream),
+            });
+        }
+        // a socket nobody listens on anymore is connected to again later rather than replaced
+        let listener = if path.exists() {
+            None
+        } else {
+            let listener = UnixListener::bind(&path)?;
+            listener.set_nonblocking(true)?;
+            Some(listener)
+        };
+        Ok(SocketSink {
+            path,
+            listener,
+            stream: None,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn connect(&self) -> io::Result<UnixStream> {
+        let stream = match &self.listener {
+            Some(listener) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                stream
+            }
+            None => UnixStream::connect(&self.path)?,
+        };
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        Ok(stream)
+    }
+
+    /// Writes the line if there is a consumer, never fails so a consumer can come and go
+    pub fn write_line(&mut self, line: &str) {
+        if self.stream.is_none() {
+            self.stream = self.connect().ok();
+        }
+        if let Some(stream) = self.stream.as_mut() {
+            if writeln!(stream, "{}", line).is_err() {
+                self.stream = None;
+            }
+        }
+    }
+}
+
+impl Drop for SocketSink {
+    fn drop(&mut self) {
+        if self.listener.is_some() {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
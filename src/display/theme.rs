@@ -0,0 +1,171 @@
+use ::std::fs;
+use ::std::path::Path;
+
+use ::tui::style::Color;
+
+use crate::config::{parse_lines, parse_value, Value};
+
+/// The colors of the tables (see `--theme`), the default is the one for a dark background
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    pub header: Color,
+    /// The background of the selected or pinned row, None shows it in reverse
+    pub selected: Option<Color>,
+    pub border: Color,
+    /// The rows by how busy they are against the busiest one, from the least busy third up.
+    /// The total and the download graph are in the low color too, the upload graph, a pause and the
+    /// wait for traffic in the middle one and a budget running low in the high one
+    pub low: Color,
+    pub middle: Color,
+    pub high: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            header: Color::Yellow,
+            selected: None,
+            border: Color::Reset,
+            low: Color::Green,
+            middle: Color::Yellow,
+            high: Color::Red,
+        }
+    }
+}
+
+// yellow washes out on a white background
+fn light_theme() -> Theme {
+    Theme {
+        header: Color::Blue,
+        selected: None,
+        border: Color::Reset,
+        low: Color::Green,
+        middle: Color::Magenta,
+        high: Color::Red,
+    }
+}
+
+// https://ethanschoonover.com/solarized/
+fn solarized_theme() -> Theme {
+    Theme {
+        header: Color::Rgb(0xb5, 0x89, 0x00),
+        selected: Some(Color::Rgb(0x07, 0x36, 0x42)),
+        border: Color::Rgb(0x58, 0x6e, 0x75),
+        low: Color::Rgb(0x85, 0x99, 0x00),
+        middle: Color::Rgb(0xcb, 0x4b, 0x16),
+        high: Color::Rgb(0xdc, 0x32, 0x2f),
+    }
+}
+
+/// The theme shipped under `name`, if there is one
+pub fn builtin_theme(name: &str) -> Option<Theme> {
+    match name {
+        "dark" => Some(Theme::default()),
+        "light" => Some(light_theme()),
+        "solarized" => Some(solarized_theme()),
+        _ => None,
+    }
+}
+
+/// A color by its name (eg. `yellow` or `light-blue`), as `#rrggbb` or as an index of the 256
+/// colors of the terminal. `default` leaves the color of the terminal as it is
+pub fn parse_color(color: &str) -> Result<Color, String> {
+    let invalid = || {
+        format!(
+            "invalid color '{}', expected a name like yellow, #rrggbb or a number up to 255",
+            color
+        )
+    };
+    if let Some(hex) = color.strip_prefix('#') {
+        let channel = |index: usize| {
+            hex.get(index..index + 2)
+                .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+                .ok_or_else(invalid)
+        };
+        if hex.len() != 6 {
+            return Err(invalid());
+        }
+        return Ok(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    if let Ok(index) = color.parse::<u8>() {
+        return Ok(Color::Indexed(index));
+    }
+    match color.to_lowercase().replace(&['-', '_'][..], "").as_str() {
+        "default" | "reset" => Ok(Color::Reset),
+        "black" => Ok(Color::Black),
+        "red" => Ok(Color::Red),
+        "green" => Ok(Color::Green),
+        "yellow" => Ok(Color::Yellow),
+        "blue" => Ok(Color::Blue),
+        "magenta" => Ok(Color::Magenta),
+        "cyan" => Ok(Color::Cyan),
+        "gray" | "grey" => Ok(Color::Gray),
+        "darkgray" | "darkgrey" => Ok(Color::DarkGray),
+        "lightred" => Ok(Color::LightRed),
+        "lightgreen" => Ok(Color::LightGreen),
+        "lightyellow" => Ok(Color::LightYellow),
+        "lightblue" => Ok(Color::LightBlue),
+        "lightmagenta" => Ok(Color::LightMagenta),
+        "lightcyan" => Ok(Color::LightCyan),
+        "white" => Ok(Color::White),
+        _ => Err(invalid()),
+    }
+}
+
+/// A theme file: TOML `key = "color"` lines over the built-in theme of its `base` key (the dark
+/// one without it), eg. `header = "blue"`.
+///
+/// The keys are `base`, `header`, `selected` (`reverse` to show the row in reverse), `border`,
+/// `low`, `middle` and `high`.
+pub fn parse_theme(contents: &str) -> Result<Theme, String> {
+    let mut entries = Vec::new();
+    for (line_number, key, value) in parse_lines(contents)? {
+        let quoted = || format!("line {}: expected a quoted string", line_number);
+        // a bare word is a mistake in TOML, however the config file takes it
+        if !value.starts_with('"') && !value.starts_with('\'') {
+            return Err(quoted());
+        }
+        match parse_value(value, line_number)? {
+            Value::Text(value) => entries.push((line_number, key, value)),
+            _ => return Err(quoted()),
+        }
+    }
+    let base = entries
+        .iter()
+        .rev()
+        .find(|(_, key, _)| *key == "base")
+        .map(|(line_number, _, value)| {
+            builtin_theme(value).ok_or_else(|| {
+                format!(
+                    "line {}: unknown theme '{}', expected dark, light or solarized",
+                    line_number, value
+                )
+            })
+        })
+        .transpose()?;
+    let mut theme = base.unwrap_or_default();
+    for (line_number, key, value) in entries {
+        let color = || parse_color(&value).map_err(|err| format!("line {}: {}", line_number, err));
+        match key {
+            "base" => {}
+            "header" => theme.header = color()?,
+            "selected" if value == "reverse" => theme.selected = None,
+            "selected" => theme.selected = Some(color()?),
+            "border" => theme.border = color()?,
+            "low" => theme.low = color()?,
+            "middle" => theme.middle = color()?,
+            "high" => theme.high = color()?,
+            _ => return Err(format!("line {}: unknown key '{}'", line_number, key)),
+        }
+    }
+    Ok(theme)
+}
+
+/// The built-in theme called `theme`, or the theme file at that path
+pub fn load_theme(theme: &str) -> Result<Theme, String> {
+    if let Some(builtin) = builtin_theme(theme) {
+        return Ok(builtin);
+    }
+    let contents = fs::read_to_string(Path::new(theme)).map_err(|err| err.to_string())?;
+    parse_theme(&contents)
+}
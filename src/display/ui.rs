@@ -1,130 +1,948 @@
-use ::std::collections::HashMap;
+use ::std::collections::{HashMap, HashSet, VecDeque};
+use ::std::io;
+use ::std::time::{Duration, Instant};
 
 use ::tui::backend::Backend;
+use ::tui::style::{Modifier, Style};
+use ::tui::widgets::{Paragraph, Text, Widget};
 use ::tui::Terminal;
 
-use crate::display::components::{HelpText, Layout, Table, TotalBandwidth};
-use crate::display::UIState;
-use crate::network::{display_connection_string, display_ip_or_host, LocalSocket, Utilization};
+use crate::display::components::{
+    display_elapsed, display_exact_bytes, display_upload_and_download, BudgetLine,
+    CaptureStatsLine, ChurnLine, ConnectionChurn, DataUnit, DisplayBandwidth, DnsStatsLine,
+    HelpOverlay, HelpText, HostAddresses, Layout, LogPane, SessionSummary, Table, TableArea,
+    TableClick, ThroughputGraph, TotalBandwidth, UnitPrefix, THROUGHPUT_HISTORY_LENGTH,
+};
+use crate::display::{
+    display_interface_name, prometheus_metrics, Budget, CapDetection, CsvHistory, EventLog,
+    GroupBy, GroupDimension, GroupEntry, JsonTick, LayoutState, LogFile, RateAlert, RateSmoothing,
+    SessionTotals, Snapshot, SortBy, UIState,
+};
+use crate::network::dns::DnsStats;
+use crate::network::{
+    display_connection_string, display_ip_or_host, CaptureStats, Connection, LocalSocket,
+    UnixSocket, Utilization,
+};
 
-use ::std::net::IpAddr;
+use ::std::net::{IpAddr, SocketAddr};
 
 use crate::RenderOpts;
 use chrono::prelude::*;
 
+/// An empty state that counts and filters connections the way `opts` say
+pub fn new_state(opts: &RenderOpts) -> UIState {
+    let mut state: UIState = Default::default();
+    state.cumulative_mode = opts.total_utilization;
+    state.process_filter = opts.process.as_ref().map(|name| name.to_lowercase());
+    state.process_excludes = opts
+        .exclude_process
+        .iter()
+        .map(|name| name.to_lowercase())
+        .collect();
+    state.port_filter = opts.port.clone();
+    state.protocol_filter = opts.protocol;
+    state.direction_filter = opts.direction;
+    state.show_loopback = opts.show_loopback;
+    state.merge_connections = opts.merge_connections;
+    state.packets_mode = opts.packets;
+    state.sort_by = opts.sort_by;
+    state.rate_window = opts.rate_window;
+    state
+}
+
+fn interface_group_by() -> GroupBy {
+    GroupBy {
+        dimensions: vec![GroupDimension::Interface],
+    }
+}
+
 pub struct Ui<B>
 where
     B: Backend,
 {
     terminal: Terminal<B>,
     state: UIState,
+    snapshot: Snapshot,
+    /// The rates of the tables averaged over the last ticks, see --smooth
+    smoothing: Option<RateSmoothing>,
+    /// Tells the connections that look held back by a rate limit from their history in the
+    /// smoothing, see --detect-caps
+    cap_detection: Option<CapDetection>,
+    capped_connections: HashSet<Connection>,
+    /// What the tables are drawn from when it is not the snapshot, see --smooth and --min-rate
+    table_snapshot: Option<Snapshot>,
+    /// What the tables showed when the `f` key froze them, the state goes on being updated
+    /// underneath so they show the current rates once unfrozen
+    frozen_snapshot: Option<Snapshot>,
+    /// How many rows of the tables are left out for being below --min-rate
+    hidden_rows: usize,
+    groups: Vec<GroupEntry>,
     ip_to_host: HashMap<IpAddr, String>,
+    /// Where the remote addresses are, see --geo
+    ip_to_geo: HashMap<IpAddr, String>,
+    host_ips: HashSet<IpAddr>,
+    host_only_interfaces: Vec<String>,
+    /// Shown in the --interface-breakdown even while they have no traffic
+    listened_interfaces: Vec<String>,
+    /// The speed of the link of each (shown) interface that tells it, in bits per second
+    link_speeds: HashMap<String, u64>,
     opts: RenderOpts,
+    event_log: EventLog,
+    show_event_log: bool,
+    show_connection_history: bool,
+    /// The key bindings are shown over the tables, see `Ui::toggle_help`
+    show_help: bool,
+    resolving: bool,
+    updates_received: u8,
+    waiting_for_traffic: bool,
+    started_at: Instant,
+    search_query: Option<String>,
+    typing_search_query: bool,
+    session_totals: SessionTotals,
+    capture_stats: CaptureStats,
+    /// The connections that showed up and went away in the latest update, see --churn
+    connection_churn: ConnectionChurn,
+    /// How the hostname lookups went in the latest tick, see --dns-stats
+    dns_stats: DnsStats,
+    /// What is left of the --budget as of the latest tick
+    budget: Option<Budget>,
+    /// Total upload and download per second of the recent ticks, oldest first (see --graph)
+    throughput_history: VecDeque<(u128, u128)>,
+    focused_table: usize,
+    scroll_offset: usize,
+    top_talker: Option<TopTalker>,
+    selected_row: Option<SelectedRow>,
+    pinned_rows: Vec<PinnedRow>,
+    clipboard_notice: Option<ClipboardNotice>,
+    /// Where the tables were last drawn, to tell what a click landed on (see --mouse)
+    table_areas: Vec<TableArea>,
+    /// The rate of every row of every table in the tick before, see --highlight-changes
+    previous_row_rates: Vec<HashMap<String, u128>>,
+}
+
+// how many ticks the busiest row stays at the top of its table once jumped to
+const TOP_TALKER_PIN_TICKS: u8 = 5;
+// how many ticks the footer says how copying a row with `y` went
+const CLIPBOARD_NOTICE_TICKS: u8 = 2;
+// how many of the processes and remote hosts the --summary report lists
+const SESSION_REPORT_ROWS: usize = 5;
+/// The version of the layout of the raw output, see `Ui::output_text_header`. It goes up whenever
+/// the lines change in a way that would trip up what reads them
+pub const RAW_FORMAT_VERSION: u32 = 1;
+
+// the busiest row of a table at the time `b` was pressed, see `Ui::jump_to_top_talker`
+struct TopTalker {
+    table: usize,
+    first_cell: String,
+    ticks_left: u8,
+}
+
+// the row clicked on, shown in reverse until it is clicked again
+struct SelectedRow {
+    table: usize,
+    first_cell: String,
+}
+
+// how copying a row with `y` went, see `Ui::copy_selected_row`
+struct ClipboardNotice {
+    text: &'static str,
+    ticks_left: u8,
+}
+
+// a row kept at the top of its table with `p`, as it was shown last
+struct PinnedRow {
+    table: usize,
+    row: Vec<String>,
+}
+
+/// What a click did, see `Ui::click`
+#[derive(Debug, PartialEq)]
+pub enum ClickAction {
+    Sorted(SortBy),
+    Selected(Option<String>),
+    Focused,
+    Missed,
 }
 
 impl<B> Ui<B>
 where
     B: Backend,
 {
-    pub fn new(terminal_backend: B, opts: RenderOpts) -> Self {
+    pub fn new(terminal_backend: B, opts: RenderOpts, host_ips: HashSet<IpAddr>) -> Self {
         let mut terminal = Terminal::new(terminal_backend).unwrap();
         terminal.clear().unwrap();
         terminal.hide_cursor().unwrap();
-        let mut state: UIState = Default::default();
-        state.cumulative_mode = opts.total_utilization;
+        let state = new_state(&opts);
+        let ip_to_host = Default::default();
+        let snapshot = Snapshot::new(&state, &ip_to_host, Local::now());
+        let cap_detection = if opts.detect_caps {
+            Some(CapDetection::new(opts.cap_ticks, opts.cap_tolerance))
+        } else {
+            None
+        };
         Ui {
             terminal,
             state,
-            ip_to_host: Default::default(),
+            snapshot,
+            smoothing: match &cap_detection {
+                Some(cap_detection) => Some(RateSmoothing::new(
+                    opts.smooth.max(1),
+                    cap_detection.ticks(),
+                )),
+                None if opts.smooth > 1 => Some(RateSmoothing::new(opts.smooth, opts.smooth)),
+                None => None,
+            },
+            cap_detection,
+            capped_connections: HashSet::new(),
+            table_snapshot: None,
+            frozen_snapshot: None,
+            hidden_rows: 0,
+            groups: Vec::new(),
+            ip_to_host,
+            ip_to_geo: HashMap::new(),
+            host_ips,
+            host_only_interfaces: Vec::new(),
+            listened_interfaces: Vec::new(),
+            link_speeds: HashMap::new(),
             opts,
+            event_log: Default::default(),
+            show_event_log: false,
+            show_connection_history: false,
+            show_help: false,
+            resolving: true,
+            updates_received: 0,
+            waiting_for_traffic: false,
+            started_at: Instant::now(),
+            search_query: None,
+            typing_search_query: false,
+            session_totals: SessionTotals::default(),
+            capture_stats: CaptureStats::default(),
+            connection_churn: ConnectionChurn::default(),
+            dns_stats: DnsStats::default(),
+            budget: None,
+            throughput_history: VecDeque::new(),
+            focused_table: 0,
+            scroll_offset: 0,
+            top_talker: None,
+            selected_row: None,
+            pinned_rows: Vec::new(),
+            clipboard_notice: None,
+            table_areas: Vec::new(),
+            previous_row_rates: Vec::new(),
+        }
+    }
+    /// What --summary prints on quit: the bytes moved and the time taken since launch (or the
+    /// last `z`), and the processes and remote hosts that moved the most of them, out of all the
+    /// ticks of the session added together
+    pub fn session_report(
+        &self,
+        connections_to_procs: HashMap<LocalSocket, String>,
+        utilization: Utilization,
+    ) -> String {
+        let mut state = new_state(&self.opts);
+        state.cumulative_mode = true;
+        state.listening_sockets = self.state.listening_sockets.clone();
+        state.processes_unknown = self.state.processes_unknown;
+        state.update(
+            connections_to_procs,
+            utilization,
+            self.session_totals.elapsed,
+        );
+        let snapshot = Snapshot::new(&state, &self.ip_to_host, Local::now());
+        let totals = &self.session_totals;
+        let mut processes = snapshot.processes.clone();
+        processes.sort_by_key(|entry| {
+            std::cmp::Reverse(entry.total_bytes_uploaded + entry.total_bytes_downloaded)
+        });
+        let mut remote_addresses = snapshot.remote_addresses.clone();
+        remote_addresses.sort_by_key(|entry| {
+            std::cmp::Reverse(entry.total_bytes_uploaded + entry.total_bytes_downloaded)
+        });
+        let mut lines = vec![
+            format!("Session: {}", display_elapsed(totals.elapsed)),
+            format!(
+                "Total up/down: {}",
+                display_upload_and_download(
+                    totals.bytes_uploaded,
+                    totals.bytes_downloaded,
+                    true,
+                    &self.opts
+                )
+            ),
+            String::from("Top processes:"),
+        ];
+        for process in processes.iter().take(SESSION_REPORT_ROWS) {
+            lines.push(format!(
+                "  {} {}",
+                process.display_name(),
+                display_upload_and_download(
+                    process.total_bytes_uploaded,
+                    process.total_bytes_downloaded,
+                    true,
+                    &self.opts
+                )
+            ));
+        }
+        lines.push(String::from("Top remote hosts:"));
+        for remote_address in remote_addresses.iter().take(SESSION_REPORT_ROWS) {
+            lines.push(format!(
+                "  {} {}",
+                display_ip_or_host(remote_address.ip, &snapshot.ip_to_host),
+                display_upload_and_download(
+                    remote_address.total_bytes_uploaded,
+                    remote_address.total_bytes_downloaded,
+                    true,
+                    &self.opts
+                )
+            ));
+        }
+        lines.join("\n")
+    }
+    /// The line the raw output starts with, eg. `# bandwhich raw v1`, so what reads it can tell
+    /// which layout follows. The compact output is for people, so it has none
+    pub fn output_text_header(&self, write_to_stdout: &mut (dyn FnMut(String) + Send)) {
+        if !self.opts.compact {
+            write_to_stdout(format!("# bandwhich raw v{}", RAW_FORMAT_VERSION));
         }
     }
     pub fn output_text(&mut self, write_to_stdout: &mut (dyn FnMut(String) + Send)) {
+        if self.opts.compact {
+            self.output_compact(write_to_stdout);
+        } else {
+            self.output_sections(write_to_stdout);
+        }
+    }
+    // one short line, eg. for a status bar: `↓ 2.10MiBps ↑ 300.00KiBps top: firefox`
+    fn output_compact(&mut self, write_to_stdout: &mut (dyn FnMut(String) + Send)) {
+        let snapshot = &self.output_snapshot();
+        let display_bandwidth = |bytes: u128| DisplayBandwidth {
+            bandwidth: bytes as f64,
+            as_rate: !snapshot.cumulative,
+            exact: self.opts.exact_bytes,
+            unit: DataUnit::from_opts(&self.opts),
+            prefix: UnitPrefix::from_opts(&self.opts),
+        };
+        let mut line = format!(
+            "↓ {} ↑ {}",
+            display_bandwidth(snapshot.total_bytes_downloaded),
+            display_bandwidth(snapshot.total_bytes_uploaded)
+        );
+        // with --no-tables no process is known
+        match snapshot.processes.first() {
+            Some(process) if !self.opts.no_tables => {
+                line.push_str(&format!(" top: {}", process.name));
+            }
+            _ => {}
+        }
+        write_to_stdout(line);
+    }
+    fn output_sections(&mut self, write_to_stdout: &mut (dyn FnMut(String) + Send)) {
         let state = &self.state;
-        let ip_to_host = &self.ip_to_host;
-        let local_time: DateTime<Local> = Local::now();
-        let timestamp = local_time.timestamp();
-        for (process, process_network_data) in &state.processes {
+        let snapshot = &self.output_snapshot();
+        let timestamp = snapshot.timestamp.timestamp();
+        let unit = DataUnit::from_opts(&self.opts);
+        let rate_label = unit.raw_rate_label();
+        let display_bytes = |bytes: u128| {
+            let bytes = unit.convert(bytes);
+            if self.opts.exact_bytes {
+                display_exact_bytes(bytes)
+            } else {
+                bytes.to_string()
+            }
+        };
+        // see --timestamp, when the tick was taken for lining it up with other logs
+        if self.opts.timestamp {
+            write_to_stdout(if self.opts.epoch {
+                format!("time: {}", timestamp)
+            } else {
+                format!(
+                    "time: {}",
+                    snapshot
+                        .timestamp
+                        .to_rfc3339_opts(SecondsFormat::Secs, false)
+                )
+            });
+        }
+        // the first line of every tick (but for the `time:` one), its format is part of the
+        // documented raw output
+        write_to_stdout(format!(
+            "tick: <{}> up/down {}: {}/{} connections: {}",
+            timestamp,
+            rate_label,
+            display_bytes(snapshot.total_bytes_uploaded),
+            display_bytes(snapshot.total_bytes_downloaded),
+            snapshot.connections.len()
+        ));
+        if self.opts.no_tables {
+            return;
+        }
+        if self.opts.churn {
             write_to_stdout(format!(
-                "process: <{}> \"{}\" up/down Bps: {}/{} connections: {}",
-                timestamp,
-                process,
-                process_network_data.total_bytes_uploaded,
-                process_network_data.total_bytes_downloaded,
-                process_network_data.connection_count
+                "churn: <{}> opened: {} closed: {}",
+                timestamp, self.connection_churn.opened, self.connection_churn.closed
             ));
         }
-        for (connection, connection_network_data) in &state.connections {
+        let count = self.row_count();
+        for process in snapshot.processes.iter().take(count) {
             write_to_stdout(format!(
-                "connection: <{}> {} up/down Bps: {}/{} process: \"{}\"",
+                "process: <{}> \"{}\" up/down {}: {}/{} connections: {}",
+                timestamp,
+                process.display_name(),
+                rate_label,
+                display_bytes(process.total_bytes_uploaded),
+                display_bytes(process.total_bytes_downloaded),
+                process.connection_count
+            ));
+        }
+        for connection in snapshot.connections.iter().take(count) {
+            let mut line = format!(
+                "connection: <{}> {} up/down {}: {}/{} process: \"{}\"",
                 timestamp,
                 display_connection_string(
-                    connection,
-                    ip_to_host,
-                    &connection_network_data.interface_name
+                    &connection.connection,
+                    &snapshot.ip_to_host,
+                    &connection.interface_name,
+                    false
                 ),
-                connection_network_data.total_bytes_uploaded,
-                connection_network_data.total_bytes_downloaded,
-                connection_network_data.process_name
+                rate_label,
+                display_bytes(connection.total_bytes_uploaded),
+                display_bytes(connection.total_bytes_downloaded),
+                connection.process_name
+            );
+            // the addresses as they are, whatever the hostnames and the interface shown above. A
+            // merged connection stands for any local address and port
+            let local_socket = connection.connection.local_socket;
+            let local = match local_socket.port {
+                0 => String::from("*"),
+                port => SocketAddr::new(local_socket.ip, port).to_string(),
+            };
+            line.push_str(&format!(
+                " local: {} remote: {}",
+                local,
+                SocketAddr::new(
+                    connection.connection.remote_socket.ip,
+                    connection.connection.remote_socket.port
+                )
+            ));
+            if self.opts.packet_stats {
+                line.push_str(&format!(" packets: {}", connection.total_packets));
+            }
+            if self.opts.tcp_stats {
+                line.push_str(&format!(
+                    " retransmitted: {}",
+                    connection.retransmitted_packets
+                ));
+            }
+            write_to_stdout(line);
+        }
+        for remote_address in snapshot.remote_addresses.iter().take(count) {
+            write_to_stdout(format!(
+                "remote_address: <{}> {} up/down {}: {}/{} connections: {}",
+                timestamp,
+                display_ip_or_host(remote_address.ip, &snapshot.ip_to_host),
+                rate_label,
+                display_bytes(remote_address.total_bytes_uploaded),
+                display_bytes(remote_address.total_bytes_downloaded),
+                remote_address.connection_count
             ));
         }
-        for (remote_address, remote_address_network_data) in &state.remote_addresses {
+        for group in self.groups.iter().take(count) {
             write_to_stdout(format!(
-                "remote_address: <{}> {} up/down Bps: {}/{} connections: {}",
+                "group: <{}> \"{}\" up/down {}: {}/{} connections: {}",
                 timestamp,
-                display_ip_or_host(*remote_address, ip_to_host),
-                remote_address_network_data.total_bytes_uploaded,
-                remote_address_network_data.total_bytes_downloaded,
-                remote_address_network_data.connection_count
+                group.key.join(" / "),
+                rate_label,
+                display_bytes(group.total_bytes_uploaded),
+                display_bytes(group.total_bytes_downloaded),
+                group.connection_count
             ));
         }
+        if self.opts.interface_breakdown {
+            for interface in self.interface_breakdown(snapshot) {
+                write_to_stdout(format!(
+                    "interface: <{}> \"{}\" up/down {}: {}/{} connections: {} link: {}",
+                    timestamp,
+                    interface.key[0],
+                    rate_label,
+                    display_bytes(interface.total_bytes_uploaded),
+                    display_bytes(interface.total_bytes_downloaded),
+                    interface.connection_count,
+                    self.link_utilization(&interface, snapshot.cumulative, "/")
+                ));
+            }
+        }
+        write_to_stdout(format!(
+            "session: <{}> up/down {}: {}/{} seconds: {}",
+            timestamp,
+            unit.json_name(),
+            display_bytes(self.session_totals.bytes_uploaded),
+            display_bytes(self.session_totals.bytes_downloaded),
+            self.session_totals.elapsed.as_secs()
+        ));
+        if self.opts.listening {
+            for (listening_socket, data) in state.listening_socket_rows().iter().take(count) {
+                write_to_stdout(format!(
+                    "listening: <{}> {} ({}) up/down {}: {}/{} connections: {} process: \"{}\"",
+                    timestamp,
+                    SocketAddr::new(listening_socket.ip, listening_socket.port),
+                    listening_socket.protocol,
+                    rate_label,
+                    display_bytes(data.total_bytes_uploaded),
+                    display_bytes(data.total_bytes_downloaded),
+                    data.connection_count,
+                    data.process_name
+                ));
+            }
+        }
+        if self.opts.unix_sockets {
+            for (path, data_for_path) in &state.unix_sockets {
+                write_to_stdout(format!(
+                    "unix_socket: <{}> \"{}\" sockets: {} processes: \"{}\"",
+                    timestamp,
+                    path,
+                    data_for_path.socket_count,
+                    data_for_path
+                        .process_names
+                        .iter()
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+        }
+    }
+    /// Appends what `output_text` would write (all of it even with --compact), the first error
+    /// stops the rest of the lines
+    pub fn output_to_log_file(&mut self, log_file: &mut LogFile) -> io::Result<()> {
+        let mut result = Ok(());
+        self.output_sections(&mut |line| {
+            if result.is_ok() {
+                result = log_file.write_line(&line);
+            }
+        });
+        result
+    }
+
+    pub fn output_to_csv_history(&mut self, csv_history: &mut CsvHistory) -> io::Result<()> {
+        csv_history.write_tick(&self.output_snapshot())
+    }
+
+    pub fn output_json(&mut self, write_to_stdout: &mut (dyn FnMut(String) + Send)) {
+        let snapshot = self.output_snapshot();
+        write_to_stdout(
+            JsonTick {
+                snapshot: &snapshot,
+                session_totals: &self.session_totals,
+                elapsed_seconds: self.started_at.elapsed().as_secs(),
+                unit: DataUnit::from_opts(&self.opts),
+                count: self.row_count(),
+                totals_only: self.opts.no_tables,
+            }
+            .to_json(),
+        );
     }
+    pub fn metrics(&self) -> String {
+        let rates = if self.snapshot.cumulative || self.opts.packets {
+            None
+        } else {
+            Some(&self.snapshot)
+        };
+        prometheus_metrics(
+            rates,
+            &self.state.launch_totals,
+            &self.ip_to_host,
+            &self.session_totals,
+        )
+    }
+    /// The tables are laid out anew for the size of the terminal at every draw, nothing of the
+    /// previous layout is kept but where the tables ended up (for the clicks and the scrolling)
     pub fn draw(&mut self, paused: bool) {
-        let state = &self.state;
+        if self.waiting_for_traffic {
+            let style = if self.opts.no_color {
+                Style::default().modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+                    .fg(self.opts.colors.middle)
+                    .modifier(Modifier::BOLD)
+            };
+            self.terminal
+                .draw(|mut frame| {
+                    let size = frame.size();
+                    let text = [Text::styled(" Waiting for traffic...", style)];
+                    Paragraph::new(text.iter()).render(&mut frame, size);
+                })
+                .unwrap();
+            return;
+        }
         let children = self.get_tables_to_display();
+        for pinned_row in self.pinned_rows.iter_mut() {
+            if let Some(row) = children
+                .get(pinned_row.table)
+                .and_then(|table| table.row(&pinned_row.row[0]))
+            {
+                pinned_row.row = row.clone();
+            }
+        }
+        // not through `shown_snapshot`, the terminal is borrowed mutably below
+        let snapshot = self
+            .frozen_snapshot
+            .as_ref()
+            .or_else(|| self.table_snapshot.as_ref())
+            .unwrap_or(&self.snapshot);
+        let frozen = self.frozen_snapshot.is_some();
+        let event_log = &self.event_log;
+        let show_event_log = self.show_event_log;
+        let show_help = self.show_help;
+        let hidden_rows = self.hidden_rows;
+        let warming_up = self.is_warming_up();
+        let resolving = self.resolving;
+        let opts = &self.opts;
+        let host_only_interfaces = &self.host_only_interfaces;
+        let search_query = &self.search_query;
+        let typing_search_query = self.typing_search_query;
+        let clipboard_notice = self
+            .clipboard_notice
+            .as_ref()
+            .map(|clipboard_notice| clipboard_notice.text);
+        let session_totals = &self.session_totals;
+        let capture_stats = self.capture_stats;
+        let connection_churn = self.connection_churn;
+        let dns_stats = self.dns_stats;
+        let budget = &self.budget;
+        let throughput_history = &self.throughput_history;
+        let mut table_areas = vec![];
         self.terminal
             .draw(|mut frame| {
                 let size = frame.size();
                 let total_bandwidth = TotalBandwidth {
-                    state: &state,
+                    snapshot,
+                    paused,
+                    frozen,
+                    warming_up,
+                    resolving,
+                    host_only_interfaces,
+                    hidden_rows,
+                    opts,
+                };
+                let help_text = HelpText {
                     paused,
+                    search_query: search_query.clone(),
+                    typing_search_query,
+                    clipboard_notice,
                 };
-                let help_text = HelpText { paused };
                 let layout = Layout {
                     header: total_bandwidth,
                     children,
+                    wide: opts.wide,
                     footer: help_text,
+                    summary: SessionSummary {
+                        totals: session_totals,
+                        opts,
+                    },
+                    log: if show_event_log {
+                        Some(LogPane { event_log })
+                    } else {
+                        None
+                    },
+                    stats: if opts.show_stats {
+                        Some(CaptureStatsLine {
+                            stats: capture_stats,
+                        })
+                    } else {
+                        None
+                    },
+                    churn: if opts.churn {
+                        Some(ChurnLine {
+                            churn: connection_churn,
+                        })
+                    } else {
+                        None
+                    },
+                    dns_stats: if opts.dns_stats {
+                        Some(DnsStatsLine { stats: dns_stats })
+                    } else {
+                        None
+                    },
+                    budget: budget.as_ref().map(|budget| BudgetLine { budget, opts }),
+                    graph: if opts.graph {
+                        Some(ThroughputGraph {
+                            history: throughput_history,
+                            opts,
+                        })
+                    } else {
+                        None
+                    },
+                    help: if show_help {
+                        Some(HelpOverlay {
+                            quit_keys: opts.quit_key,
+                        })
+                    } else {
+                        None
+                    },
                 };
-                layout.render(&mut frame, size);
+                table_areas = layout.render(&mut frame, size);
             })
             .unwrap();
+        // scrolling past the last row only goes as far as it is shown, so scrolling back up
+        // moves right away
+        if let Some(table_area) = table_areas.get(self.focused_table) {
+            self.scroll_offset = table_area.scroll_offset;
+        }
+        self.table_areas = table_areas;
+    }
+
+    fn interface_breakdown(&self, snapshot: &Snapshot) -> Vec<GroupEntry> {
+        let mut interfaces = interface_group_by().group(snapshot, self.opts.sort_by);
+        for name in &self.listened_interfaces {
+            if !interfaces.iter().any(|entry| &entry.key[0] == name) {
+                interfaces.push(GroupEntry {
+                    key: vec![name.clone()],
+                    connection_count: 0,
+                    total_bytes_uploaded: 0,
+                    total_bytes_downloaded: 0,
+                });
+            }
+        }
+        interfaces
     }
 
-    fn get_tables_to_display(&self) -> Vec<Table<'static>> {
+    // the share of its link's speed the interface uses up and down, `--` when the speed is
+    // unknown or there is no rate in bits to compare it with
+    fn link_utilization(
+        &self,
+        interface: &GroupEntry,
+        cumulative: bool,
+        separator: &str,
+    ) -> String {
+        match self.link_speeds.get(&interface.key[0]) {
+            Some(&bits_per_second) if !cumulative && !self.opts.packets => {
+                let percent = |bytes_per_second: u128| {
+                    bytes_per_second as f64 * 8.0 * 100.0 / bits_per_second as f64
+                };
+                format!(
+                    "{:.1}%{}{:.1}%",
+                    percent(interface.total_bytes_uploaded),
+                    separator,
+                    percent(interface.total_bytes_downloaded)
+                )
+            }
+            _ => String::from("--"),
+        }
+    }
+
+    // what the raw and JSON output are written from, in an order that does not change from one
+    // write of the same state to the next
+    fn output_snapshot(&self) -> Snapshot {
+        let snapshot = self.snapshot.in_stable_order(self.opts.sort_by);
+        match self.opts.top {
+            0 => snapshot,
+            top => snapshot.with_top_processes(top),
+        }
+    }
+
+    // what the tables and the total are drawn from, the raw and JSON output are never smoothed
+    // nor filtered by --min-rate
+    fn shown_snapshot(&self) -> &Snapshot {
+        self.frozen_snapshot
+            .as_ref()
+            .or_else(|| self.table_snapshot.as_ref())
+            .unwrap_or(&self.snapshot)
+    }
+
+    fn get_tables_to_display(&self) -> Vec<Table> {
         let opts = &self.opts;
+        if opts.no_tables {
+            return Vec::new();
+        }
+        let matching_snapshot;
+        let snapshot = match &self.search_query {
+            Some(query) => {
+                matching_snapshot = self.shown_snapshot().matching(query);
+                &matching_snapshot
+            }
+            None => self.shown_snapshot(),
+        };
+        let top_snapshot;
+        let snapshot = match opts.top {
+            0 => snapshot,
+            top => {
+                top_snapshot = snapshot.with_top_processes(top);
+                &top_snapshot
+            }
+        };
+        let host_addresses = if opts.highlight_host {
+            Some(HostAddresses {
+                ips: &self.host_ips,
+                use_color: !opts.no_color,
+            })
+        } else {
+            None
+        };
+        let host_addresses = host_addresses.as_ref();
+        let connection_history = if self.show_connection_history {
+            Some(&self.state.process_connection_history)
+        } else {
+            None
+        };
+        let process_users = if opts.show_user {
+            Some(&self.state.process_users)
+        } else {
+            None
+        };
+        // grouped here rather than with the other groups, so the search applies to it too
+        let domain_group_by = GroupBy {
+            dimensions: vec![GroupDimension::RemoteDomain],
+        };
+        let create_remote_addresses_table = || {
+            if opts.group_by_domain {
+                Table::create_grouped_table(
+                    &domain_group_by.group(snapshot, opts.sort_by),
+                    &domain_group_by,
+                    snapshot.cumulative,
+                    opts,
+                )
+            } else {
+                Table::create_remote_addresses_table(
+                    snapshot,
+                    opts,
+                    host_addresses,
+                    if opts.geo {
+                        Some(&self.ip_to_geo)
+                    } else {
+                        None
+                    },
+                )
+            }
+        };
         let mut children: Vec<Table> = Vec::new();
         if opts.processes {
-            children.push(Table::create_processes_table(&self.state));
+            children.push(Table::create_processes_table(
+                snapshot,
+                connection_history,
+                process_users,
+                opts,
+            ));
         }
         if opts.addresses {
-            children.push(Table::create_remote_addresses_table(
-                &self.state,
-                &self.ip_to_host,
-            ));
+            children.push(create_remote_addresses_table());
         }
         if opts.connections {
             children.push(Table::create_connections_table(
-                &self.state,
-                &self.ip_to_host,
+                snapshot,
+                opts,
+                host_addresses,
+                &self.capped_connections,
+            ));
+        }
+        if let Some(group_by) = &opts.group_by {
+            let table_groups;
+            let groups = match self
+                .frozen_snapshot
+                .as_ref()
+                .or_else(|| self.table_snapshot.as_ref())
+            {
+                Some(table_snapshot) => {
+                    table_groups = group_by.group(table_snapshot, opts.sort_by);
+                    &table_groups
+                }
+                None => &self.groups,
+            };
+            children.push(Table::create_grouped_table(
+                groups,
+                group_by,
+                snapshot.cumulative,
+                opts,
             ));
         }
-        if !(opts.processes || opts.addresses || opts.connections) {
+        if opts.listening {
+            children.push(Table::create_listening_sockets_table(&self.state, opts));
+        }
+        if !(opts.processes
+            || opts.addresses
+            || opts.connections
+            || opts.group_by.is_some()
+            || opts.listening)
+        {
             children = vec![
-                Table::create_processes_table(&self.state),
-                Table::create_remote_addresses_table(&self.state, &self.ip_to_host),
-                Table::create_connections_table(&self.state, &self.ip_to_host),
+                Table::create_processes_table(snapshot, connection_history, process_users, opts),
+                create_remote_addresses_table(),
+                Table::create_connections_table(
+                    snapshot,
+                    opts,
+                    host_addresses,
+                    &self.capped_connections,
+                ),
             ];
         }
+        if opts.interface_breakdown {
+            let interfaces = self.interface_breakdown(snapshot);
+            let mut table = Table::create_grouped_table(
+                &interfaces,
+                &interface_group_by(),
+                snapshot.cumulative,
+                opts,
+            );
+            // not worth a column of `--` when no link tells its speed
+            if !self.link_speeds.is_empty() {
+                table.add_link_utilization(
+                    interfaces
+                        .iter()
+                        .map(|interface| {
+                            self.link_utilization(interface, snapshot.cumulative, " / ")
+                        })
+                        .collect(),
+                );
+            }
+            children.push(table);
+        }
+        if opts.unix_sockets {
+            children.push(Table::create_unix_sockets_table(&self.state, opts));
+        }
+        if opts.percent {
+            // of what is shown, so the search and the filters are taken into account
+            for table in children.iter_mut() {
+                table.add_percentages();
+            }
+        }
+        if opts.bars {
+            for table in children.iter_mut() {
+                table.add_bars();
+            }
+        }
+        if self.is_warming_up() {
+            for table in children.iter_mut() {
+                table.hide_rates();
+            }
+        }
+        let idle_rate = display_upload_and_download(0, 0, snapshot.cumulative, opts);
+        for (index, table) in children.iter_mut().enumerate() {
+            let pinned = self
+                .pinned_rows
+                .iter()
+                .filter(|pinned_row| pinned_row.table == index)
+                .map(|pinned_row| pinned_row.row.clone())
+                .collect::<Vec<_>>();
+            table.keep_on_top(&pinned, &idle_rate);
+        }
+        if let Some(top_talker) = &self.top_talker {
+            if let Some(table) = children.get_mut(top_talker.table) {
+                table.pin_row(&top_talker.first_cell);
+            }
+        }
+        if let Some(selected_row) = &self.selected_row {
+            if let Some(table) = children.get_mut(selected_row.table) {
+                table.select_row(&selected_row.first_cell);
+            }
+        }
+        for (index, table) in children.iter_mut().enumerate() {
+            table.limit_rows(opts.count);
+            if let Some(previous_rates) = self.previous_row_rates.get(index) {
+                table.mark_changes(previous_rates);
+            }
+        }
+        if let Some(table) = children.get_mut(self.focused_table) {
+            table.focus(self.scroll_offset);
+        }
         children
     }
     pub fn update_state(
@@ -132,9 +950,460 @@ where
         connections_to_procs: HashMap<LocalSocket, String>,
         utilization: Utilization,
         ip_to_host: HashMap<IpAddr, String>,
+        window: Duration,
     ) {
-        self.state.update(connections_to_procs, utilization);
+        self.push_throughput(&utilization, window);
+        // the rows as they were shown, nothing is new before the first tick
+        if self.opts.highlight_changes && self.updates_received > 0 {
+            self.previous_row_rates = self
+                .get_tables_to_display()
+                .iter()
+                .map(Table::row_rates)
+                .collect();
+        }
+        if let Some(top_talker) = self.top_talker.as_mut() {
+            top_talker.ticks_left -= 1;
+            if top_talker.ticks_left == 0 {
+                self.top_talker = None;
+            }
+        }
+        if let Some(clipboard_notice) = self.clipboard_notice.as_mut() {
+            clipboard_notice.ticks_left -= 1;
+            if clipboard_notice.ticks_left == 0 {
+                self.clipboard_notice = None;
+            }
+        }
+        self.state.update(connections_to_procs, utilization, window);
+        self.updates_received = self.updates_received.saturating_add(1);
         self.ip_to_host.extend(ip_to_host);
+        let mut snapshot = Snapshot::new(&self.state, &self.ip_to_host, Local::now());
+        // every output shows the aliases, the capture goes on by the names of the interfaces
+        for entry in snapshot.connections.iter_mut() {
+            entry.interface_name = self.display_interface_name(&entry.interface_name);
+        }
+        let previous_connections: HashSet<Connection> = self
+            .snapshot
+            .connections
+            .iter()
+            .map(|entry| entry.connection)
+            .collect();
+        let current_connections: HashSet<Connection> = snapshot
+            .connections
+            .iter()
+            .map(|entry| entry.connection)
+            .collect();
+        self.connection_churn = ConnectionChurn {
+            opened: current_connections
+                .difference(&previous_connections)
+                .count(),
+            closed: previous_connections
+                .difference(&current_connections)
+                .count(),
+        };
+        for entry in &snapshot.connections {
+            if !previous_connections.contains(&entry.connection) {
+                self.event_log.push(format!(
+                    "new connection: {} process: \"{}\"",
+                    display_connection_string(
+                        &entry.connection,
+                        &snapshot.ip_to_host,
+                        &entry.interface_name,
+                        !self.opts.numeric_ports
+                    ),
+                    entry.process_name
+                ));
+            }
+        }
+        if let Some(group_by) = &self.opts.group_by {
+            self.groups = group_by.group(&snapshot, self.opts.sort_by);
+        }
+        let sort_by = self.opts.sort_by;
+        let smoothed_snapshot = self
+            .smoothing
+            .as_mut()
+            .map(|smoothing| smoothing.smooth(&snapshot, sort_by));
+        self.capped_connections = match (&self.cap_detection, &self.smoothing) {
+            (Some(cap_detection), Some(smoothing)) => smoothing
+                .connection_histories()
+                .filter(|(_, history)| cap_detection.looks_capped(history))
+                .map(|(connection, _)| *connection)
+                .collect(),
+            _ => HashSet::new(),
+        };
+        // totals since launch are no rates, so they are all shown
+        let min_rate = self.opts.min_rate.bytes_per_second as u128;
+        self.table_snapshot = if min_rate > 0 && !snapshot.cumulative {
+            let shown_snapshot = smoothed_snapshot.as_ref().unwrap_or(&snapshot);
+            let fast_snapshot = shown_snapshot.at_least(min_rate);
+            self.hidden_rows = shown_snapshot.row_count() - fast_snapshot.row_count();
+            Some(fast_snapshot)
+        } else {
+            self.hidden_rows = 0;
+            smoothed_snapshot
+        };
+        self.snapshot = snapshot;
+    }
+    // how many rows of each table the raw and JSON output hold, see --count
+    fn row_count(&self) -> usize {
+        match self.opts.count {
+            0 => usize::MAX,
+            count => count,
+        }
+    }
+    // everything captured in the window, whatever the tables are filtered by or whether they
+    // add up since launch
+    fn push_throughput(&mut self, utilization: &Utilization, window: Duration) {
+        let window_millis = window.as_millis().max(1);
+        let (uploaded, downloaded) = utilization.connections.values().fold(
+            (0, 0),
+            |(uploaded, downloaded), connection_info| {
+                (
+                    uploaded + connection_info.total_bytes_uploaded,
+                    downloaded + connection_info.total_bytes_downloaded,
+                )
+            },
+        );
+        self.throughput_history.push_back((
+            uploaded * 1000 / window_millis,
+            downloaded * 1000 / window_millis,
+        ));
+        if self.throughput_history.len() > THROUGHPUT_HISTORY_LENGTH {
+            self.throughput_history.pop_front();
+        }
+    }
+    fn is_warming_up(&self) -> bool {
+        // the first update only holds data from launch until the first tick, so rates are only
+        // meaningful once a full interval follows it
+        self.updates_received < 2
+    }
+    pub fn cycle_sort_by(&mut self) {
+        self.opts.sort_by = self.opts.sort_by.next();
+        self.state.sort_by = self.opts.sort_by;
+    }
+    /// Handles a click at `x`, `y` (0-based) as it landed on the tables last drawn: the table is
+    /// focused, a header sorts by its column and a row is selected (or unselected)
+    pub fn click(&mut self, x: u16, y: u16) -> ClickAction {
+        let (table_index, table_click) = match self
+            .table_areas
+            .iter()
+            .enumerate()
+            .find(|(_, table_area)| table_area.contains(x, y))
+        {
+            Some((table_index, table_area)) => (table_index, table_area.click(x, y)),
+            None => return ClickAction::Missed,
+        };
+        if table_index != self.focused_table {
+            self.focused_table = table_index;
+            self.scroll_offset = 0;
+            self.top_talker = None;
+        }
+        let tables = self.get_tables_to_display();
+        let table = match tables.get(table_index) {
+            Some(table) => table,
+            None => return ClickAction::Missed,
+        };
+        match table_click {
+            TableClick::Header(column) => match table.sort_column(column) {
+                Some(column_sort) => {
+                    self.opts.sort_by = self.opts.sort_by.clicked(column_sort);
+                    self.state.sort_by = self.opts.sort_by;
+                    ClickAction::Sorted(self.opts.sort_by)
+                }
+                None => ClickAction::Focused,
+            },
+            TableClick::Row(row) => {
+                let first_cell = table.first_cell(row).cloned();
+                let unselected = match (&self.selected_row, &first_cell) {
+                    (Some(selected_row), Some(first_cell)) => {
+                        selected_row.table == table_index && &selected_row.first_cell == first_cell
+                    }
+                    _ => false,
+                };
+                self.selected_row = if unselected {
+                    None
+                } else {
+                    first_cell.clone().map(|first_cell| SelectedRow {
+                        table: table_index,
+                        first_cell,
+                    })
+                };
+                ClickAction::Selected(if unselected { None } else { first_cell })
+            }
+            TableClick::Elsewhere => ClickAction::Focused,
+        }
+    }
+    /// Pins the selected row to the top of its table, where it stays even once it goes idle, or
+    /// unpins it if it already is
+    pub fn toggle_pin(&mut self) {
+        let selected_row = match &self.selected_row {
+            Some(selected_row) => selected_row,
+            None => return,
+        };
+        let pinned = self.pinned_rows.iter().position(|pinned_row| {
+            pinned_row.table == selected_row.table && pinned_row.row[0] == selected_row.first_cell
+        });
+        match pinned {
+            Some(index) => {
+                self.pinned_rows.remove(index);
+            }
+            None => {
+                let tables = self.get_tables_to_display();
+                if let Some(row) = tables
+                    .get(selected_row.table)
+                    .and_then(|table| table.row(&selected_row.first_cell))
+                {
+                    self.pinned_rows.push(PinnedRow {
+                        table: selected_row.table,
+                        row: row.clone(),
+                    });
+                }
+            }
+        }
+    }
+    /// Copies what tells the selected row apart (eg. the connection and its process) with
+    /// `copy_to_clipboard`, the footer says how it went for a few ticks. Without a clipboard (eg.
+    /// over ssh) nothing is copied, the footer only says so
+    pub fn copy_selected_row(&mut self, copy_to_clipboard: fn(&str) -> io::Result<()>) {
+        let selected_row = match &self.selected_row {
+            Some(selected_row) => selected_row,
+            None => return,
+        };
+        let tables = self.get_tables_to_display();
+        let row_keys = match tables
+            .get(selected_row.table)
+            .and_then(|table| table.row_keys(&selected_row.first_cell))
+        {
+            Some(row_keys) => row_keys,
+            None => return,
+        };
+        let text = match copy_to_clipboard(&row_keys) {
+            Ok(()) => "copied",
+            Err(_) => "no clipboard to copy to",
+        };
+        self.clipboard_notice = Some(ClipboardNotice {
+            text,
+            ticks_left: CLIPBOARD_NOTICE_TICKS,
+        });
+    }
+    pub fn focus_next_table(&mut self) {
+        let table_count = self.get_tables_to_display().len();
+        self.focused_table = (self.focused_table + 1) % table_count.max(1);
+        self.scroll_offset = 0;
+        self.top_talker = None;
+    }
+    pub fn focus_previous_table(&mut self) {
+        let table_count = self.get_tables_to_display().len().max(1);
+        self.focused_table = (self.focused_table + table_count - 1) % table_count;
+        self.scroll_offset = 0;
+        self.top_talker = None;
+    }
+    /// Pins the busiest row of the focused table to its top for a few ticks, scrolled up to it
+    pub fn jump_to_top_talker(&mut self) {
+        let tables = self.get_tables_to_display();
+        let first_cell = match tables.get(self.focused_table) {
+            Some(table) => table.busiest_row(),
+            None => None,
+        };
+        self.top_talker = first_cell.map(|first_cell| TopTalker {
+            table: self.focused_table,
+            first_cell,
+            ticks_left: TOP_TALKER_PIN_TICKS,
+        });
+        self.scroll_offset = 0;
+    }
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.scroll_offset = self.scroll_offset.saturating_sub(rows);
+    }
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.scroll_offset = self.scroll_offset.saturating_add(rows);
+    }
+    pub fn is_typing_search_query(&self) -> bool {
+        self.typing_search_query
+    }
+    pub fn start_search(&mut self) {
+        // a query that is still applied is edited rather than started over
+        self.search_query.get_or_insert_with(String::new);
+        self.typing_search_query = true;
+    }
+    pub fn push_search_char(&mut self, character: char) {
+        if let Some(query) = self.search_query.as_mut() {
+            query.push(character);
+        }
+    }
+    pub fn pop_search_char(&mut self) {
+        if let Some(query) = self.search_query.as_mut() {
+            query.pop();
+        }
+    }
+    pub fn finish_search(&mut self) {
+        self.typing_search_query = false;
+        if self.search_query.as_deref() == Some("") {
+            self.search_query = None;
+        }
+    }
+    pub fn clear_search(&mut self) {
+        self.typing_search_query = false;
+        self.search_query = None;
+    }
+    pub fn update_session_totals(&mut self, utilization: &Utilization, elapsed: Duration) {
+        self.session_totals = if self.opts.packets {
+            SessionTotals {
+                bytes_uploaded: utilization.lifetime_packets_uploaded,
+                bytes_downloaded: utilization.lifetime_packets_downloaded,
+                elapsed,
+            }
+        } else {
+            SessionTotals {
+                bytes_uploaded: utilization.lifetime_bytes_uploaded,
+                bytes_downloaded: utilization.lifetime_bytes_downloaded,
+                elapsed,
+            }
+        };
+        self.capture_stats = utilization.capture_stats();
+    }
+    /// Starts the session over from now, the rates of the next tick are not affected
+    pub fn reset_session(&mut self) {
+        self.state.reset_session();
+        self.session_totals = SessionTotals::default();
+        self.started_at = Instant::now();
+        self.event_log.push(String::from("session counters reset"));
+    }
+    pub fn update_process_pid_counts(
+        &mut self,
+        sockets_to_procs: &HashMap<LocalSocket, String>,
+        sockets_to_pids: &HashMap<LocalSocket, u32>,
+    ) {
+        self.state
+            .update_process_pid_counts(sockets_to_procs, sockets_to_pids);
+    }
+    pub fn update_process_users(
+        &mut self,
+        sockets_to_procs: &HashMap<LocalSocket, String>,
+        sockets_to_users: &HashMap<LocalSocket, String>,
+    ) {
+        self.state
+            .update_process_users(sockets_to_procs, sockets_to_users);
+    }
+    pub fn update_listening_sockets(&mut self, listening_sockets: HashMap<LocalSocket, String>) {
+        self.state.update_listening_sockets(listening_sockets);
+    }
+    pub fn update_unix_sockets(&mut self, unix_sockets: Vec<UnixSocket>) {
+        self.state.update_unix_sockets(unix_sockets);
+    }
+    pub fn update_ip_to_geo(&mut self, ip_to_geo: HashMap<IpAddr, String>) {
+        self.ip_to_geo = ip_to_geo;
+    }
+    pub fn update_dns_stats(&mut self, dns_stats: DnsStats) {
+        self.dns_stats = dns_stats;
+    }
+    pub fn update_budget(&mut self, budget: Budget) {
+        self.budget = Some(budget);
+    }
+    pub fn set_listened_interfaces(&mut self, listened_interfaces: Vec<String>) {
+        self.listened_interfaces = listened_interfaces
+            .iter()
+            .map(|name| self.display_interface_name(name))
+            .collect();
+    }
+    pub fn set_link_speeds(&mut self, link_speeds: HashMap<String, u64>) {
+        self.link_speeds = link_speeds
+            .into_iter()
+            .map(|(name, bits_per_second)| (self.display_interface_name(&name), bits_per_second))
+            .collect();
+    }
+    pub fn set_host_only_interfaces(&mut self, host_only_interfaces: Vec<String>) {
+        self.host_only_interfaces = host_only_interfaces
+            .iter()
+            .map(|name| self.display_interface_name(name))
+            .collect();
+    }
+    /// The name `name` is shown by, see --alias
+    pub fn display_interface_name(&self, name: &str) -> String {
+        display_interface_name(name, &self.opts.alias)
+    }
+    /// Keeps the tables as they are shown now, or lets them follow the capture again
+    pub fn toggle_frozen(&mut self) {
+        self.frozen_snapshot = match self.frozen_snapshot {
+            Some(_) => None,
+            None => Some(self.shown_snapshot().clone()),
+        };
+    }
+    pub fn set_processes_unknown(&mut self) {
+        self.state.processes_unknown = true;
+    }
+    pub fn set_waiting_for_traffic(&mut self, waiting_for_traffic: bool) {
+        self.waiting_for_traffic = waiting_for_traffic;
+    }
+    pub fn log_lost_interface(&mut self, interface_name: &str, err: &io::Error) {
+        self.event_log.push(format!(
+            "interface {} is gone and no longer captured: {}",
+            self.display_interface_name(interface_name),
+            err
+        ));
+    }
+    pub fn log_output_error(&mut self, output_name: &str, err: &io::Error) {
+        self.event_log.push(format!(
+            "failed to write {}, stopped writing to it: {}",
+            output_name, err
+        ));
+    }
+    /// Something that is likely wrong with the capture, the event log is opened to show it
+    pub fn log_warning(&mut self, warning: &str) {
+        self.event_log.push(format!("warning: {}", warning));
+        self.show_event_log = true;
+    }
+    /// The alerts of the latest update, they also go to the event log
+    pub fn check_alert(&mut self, rate_alert: &mut RateAlert) -> Vec<String> {
+        let alerts = rate_alert.check(&self.snapshot);
+        for alert in &alerts {
+            self.event_log.push(alert.clone());
+        }
+        alerts
+    }
+    pub fn toggle_help(&mut self) {
+        self.show_help = !self.show_help;
+    }
+    pub fn is_showing_help(&self) -> bool {
+        self.show_help
+    }
+    pub fn toggle_event_log(&mut self) {
+        self.show_event_log = !self.show_event_log;
+    }
+    pub fn toggle_connection_history(&mut self) {
+        self.show_connection_history = !self.show_connection_history;
+    }
+    pub fn toggle_session_bytes(&mut self) {
+        self.opts.session_bytes = !self.opts.session_bytes;
+    }
+    /// The sort, focused table and toggled columns, to be remembered for the next run
+    pub fn layout_state(&self) -> LayoutState {
+        LayoutState {
+            sort_by: self.opts.sort_by,
+            focused_table: self.focused_table,
+            session_bytes: self.opts.session_bytes,
+            connection_history: self.show_connection_history,
+        }
+    }
+    /// Puts the tables back the way a previous run left them. A focused table that is not there
+    /// anymore (eg. since other tables are shown now) leaves the first one focused
+    pub fn restore_layout(&mut self, layout: LayoutState) {
+        self.opts.sort_by = layout.sort_by;
+        self.state.sort_by = layout.sort_by;
+        self.opts.session_bytes = layout.session_bytes;
+        self.show_connection_history = layout.connection_history;
+        self.focused_table = if layout.focused_table < self.get_tables_to_display().len() {
+            layout.focused_table
+        } else {
+            0
+        };
+    }
+    pub fn set_resolving(&mut self, resolving: bool) {
+        self.resolving = resolving;
+        self.event_log.push(String::from(if resolving {
+            "resumed resolving hostnames"
+        } else {
+            "stopped resolving hostnames, press <r> to resume"
+        }));
     }
     pub fn end(&mut self) {
         self.terminal.clear().unwrap();
@@ -0,0 +1,9 @@
+//! The capture and the accounting behind bandwhich, without its terminal UI, to embed bandwidth
+//! monitoring in other programs (see `Monitor`).
+#![deny(clippy::all)]
+
+pub mod model;
+mod monitor;
+pub mod network;
+
+pub use monitor::*;
@@ -1,54 +1,196 @@
 #![deny(clippy::all)]
 
+mod config;
 mod display;
-mod network;
 mod os;
+mod self_test;
 #[cfg(test)]
 mod tests;
+mod tick;
 
-use display::{RawTerminalBackend, Ui};
+use bandwhich::network;
+use display::{
+    configured_outputs, default_budget_path, default_layout_path, display_json_error, load_theme,
+    new_state, parse_budget, BandwidthLimit, BorderStyle, Budget, ClickAction, ConnectionDirection,
+    FrameExportBackend, GroupBy, GroupDimension, InterfaceAlias, JsonCapabilities,
+    JsonOpenConnections, LayoutState, MetricsServer, NetworkTag, OutputSink, OutputTarget,
+    QuitKeys, RateAlert, RawTerminalBackend, SortBy, Theme, Ui, UnitPrefix,
+};
 use network::{
-    dns::{self, IpTable},
-    Connection, LocalSocket, Sniffer, Utilization,
+    capture_queue, dns, CaptureDirection, CaptureFilter, Connection, GeoIp, GeoIpDatabase,
+    LocalSocket, Protocol, Sample, Sniffer, UnixSocket, CAPTURE_QUEUE_LENGTH,
 };
-use os::OnSigWinch;
+use os::{Ancestor, OnSigTerm, OnSigWinch, StartupError};
+use self_test::{parse_self_test_rate, run_self_test, SELF_TEST_SECONDS};
+use tick::{finish_run, run_tick, AdaptiveRefresh, DisplayState, SharedState, Tick, TickOptions};
 
+use ::chrono::Local;
 use ::pnet_bandwhich_fork::datalink::{DataLinkReceiver, NetworkInterface};
 use ::std::collections::HashMap;
+use ::std::iter;
+use ::std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
 use ::std::sync::atomic::{AtomicBool, Ordering};
-use ::std::sync::{Arc, Mutex};
-use ::std::thread::park_timeout;
+use ::std::sync::{Arc, Mutex, MutexGuard};
+use ::std::thread::{park_timeout, JoinHandle};
 use ::std::{thread, time};
-use ::termion::event::{Event, Key};
+use ::termion::event::{Event, Key, MouseButton, MouseEvent};
 use ::tui::backend::Backend;
 
 use std::process;
 
+use ::std::env;
 use ::std::io;
+use ::std::path::PathBuf;
 use ::std::time::Instant;
+use ::termion::input::MouseTerminal;
 use ::termion::raw::IntoRawMode;
 use ::tui::backend::TermionBackend;
 use structopt::StructOpt;
 
+// the default refresh interval, see --interval
 const DISPLAY_DELTA: time::Duration = time::Duration::from_millis(1000);
+// long enough for the exported frame to show a full interval of traffic
+const EXPORT_FRAME_DURATION_SECONDS: u64 = 2;
+// how many rows <PAGE UP> and <PAGE DOWN> scroll the focused table by
+const SCROLL_PAGE_ROWS: usize = 10;
 
-#[derive(StructOpt, Debug)]
+#[derive(StructOpt, Debug, Default)]
 #[structopt(name = "bandwhich")]
 pub struct Opt {
+    #[structopt(long, parse(from_os_str))]
+    /// Read the defaults of these options from this file instead of ~/.config/bandwhich/config.toml (eg. `processes = true` or `interface = "eth0"`)
+    config: Option<PathBuf>,
+    #[structopt(long, conflicts_with = "config")]
+    /// Do not read the defaults of these options from a file
+    no_config: bool,
     #[structopt(short, long)]
-    /// The network interface to listen on, eg. eth0
+    /// The network interfaces to listen on separated by commas, eg. eth0 or eth0,wg0, or `any` for all of them but loopback
     interface: Option<String>,
+    #[structopt(long, parse(from_os_str), conflicts_with_all = &["interface", "exclude-interface"])]
+    /// Replay the traffic of this pcap file (eg. from tcpdump -w) as far apart as it was captured and exit at its end, instead of capturing (processes show up as n/a)
+    read_pcap: Option<PathBuf>,
+    #[structopt(long, number_of_values = 1)]
+    /// Do not listen on this network interface, eg. docker0, can be given more than once
+    exclude_interface: Vec<String>,
     #[structopt(short, long)]
     /// Machine friendlier output
     raw: bool,
+    #[structopt(long, conflicts_with = "raw")]
+    /// Machine friendlier output, one JSON object per line
+    json: bool,
+    #[structopt(long)]
+    /// Print the interfaces found and whether they can be captured, whether hostnames can be resolved and where the sockets of the processes are looked up, then exit (for bug reports)
+    print_capabilities: bool,
+    #[structopt(long, conflicts_with_all = &["read-pcap", "print-capabilities"])]
+    /// Print the open connections and the processes they belong to, then exit without capturing (no rates)
+    once: bool,
+    #[structopt(long, hidden = true, parse(try_from_str = parse_self_test_rate), conflicts_with_all = &["read-pcap", "print-capabilities", "once"])]
+    /// Count this many synthetic packets per second the way the captured ones are, for --duration seconds (10 without it), then print how fast they were counted and exit, without capturing
+    self_test: Option<u64>,
+    #[structopt(long)]
+    /// Click a column header to sort by it and a row to select it (the terminal then needs shift held to select text)
+    mouse: bool,
     #[structopt(short, long)]
     /// Do not attempt to resolve IPs to their hostnames
     no_resolve: bool,
+    #[structopt(long)]
+    /// Only count the traffic matching this, eg. "host 10.0.0.5 or net 192.168.0.0/16 and port 443" (host, net and port terms joined by and/or, and goes first)
+    filter: Option<CaptureFilter>,
+    #[structopt(long, default_value = "1/1")]
+    /// Only attribute one in this many packets to its connection, counted that many times, eg. 1/10 for links too fast to keep up with otherwise (the tables are estimates then, the session totals stay exact)
+    sample: Sample,
+    #[structopt(long)]
+    /// Attribute traffic of descendant processes to this ancestor, by pid or name (linux only)
+    attribute_to_ancestor: Option<Ancestor>,
+    #[structopt(long, parse(try_from_str = parse_interval))]
+    /// How often to refresh, in seconds (eg. 0.5) [default: 1]
+    interval: Option<time::Duration>,
+    #[structopt(long, parse(try_from_str = parse_interval), conflicts_with = "interval")]
+    /// How often to redraw, in seconds (eg. 0.25), like --interval but with the rates still averaged over the --rate-window
+    redraw_interval: Option<time::Duration>,
+    #[structopt(long, parse(try_from_str = parse_interval))]
+    /// How often to look up which process each socket belongs to, in seconds, the tables are drawn in between [default: the --interval]
+    sockets_interval: Option<time::Duration>,
+    #[structopt(long)]
+    /// Draw the next tick early (down to a quarter of the --interval) when a lot of traffic comes in, and late (up to four times the --interval) when none does, the rates stay per second
+    adaptive: bool,
+    #[structopt(long, parse(try_from_str = parse_dns_server))]
+    /// Resolve hostnames with this DNS server instead of the system's, eg. 10.0.0.1 or 10.0.0.1:5353
+    dns_server: Option<SocketAddr>,
+    #[structopt(long, parse(from_os_str))]
+    /// Keep resolved hostnames in this file, so the next run does not have to resolve them again
+    dns_cache: Option<PathBuf>,
+    #[structopt(long, default_value = "86400")]
+    /// How long a hostname from the --dns-cache file is used for, in seconds
+    dns_cache_ttl: u64,
+    #[structopt(long, parse(from_os_str))]
+    /// Remember the sort, the focused table and the columns toggled with keys in this file between runs [default: $XDG_STATE_HOME/bandwhich/layout]
+    layout_file: Option<PathBuf>,
+    #[structopt(long)]
+    /// Forget the remembered sort, focused table and toggled columns, and start from the options
+    reset_layout: bool,
+    #[structopt(long, parse(from_os_str), number_of_values = 1)]
+    /// Look the remote addresses up in this MaxMind DB file for --geo, eg. GeoLite2-Country.mmdb or GeoLite2-ASN.mmdb, can be given more than once
+    geoip: Vec<PathBuf>,
+    #[structopt(long, parse(from_os_str))]
+    /// Also append the raw output of every tick to this file, the tables are still shown
+    log_file: Option<PathBuf>,
+    #[structopt(long, default_value = "10485760")]
+    /// Once the --log-file would grow past this many bytes it is moved to <log-file>.1 (and that one to .2...)
+    log_max_size: u64,
+    #[structopt(long, parse(from_os_str))]
+    /// Also write the total upload and download of every tick to this CSV file (timestamp,up,down), for a spreadsheet
+    csv_history: Option<PathBuf>,
+    #[structopt(long, requires = "csv-history")]
+    /// Write a row per process per tick to the --csv-history file instead (timestamp,process,up,down)
+    csv_per_process: bool,
+    #[structopt(long)]
+    /// Serve the bytes since launch and the current rates in the Prometheus text format at http://localhost:<port>/metrics
+    metrics_port: Option<u16>,
+    #[structopt(long, parse(from_os_str))]
+    /// Also stream the JSON output of every tick to this Unix socket, connecting to it or creating it
+    socket: Option<PathBuf>,
+    #[structopt(long, number_of_values = 1)]
+    /// Also write the output of every tick here, can be given more than once: stdout-raw, stdout-json, file:<path> (JSON if it ends with .json, raw otherwise), socket:<path> or none (drops the ones before it)
+    output: Vec<OutputTarget>,
+    #[structopt(long, env = "BANDWHICH_DURATION")]
+    /// Stop capturing and exit after this many seconds, with --raw or --json only the totals of the whole run are printed
+    duration: Option<u64>,
+    #[structopt(long)]
+    /// Stop capturing and exit once the traffic stayed below 100 bytes per second for this many seconds, eg. when what was watched is done
+    idle_timeout: Option<u64>,
+    #[structopt(long)]
+    /// Exit with code 3 if upload and download combined went above this rate, eg. 100Mbps or 5MBps
+    fail_if_above: Option<BandwidthLimit>,
+    #[structopt(long)]
+    /// Alert once a process goes above this rate, eg. 5MB or 100Mbps, and again only after it dropped below it (on stderr unless the tables are drawn there, and in the event log)
+    alert_threshold: Option<BandwidthLimit>,
+    #[structopt(long, requires = "alert-threshold")]
+    /// Only alert on the processes whose name contains this (case insensitive), eg. firefox
+    alert_process: Option<String>,
+    #[structopt(long)]
+    /// Show nothing until the first traffic shows up, --duration then counts from there
+    quiet_until_traffic: bool,
+    #[structopt(long, parse(try_from_str = parse_budget), conflicts_with = "packets")]
+    /// Count the traffic of every run against this many bytes per --budget-period, eg. 50GB, and show what is left and when it runs out at this pace
+    budget: Option<u128>,
+    #[structopt(long, default_value = "30")]
+    /// How many days the --budget lasts before it starts over
+    budget_period: u32,
+    #[structopt(long, parse(from_os_str))]
+    /// Remember what the --budget used in this file between runs [default: $XDG_STATE_HOME/bandwhich/budget]
+    budget_file: Option<PathBuf>,
+    #[structopt(long, conflicts_with_all = &["raw", "json"])]
+    /// On quit, print what moved in the whole session, the 5 processes and remote hosts that moved the most of it and how long it took
+    summary: bool,
+    #[structopt(long, parse(from_os_str))]
+    /// Render to this file instead of the terminal and exit (SVG if it ends with .svg, ANSI text otherwise)
+    export_frame: Option<PathBuf>,
     #[structopt(flatten)]
     render_opts: RenderOpts,
 }
 
-#[derive(StructOpt, Debug)]
+#[derive(StructOpt, Debug, Default)]
 pub struct RenderOpts {
     #[structopt(short, long)]
     /// Show processes table only
@@ -62,159 +204,1187 @@ pub struct RenderOpts {
     #[structopt(short, long)]
     /// Show total (cumulative) usages
     total_utilization: bool,
+    #[structopt(long)]
+    /// Show and print only the total upload and download, without looking up which process each socket belongs to (much cheaper on a busy host)
+    no_tables: bool,
+    #[structopt(long)]
+    /// Show the pid next to each process name, eg. `firefox (4242)`
+    show_pid: bool,
+    #[structopt(long)]
+    /// Show the container (docker, containerd or kubernetes) each process runs in next to its name, eg. `nginx [web]`, by name if docker has it and by id otherwise (linux only)
+    containers: bool,
+    #[structopt(long)]
+    /// Show the user each process runs as in the processes table (the uid when it has no name)
+    show_user: bool,
+    #[structopt(long)]
+    /// Show one row per process name with how many processes share it, eg. `firefox (×14)`, even with --show-pid
+    group_processes: bool,
+    #[structopt(long)]
+    /// Show the full command line of each process instead of its name (the executable path on macOS), cut on the left when it does not fit
+    full_cmdline: bool,
+    #[structopt(long)]
+    /// Also show a table of the traffic on each interface listened on, in the raw output too, with the share of its link speed it uses when the link tells it (linux only)
+    interface_breakdown: bool,
+    #[structopt(long, number_of_values = 1)]
+    /// Show an interface by another name, eg. enp0s31f6=lan, can be given more than once (it is still captured by its own)
+    alias: Vec<InterfaceAlias>,
+    #[structopt(long, number_of_values = 1)]
+    /// Show the remote addresses in a network with a label in a color of their own in the remote addresses and connections tables, eg. 192.168.1.0/24=nas, can be given more than once (the most specific network wins)
+    tag: Vec<NetworkTag>,
+    #[structopt(long, requires = "geoip")]
+    /// Add the country and autonomous system of each remote address to the remote addresses table, from the --geoip files
+    geo: bool,
+    #[structopt(short, long)]
+    /// Also show a table of UNIX domain sockets (linux only, no rates available)
+    unix_sockets: bool,
+    #[structopt(long, conflicts_with = "json")]
+    /// Raw output of a single short line per tick, the totals and the busiest process, eg. for a status bar
+    compact: bool,
+    #[structopt(long, conflicts_with = "compact")]
+    /// Start the raw output of every tick with a line of its wall-clock time in RFC3339, eg. `time: 2020-04-27T17:06:40+02:00`
+    timestamp: bool,
+    #[structopt(long, requires = "timestamp")]
+    /// Give the --timestamp in unix seconds instead, eg. `time: 1588000000`
+    epoch: bool,
+    #[structopt(long)]
+    /// Show only the table of the sockets listening for connections, with the traffic of the connections they accepted
+    listening: bool,
+    #[structopt(long)]
+    /// Show the remote ports of the connections table as numbers, eg. 443 rather than https
+    numeric_ports: bool,
+    #[structopt(long)]
+    /// Show exact byte counts with thousands separators instead of scaled units
+    exact_bytes: bool,
+    #[structopt(long)]
+    /// Show rates in bits per second (Kibit/s, Mibit/s...) instead of bytes, in the raw and JSON output too
+    bits: bool,
+    #[structopt(long, conflicts_with_all = &["bits", "alert-threshold", "fail-if-above"])]
+    /// Count packets rather than bytes, the tables, the totals and the raw and JSON output show packets per second (eg. to spot floods of small packets)
+    packets: bool,
+    #[structopt(long)]
+    /// Scale sizes and rates in powers of 1000 (kB, MB...) instead of 1024 (KiB, MiB...)
+    si_units: bool,
+    #[structopt(long)]
+    /// Do not use colors, eg. for the rows by how busy they are (also when NO_COLOR is set or the output is not a terminal)
+    no_color: bool,
+    #[structopt(long)]
+    /// The colors of the headers, the selected row, the borders, the rows by how busy they are and the rest of what is colored: dark (the default), light, solarized, or a TOML file of them (see the README), the default ones are used if it cannot be read
+    theme: Option<String>,
+    #[structopt(skip)]
+    colors: Theme,
+    #[structopt(long, default_value = "plain", possible_values = &["plain", "ascii", "rounded", "none"])]
+    /// How the borders of the tables are drawn: ascii for fonts without box-drawing lines (eg. over SSH), rounded corners, or none to fit a row more on a small terminal
+    border: BorderStyle,
+    #[structopt(long)]
+    /// Add a column of the bytes each connection moved since launch to the connections table, can be toggled with the `t` key (needs a wide terminal)
+    session_bytes: bool,
+    #[structopt(long)]
+    /// Add an up:down ratio column to the connections table (needs a wide terminal)
+    up_down_ratio: bool,
+    #[structopt(long)]
+    /// Add packet rate and average packet size columns to the connections table (needs a wide terminal)
+    packet_stats: bool,
+    #[structopt(long)]
+    /// Add a column of the share of tcp segments that were sent again or came out of order to the connections table, this remembers where each connection is at (needs a wide terminal)
+    tcp_stats: bool,
+    #[structopt(long)]
+    /// Add a column that guesses from the ports whether each connection is encrypted (🔒) or plaintext (🔓) to the connections table, eg. 🔓 for http on port 80 (needs a wide terminal)
+    security: bool,
+    #[structopt(long, number_of_values = 1)]
+    /// Guess the connections on this port to be plaintext for --security, eg. 8000, can be given more than once
+    plaintext_port: Vec<u16>,
+    #[structopt(long, number_of_values = 1)]
+    /// Guess the connections on this port to be encrypted for --security, eg. 8883, can be given more than once
+    encrypted_port: Vec<u16>,
+    #[structopt(long)]
+    /// Add a column of the share of the table's traffic each row has to the tables with a rate, eg. 80.0%
+    percent: bool,
+    #[structopt(long)]
+    /// Draw a bar of each row's rate against the busiest row's after the columns of the tables with a rate
+    bars: bool,
+    #[structopt(long)]
+    /// Put the processes and connections tables side by side with the remote addresses below on a terminal of at least 180 columns and 30 rows
+    wide: bool,
+    #[structopt(long)]
+    /// Show a graph of the total upload and download of the last minute (or 60 intervals) above the tables
+    graph: bool,
+    #[structopt(long)]
+    /// Show how many packets were captured and how many of them could not be counted below the tables
+    show_stats: bool,
+    #[structopt(long)]
+    /// Show how many connections showed up and how many went away since the last tick below the tables, in the raw output too
+    churn: bool,
+    #[structopt(long, conflicts_with = "no-resolve")]
+    /// Show how long the hostname lookups of the last tick took and how many are still pending below the tables
+    dns_stats: bool,
+    #[structopt(long)]
+    /// Show the rows whose rate changed since the last tick in bold, and the new ones underlined too
+    highlight_changes: bool,
+    #[structopt(long)]
+    /// Highlight remote addresses that belong to this host (set NO_COLOR or --no-color to annotate them instead)
+    highlight_host: bool,
+    #[structopt(long, default_value = "both", possible_values = &["egress", "ingress", "both"])]
+    /// Which directions the capture holds, eg. egress for a tap that only mirrors outgoing traffic
+    capture_direction: CaptureDirection,
+    #[structopt(long)]
+    /// Show one table grouped by these comma separated keys instead of the default tables, eg. interface,process (interface, process, remote-address, remote-domain, remote-port, protocol or user)
+    group_by: Option<GroupBy>,
+    #[structopt(long)]
+    /// Roll the remote addresses table up by domain, eg. all of *.googlevideo.com in one row (addresses that did not resolve share one too)
+    group_by_domain: bool,
+    #[structopt(long)]
+    /// Only show the traffic of processes whose name contains this (case insensitive), eg. firefox
+    process: Option<String>,
+    #[structopt(long, number_of_values = 1)]
+    /// Leave out the traffic of processes whose name contains this (case insensitive), eg. NetworkManager, can be given more than once and wins over --process
+    exclude_process: Vec<String>,
+    #[structopt(long, number_of_values = 1)]
+    /// Only show connections using this local or remote port, can be given more than once
+    port: Vec<u16>,
+    #[structopt(long, possible_values = &["tcp", "udp"])]
+    /// Only show connections using this protocol, the connections table has a column for it otherwise (other protocols, eg. ICMP, are never captured)
+    protocol: Option<Protocol>,
+    #[structopt(long, default_value = "0")]
+    /// Hide the rows of the tables moving less than this rate up and down together, eg. 1KBps or 8kbps (0 shows them all)
+    min_rate: BandwidthLimit,
+    #[structopt(long, default_value = "both", possible_values = &["in", "out", "both"])]
+    /// Only show connections opened to this host (in, accepted by one of its listening sockets) or by it (out), however much either way moves
+    direction: ConnectionDirection,
+    #[structopt(long)]
+    /// Show one row per remote address, port and protocol in the connections table, whatever process or local port they are from
+    merge_connections: bool,
+    #[structopt(long)]
+    /// Also show connections to this host itself (eg. 127.0.0.1 or ::1), these are left out by default
+    show_loopback: bool,
+    #[structopt(long, default_value = "total", possible_values = &["total", "download", "upload", "name", "age"])]
+    /// What to sort the tables by, can be changed with the `s` key (age only sorts the connections table)
+    sort_by: SortBy,
+    #[structopt(long, conflicts_with = "packets")]
+    /// Mark the connections whose rate held steady near a round number (eg. 10Mbps or 512KiBps) for the last --cap-ticks ticks as `[capped?]` in the connections table, a guess at a rate limit
+    detect_caps: bool,
+    #[structopt(long, default_value = "5", requires = "detect-caps")]
+    /// How many ticks in a row a connection has to hold its rate for --detect-caps (at least 2)
+    cap_ticks: usize,
+    #[structopt(long, default_value = "2", requires = "detect-caps")]
+    /// How far off its average and the round number a rate can be for --detect-caps, in percent
+    cap_tolerance: f64,
+    #[structopt(long, default_value = "both", possible_values = &["ctrl-c", "q", "both"])]
+    /// Which keys quit, the other one is left alone then (a q goes into the search like any other key, a <CTRL-C> is ignored)
+    quit_key: QuitKeys,
+    #[structopt(long, default_value = "1")]
+    /// Show the rates of the tables averaged over this many ticks, so they jump around less (the raw and JSON output stay as they are)
+    smooth: usize,
+    #[structopt(long, parse(try_from_str = parse_interval))]
+    /// How many seconds of traffic the rates are averaged over (eg. 2), however often they are redrawn [default: the last 5 ticks]
+    rate_window: Option<time::Duration>,
+    #[structopt(long, default_value = "0")]
+    /// Only show the first this many rows of each table, in the raw and JSON output too (0 shows all of them)
+    count: usize,
+    #[structopt(long, default_value = "0")]
+    /// Only show the busiest this many processes and sum up the rest in an `others` row, in the raw and JSON output too (0 shows all of them)
+    top: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ExitStatus {
+    Clean,
+    ThresholdBreached,
+    /// The --duration ran out without a single packet captured
+    NothingCaptured,
+    /// The --self-test could not count the packets as fast as they came
+    FellBehind,
+}
+
+impl ExitStatus {
+    pub fn code(&self) -> i32 {
+        // 2 is taken by errors during setup, see main
+        match self {
+            ExitStatus::Clean => 0,
+            ExitStatus::ThresholdBreached => 3,
+            ExitStatus::NothingCaptured => 4,
+            ExitStatus::FellBehind => 5,
+        }
+    }
+}
+
+fn parse_dns_server(dns_server: &str) -> Result<SocketAddr, String> {
+    dns_server
+        .parse::<SocketAddr>()
+        .or_else(|_| {
+            dns_server
+                .parse::<IpAddr>()
+                .map(|ip| SocketAddr::new(ip, 53))
+        })
+        .map_err(|_| {
+            format!(
+                "invalid DNS server '{}', expected an address like 10.0.0.1 or 10.0.0.1:5353",
+                dns_server
+            )
+        })
+}
+
+fn parse_interval(interval: &str) -> Result<time::Duration, String> {
+    match interval.parse::<f64>() {
+        Ok(seconds) if seconds > 0.0 && seconds.is_finite() => {
+            Ok(time::Duration::from_secs_f64(seconds))
+        }
+        _ => Err(format!(
+            "invalid interval '{}', expected a number of seconds above 0",
+            interval
+        )),
+    }
 }
 
 fn main() {
-    if let Err(err) = try_main() {
-        eprintln!("Error: {}", err);
-        process::exit(2);
+    let args =
+        match config::args_with_config(env::args_os().collect(), config::default_config_path()) {
+            Ok((args, warnings)) => {
+                for warning in warnings {
+                    eprintln!("{}", warning);
+                }
+                args
+            }
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                process::exit(2);
+            }
+        };
+    let mut opts = Opt::from_iter(args);
+    config::apply_env_flags(&mut opts, |name| env::var(name).ok());
+    apply_stdout_outputs(&mut opts);
+    if opts.layout_file.is_none() {
+        opts.layout_file = default_layout_path();
+    }
+    // a variant of the raw output
+    opts.raw |= opts.render_opts.compact;
+    let raw_mode = opts.raw;
+    let json_mode = opts.json;
+    match try_main(opts) {
+        Ok(exit_status) => process::exit(exit_status.code()),
+        Err(err) => {
+            if json_mode {
+                println!("{}", display_json_error(&err));
+            } else if raw_mode {
+                // whatever reads the raw output should not have to parse a sentence meant for humans
+                println!("{}", display_raw_error(&err));
+            } else {
+                eprintln!("Error: {}", err);
+            }
+            process::exit(2);
+        }
     }
 }
 
-fn try_main() -> Result<(), failure::Error> {
+// stdout can only hold one of the tables, the raw and the JSON output, the last one given wins
+fn apply_stdout_outputs(opts: &mut Opt) {
+    for target in configured_outputs(&opts.output) {
+        match target {
+            OutputTarget::StdoutRaw => {
+                opts.raw = true;
+                opts.json = false;
+            }
+            OutputTarget::StdoutJson => {
+                opts.json = true;
+                opts.raw = false;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn display_raw_error(err: &failure::Error) -> String {
+    let message = err.to_string();
+    match err.downcast_ref::<StartupError>() {
+        Some(startup_error) => format!(
+            "error: code: {} message: {:?} hint: {:?}",
+            startup_error.code(),
+            message.trim(),
+            startup_error.hint()
+        ),
+        None => format!("error: code: other message: {:?}", message.trim()),
+    }
+}
+
+/// What this run could capture and look up, as found by `get_input` (see --print-capabilities)
+pub fn print_capabilities(os_input: &mut OsInputOutput, opts: &Opt) {
+    let version = env!("CARGO_PKG_VERSION");
+    let platform = env::consts::OS;
+    let resolves = os_input.dns_client.is_some();
+    // captured, promiscuous and why not, per interface
+    let interfaces: Vec<(&str, bool, bool, Option<&str>)> = os_input
+        .network_interfaces
+        .iter()
+        .map(|interface| {
+            let promiscuous = !os_input.host_only_interfaces.contains(&interface.name);
+            (interface.name.as_str(), true, promiscuous, None)
+        })
+        .chain(
+            os_input
+                .uncaptured_interfaces
+                .iter()
+                .map(|(name, reason)| (name.as_str(), false, false, Some(reason.as_str()))),
+        )
+        .collect();
+    let mut lines = vec![];
+    if opts.json {
+        lines.push(
+            JsonCapabilities {
+                version,
+                platform,
+                open_sockets: os::OPEN_SOCKETS_BACKEND,
+                dns: resolves,
+                interfaces: &interfaces,
+            }
+            .to_json(),
+        );
+    } else if opts.raw {
+        lines.push(format!(
+            "capabilities: version: {} platform: {} open_sockets: {} dns: {}",
+            version,
+            platform,
+            os::OPEN_SOCKETS_BACKEND,
+            resolves
+        ));
+        for &(name, captured, promiscuous, reason) in &interfaces {
+            let mut line = format!(
+                "interface: \"{}\" captured: {} promiscuous: {}",
+                name, captured, promiscuous
+            );
+            if let Some(reason) = reason {
+                line.push_str(&format!(" reason: \"{}\"", reason));
+            }
+            lines.push(line);
+        }
+    } else {
+        lines.push(format!("bandwhich {} on {}", version, platform));
+        lines.push(format!(
+            "Sockets of the processes looked up with: {}",
+            os::OPEN_SOCKETS_BACKEND
+        ));
+        lines.push(format!(
+            "Hostname resolution: {}",
+            if resolves {
+                "available"
+            } else {
+                "off (--no-resolve)"
+            }
+        ));
+        lines.push(String::from("Interfaces:"));
+        for &(name, captured, promiscuous, reason) in &interfaces {
+            lines.push(match (captured, promiscuous, reason) {
+                (true, true, _) => format!("  {}: captured", name),
+                (true, false, _) => format!(
+                    "  {}: captured, only the traffic of this host (not in promiscuous mode)",
+                    name
+                ),
+                (false, _, reason) => {
+                    format!("  {}: not captured, {}", name, reason.unwrap_or("unknown"))
+                }
+            });
+        }
+    }
+    for line in lines {
+        (os_input.write_to_stdout)(line);
+    }
+}
+
+/// The connections open right now and the sockets listening, with the processes they belong to,
+/// filtered like the tables (see --once)
+pub fn print_open_connections(
+    open_sockets: &OpenSockets,
+    opts: &Opt,
+    write_to_stdout: &mut dyn FnMut(String),
+) {
+    let mut state = new_state(&opts.render_opts);
+    // --direction tells connections apart by them
+    state.update_listening_sockets(open_sockets.listening_sockets.clone());
+    let connections =
+        state.open_connections(&open_sockets.connections, &open_sockets.sockets_to_procs);
+    let listening_sockets = state.open_listening_sockets();
+    let mut lines = vec![];
+    if opts.json {
+        lines.push(
+            JsonOpenConnections {
+                connections: &connections,
+                listening_sockets: &listening_sockets,
+            }
+            .to_json(),
+        );
+    } else if opts.raw {
+        for (connection, process_name) in &connections {
+            lines.push(format!(
+                "connection: {} => {} ({}) process: \"{}\"",
+                SocketAddr::new(connection.local_socket.ip, connection.local_socket.port),
+                SocketAddr::new(connection.remote_socket.ip, connection.remote_socket.port),
+                connection.local_socket.protocol,
+                process_name
+            ));
+        }
+        for (listening_socket, process_name) in &listening_sockets {
+            lines.push(format!(
+                "listening: {} ({}) process: \"{}\"",
+                SocketAddr::new(listening_socket.ip, listening_socket.port),
+                listening_socket.protocol,
+                process_name
+            ));
+        }
+    } else {
+        let process_width = connections
+            .iter()
+            .map(|(_, process_name)| process_name)
+            .chain(
+                listening_sockets
+                    .iter()
+                    .map(|(_, process_name)| process_name),
+            )
+            .map(|process_name| process_name.chars().count())
+            .max()
+            .unwrap_or(0);
+        for (connection, process_name) in &connections {
+            lines.push(format!(
+                "{:width$}  {} => {} ({})",
+                process_name,
+                SocketAddr::new(connection.local_socket.ip, connection.local_socket.port),
+                SocketAddr::new(connection.remote_socket.ip, connection.remote_socket.port),
+                connection.local_socket.protocol,
+                width = process_width
+            ));
+        }
+        for (listening_socket, process_name) in &listening_sockets {
+            lines.push(format!(
+                "{:width$}  listening on {} ({})",
+                process_name,
+                SocketAddr::new(listening_socket.ip, listening_socket.port),
+                listening_socket.protocol,
+                width = process_width
+            ));
+        }
+    }
+    for line in lines {
+        write_to_stdout(line);
+    }
+}
+
+fn try_main(mut opts: Opt) -> Result<ExitStatus, failure::Error> {
     #[cfg(target_os = "windows")]
     compile_error!("Sorry, no implementations for Windows yet :( - PRs welcome!");
 
+    if opts.once {
+        // the sockets are all there is to it, so no interface has to be opened
+        let show_pid = opts.render_opts.show_pid && !opts.render_opts.group_processes;
+        let open_sockets = os::get_current_sockets(
+            opts.attribute_to_ancestor.as_ref(),
+            opts.render_opts.full_cmdline,
+            opts.render_opts.containers,
+            show_pid,
+        );
+        print_open_connections(&open_sockets, &opts, &mut |line| println!("{}", line));
+        return Ok(ExitStatus::Clean);
+    }
+    if let Some(packets_per_second) = opts.self_test {
+        // the counting is all there is to it, so no interface has to be opened either
+        let duration = time::Duration::from_secs(opts.duration.unwrap_or(SELF_TEST_SECONDS));
+        let report = run_self_test(packets_per_second, duration);
+        for line in report.lines(opts.raw, opts.json) {
+            println!("{}", line);
+        }
+        return Ok(if report.kept_up() {
+            ExitStatus::Clean
+        } else {
+            ExitStatus::FellBehind
+        });
+    }
     use os::get_input;
-    let opts = Opt::from_args();
-    let os_input = get_input(&opts.interface, !opts.no_resolve)?;
-    let raw_mode = opts.raw;
-    if raw_mode {
+    let mut os_input = get_input(
+        &opts.interface,
+        &opts.exclude_interface,
+        opts.read_pcap.as_deref(),
+        !opts.no_resolve,
+        opts.dns_server,
+    )?;
+    let raw_mode = opts.raw || opts.json;
+    if opts.print_capabilities {
+        print_capabilities(&mut os_input, &opts);
+        return Ok(ExitStatus::Clean);
+    }
+    if let Some(path) = opts.export_frame.clone() {
+        let (width, height) = ::termion::terminal_size().unwrap_or((120, 40));
+        let terminal_backend = match FrameExportBackend::new(path.clone(), width, height) {
+            Ok(terminal_backend) => terminal_backend,
+            Err(err) => failure::bail!("Failed to write {}: {}", path.display(), err),
+        };
+        opts.duration = opts.duration.or(Some(EXPORT_FRAME_DURATION_SECONDS));
+        start(terminal_backend, os_input, opts)
+    } else if raw_mode {
         let terminal_backend = RawTerminalBackend {};
-        start(terminal_backend, os_input, opts);
+        start(terminal_backend, os_input, opts)
     } else {
+        // escape codes are just noise wherever the output ends up other than a terminal
+        opts.render_opts.no_color |= !termion::is_tty(&io::stdout());
         match io::stdout().into_raw_mode() {
+            // mouse reporting is turned back off when the terminal is dropped
+            Ok(stdout) if opts.mouse => {
+                let terminal_backend = TermionBackend::new(MouseTerminal::from(stdout));
+                start(terminal_backend, os_input, opts)
+            }
             Ok(stdout) => {
                 let terminal_backend = TermionBackend::new(stdout);
-                start(terminal_backend, os_input, opts);
+                start(terminal_backend, os_input, opts)
             }
             Err(_) => failure::bail!(
                 "Failed to get stdout: if you are trying to pipe 'bandwhich' you should use the --raw flag"
             ),
         }
     }
-    Ok(())
 }
 
+#[derive(Clone, Default)]
 pub struct OpenSockets {
     sockets_to_procs: HashMap<LocalSocket, String>,
+    // only the sockets whose pid could be found, see --show-pid
+    sockets_to_pids: HashMap<LocalSocket, u32>,
+    // the owners of the processes, by name or uid, see --show-user
+    sockets_to_users: HashMap<LocalSocket, String>,
     connections: Vec<Connection>,
+    // the sockets waiting for connections (or, for udp, datagrams from anywhere)
+    listening_sockets: HashMap<LocalSocket, String>,
+    unix_sockets: Vec<UnixSocket>,
+}
+
+impl OpenSockets {
+    // the command line replaces the name wherever it can be read, it is looked up once per process
+    fn label_procs_with_cmdlines(&mut self, get_process_cmdline: fn(u32) -> Option<String>) {
+        let mut cmdlines = HashMap::new();
+        for (local_socket, process_name) in self.sockets_to_procs.iter_mut() {
+            if let Some(&pid) = self.sockets_to_pids.get(local_socket) {
+                if let Some(cmdline) = cmdlines
+                    .entry(pid)
+                    .or_insert_with(|| get_process_cmdline(pid))
+                {
+                    *process_name = cmdline.clone();
+                }
+            }
+        }
+    }
+    // the container a process runs in goes after its name, it is looked up once per process
+    fn label_procs_with_containers(&mut self, get_process_container: fn(u32) -> Option<String>) {
+        let mut containers = HashMap::new();
+        for (local_socket, process_name) in self.sockets_to_procs.iter_mut() {
+            if let Some(&pid) = self.sockets_to_pids.get(local_socket) {
+                if let Some(container) = containers
+                    .entry(pid)
+                    .or_insert_with(|| get_process_container(pid))
+                {
+                    *process_name = format!("{} [{}]", process_name, container);
+                }
+            }
+        }
+    }
+    // the pid becomes part of the name, so processes that share a name get a row each
+    fn label_procs_with_pids(&mut self) {
+        for (local_socket, process_name) in self.sockets_to_procs.iter_mut() {
+            if let Some(pid) = self.sockets_to_pids.get(local_socket) {
+                *process_name = format!("{} ({})", process_name, pid);
+            }
+        }
+    }
+}
+
+// the open sockets along with the processes they belong to, as the tables show them
+fn enumerate_sockets(
+    get_open_sockets: fn(Option<&Ancestor>) -> OpenSockets,
+    get_process_cmdline: Option<fn(u32) -> Option<String>>,
+    get_process_container: Option<fn(u32) -> Option<String>>,
+    attribute_to_ancestor: Option<&Ancestor>,
+    show_pid: bool,
+) -> OpenSockets {
+    let mut open_sockets = get_open_sockets(attribute_to_ancestor);
+    if let Some(get_process_cmdline) = get_process_cmdline {
+        open_sockets.label_procs_with_cmdlines(get_process_cmdline);
+    }
+    if let Some(get_process_container) = get_process_container {
+        open_sockets.label_procs_with_containers(get_process_container);
+    }
+    if show_pid {
+        open_sockets.label_procs_with_pids();
+    }
+    open_sockets
 }
 
 pub struct OsInputOutput {
     pub network_interfaces: Vec<NetworkInterface>,
     pub network_frames: Vec<Box<dyn DataLinkReceiver>>,
-    pub get_open_sockets: fn() -> OpenSockets,
+    /// Interfaces that did not go into promiscuous mode, so only traffic to and from this host shows up
+    pub host_only_interfaces: Vec<String>,
+    /// The interfaces found but not captured, with why (eg. permission denied)
+    pub uncaptured_interfaces: Vec<(String, String)>,
+    /// The speed of the link of each interface that tells it, in bits per second
+    pub link_speeds: HashMap<String, u64>,
+    pub get_open_sockets: fn(Option<&Ancestor>) -> OpenSockets,
+    /// The full command line (or executable path) of a process by its pid, see --full-cmdline
+    pub get_process_cmdline: fn(u32) -> Option<String>,
+    /// The container a process runs in by its pid, see --containers
+    pub get_process_container: fn(u32) -> Option<String>,
+    /// Puts the text on the system clipboard, see the `y` key
+    pub copy_to_clipboard: fn(&str) -> io::Result<()>,
     pub keyboard_events: Box<dyn Iterator<Item = Event> + Send>,
     pub dns_client: Option<dns::Client>,
     pub on_winch: Box<OnSigWinch>,
+    /// Called on SIGTERM and SIGINT, until `cleanup` is
+    pub on_terminate: Box<OnSigTerm>,
     pub cleanup: Box<dyn Fn() + Send>,
     pub write_to_stdout: Box<dyn FnMut(String) + Send>,
 }
 
-pub fn start<B>(terminal_backend: B, os_input: OsInputOutput, opts: Opt)
+// the --log-file, the --csv-history, the --socket and the files and sockets of --output, stdout
+// is not one of them
+fn open_output_sinks(opts: &mut Opt) -> Vec<OutputSink> {
+    let mut opened = Vec::new();
+    if let Some(path) = opts.log_file.take() {
+        opened.push((
+            OutputSink::file(path.clone(), opts.log_max_size, false),
+            path,
+        ));
+    }
+    if let Some(path) = opts.csv_history.take() {
+        opened.push((OutputSink::csv(path.clone(), opts.csv_per_process), path));
+    }
+    if let Some(path) = opts.socket.take() {
+        opened.push((OutputSink::socket(path.clone()), path));
+    }
+    for target in configured_outputs(&opts.output) {
+        match target {
+            OutputTarget::File(path) => {
+                let json = path
+                    .extension()
+                    .is_some_and(|extension| extension == "json");
+                opened.push((
+                    OutputSink::file(path.clone(), opts.log_max_size, json),
+                    path.clone(),
+                ));
+            }
+            OutputTarget::Socket(path) => {
+                opened.push((OutputSink::socket(path.clone()), path.clone()))
+            }
+            _ => {}
+        }
+    }
+    let mut output_sinks = Vec::new();
+    for (output_sink, path) in opened {
+        match output_sink {
+            Ok(output_sink) => output_sinks.push(output_sink),
+            Err(err) => eprintln!("Failed to open {}: {}", path.display(), err),
+        }
+    }
+    output_sinks
+}
+
+// a file that cannot be written to is given up on, rather than failing again every tick
+fn write_output_sinks<B: Backend>(
+    ui: &mut Ui<B>,
+    output_sinks: &mut Vec<OutputSink>,
+    raw_mode: bool,
+) {
+    output_sinks.retain_mut(|output_sink| match output_sink.write(ui) {
+        Ok(()) => true,
+        Err(err) => {
+            let name = output_sink.name();
+            if raw_mode {
+                eprintln!("Failed to write {}, stopped writing to it: {}", name, err);
+            }
+            ui.log_output_error(&name, &err);
+            false
+        }
+    });
+}
+
+fn thread_error(name: &str, err: io::Error) -> failure::Error {
+    failure::format_err!("Failed to start the {} thread: {}", name, err)
+}
+
+// a thread that panicked while holding the lock left what it guards as it was, which is still
+// better shown than taking every other thread (and the terminal) down with it
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// runs on the way out of a thread, also when it panics
+struct OnExit<F: FnMut()>(F);
+
+impl<F: FnMut()> Drop for OnExit<F> {
+    fn drop(&mut self) {
+        (self.0)()
+    }
+}
+
+// the display handler restores the terminal on its way out, so the error can be shown on it
+fn stop_threads(
+    running: &AtomicBool,
+    display_handler: JoinHandle<()>,
+    active_threads: Vec<JoinHandle<()>>,
+) {
+    running.store(false, Ordering::Release);
+    display_handler.thread().unpark();
+    for thread_handler in active_threads
+        .into_iter()
+        .chain(iter::once(display_handler))
+    {
+        let _ = thread_handler.join();
+    }
+}
+
+pub fn start<B>(
+    terminal_backend: B,
+    os_input: OsInputOutput,
+    mut opts: Opt,
+) -> Result<ExitStatus, failure::Error>
 where
     B: Backend + Send + 'static,
 {
     let running = Arc::new(AtomicBool::new(true));
     let paused = Arc::new(AtomicBool::new(false));
+    let threshold_breached = Arc::new(AtomicBool::new(false));
+    let nothing_captured = Arc::new(AtomicBool::new(false));
+    let terminated = Arc::new(AtomicBool::new(false));
+    // the capture file ran out, see --read-pcap
+    let replay_finished = Arc::new(AtomicBool::new(false));
+    let resolving = Arc::new(AtomicBool::new(true));
+    let reset_session = Arc::new(AtomicBool::new(false));
 
     let mut active_threads = vec![];
 
     let keyboard_events = os_input.keyboard_events;
     let get_open_sockets = os_input.get_open_sockets;
-    let mut write_to_stdout = os_input.write_to_stdout;
+    let copy_to_clipboard = os_input.copy_to_clipboard;
+    // the --summary report is printed once the display thread has given the terminal back
+    let write_to_stdout = Arc::new(Mutex::new(os_input.write_to_stdout));
     let mut dns_client = os_input.dns_client;
     let on_winch = os_input.on_winch;
+    let on_terminate = os_input.on_terminate;
     let cleanup = os_input.cleanup;
 
-    let raw_mode = opts.raw;
+    let raw_mode = opts.raw || opts.json;
+    let json_mode = opts.json;
+    let attribute_to_ancestor = opts.attribute_to_ancestor;
+    // the pid would give each process of a group a row of its own
+    let show_pid = opts.render_opts.show_pid && !opts.render_opts.group_processes;
+    let group_processes = opts.render_opts.group_processes;
+    // the users are looked up for the column and for the grouping alike
+    let show_user = opts.render_opts.show_user
+        || opts
+            .render_opts
+            .group_by
+            .as_ref()
+            .map_or(false, |group_by| {
+                group_by.dimensions.contains(&GroupDimension::User)
+            });
+    let quit_keys = opts.render_opts.quit_key;
+    let get_process_cmdline = if opts.render_opts.full_cmdline {
+        Some(os_input.get_process_cmdline)
+    } else {
+        None
+    };
+    let get_process_container = if opts.render_opts.containers {
+        Some(os_input.get_process_container)
+    } else {
+        None
+    };
+    let duration = opts.duration.map(time::Duration::from_secs);
+    let idle_timeout = opts.idle_timeout.map(time::Duration::from_secs);
+    let fail_if_above = opts.fail_if_above;
+    let interval = opts
+        .redraw_interval
+        .or(opts.interval)
+        .unwrap_or(DISPLAY_DELTA);
+    let sockets_interval = opts.sockets_interval.unwrap_or(interval);
+    let adaptive_refresh = if opts.adaptive {
+        Some(Arc::new(AdaptiveRefresh::default()))
+    } else {
+        None
+    };
+    let capture_direction = opts.render_opts.capture_direction;
+    let filter = opts.filter.take();
+    let sample = opts.sample;
+    let tcp_stats = opts.render_opts.tcp_stats;
+    let quiet_until_traffic = opts.quiet_until_traffic;
+    let no_tables = opts.render_opts.no_tables;
+    let dns_stats = opts.render_opts.dns_stats;
+    let summary = opts.summary;
+    let session_report = Arc::new(Mutex::new(None));
+    let rate_alert = match opts.alert_threshold {
+        Some(threshold) => Some(RateAlert::new(
+            threshold,
+            opts.alert_process.take(),
+            UnitPrefix::from_opts(&opts.render_opts),
+        )),
+        None => None,
+    };
+    // with a duration, the raw and JSON output only sum up the whole run once it is over
+    // a replayed capture file ends like a duration does
+    let replaying = opts.read_pcap.is_some();
+    let summarize_at_end = raw_mode && (duration.is_some() || replaying);
+    if summarize_at_end {
+        opts.render_opts.total_utilization = true;
+    }
+    let dns_cache_file = match (&opts.dns_cache, dns_client.as_mut()) {
+        (Some(path), Some(dns_client)) => {
+            let mut cache_file =
+                dns::CacheFile::new(path.clone(), time::Duration::from_secs(opts.dns_cache_ttl));
+            match cache_file.load() {
+                Ok(ip_table) => dns_client.extend_cache(ip_table),
+                Err(err) => eprintln!("Failed to read the DNS cache {}: {}", path.display(), err),
+            }
+            Some(cache_file)
+        }
+        _ => None,
+    };
+    let geoip = if opts.render_opts.geo {
+        let mut databases = Vec::new();
+        for path in &opts.geoip {
+            match GeoIpDatabase::open(path) {
+                Ok(database) => databases.push(database),
+                Err(err) => failure::bail!("Failed to read {}: {}", path.display(), err),
+            }
+        }
+        Some(GeoIp::new(databases))
+    } else {
+        None
+    };
+    // the terminal is not ours to print on anymore, so what goes wrong ends the run instead
+    let budget = match opts.budget {
+        Some(limit) => match opts.budget_file.clone().or_else(default_budget_path) {
+            Some(path) => {
+                match Budget::load(path.clone(), limit, opts.budget_period, Local::now()) {
+                    Ok(budget) => Some(budget),
+                    Err(err) => {
+                        failure::bail!("Failed to read the budget {}: {}", path.display(), err)
+                    }
+                }
+            }
+            None => failure::bail!("Failed to find where to keep the budget, pass --budget-file"),
+        },
+        None => None,
+    };
+    // the raw output has no colors, a theme that cannot be read leaves them as they are
+    let theme_warning = match opts.render_opts.theme.clone().filter(|_| !raw_mode) {
+        Some(theme) => match load_theme(&theme) {
+            Ok(colors) => {
+                opts.render_opts.colors = colors;
+                None
+            }
+            Err(err) => Some(format!(
+                "could not read the theme {}: {}, the default colors are used",
+                theme, err
+            )),
+        },
+        None => None,
+    };
+    let output_sinks = open_output_sinks(&mut opts);
 
-    let network_utilization = Arc::new(Mutex::new(Utilization::new()));
-    let ui = Arc::new(Mutex::new(Ui::new(terminal_backend, opts.render_opts)));
+    // the display thread counts what the sniffers send it when it renders a tick
+    let (capture_events, capture_events_receiver) = capture_queue(CAPTURE_QUEUE_LENGTH);
+    let host_ips = os_input
+        .network_interfaces
+        .iter()
+        .flat_map(|interface| interface.ips.iter().map(|ip_network| ip_network.ip()))
+        .collect();
+    let mut ui = Ui::new(terminal_backend, opts.render_opts, host_ips);
+    ui.set_listened_interfaces(
+        os_input
+            .network_interfaces
+            .iter()
+            .map(|interface| interface.name.clone())
+            .collect(),
+    );
+    ui.set_link_speeds(os_input.link_speeds);
+    if let Some(warning) = theme_warning {
+        ui.log_warning(&warning);
+    }
+    if !os_input.host_only_interfaces.is_empty() {
+        if raw_mode {
+            eprintln!(
+                "warning: could not enable promiscuous mode on {}, only traffic to and from this host will show up",
+                os_input.host_only_interfaces.join(", ")
+            );
+        }
+        ui.set_host_only_interfaces(os_input.host_only_interfaces);
+    }
+    ui.set_waiting_for_traffic(quiet_until_traffic);
+    if replaying {
+        ui.set_processes_unknown();
+    }
+    // there is no layout to speak of in the raw and JSON output
+    let layout_file = opts.layout_file.take().filter(|_| !raw_mode);
+    if let Some(path) = &layout_file {
+        if opts.reset_layout {
+            if let Err(err) = LayoutState::reset(path) {
+                eprintln!("Failed to reset the layout {}: {}", path.display(), err);
+            }
+        } else {
+            match LayoutState::load(path, ui.layout_state()) {
+                Ok(layout) => ui.restore_layout(layout),
+                Err(err) => eprintln!("Failed to read the layout {}: {}", path.display(), err),
+            }
+        }
+    }
+    let ui = Arc::new(Mutex::new(ui));
 
-    if !raw_mode {
-        active_threads.push(
-            thread::Builder::new()
-                .name("resize_handler".to_string())
-                .spawn({
-                    let ui = ui.clone();
-                    let paused = paused.clone();
-                    move || {
-                        on_winch({
-                            Box::new(move || {
-                                let mut ui = ui.lock().unwrap();
-                                ui.draw(paused.load(Ordering::SeqCst));
-                            })
-                        });
+    // looking the sockets up can take long on a busy host, so it does not hold up the tables.
+    // The first tick already has them to attribute its traffic to.
+    let open_sockets = Arc::new(Mutex::new(if no_tables {
+        OpenSockets::default()
+    } else {
+        enumerate_sockets(
+            get_open_sockets,
+            get_process_cmdline,
+            get_process_container,
+            attribute_to_ancestor.as_ref(),
+            show_pid,
+        )
+    }));
+    let sockets_handler = match thread::Builder::new()
+        .name("sockets_handler".to_string())
+        .spawn({
+            let running = running.clone();
+            let open_sockets = open_sockets.clone();
+            move || {
+                // nothing is attributed to the processes with --no-tables
+                if no_tables {
+                    return;
+                }
+                park_timeout(sockets_interval);
+                while running.load(Ordering::Acquire) {
+                    let enumeration_start_time = Instant::now();
+                    let latest = enumerate_sockets(
+                        get_open_sockets,
+                        get_process_cmdline,
+                        get_process_container,
+                        attribute_to_ancestor.as_ref(),
+                        show_pid,
+                    );
+                    *lock(&open_sockets) = latest;
+                    let enumeration_duration = enumeration_start_time.elapsed();
+                    if enumeration_duration < sockets_interval {
+                        park_timeout(sockets_interval - enumeration_duration);
                     }
-                })
-                .unwrap(),
-        );
-    }
+                }
+            }
+        }) {
+        Ok(sockets_handler) => sockets_handler,
+        Err(err) => {
+            if !raw_mode {
+                lock(&ui).end();
+            }
+            return Err(thread_error("sockets_handler", err));
+        }
+    };
 
-    let display_handler = thread::Builder::new()
+    if raw_mode && !json_mode {
+        lock(&ui).output_text_header(&mut **lock(&write_to_stdout));
+    }
+    // with --no-resolve there is nothing to turn on or off
+    let can_resolve = dns_client.is_some();
+    let shared_state = SharedState {
+        ui: ui.clone(),
+        open_sockets: open_sockets.clone(),
+        write_to_stdout: write_to_stdout.clone(),
+        session_report: session_report.clone(),
+        paused: paused.clone(),
+        resolving: resolving.clone(),
+        reset_session: reset_session.clone(),
+        replay_finished: replay_finished.clone(),
+        threshold_breached: threshold_breached.clone(),
+        nothing_captured: nothing_captured.clone(),
+        adaptive_refresh: adaptive_refresh.clone(),
+    };
+    let tick_options = TickOptions {
+        interval,
+        duration,
+        idle_timeout,
+        fail_if_above,
+        quiet_until_traffic,
+        raw_mode,
+        json_mode,
+        summarize_at_end,
+        summary,
+        group_processes,
+        show_user,
+        dns_stats,
+    };
+    let display_handler = match thread::Builder::new()
         .name("display_handler".to_string())
         .spawn({
             let running = running.clone();
-            let paused = paused.clone();
-            let network_utilization = network_utilization.clone();
+            let sockets_handler = sockets_handler.thread().clone();
             move || {
+                // a panic further down still stops the other threads, gives the terminal back and
+                // lets go of the signals, so that bandwhich ends rather than hangs
+                let _on_exit = OnExit({
+                    let running = running.clone();
+                    let ui = shared_state.ui.clone();
+                    let sockets_handler = sockets_handler.clone();
+                    move || {
+                        if thread::panicking() {
+                            running.store(false, Ordering::Release);
+                            sockets_handler.unpark();
+                            if !raw_mode {
+                                lock(&ui).end();
+                            }
+                        }
+                        cleanup();
+                    }
+                });
+                let mut state =
+                    DisplayState::new(tick_options, capture_events_receiver, Instant::now());
+                state.dns_client = dns_client;
+                state.geoip = geoip;
+                state.rate_alert = rate_alert;
+                state.output_sinks = output_sinks;
+                state.budget = budget;
                 while running.load(Ordering::Acquire) {
                     let render_start_time = Instant::now();
-                    let utilization = { network_utilization.lock().unwrap().clone_and_reset() };
-                    let OpenSockets {
-                        sockets_to_procs,
-                        connections,
-                    } = get_open_sockets();
-                    let mut ip_to_host = IpTable::new();
-                    if let Some(dns_client) = dns_client.as_mut() {
-                        ip_to_host = dns_client.cache();
-                        let unresolved_ips = connections
-                            .iter()
-                            .filter(|conn| !ip_to_host.contains_key(&conn.remote_socket.ip))
-                            .map(|conn| conn.remote_socket.ip)
-                            .collect::<Vec<_>>();
-
-                        dns_client.resolve(unresolved_ips);
-                    }
-                    {
-                        let mut ui = ui.lock().unwrap();
-                        let paused = paused.load(Ordering::SeqCst);
-                        if !paused {
-                            ui.update_state(sockets_to_procs, utilization, ip_to_host);
-                        }
-                        if raw_mode {
-                            ui.output_text(&mut write_to_stdout);
-                        } else {
-                            ui.draw(paused);
-                        }
+                    if let Tick::Last = run_tick(&shared_state, &mut state, render_start_time) {
+                        running.store(false, Ordering::Release);
+                        break;
                     }
                     let render_duration = render_start_time.elapsed();
-                    if render_duration < DISPLAY_DELTA {
-                        park_timeout(DISPLAY_DELTA - render_duration);
+                    if let Some(adaptive_refresh) = &shared_state.adaptive_refresh {
+                        adaptive_refresh.park_until_next_tick(render_start_time, interval);
+                    } else if render_duration < interval {
+                        park_timeout(interval - render_duration);
                     }
                 }
+                finish_run(&shared_state, &mut state);
+                sockets_handler.unpark();
                 if !raw_mode {
-                    let mut ui = ui.lock().unwrap();
+                    let mut ui = lock(&shared_state.ui);
                     ui.end();
+                    if let Some(path) = &layout_file {
+                        if let Err(err) = ui.layout_state().save(path) {
+                            eprintln!("Failed to write the layout {}: {}", path.display(), err);
+                        }
+                    }
+                }
+                if let Some(budget) = &state.budget {
+                    if let Err(err) = budget.save() {
+                        eprintln!(
+                            "Failed to write the budget {}: {}",
+                            budget.path().display(),
+                            err
+                        );
+                    }
+                }
+                if let (Some(cache_file), Some(dns_client)) =
+                    (&dns_cache_file, state.dns_client.as_mut())
+                {
+                    if let Err(err) = cache_file.save(&dns_client.cache()) {
+                        eprintln!("Failed to write the DNS cache: {}", err);
+                    }
                 }
             }
-        })
-        .unwrap();
+        }) {
+        Ok(display_handler) => display_handler,
+        Err(err) => {
+            running.store(false, Ordering::Release);
+            sockets_handler.thread().unpark();
+            let _ = sockets_handler.join();
+            if !raw_mode {
+                lock(&ui).end();
+            }
+            return Err(thread_error("display_handler", err));
+        }
+    };
+    active_threads.push(sockets_handler);
+
+    if let Some(port) = opts.metrics_port {
+        // only reachable from this host, since the process names and hosts are nobody else's business
+        match TcpListener::bind((Ipv4Addr::LOCALHOST, port)).and_then(MetricsServer::new) {
+            Ok(metrics_server) => {
+                let metrics_handler = thread::Builder::new()
+                    .name("metrics_handler".to_string())
+                    .spawn({
+                        let running = running.clone();
+                        let ui = ui.clone();
+                        move || metrics_server.serve(&running, || lock(&ui).metrics())
+                    });
+                match metrics_handler {
+                    Ok(metrics_handler) => active_threads.push(metrics_handler),
+                    Err(err) => {
+                        stop_threads(&running, display_handler, active_threads);
+                        return Err(thread_error("metrics_handler", err));
+                    }
+                }
+            }
+            Err(err) => eprintln!("Failed to serve metrics on port {}: {}", port, err),
+        }
+    }
 
-    active_threads.push(
-        thread::Builder::new()
-            .name("stdin_handler".to_string())
+    if !raw_mode {
+        let resize_handler = thread::Builder::new()
+            .name("resize_handler".to_string())
             .spawn({
-                let running = running.clone();
-                let display_handler = display_handler.thread().clone();
+                let ui = ui.clone();
+                let paused = paused.clone();
                 move || {
-                    for evt in keyboard_events {
+                    on_winch({
+                        Box::new(move || {
+                            let mut ui = lock(&ui);
+                            ui.draw(paused.load(Ordering::SeqCst));
+                        })
+                    });
+                }
+            });
+        match resize_handler {
+            Ok(resize_handler) => active_threads.push(resize_handler),
+            Err(err) => {
+                stop_threads(&running, display_handler, active_threads);
+                return Err(thread_error("resize_handler", err));
+            }
+        }
+    }
+
+    let stdin_handler = match thread::Builder::new()
+        .name("stdin_handler".to_string())
+        .spawn({
+            let running = running.clone();
+            // only held while handling a key, so the terminal is restored even while this waits
+            let ui = Arc::downgrade(&ui);
+            let display_handler = display_handler.thread().clone();
+            move || {
+                for evt in keyboard_events {
+                    // the display thread is gone (eg. it panicked), nothing is left to handle keys for
+                    if !running.load(Ordering::Acquire) {
+                        break;
+                    }
+                    let ui = match ui.upgrade() {
+                        Some(ui) => ui,
+                        None => break,
+                    };
+                    if lock(&ui).is_showing_help() {
+                        // any key closes the help, <CTRL-C> still quits (or is ignored, see --quit-key)
+                        match evt {
+                            Event::Key(Key::Ctrl('c')) if quit_keys.quits(Key::Ctrl('c')) => {
+                                running.store(false, Ordering::Release);
+                                display_handler.unpark();
+                                break;
+                            }
+                            Event::Key(Key::Ctrl('c')) => (),
+                            Event::Key(_) => {
+                                let mut ui = lock(&ui);
+                                ui.toggle_help();
+                                ui.draw(paused.load(Ordering::SeqCst));
+                            }
+                            _ => (),
+                        }
+                        continue;
+                    }
+                    if lock(&ui).is_typing_search_query() {
+                        // every other key goes into the query, <ESC> gets out of it
                         match evt {
-                            Event::Key(Key::Ctrl('c')) | Event::Key(Key::Char('q')) => {
+                            Event::Key(Key::Ctrl('c')) if quit_keys.quits(Key::Ctrl('c')) => {
                                 running.store(false, Ordering::Release);
-                                cleanup();
                                 display_handler.unpark();
                                 break;
                             }
@@ -222,41 +1392,247 @@ where
                                 paused.fetch_xor(true, Ordering::SeqCst);
                                 display_handler.unpark();
                             }
+                            Event::Key(key) => {
+                                let mut ui = lock(&ui);
+                                match key {
+                                    Key::Char('\n') => ui.finish_search(),
+                                    Key::Esc => ui.clear_search(),
+                                    Key::Backspace => ui.pop_search_char(),
+                                    Key::Char(character) => ui.push_search_char(character),
+                                    _ => (),
+                                }
+                                ui.draw(paused.load(Ordering::SeqCst));
+                            }
                             _ => (),
-                        };
+                        }
+                        continue;
                     }
+                    match evt {
+                        Event::Key(key) if quit_keys.quits(key) => {
+                            running.store(false, Ordering::Release);
+                            display_handler.unpark();
+                            break;
+                        }
+                        Event::Key(Key::Char(' ')) => {
+                            paused.fetch_xor(true, Ordering::SeqCst);
+                            display_handler.unpark();
+                        }
+                        Event::Key(Key::Char('f')) => {
+                            let mut ui = lock(&ui);
+                            ui.toggle_frozen();
+                            ui.draw(paused.load(Ordering::SeqCst));
+                        }
+                        Event::Key(Key::Char('r')) if can_resolve => {
+                            let resolving = !resolving.fetch_xor(true, Ordering::SeqCst);
+                            let mut ui = lock(&ui);
+                            ui.set_resolving(resolving);
+                            ui.draw(paused.load(Ordering::SeqCst));
+                        }
+                        Event::Key(Key::Char('l')) => {
+                            let mut ui = lock(&ui);
+                            ui.toggle_event_log();
+                            ui.draw(paused.load(Ordering::SeqCst));
+                        }
+                        Event::Key(Key::Char('s')) => {
+                            lock(&ui).cycle_sort_by();
+                            display_handler.unpark();
+                        }
+                        Event::Key(key @ Key::Char('\t')) | Event::Key(key @ Key::BackTab) => {
+                            let mut ui = lock(&ui);
+                            if key == Key::BackTab {
+                                ui.focus_previous_table();
+                            } else {
+                                ui.focus_next_table();
+                            }
+                            ui.draw(paused.load(Ordering::SeqCst));
+                        }
+                        Event::Key(key @ Key::Up)
+                        | Event::Key(key @ Key::Down)
+                        | Event::Key(key @ Key::PageUp)
+                        | Event::Key(key @ Key::PageDown) => {
+                            let mut ui = lock(&ui);
+                            match key {
+                                Key::Up => ui.scroll_up(1),
+                                Key::Down => ui.scroll_down(1),
+                                Key::PageUp => ui.scroll_up(SCROLL_PAGE_ROWS),
+                                _ => ui.scroll_down(SCROLL_PAGE_ROWS),
+                            }
+                            ui.draw(paused.load(Ordering::SeqCst));
+                        }
+                        Event::Key(Key::Char('/')) => {
+                            let mut ui = lock(&ui);
+                            ui.start_search();
+                            ui.draw(paused.load(Ordering::SeqCst));
+                        }
+                        Event::Key(Key::Char('h')) => {
+                            let mut ui = lock(&ui);
+                            ui.toggle_connection_history();
+                            ui.draw(paused.load(Ordering::SeqCst));
+                        }
+                        Event::Key(Key::Char('t')) => {
+                            let mut ui = lock(&ui);
+                            ui.toggle_session_bytes();
+                            ui.draw(paused.load(Ordering::SeqCst));
+                        }
+                        Event::Key(Key::Char('b')) => {
+                            let mut ui = lock(&ui);
+                            ui.jump_to_top_talker();
+                            ui.draw(paused.load(Ordering::SeqCst));
+                        }
+                        Event::Key(Key::Char('p')) => {
+                            let mut ui = lock(&ui);
+                            ui.toggle_pin();
+                            ui.draw(paused.load(Ordering::SeqCst));
+                        }
+                        Event::Key(Key::Char('y')) => {
+                            let mut ui = lock(&ui);
+                            ui.copy_selected_row(copy_to_clipboard);
+                            ui.draw(paused.load(Ordering::SeqCst));
+                        }
+                        Event::Key(Key::Char('z')) => {
+                            reset_session.store(true, Ordering::SeqCst);
+                            display_handler.unpark();
+                        }
+                        // there is nothing to show it over in raw mode
+                        Event::Key(Key::Char('?')) if !raw_mode => {
+                            let mut ui = lock(&ui);
+                            ui.toggle_help();
+                            ui.draw(paused.load(Ordering::SeqCst));
+                        }
+                        // termion counts from 1
+                        Event::Mouse(MouseEvent::Press(MouseButton::Left, x, y)) => {
+                            let mut ui = lock(&ui);
+                            match ui.click(x.saturating_sub(1), y.saturating_sub(1)) {
+                                // the tables are sorted anew on the next tick, like with `s`
+                                ClickAction::Sorted(_) => display_handler.unpark(),
+                                _ => ui.draw(paused.load(Ordering::SeqCst)),
+                            }
+                        }
+                        _ => (),
+                    };
                 }
-            })
-            .unwrap(),
-    );
-    active_threads.push(display_handler);
+            }
+        }) {
+        Ok(stdin_handler) => stdin_handler,
+        Err(err) => {
+            stop_threads(&running, display_handler, active_threads);
+            return Err(thread_error("stdin_handler", err));
+        }
+    };
+    let terminate_handler = match thread::Builder::new()
+        .name("terminate_handler".to_string())
+        .spawn({
+            let running = running.clone();
+            let terminated = terminated.clone();
+            let display_handler = display_handler.thread().clone();
+            move || {
+                // the display handler restores the terminal and calls cleanup on its way out
+                on_terminate(Box::new(move || {
+                    terminated.store(true, Ordering::SeqCst);
+                    running.store(false, Ordering::Release);
+                    display_handler.unpark();
+                }));
+            }
+        }) {
+        Ok(terminate_handler) => terminate_handler,
+        Err(err) => {
+            stop_threads(&running, display_handler, active_threads);
+            return Err(thread_error("terminate_handler", err));
+        }
+    };
+    active_threads.push(terminate_handler);
 
-    let sniffer_threads = os_input
+    for (iface, frames) in os_input
         .network_interfaces
         .into_iter()
         .zip(os_input.network_frames.into_iter())
-        .map(|(iface, frames)| {
-            let name = format!("sniffing_handler_{}", iface.name);
+    {
+        let name = format!("sniffing_handler_{}", iface.name);
+        let sniffer_thread = thread::Builder::new().name(name.clone()).spawn({
+            let filter = filter.clone();
             let running = running.clone();
-            let network_utilization = network_utilization.clone();
-
-            thread::Builder::new()
-                .name(name)
-                .spawn(move || {
-                    let mut sniffer = Sniffer::new(iface, frames);
-
-                    while running.load(Ordering::Acquire) {
-                        if let Some(segment) = sniffer.next() {
-                            network_utilization.lock().unwrap().update(segment);
+            let mut capture_events = capture_events.clone();
+            let adaptive_refresh = adaptive_refresh.clone();
+            let display_handler = display_handler.thread().clone();
+            let replay_finished = replay_finished.clone();
+            let ui = ui.clone();
+            move || {
+                let interface_name = iface.name.clone();
+                let mut sniffer = Sniffer::new(iface, frames, capture_direction, filter, tcp_stats);
+                sniffer.set_sample(sample);
+                // the same capture as a `Monitor`'s, but for what the display thread makes of it
+                let captured = sniffer.capture(&mut capture_events, &running, |segment| {
+                    if let Some(adaptive_refresh) = &adaptive_refresh {
+                        adaptive_refresh.count(
+                            segment.data_length * u128::from(sample.every()),
+                            &display_handler,
+                        );
+                    }
+                });
+                match captured {
+                    Ok(()) => (),
+                    Err(ref err) if replaying && err.kind() == io::ErrorKind::UnexpectedEof => {
+                        replay_finished.store(true, Ordering::SeqCst);
+                        display_handler.unpark();
+                    }
+                    Err(err) => {
+                        capture_events.send_interface_gone(&interface_name);
+                        let mut ui = lock(&ui);
+                        if raw_mode {
+                            eprintln!(
+                                "Interface {} is gone and no longer captured: {}",
+                                ui.display_interface_name(&interface_name),
+                                err
+                            );
                         }
+                        ui.log_lost_interface(&interface_name, &err);
                     }
-                })
-                .unwrap()
-        })
-        .collect::<Vec<_>>();
-    active_threads.extend(sniffer_threads);
+                }
+            }
+        });
+        match sniffer_thread {
+            Ok(sniffer_thread) => active_threads.push(sniffer_thread),
+            Err(err) => {
+                stop_threads(&running, display_handler, active_threads);
+                return Err(thread_error(&name, err));
+            }
+        }
+    }
+    active_threads.push(display_handler);
 
+    // a thread that panicked already said why, the others still get to finish
+    let mut panicked_threads = Vec::new();
     for thread_handler in active_threads {
-        thread_handler.join().unwrap()
+        let name = thread_handler.thread().name().map(String::from);
+        if thread_handler.join().is_err() {
+            panicked_threads.extend(name);
+        }
+    }
+    // once the duration or the idle timeout is up or a signal came in nobody presses a key
+    // anymore, so this would block forever
+    if duration.is_none()
+        && idle_timeout.is_none()
+        && !replaying
+        && !terminated.load(Ordering::SeqCst)
+    {
+        let name = stdin_handler.thread().name().map(String::from);
+        if stdin_handler.join().is_err() {
+            panicked_threads.extend(name);
+        }
+    }
+    if let Some(report) = lock(&session_report).take() {
+        // the terminal only leaves raw mode once the ui is dropped, the report is printed after
+        drop(ui);
+        (lock(&write_to_stdout))(report);
+    }
+    if !panicked_threads.is_empty() {
+        failure::bail!("The {} thread panicked", panicked_threads.join(", "));
+    }
+    if threshold_breached.load(Ordering::SeqCst) {
+        Ok(ExitStatus::ThresholdBreached)
+    } else if nothing_captured.load(Ordering::SeqCst) {
+        Ok(ExitStatus::NothingCaptured)
+    } else {
+        Ok(ExitStatus::Clean)
     }
 }
@@ -0,0 +1,7 @@
+mod snapshot;
+mod sorting;
+mod ui_state;
+
+pub use snapshot::*;
+pub use sorting::*;
+pub use ui_state::*;
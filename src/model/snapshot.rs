@@ -0,0 +1,279 @@
+use ::std::collections::HashMap;
+use ::std::net::IpAddr;
+use ::std::time::Duration;
+
+use chrono::prelude::*;
+
+use crate::model::{SortBy, UIState};
+use crate::network::{display_connection_string, display_ip_or_host, Connection};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcessEntry {
+    pub name: String,
+    pub connection_count: u128,
+    pub total_bytes_uploaded: u128,
+    pub total_bytes_downloaded: u128,
+    /// How many processes share the name, 0 unless --group-processes
+    pub pid_count: u128,
+}
+
+impl ProcessEntry {
+    /// The name with how many processes share it, eg. `firefox (×14)`
+    pub fn display_name(&self) -> String {
+        match self.pid_count {
+            0 => self.name.clone(),
+            pid_count => format!("{} (×{})", self.name, pid_count),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RemoteAddressEntry {
+    pub ip: IpAddr,
+    pub connection_count: u128,
+    pub total_bytes_uploaded: u128,
+    pub total_bytes_downloaded: u128,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectionEntry {
+    pub connection: Connection,
+    pub interface_name: String,
+    pub process_name: String,
+    pub total_bytes_uploaded: u128,
+    pub total_bytes_downloaded: u128,
+    pub total_packets: u128,
+    pub retransmitted_packets: u128,
+    pub age: Duration,
+    /// Bytes moved both ways since launch, never a rate
+    pub session_bytes: u128,
+    /// Who the socket on this end belongs to, if it is known (see `UIState::socket_users`)
+    pub user: Option<String>,
+}
+
+/// Everything known about a single tick, in the form every output (tables, raw text...) renders.
+///
+/// Byte and packet counts are per-second rates averaged over the recent ticks, or running totals since
+/// launch when `cumulative` is set. Entries are sorted by bandwidth, highest first.
+/// `ip_to_host` only holds the hostnames of the remote addresses that appear in this snapshot.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub timestamp: DateTime<Local>,
+    pub cumulative: bool,
+    pub total_bytes_uploaded: u128,
+    pub total_bytes_downloaded: u128,
+    pub processes: Vec<ProcessEntry>,
+    pub remote_addresses: Vec<RemoteAddressEntry>,
+    pub connections: Vec<ConnectionEntry>,
+    pub ip_to_host: HashMap<IpAddr, String>,
+}
+
+impl Snapshot {
+    pub fn new(
+        state: &UIState,
+        ip_to_host: &HashMap<IpAddr, String>,
+        timestamp: DateTime<Local>,
+    ) -> Self {
+        let processes = state
+            .processes
+            .iter()
+            .map(|(name, network_data)| ProcessEntry {
+                name: name.clone(),
+                connection_count: network_data.connection_count,
+                total_bytes_uploaded: network_data.total_bytes_uploaded,
+                total_bytes_downloaded: network_data.total_bytes_downloaded,
+                pid_count: state.process_pid_counts.get(name).cloned().unwrap_or(0),
+            })
+            .collect();
+        let remote_addresses = state
+            .remote_addresses
+            .iter()
+            .map(|(ip, network_data)| RemoteAddressEntry {
+                ip: *ip,
+                connection_count: network_data.connection_count,
+                total_bytes_uploaded: network_data.total_bytes_uploaded,
+                total_bytes_downloaded: network_data.total_bytes_downloaded,
+            })
+            .collect();
+        let connections = state
+            .connections
+            .iter()
+            .map(|(connection, connection_data)| ConnectionEntry {
+                connection: *connection,
+                interface_name: connection_data.interface_name.clone(),
+                process_name: connection_data.process_name.clone(),
+                total_bytes_uploaded: connection_data.total_bytes_uploaded,
+                total_bytes_downloaded: connection_data.total_bytes_downloaded,
+                total_packets: connection_data.total_packets,
+                retransmitted_packets: connection_data.retransmitted_packets,
+                age: connection_data.age,
+                session_bytes: connection_data.session_bytes,
+                user: state.socket_users.get(&connection.local_socket).cloned(),
+            })
+            .collect();
+        let ip_to_host = state
+            .remote_addresses
+            .iter()
+            .map(|(ip, _)| *ip)
+            .chain(
+                state
+                    .connections
+                    .iter()
+                    .map(|(connection, _)| connection.remote_socket.ip),
+            )
+            .filter_map(|ip| ip_to_host.get(&ip).map(|host| (ip, host.clone())))
+            .collect();
+        Snapshot {
+            timestamp,
+            cumulative: state.cumulative_mode,
+            total_bytes_uploaded: state.total_bytes_uploaded,
+            total_bytes_downloaded: state.total_bytes_downloaded,
+            processes,
+            remote_addresses,
+            connections,
+            ip_to_host,
+        }
+    }
+    /// A copy holding only the rows moving at least `bytes_per_second` up and down together. The
+    /// totals are left as they are
+    pub fn at_least(&self, bytes_per_second: u128) -> Snapshot {
+        let fast_enough =
+            |uploaded: u128, downloaded: u128| uploaded + downloaded >= bytes_per_second;
+        Snapshot {
+            processes: self
+                .processes
+                .iter()
+                .filter(|entry| {
+                    fast_enough(entry.total_bytes_uploaded, entry.total_bytes_downloaded)
+                })
+                .cloned()
+                .collect(),
+            remote_addresses: self
+                .remote_addresses
+                .iter()
+                .filter(|entry| {
+                    fast_enough(entry.total_bytes_uploaded, entry.total_bytes_downloaded)
+                })
+                .cloned()
+                .collect(),
+            connections: self
+                .connections
+                .iter()
+                .filter(|entry| {
+                    fast_enough(entry.total_bytes_uploaded, entry.total_bytes_downloaded)
+                })
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+    /// A copy sorted by `sort_by` whose rows just as busy are in the order of their name,
+    /// connection or address, so the same state always comes out the same way however the maps
+    /// it was counted in iterate (see the raw and JSON output)
+    pub fn in_stable_order(&self, sort_by: SortBy) -> Snapshot {
+        let mut snapshot = self.clone();
+        snapshot.processes.sort_by(|a, b| {
+            sort_by
+                .compare(
+                    (&a.name, a.total_bytes_uploaded, a.total_bytes_downloaded),
+                    (&b.name, b.total_bytes_uploaded, b.total_bytes_downloaded),
+                )
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        snapshot.remote_addresses.sort_by(|a, b| {
+            sort_by
+                .compare(
+                    (&a.ip, a.total_bytes_uploaded, a.total_bytes_downloaded),
+                    (&b.ip, b.total_bytes_uploaded, b.total_bytes_downloaded),
+                )
+                .then_with(|| a.ip.cmp(&b.ip))
+        });
+        snapshot.connections.sort_by(|a, b| {
+            // only connections have an age to sort by, see `SortBy`
+            let order = if sort_by == SortBy::Age {
+                b.age.cmp(&a.age)
+            } else {
+                sort_by.compare(
+                    (
+                        &a.connection,
+                        a.total_bytes_uploaded,
+                        a.total_bytes_downloaded,
+                    ),
+                    (
+                        &b.connection,
+                        b.total_bytes_uploaded,
+                        b.total_bytes_downloaded,
+                    ),
+                )
+            };
+            order.then_with(|| a.connection.cmp(&b.connection))
+        });
+        snapshot
+    }
+    /// A copy with the first `top` processes as they are and the rest summed up in a last row,
+    /// eg. `others (12)`, so the processes still add up to the totals (see --top)
+    pub fn with_top_processes(&self, top: usize) -> Snapshot {
+        let mut snapshot = self.clone();
+        if snapshot.processes.len() <= top {
+            return snapshot;
+        }
+        let others = snapshot.processes.split_off(top);
+        snapshot.processes.push(ProcessEntry {
+            name: format!("others ({})", others.len()),
+            connection_count: others.iter().map(|entry| entry.connection_count).sum(),
+            total_bytes_uploaded: others.iter().map(|entry| entry.total_bytes_uploaded).sum(),
+            total_bytes_downloaded: others
+                .iter()
+                .map(|entry| entry.total_bytes_downloaded)
+                .sum(),
+            pid_count: 0,
+        });
+        snapshot
+    }
+    /// How many rows the three tables have together
+    pub fn row_count(&self) -> usize {
+        self.processes.len() + self.remote_addresses.len() + self.connections.len()
+    }
+    /// A copy holding only the rows whose process name, connection or remote address contains
+    /// `query` (case insensitive). The totals are left as they are.
+    pub fn matching(&self, query: &str) -> Snapshot {
+        let query = query.to_lowercase();
+        let matches = |text: &str| text.to_lowercase().contains(&query);
+        let remote_address_matches = |ip: IpAddr| {
+            matches(&ip.to_string()) || matches(&display_ip_or_host(ip, &self.ip_to_host))
+        };
+        Snapshot {
+            processes: self
+                .processes
+                .iter()
+                .filter(|entry| matches(&entry.name))
+                .cloned()
+                .collect(),
+            remote_addresses: self
+                .remote_addresses
+                .iter()
+                .filter(|entry| remote_address_matches(entry.ip))
+                .cloned()
+                .collect(),
+            connections: self
+                .connections
+                .iter()
+                .filter(|entry| {
+                    matches(&entry.process_name)
+                        || remote_address_matches(entry.connection.remote_socket.ip)
+                        // whether the ports are shown as numbers or as service names
+                        || [false, true].iter().any(|&service_names| {
+                            matches(&display_connection_string(
+                                &entry.connection,
+                                &self.ip_to_host,
+                                &entry.interface_name,
+                                service_names,
+                            ))
+                        })
+                })
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use ::std::cmp::Ordering;
+use ::std::str::FromStr;
+
+/// The column the tables are sorted by, cycled with the `s` key.
+///
+/// Rates sort highest first, names alphabetically. Only connections have an age, the other
+/// tables stay sorted by total when sorting by it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortBy {
+    #[default]
+    Total,
+    Download,
+    Upload,
+    Name,
+    Age,
+}
+
+const SORT_MARKER: &str = " ▾";
+
+impl SortBy {
+    pub fn next(self) -> Self {
+        match self {
+            SortBy::Total => SortBy::Download,
+            SortBy::Download => SortBy::Upload,
+            SortBy::Upload => SortBy::Name,
+            SortBy::Name => SortBy::Age,
+            SortBy::Age => SortBy::Total,
+        }
+    }
+    /// What clicking the header of a column sorting by `column` sorts by: the bandwidth column
+    /// (`Total`) cycles through the rates, any other one sorts by it or back to the total
+    pub fn clicked(self, column: SortBy) -> Self {
+        match (self, column) {
+            (SortBy::Total, SortBy::Total) => SortBy::Download,
+            (SortBy::Download, SortBy::Total) => SortBy::Upload,
+            (_, SortBy::Total) => SortBy::Total,
+            (current, column) if current == column => SortBy::Total,
+            (_, column) => column,
+        }
+    }
+    /// What the sort key is called on the command line, eg. `download`
+    pub fn name(self) -> &'static str {
+        match self {
+            SortBy::Total => "total",
+            SortBy::Download => "download",
+            SortBy::Upload => "upload",
+            SortBy::Name => "name",
+            SortBy::Age => "age",
+        }
+    }
+    /// Marks the header of the column a table is sorted by, the default total sort leaves them as they are
+    pub fn mark_columns(
+        self,
+        column_names: &mut [String],
+        name_column: usize,
+        bandwidth_column: usize,
+    ) {
+        match self {
+            // only the connections table has an age column, it marks that one itself
+            SortBy::Total | SortBy::Age => {}
+            SortBy::Name => column_names[name_column].push_str(SORT_MARKER),
+            SortBy::Download => mark_word(&mut column_names[bandwidth_column], "Down"),
+            SortBy::Upload => mark_word(&mut column_names[bandwidth_column], "Up"),
+        }
+    }
+    /// Marks the age column of the connections table, the only one sorting by age applies to
+    pub fn mark_age_column(self, column_name: &mut String) {
+        if self == SortBy::Age {
+            column_name.push_str(SORT_MARKER);
+        }
+    }
+    /// Orders two rows, given their keys and their uploaded and downloaded bytes
+    pub fn compare<K: Ord>(
+        self,
+        (key_a, uploaded_a, downloaded_a): (&K, u128, u128),
+        (key_b, uploaded_b, downloaded_b): (&K, u128, u128),
+    ) -> Ordering {
+        match self {
+            SortBy::Total | SortBy::Age => {
+                (uploaded_b + downloaded_b).cmp(&(uploaded_a + downloaded_a))
+            }
+            SortBy::Download => downloaded_b.cmp(&downloaded_a),
+            SortBy::Upload => uploaded_b.cmp(&uploaded_a),
+            SortBy::Name => key_a.cmp(key_b),
+        }
+    }
+}
+
+fn mark_word(column_name: &mut String, word: &str) {
+    *column_name = column_name.replacen(word, &format!("{}{}", word, SORT_MARKER), 1);
+}
+
+impl FromStr for SortBy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "total" => Ok(SortBy::Total),
+            "download" => Ok(SortBy::Download),
+            "upload" => Ok(SortBy::Upload),
+            "name" => Ok(SortBy::Name),
+            "age" => Ok(SortBy::Age),
+            other => Err(format!(
+                "unknown sort key '{}', expected total, download, upload, name or age",
+                other
+            )),
+        }
+    }
+}
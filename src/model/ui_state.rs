@@ -0,0 +1,756 @@
+use ::std::cmp;
+use ::std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use ::std::hash::Hash;
+use ::std::iter::FromIterator;
+use ::std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use ::std::str::FromStr;
+use ::std::time::Duration;
+
+use crate::model::SortBy;
+use crate::network::{Connection, LocalSocket, Protocol, UnixSocket, Utilization};
+
+static RECALL_LENGTH: usize = 5;
+// the most windows a --rate-window recalls however short they are, eg. a minute of 0.05s ticks
+static MAX_RECALL_LENGTH: usize = 1200;
+static MAX_BANDWIDTH_ITEMS: usize = 1000;
+static CONNECTION_HISTORY_LENGTH: usize = 8;
+
+pub trait Bandwidth {
+    fn get_total_bytes_downloaded(&self) -> u128;
+    fn get_total_bytes_uploaded(&self) -> u128;
+    fn combine_bandwidth(&mut self, other: &Self);
+    fn scale(&mut self, numerator: u128, denominator: u128);
+}
+
+#[derive(Clone, Default)]
+pub struct NetworkData {
+    pub total_bytes_downloaded: u128,
+    pub total_bytes_uploaded: u128,
+    pub connection_count: u128,
+}
+
+#[derive(Clone, Default)]
+pub struct ConnectionData {
+    pub total_bytes_downloaded: u128,
+    pub total_bytes_uploaded: u128,
+    pub total_packets: u128,
+    pub retransmitted_packets: u128,
+    pub process_name: String,
+    pub interface_name: String,
+    /// How long the connection has been in the table, see `UIState::update_connection_ages`
+    pub age: Duration,
+    /// Bytes moved both ways since launch, see `UIState::update_connection_session_bytes`
+    pub session_bytes: u128,
+}
+
+// a merged connection can belong to several processes, they are all named (in order, so the name
+// does not change from tick to tick)
+fn add_process_name(process_names: &mut String, process_name: &str) {
+    if process_names.is_empty() {
+        process_names.push_str(process_name);
+        return;
+    }
+    let mut names: Vec<&str> = process_names.split(", ").collect();
+    if !names.contains(&process_name) {
+        names.push(process_name);
+        names.sort_unstable();
+        *process_names = names.join(", ");
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct ListeningSocketData {
+    pub process_name: String,
+    /// The connections it accepted that had traffic
+    pub connection_count: u128,
+    pub total_bytes_downloaded: u128,
+    pub total_bytes_uploaded: u128,
+}
+
+#[derive(Clone, Default)]
+pub struct UnixSocketData {
+    pub socket_count: u128,
+    pub process_names: BTreeSet<String>,
+}
+
+impl Bandwidth for NetworkData {
+    fn get_total_bytes_downloaded(&self) -> u128 {
+        self.total_bytes_downloaded
+    }
+    fn get_total_bytes_uploaded(&self) -> u128 {
+        self.total_bytes_uploaded
+    }
+    fn combine_bandwidth(&mut self, other: &NetworkData) {
+        self.total_bytes_downloaded += other.get_total_bytes_downloaded();
+        self.total_bytes_uploaded += other.get_total_bytes_uploaded();
+        self.connection_count = other.connection_count;
+    }
+    fn scale(&mut self, numerator: u128, denominator: u128) {
+        self.total_bytes_downloaded = self.total_bytes_downloaded * numerator / denominator;
+        self.total_bytes_uploaded = self.total_bytes_uploaded * numerator / denominator;
+    }
+}
+
+impl Bandwidth for ConnectionData {
+    fn get_total_bytes_downloaded(&self) -> u128 {
+        self.total_bytes_downloaded
+    }
+    fn get_total_bytes_uploaded(&self) -> u128 {
+        self.total_bytes_uploaded
+    }
+    fn combine_bandwidth(&mut self, other: &ConnectionData) {
+        self.total_bytes_downloaded += other.get_total_bytes_downloaded();
+        self.total_bytes_uploaded += other.get_total_bytes_uploaded();
+        self.total_packets += other.total_packets;
+        self.retransmitted_packets += other.retransmitted_packets;
+    }
+    fn scale(&mut self, numerator: u128, denominator: u128) {
+        self.total_bytes_downloaded = self.total_bytes_downloaded * numerator / denominator;
+        self.total_bytes_uploaded = self.total_bytes_uploaded * numerator / denominator;
+        self.total_packets = self.total_packets * numerator / denominator;
+        self.retransmitted_packets = self.retransmitted_packets * numerator / denominator;
+    }
+}
+
+/// Everything captured since launch, unlike the rates this is never averaged or reset
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SessionTotals {
+    pub bytes_uploaded: u128,
+    pub bytes_downloaded: u128,
+    pub elapsed: Duration,
+}
+
+impl SessionTotals {
+    /// `bytes` per second over the whole session, 0 before any time passed
+    pub fn average(&self, bytes: u128) -> u128 {
+        (bytes * 1000)
+            .checked_div(self.elapsed.as_millis())
+            .unwrap_or(0)
+    }
+}
+
+/// The bytes moved up and down since launch by every process and by every row of the connections
+/// table (with its process), which only ever go up unlike the rates (see --metrics-port)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LaunchTotals {
+    pub processes: HashMap<String, (u128, u128)>,
+    pub connections: HashMap<Connection, (String, u128, u128)>,
+}
+
+pub struct UtilizationData {
+    connections_to_procs: HashMap<LocalSocket, String>,
+    network_utilization: Utilization,
+    window: Duration,
+}
+
+/// Who opened the connections that are shown, see `--direction`.
+///
+/// A connection is inbound if one of the listening sockets of this host accepted it and outbound
+/// otherwise, whichever way most of its bytes go: downloading from a web server is outbound and
+/// pushing to our ssh server is inbound.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConnectionDirection {
+    In,
+    Out,
+    #[default]
+    Both,
+}
+
+impl FromStr for ConnectionDirection {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "in" => Ok(ConnectionDirection::In),
+            "out" => Ok(ConnectionDirection::Out),
+            "both" => Ok(ConnectionDirection::Both),
+            _ => Err(format!(
+                "invalid direction '{}', expected in, out or both",
+                s
+            )),
+        }
+    }
+}
+
+/// The process row the traffic of connections missing from the socket table is counted in, so
+/// that the processes always add up to the totals
+pub const UNKNOWN_PROCESS: &str = "<UNKNOWN>";
+
+#[derive(Default)]
+pub struct UIState {
+    pub processes: Vec<(String, NetworkData)>,
+    pub remote_addresses: Vec<(IpAddr, NetworkData)>,
+    pub connections: Vec<(Connection, ConnectionData)>,
+    /// The sockets waiting for connections and the processes they belong to
+    pub listening_sockets: Vec<(LocalSocket, String)>,
+    pub unix_sockets: Vec<(String, UnixSocketData)>,
+    pub total_bytes_downloaded: u128,
+    pub total_bytes_uploaded: u128,
+    pub cumulative_mode: bool,
+    /// Lowercase part of a process name, traffic of any other process is left out (totals included)
+    pub process_filter: Option<String>,
+    /// Lowercase parts of process names whose traffic is left out (totals included), even if it matches `process_filter`
+    pub process_excludes: Vec<String>,
+    /// Ports a connection has to use on either end to be counted, any connection if empty
+    pub port_filter: Vec<u16>,
+    /// Protocol a connection has to use to be counted, any protocol if unset
+    pub protocol_filter: Option<Protocol>,
+    /// Who has to have opened a connection for it to be counted (see `ConnectionDirection`)
+    pub direction_filter: ConnectionDirection,
+    /// Count connections to this host itself too, they are left out (totals included) otherwise
+    pub show_loopback: bool,
+    /// One row per remote end in the connections table, whatever the local end (see `Connection::merged`)
+    pub merge_connections: bool,
+    /// The traffic comes from a capture file (see --read-pcap), so its processes show up as n/a
+    pub processes_unknown: bool,
+    /// Count the segments rather than the bytes they carry, everything called bytes in here is
+    /// packets then (see --packets)
+    pub packets_mode: bool,
+    pub sort_by: SortBy,
+    /// How much of the latest traffic the rates are averaged over, the last `RECALL_LENGTH`
+    /// windows if unset (see --rate-window)
+    pub rate_window: Option<Duration>,
+    pub process_connection_history: HashMap<String, VecDeque<u128>>,
+    /// How many processes (by pid) share each name, only filled in with --group-processes
+    pub process_pid_counts: HashMap<String, u128>,
+    /// The users the processes of each name run as, only filled in with --show-user or
+    /// --group-by user
+    pub process_users: HashMap<String, String>,
+    /// The user each socket belongs to, filled in along with `process_users`
+    pub socket_users: HashMap<LocalSocket, String>,
+    pub launch_totals: LaunchTotals,
+    connection_ages: HashMap<Connection, Duration>,
+    connection_session_bytes: HashMap<Connection, u128>,
+    utilization_data: VecDeque<UtilizationData>,
+    processes_map: HashMap<String, NetworkData>,
+    remote_addresses_map: HashMap<IpAddr, NetworkData>,
+    connections_map: HashMap<Connection, ConnectionData>,
+}
+
+impl UIState {
+    fn get_proc_name<'a>(
+        connections_to_procs: &'a HashMap<LocalSocket, String>,
+        local_socket: &LocalSocket,
+    ) -> Option<&'a String> {
+        if let Some(process_name) = connections_to_procs.get(local_socket) {
+            Some(process_name)
+        } else if let Some(process_name) = connections_to_procs.get(&LocalSocket {
+            ip: IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
+            port: local_socket.port,
+            protocol: local_socket.protocol,
+        }) {
+            Some(process_name)
+        } else {
+            connections_to_procs.get(&LocalSocket {
+                ip: IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0)),
+                port: local_socket.port,
+                protocol: local_socket.protocol,
+            })
+        }
+    }
+    fn process_name(
+        &self,
+        connections_to_procs: &HashMap<LocalSocket, String>,
+        connection: &Connection,
+    ) -> String {
+        UIState::get_proc_name(connections_to_procs, &connection.local_socket)
+            .cloned()
+            .unwrap_or_else(|| {
+                String::from(if self.processes_unknown {
+                    "n/a"
+                } else {
+                    UNKNOWN_PROCESS
+                })
+            })
+    }
+    // the key of the row of the connections table a connection is counted in
+    fn connection_row(&self, connection: &Connection) -> Connection {
+        if self.merge_connections {
+            connection.merged()
+        } else {
+            *connection
+        }
+    }
+    fn passes_filters(&self, connection: &Connection, process_name: &str) -> bool {
+        let port_matches = self.port_filter.is_empty()
+            || self.port_filter.contains(&connection.local_socket.port)
+            || self.port_filter.contains(&connection.remote_socket.port);
+        let protocol_matches = match self.protocol_filter {
+            Some(protocol) => connection.local_socket.protocol == protocol,
+            None => true,
+        };
+        let direction_matches = match self.direction_filter {
+            ConnectionDirection::Both => true,
+            direction => direction == self.connection_direction(connection),
+        };
+        self.process_passes_filters(process_name)
+            && port_matches
+            && protocol_matches
+            && direction_matches
+            && (self.show_loopback || !connection.is_loopback())
+    }
+    fn process_passes_filters(&self, process_name: &str) -> bool {
+        let process_name = process_name.to_lowercase();
+        let process_matches = match &self.process_filter {
+            Some(filter) => process_name.contains(filter.as_str()),
+            None => true,
+        };
+        let process_excluded = self
+            .process_excludes
+            .iter()
+            .any(|exclude| process_name.contains(exclude.as_str()));
+        process_matches && !process_excluded
+    }
+    fn connection_direction(&self, connection: &Connection) -> ConnectionDirection {
+        let accepted = self
+            .listening_sockets
+            .iter()
+            .any(|(listening_socket, _)| accepted_by(listening_socket, &connection.local_socket));
+        if accepted {
+            ConnectionDirection::In
+        } else {
+            ConnectionDirection::Out
+        }
+    }
+    pub fn update(
+        &mut self,
+        connections_to_procs: HashMap<LocalSocket, String>,
+        network_utilization: Utilization,
+        window: Duration,
+    ) {
+        self.add_connection_session_bytes(&network_utilization);
+        self.add_launch_totals(&connections_to_procs, &network_utilization);
+        self.utilization_data.push_back(UtilizationData {
+            connections_to_procs,
+            network_utilization,
+            window,
+        });
+        self.forget_windows_out_of_recall();
+        let mut processes: HashMap<String, NetworkData> = HashMap::new();
+        let mut remote_addresses: HashMap<IpAddr, NetworkData> = HashMap::new();
+        let mut connections: HashMap<Connection, ConnectionData> = HashMap::new();
+        let mut total_bytes_downloaded: u128 = 0;
+        let mut total_bytes_uploaded: u128 = 0;
+
+        let mut seen_connections = HashSet::new();
+        for state in self.utilization_data.iter().rev() {
+            let connections_to_procs = &state.connections_to_procs;
+            let network_utilization = &state.network_utilization;
+
+            for (connection, connection_info) in &network_utilization.connections {
+                let process_name = self.process_name(connections_to_procs, connection);
+                if !self.passes_filters(connection, &process_name) {
+                    continue;
+                }
+                let (downloaded, uploaded) = if self.packets_mode {
+                    (
+                        connection_info.packets_downloaded,
+                        connection_info.packets_uploaded,
+                    )
+                } else {
+                    (
+                        connection_info.total_bytes_downloaded,
+                        connection_info.total_bytes_uploaded,
+                    )
+                };
+                // the connection counts stay those of the real connections
+                let connection_previously_seen = !seen_connections.insert(connection);
+                let connection_data = connections
+                    .entry(self.connection_row(connection))
+                    .or_default();
+                let data_for_remote_address = remote_addresses
+                    .entry(connection.remote_socket.ip)
+                    .or_default();
+                connection_data.total_bytes_downloaded += downloaded;
+                connection_data.total_bytes_uploaded += uploaded;
+                connection_data.total_packets += connection_info.total_packets;
+                connection_data.retransmitted_packets += connection_info.retransmitted_packets;
+                connection_data.interface_name = connection_info.interface_name.clone();
+                data_for_remote_address.total_bytes_downloaded += downloaded;
+                data_for_remote_address.total_bytes_uploaded += uploaded;
+                if !connection_previously_seen {
+                    data_for_remote_address.connection_count += 1;
+                }
+                total_bytes_downloaded += downloaded;
+                total_bytes_uploaded += uploaded;
+
+                let data_for_process = processes.entry(process_name.clone()).or_default();
+                add_process_name(&mut connection_data.process_name, &process_name);
+
+                data_for_process.total_bytes_downloaded += downloaded;
+                data_for_process.total_bytes_uploaded += uploaded;
+                if !connection_previously_seen {
+                    data_for_process.connection_count += 1;
+                }
+            }
+        }
+        let window_count = cmp::max(self.utilization_data.len() as u128, 1);
+        let window_millis: u128 = self
+            .utilization_data
+            .iter()
+            .map(|state| state.window.as_millis())
+            .sum();
+        // rates are divided by the time the windows actually took, which is not always the
+        // interval (eg. the one before the first tick or the one cut short by unpausing).
+        // the cumulative totals add up an average window per tick instead
+        let (multiply_by, divide_by) = if self.cumulative_mode || window_millis == 0 {
+            (1, window_count)
+        } else {
+            (1000, window_millis)
+        };
+        for (_, network_data) in processes.iter_mut() {
+            network_data.scale(multiply_by, divide_by)
+        }
+        for (_, network_data) in remote_addresses.iter_mut() {
+            network_data.scale(multiply_by, divide_by)
+        }
+        for (_, connection_data) in connections.iter_mut() {
+            connection_data.scale(multiply_by, divide_by)
+        }
+        let total_bytes_downloaded = total_bytes_downloaded * multiply_by;
+        let total_bytes_uploaded = total_bytes_uploaded * multiply_by;
+
+        if self.cumulative_mode {
+            merge_bandwidth(&mut self.processes_map, processes);
+            merge_bandwidth(&mut self.remote_addresses_map, remote_addresses);
+            merge_bandwidth(&mut self.connections_map, connections);
+            self.total_bytes_downloaded += total_bytes_downloaded / divide_by;
+            self.total_bytes_uploaded += total_bytes_uploaded / divide_by;
+        } else {
+            self.processes_map = processes;
+            self.remote_addresses_map = remote_addresses;
+            self.connections_map = connections;
+            self.total_bytes_downloaded = total_bytes_downloaded / divide_by;
+            self.total_bytes_uploaded = total_bytes_uploaded / divide_by;
+        }
+        self.update_process_connection_history();
+        self.update_connection_ages(window);
+        self.forget_launch_totals_of_gone_connections();
+        self.update_connection_session_bytes();
+        self.processes = sort_and_prune(&mut self.processes_map, self.sort_by);
+        self.remote_addresses = sort_and_prune(&mut self.remote_addresses_map, self.sort_by);
+        self.connections = sort_and_prune(&mut self.connections_map, self.sort_by);
+        if self.sort_by == SortBy::Age {
+            self.connections
+                .sort_by(|(connection_a, a), (connection_b, b)| {
+                    b.age
+                        .cmp(&a.age)
+                        .then_with(|| connection_a.cmp(connection_b))
+                });
+        }
+    }
+    // the oldest windows are dropped as long as the ones after them still cover the rate window,
+    // so the rates are averaged over at least as much of it as there is (and no more than
+    // `MAX_RECALL_LENGTH` windows of it)
+    fn forget_windows_out_of_recall(&mut self) {
+        match self.rate_window {
+            Some(rate_window) => {
+                let mut recalled: Duration =
+                    self.utilization_data.iter().map(|state| state.window).sum();
+                while let Some(oldest) = self.utilization_data.front() {
+                    let too_many = self.utilization_data.len() > MAX_RECALL_LENGTH;
+                    if !too_many
+                        && (self.utilization_data.len() == 1
+                            || recalled - oldest.window < rate_window)
+                    {
+                        break;
+                    }
+                    recalled -= oldest.window;
+                    self.utilization_data.pop_front();
+                }
+            }
+            None => {
+                if self.utilization_data.len() > RECALL_LENGTH {
+                    self.utilization_data.pop_front();
+                }
+            }
+        }
+    }
+    /// Forgets the connection ages and session bytes, and the totals so far in cumulative mode.
+    /// The recent windows the rates are averaged over are kept
+    pub fn reset_session(&mut self) {
+        if self.cumulative_mode {
+            self.processes_map.clear();
+            self.remote_addresses_map.clear();
+            self.connections_map.clear();
+            self.total_bytes_downloaded = 0;
+            self.total_bytes_uploaded = 0;
+        }
+        self.connection_ages.clear();
+        self.connection_session_bytes.clear();
+    }
+    // a connection ages by every window it stays in the table from the one it showed up in on.
+    // one that drops out of it (eg. after going quiet for a while) starts from scratch if it comes back
+    fn update_connection_ages(&mut self, window: Duration) {
+        let connections_map = &mut self.connections_map;
+        self.connection_ages
+            .retain(|connection, _| connections_map.contains_key(connection));
+        for (connection, connection_data) in connections_map.iter_mut() {
+            let age = self
+                .connection_ages
+                .entry(*connection)
+                .and_modify(|age| *age += window)
+                .or_default();
+            connection_data.age = *age;
+        }
+    }
+    // unlike the rates these are never averaged or reset, and a connection that drops out of the
+    // table keeps its count for when it comes back
+    fn add_connection_session_bytes(&mut self, network_utilization: &Utilization) {
+        for (connection, connection_info) in &network_utilization.connections {
+            let connection_row = self.connection_row(connection);
+            *self
+                .connection_session_bytes
+                .entry(connection_row)
+                .or_default() +=
+                connection_info.total_bytes_uploaded + connection_info.total_bytes_downloaded;
+        }
+    }
+    // in bytes whatever the tables count, and through the same filters
+    fn add_launch_totals(
+        &mut self,
+        connections_to_procs: &HashMap<LocalSocket, String>,
+        network_utilization: &Utilization,
+    ) {
+        for (connection, connection_info) in &network_utilization.connections {
+            let process_name = self.process_name(connections_to_procs, connection);
+            if !self.passes_filters(connection, &process_name) {
+                continue;
+            }
+            let uploaded = connection_info.total_bytes_uploaded;
+            let downloaded = connection_info.total_bytes_downloaded;
+            let connection_row = self.connection_row(connection);
+            let process_totals = self
+                .launch_totals
+                .processes
+                .entry(process_name.clone())
+                .or_default();
+            process_totals.0 += uploaded;
+            process_totals.1 += downloaded;
+            let connection_totals = self
+                .launch_totals
+                .connections
+                .entry(connection_row)
+                .or_insert_with(|| (process_name, 0, 0));
+            connection_totals.1 += uploaded;
+            connection_totals.2 += downloaded;
+        }
+    }
+    // there would be no end to them otherwise, one that comes back starts from scratch (which
+    // a counter may do)
+    fn forget_launch_totals_of_gone_connections(&mut self) {
+        let connections_map = &self.connections_map;
+        self.launch_totals
+            .connections
+            .retain(|connection, _| connections_map.contains_key(connection));
+    }
+    fn update_connection_session_bytes(&mut self) {
+        for (connection, connection_data) in self.connections_map.iter_mut() {
+            connection_data.session_bytes = self
+                .connection_session_bytes
+                .get(connection)
+                .cloned()
+                .unwrap_or(0);
+        }
+    }
+    fn update_process_connection_history(&mut self) {
+        let processes_map = &self.processes_map;
+        // processes that are gone start from scratch if they ever come back
+        self.process_connection_history
+            .retain(|process_name, _| processes_map.contains_key(process_name));
+        for (process_name, network_data) in processes_map {
+            let history = self
+                .process_connection_history
+                .entry(process_name.clone())
+                .or_default();
+            history.push_back(network_data.connection_count);
+            if history.len() > CONNECTION_HISTORY_LENGTH {
+                history.pop_front();
+            }
+        }
+    }
+    pub fn update_process_pid_counts(
+        &mut self,
+        sockets_to_procs: &HashMap<LocalSocket, String>,
+        sockets_to_pids: &HashMap<LocalSocket, u32>,
+    ) {
+        let mut pids: HashMap<&String, HashSet<u32>> = HashMap::new();
+        for (local_socket, process_name) in sockets_to_procs {
+            if let Some(pid) = sockets_to_pids.get(local_socket) {
+                pids.entry(process_name).or_default().insert(*pid);
+            }
+        }
+        self.process_pid_counts = pids
+            .into_iter()
+            .map(|(process_name, pids)| (process_name.clone(), pids.len() as u128))
+            .collect();
+    }
+    pub fn update_process_users(
+        &mut self,
+        sockets_to_procs: &HashMap<LocalSocket, String>,
+        sockets_to_users: &HashMap<LocalSocket, String>,
+    ) {
+        let mut users: HashMap<&String, BTreeSet<&String>> = HashMap::new();
+        for (local_socket, process_name) in sockets_to_procs {
+            if let Some(user) = sockets_to_users.get(local_socket) {
+                users.entry(process_name).or_default().insert(user);
+            }
+        }
+        // processes of the same name can run as several users, eg. `root, www-data` for nginx
+        self.process_users = users
+            .into_iter()
+            .map(|(process_name, users)| {
+                let users: Vec<&str> = users.into_iter().map(String::as_str).collect();
+                (process_name.clone(), users.join(", "))
+            })
+            .collect();
+        self.socket_users = sockets_to_users.clone();
+    }
+    pub fn update_listening_sockets(&mut self, listening_sockets: HashMap<LocalSocket, String>) {
+        let mut listening_sockets = Vec::from_iter(listening_sockets);
+        listening_sockets.sort();
+        self.listening_sockets = listening_sockets;
+    }
+    /// The connections open right now and the processes they belong to, traffic or not (see
+    /// --once), filtered like the tables and sorted by process
+    pub fn open_connections(
+        &self,
+        connections: &[Connection],
+        connections_to_procs: &HashMap<LocalSocket, String>,
+    ) -> Vec<(Connection, String)> {
+        let mut open_connections: Vec<(Connection, String)> = connections
+            .iter()
+            .map(|connection| {
+                let process_name =
+                    UIState::get_proc_name(connections_to_procs, &connection.local_socket)
+                        .cloned()
+                        .unwrap_or_else(|| String::from(UNKNOWN_PROCESS));
+                (*connection, process_name)
+            })
+            .filter(|(connection, process_name)| self.passes_filters(connection, process_name))
+            .collect();
+        open_connections.sort_by(|(connection_a, process_a), (connection_b, process_b)| {
+            process_a
+                .cmp(process_b)
+                .then_with(|| connection_a.cmp(connection_b))
+        });
+        open_connections
+    }
+    /// The listening sockets of the processes the filters let through (see --once), the ports and
+    /// the protocol are those of the sockets
+    pub fn open_listening_sockets(&self) -> Vec<(LocalSocket, String)> {
+        self.listening_sockets
+            .iter()
+            .filter(|(listening_socket, process_name)| {
+                (self.port_filter.is_empty() || self.port_filter.contains(&listening_socket.port))
+                    && self
+                        .protocol_filter
+                        .map_or(true, |protocol| listening_socket.protocol == protocol)
+                    && self.process_passes_filters(process_name)
+            })
+            .cloned()
+            .collect()
+    }
+    /// The listening sockets with the traffic of the connections they accepted, busiest first
+    pub fn listening_socket_rows(&self) -> Vec<(LocalSocket, ListeningSocketData)> {
+        let mut rows: Vec<(LocalSocket, ListeningSocketData)> = self
+            .listening_sockets
+            .iter()
+            .map(|(listening_socket, process_name)| {
+                let mut data = ListeningSocketData {
+                    process_name: process_name.clone(),
+                    ..Default::default()
+                };
+                for (connection, connection_data) in &self.connections {
+                    if accepted_by(listening_socket, &connection.local_socket) {
+                        data.connection_count += 1;
+                        data.total_bytes_downloaded += connection_data.total_bytes_downloaded;
+                        data.total_bytes_uploaded += connection_data.total_bytes_uploaded;
+                    }
+                }
+                (*listening_socket, data)
+            })
+            .collect();
+        rows.sort_by(|(socket_a, data_a), (socket_b, data_b)| {
+            let bandwidth_a = data_a.total_bytes_downloaded + data_a.total_bytes_uploaded;
+            let bandwidth_b = data_b.total_bytes_downloaded + data_b.total_bytes_uploaded;
+            bandwidth_b
+                .cmp(&bandwidth_a)
+                .then_with(|| socket_a.cmp(socket_b))
+        });
+        rows.truncate(MAX_BANDWIDTH_ITEMS);
+        rows
+    }
+    pub fn update_unix_sockets(&mut self, unix_sockets: Vec<UnixSocket>) {
+        let mut paths: HashMap<String, UnixSocketData> = HashMap::new();
+        for unix_socket in unix_sockets {
+            let data_for_path = paths.entry(unix_socket.path).or_default();
+            data_for_path.socket_count += 1;
+            data_for_path.process_names.insert(unix_socket.process_name);
+        }
+        let mut unix_sockets = Vec::from_iter(paths);
+        unix_sockets.sort_by(|(path_a, data_a), (path_b, data_b)| {
+            data_b
+                .socket_count
+                .cmp(&data_a.socket_count)
+                .then_with(|| path_a.cmp(path_b))
+        });
+        unix_sockets.truncate(MAX_BANDWIDTH_ITEMS);
+        self.unix_sockets = unix_sockets;
+    }
+}
+
+// a socket listening on the unspecified address takes connections to any address of the host
+fn accepted_by(listening_socket: &LocalSocket, local_socket: &LocalSocket) -> bool {
+    listening_socket.port == local_socket.port
+        && listening_socket.protocol == local_socket.protocol
+        && (listening_socket.ip.is_unspecified() || listening_socket.ip == local_socket.ip)
+}
+
+fn merge_bandwidth<K, V>(self_map: &mut HashMap<K, V>, other_map: HashMap<K, V>)
+where
+    K: Eq + Hash,
+    V: Bandwidth,
+{
+    for (key, b_other) in other_map {
+        self_map
+            .entry(key)
+            .and_modify(|b_self| b_self.combine_bandwidth(&b_other))
+            .or_insert(b_other);
+    }
+}
+
+fn sort_and_prune<K, V>(map: &mut HashMap<K, V>, sort_by: SortBy) -> Vec<(K, V)>
+where
+    K: Eq + Hash + Clone + Ord,
+    V: Bandwidth + Clone,
+{
+    let mut bandwidth_list = Vec::from_iter(map.clone());
+    bandwidth_list.sort_by_key(|(_, b)| {
+        cmp::Reverse(b.get_total_bytes_downloaded() + b.get_total_bytes_uploaded())
+    });
+
+    if bandwidth_list.len() > MAX_BANDWIDTH_ITEMS {
+        for (key, _) in &bandwidth_list[MAX_BANDWIDTH_ITEMS..] {
+            map.remove(key);
+        }
+    }
+    // the busiest items are the ones that are kept, whatever they are then sorted by
+    if sort_by != SortBy::Total {
+        bandwidth_list.sort_by(|(key_a, a), (key_b, b)| {
+            sort_by.compare(
+                (
+                    key_a,
+                    a.get_total_bytes_uploaded(),
+                    a.get_total_bytes_downloaded(),
+                ),
+                (
+                    key_b,
+                    b.get_total_bytes_uploaded(),
+                    b.get_total_bytes_downloaded(),
+                ),
+            )
+        });
+    }
+
+    bandwidth_list
+}
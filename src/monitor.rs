@@ -0,0 +1,127 @@
+use ::pnet_bandwhich_fork::datalink::{self, Channel, Config, DataLinkReceiver, NetworkInterface};
+use ::std::collections::HashMap;
+use ::std::io;
+use ::std::net::IpAddr;
+use ::std::sync::atomic::{AtomicBool, Ordering};
+use ::std::sync::mpsc::Receiver;
+use ::std::sync::Arc;
+use ::std::thread;
+use ::std::time::{Duration, Instant};
+
+use chrono::prelude::*;
+
+use crate::model::{Snapshot, UIState};
+use crate::network::{
+    capture_queue, CaptureDirection, CaptureEvent, LocalSocket, Sniffer, Utilization,
+    CAPTURE_QUEUE_LENGTH,
+};
+
+// how long a capture waits for a packet at a time, so it notices when the monitor is dropped
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Bandwidth monitoring without the terminal UI: captures from some interfaces in the background
+/// and sums up what came in since the last `poll`.
+///
+/// Which process a connection belongs to is up to the caller to tell (see `set_process_names`),
+/// just like the hostnames of the remote addresses (see `network::dns::Client`).
+pub struct Monitor {
+    running: Arc<AtomicBool>,
+    capture_events: Receiver<CaptureEvent>,
+    utilization: Utilization,
+    state: UIState,
+    process_names: HashMap<LocalSocket, String>,
+    ip_to_host: HashMap<IpAddr, String>,
+    last_poll: Instant,
+}
+
+impl Monitor {
+    /// Captures from the interfaces with these names, from every interface that is up but the
+    /// loopback ones if none are given. This usually takes root (or CAP_NET_RAW)
+    pub fn new(interface_names: &[&str]) -> io::Result<Self> {
+        let interfaces = datalink::interfaces().into_iter().filter(|interface| {
+            if interface_names.is_empty() {
+                interface.is_up() && !interface.is_loopback() && !interface.ips.is_empty()
+            } else {
+                interface_names.contains(&interface.name.as_str())
+            }
+        });
+        let mut captures = vec![];
+        for interface in interfaces {
+            let config = Config {
+                read_timeout: Some(READ_TIMEOUT),
+                ..Default::default()
+            };
+            match datalink::channel(&interface, config)? {
+                Channel::Ethernet(_, frames) => captures.push((interface, frames)),
+                _ => {
+                    return Err(io::Error::other(format!(
+                        "{}: Unsupported interface type",
+                        interface.name
+                    )))
+                }
+            }
+        }
+        if captures.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no interface to capture from",
+            ));
+        }
+        Ok(Monitor::from_frames(captures))
+    }
+    /// Captures from frames read some other way, eg. from a file, along with the interface they
+    /// are from (whose addresses tell uploads from downloads)
+    pub fn from_frames(captures: Vec<(NetworkInterface, Box<dyn DataLinkReceiver>)>) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let (sender, capture_events) = capture_queue(CAPTURE_QUEUE_LENGTH);
+        for (interface, frames) in captures {
+            let running = running.clone();
+            let mut sender = sender.clone();
+            thread::spawn(move || {
+                let interface_name = interface.name.clone();
+                let mut sniffer =
+                    Sniffer::new(interface, frames, CaptureDirection::Both, None, false);
+                if sniffer.capture(&mut sender, &running, |_| {}).is_err() {
+                    sender.send_interface_gone(&interface_name);
+                }
+            });
+        }
+        Monitor {
+            running,
+            capture_events,
+            utilization: Utilization::new(),
+            state: UIState::default(),
+            process_names: HashMap::new(),
+            ip_to_host: HashMap::new(),
+            last_poll: Instant::now(),
+        }
+    }
+    /// The processes of the local sockets, those of the connections that are not in here are
+    /// counted as `<UNKNOWN>`
+    pub fn set_process_names(&mut self, process_names: HashMap<LocalSocket, String>) {
+        self.process_names = process_names;
+    }
+    /// The hostnames of remote addresses, eg. from `network::dns::Client::cache`
+    pub fn set_hostnames(&mut self, ip_to_host: HashMap<IpAddr, String>) {
+        self.ip_to_host = ip_to_host;
+    }
+    /// What was captured since the last poll (or since the monitor was created), as per second
+    /// rates averaged over the last few polls
+    pub fn poll(&mut self) -> Snapshot {
+        self.utilization.drain(&self.capture_events);
+        let utilization = self.utilization.clone_and_reset();
+        let now = Instant::now();
+        let window = now.duration_since(self.last_poll);
+        self.last_poll = now;
+        self.state
+            .update(self.process_names.clone(), utilization, window);
+        Snapshot::new(&self.state, &self.ip_to_host, Local::now())
+    }
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        // the captures stop within a read timeout
+        self.running.store(false, Ordering::Release);
+    }
+}
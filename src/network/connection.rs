@@ -1,25 +1,26 @@
 use ::std::collections::HashMap;
 use ::std::fmt;
-use ::std::net::IpAddr;
+use ::std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use ::std::str::FromStr;
 
 use ::std::net::SocketAddr;
 
+use crate::network::service_name;
+
 #[derive(PartialEq, Hash, Eq, Clone, PartialOrd, Ord, Debug, Copy)]
 pub enum Protocol {
     Tcp,
     Udp,
 }
 
-impl Protocol {
-    // Currently, linux implementation doesn't use this function.
-    // Without this #[cfg] clippy complains about dead code, and CI refuses
-    // to pass.
-    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
-    pub fn from_str(string: &str) -> Option<Self> {
-        match string {
-            "TCP" => Some(Protocol::Tcp),
-            "UDP" => Some(Protocol::Udp),
-            _ => None,
+// lsof spells them in uppercase, --protocol in lowercase
+impl FromStr for Protocol {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "tcp" => Ok(Protocol::Tcp),
+            "udp" => Ok(Protocol::Udp),
+            _ => Err(format!("unknown protocol '{}', expected tcp or udp", s)),
         }
     }
 }
@@ -59,18 +60,33 @@ pub fn display_ip_or_host(ip: IpAddr, ip_to_host: &HashMap<IpAddr, String>) -> S
     }
 }
 
+/// `service_names` shows the remote port as the service usually on it (eg. https rather than
+/// 443) where it is a well-known one
 pub fn display_connection_string(
     connection: &Connection,
     ip_to_host: &HashMap<IpAddr, String>,
     interface_name: &str,
+    service_names: bool,
 ) -> String {
+    let remote_ip = connection.remote_socket.ip;
+    // the port would otherwise read as another group of the address
+    let remote_host = match remote_ip {
+        IpAddr::V6(_) if !ip_to_host.contains_key(&remote_ip) => format!("[{}]", remote_ip),
+        _ => display_ip_or_host(remote_ip, ip_to_host),
+    };
+    let protocol = connection.local_socket.protocol;
+    let remote_port = match service_name(connection.remote_socket.port, protocol) {
+        Some(name) if service_names => String::from(name),
+        _ => connection.remote_socket.port.to_string(),
+    };
+    // a merged connection stands for any local port
+    let local_port = match connection.local_socket.port {
+        0 => String::from("*"),
+        port => port.to_string(),
+    };
     format!(
         "<{}>:{} => {}:{} ({})",
-        interface_name,
-        connection.local_socket.port,
-        display_ip_or_host(connection.remote_socket.ip, ip_to_host),
-        connection.remote_socket.port,
-        connection.local_socket.protocol,
+        interface_name, local_port, remote_host, remote_port, protocol,
     )
 }
 
@@ -93,4 +109,24 @@ impl Connection {
             },
         }
     }
+    /// Whether the other end is this host, eg. 127.0.0.1 or ::1
+    pub fn is_loopback(&self) -> bool {
+        self.remote_socket.ip.is_loopback()
+    }
+    /// The same remote end without the local one, so every connection to it shares this one
+    /// (see --merge-connections). Its local port is 0, which no real connection uses
+    pub fn merged(&self) -> Connection {
+        let unspecified = match self.local_socket.ip {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        };
+        Connection {
+            remote_socket: self.remote_socket,
+            local_socket: LocalSocket {
+                ip: unspecified,
+                port: 0,
+                protocol: self.local_socket.protocol,
+            },
+        }
+    }
 }
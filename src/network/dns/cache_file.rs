@@ -0,0 +1,136 @@
+use crate::network::dns::IpTable;
+use std::{
+    collections::HashMap,
+    fs, io,
+    net::IpAddr,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Hostnames resolved in earlier runs, kept in a file between them (see `--dns-cache`).
+///
+/// Every line holds an ip, the unix time its hostname expires at and the hostname, eg.
+/// `1.1.1.1 1600000000 one.one.one.one`.
+pub struct CacheFile {
+    path: PathBuf,
+    ttl: Duration,
+    expiries: HashMap<IpAddr, u64>,
+}
+
+fn unix_time_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0)
+}
+
+impl CacheFile {
+    pub fn new(path: PathBuf, ttl: Duration) -> Self {
+        CacheFile {
+            path,
+            ttl,
+            expiries: HashMap::new(),
+        }
+    }
+
+    /// The entries that did not expire yet, a missing file is an empty cache
+    pub fn load(&mut self) -> io::Result<IpTable> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(IpTable::new()),
+            Err(err) => return Err(err),
+        };
+        let now = unix_time_now();
+        let mut ip_table = IpTable::new();
+        for line in contents.lines() {
+            let mut fields = line.split_whitespace();
+            // lines that cannot be read are dropped, they are written again once resolved
+            if let (Some(ip), Some(expires_at), Some(host)) =
+                (fields.next(), fields.next(), fields.next())
+            {
+                if let (Ok(ip), Ok(expires_at)) = (ip.parse(), expires_at.parse::<u64>()) {
+                    if expires_at > now {
+                        self.expiries.insert(ip, expires_at);
+                        ip_table.insert(ip, String::from(host));
+                    }
+                }
+            }
+        }
+        Ok(ip_table)
+    }
+
+    /// Writes `ip_table` over the file, entries loaded from it keep the expiry they had
+    pub fn save(&self, ip_table: &IpTable) -> io::Result<()> {
+        let expires_at = unix_time_now() + self.ttl.as_secs();
+        let mut lines: Vec<String> = ip_table
+            .iter()
+            .map(|(ip, host)| {
+                format!(
+                    "{} {} {}\n",
+                    ip,
+                    self.expiries.get(ip).unwrap_or(&expires_at),
+                    host
+                )
+            })
+            .collect();
+        lines.sort();
+        fs::write(&self.path, lines.concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "bandwhich-dns-cache-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_missing_cache_file_is_empty() {
+        let mut cache_file = CacheFile::new(cache_path("missing"), Duration::from_secs(60));
+        assert!(cache_file.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_saved_hostnames_are_loaded_until_they_expire() {
+        let path = cache_path("expiry");
+        let mut ip_table = IpTable::new();
+        ip_table.insert("1.1.1.1".parse().unwrap(), String::from("one.one.one.one"));
+        CacheFile::new(path.clone(), Duration::from_secs(60))
+            .save(&ip_table)
+            .unwrap();
+        assert_eq!(
+            CacheFile::new(path.clone(), Duration::from_secs(60))
+                .load()
+                .unwrap(),
+            ip_table
+        );
+
+        CacheFile::new(path.clone(), Duration::from_secs(0))
+            .save(&ip_table)
+            .unwrap();
+        assert!(CacheFile::new(path.clone(), Duration::from_secs(60))
+            .load()
+            .unwrap()
+            .is_empty());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_unreadable_lines_are_dropped() {
+        let path = cache_path("garbage");
+        fs::write(&path, "not an entry\n2.2.2.2 99999999999 two.two\n").unwrap();
+        let ip_table = CacheFile::new(path.clone(), Duration::from_secs(60))
+            .load()
+            .unwrap();
+        assert_eq!(ip_table.len(), 1);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -4,6 +4,7 @@ use std::{
     net::IpAddr,
     sync::{Arc, Mutex},
     thread::{Builder, JoinHandle},
+    time::{Duration, Instant},
 };
 use tokio::{
     runtime::Runtime,
@@ -14,9 +15,22 @@ type PendingAddrs = HashSet<IpAddr>;
 
 const CHANNEL_SIZE: usize = 1_000;
 
+/// How the lookups went since the stats were last taken (see `--dns-stats`)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DnsStats {
+    /// The lookups that completed, whether they found a name or not
+    pub lookups: usize,
+    pub average: Option<Duration>,
+    pub max: Option<Duration>,
+    /// The addresses still being resolved
+    pub pending: usize,
+}
+
 pub struct Client {
     cache: Arc<Mutex<IpTable>>,
     pending: Arc<Mutex<PendingAddrs>>,
+    /// How long each lookup that completed since the stats were last taken took
+    durations: Arc<Mutex<Vec<Duration>>>,
     tx: Option<Sender<Vec<IpAddr>>>,
     handle: Option<JoinHandle<()>>,
 }
@@ -28,11 +42,13 @@ impl Client {
     {
         let cache = Arc::new(Mutex::new(IpTable::new()));
         let pending = Arc::new(Mutex::new(PendingAddrs::new()));
+        let durations = Arc::new(Mutex::new(Vec::new()));
         let (tx, mut rx) = mpsc::channel::<Vec<IpAddr>>(CHANNEL_SIZE);
 
         let handle = Builder::new().name("resolver".into()).spawn({
             let cache = cache.clone();
             let pending = pending.clone();
+            let durations = durations.clone();
             move || {
                 runtime.block_on(async {
                     let resolver = Arc::new(resolver);
@@ -43,11 +59,14 @@ impl Client {
                                 let resolver = resolver.clone();
                                 let cache = cache.clone();
                                 let pending = pending.clone();
+                                let durations = durations.clone();
 
                                 async move {
+                                    let started = Instant::now();
                                     if let Some(name) = resolver.lookup(ip).await {
                                         cache.lock().unwrap().insert(ip, name);
                                     }
+                                    durations.lock().unwrap().push(started.elapsed());
                                     pending.lock().unwrap().remove(&ip);
                                 }
                             });
@@ -60,6 +79,7 @@ impl Client {
         Ok(Self {
             cache,
             pending,
+            durations,
             tx: Some(tx),
             handle: Some(handle),
         })
@@ -78,10 +98,31 @@ impl Client {
         }
     }
 
+    pub fn extend_cache(&mut self, ip_table: IpTable) {
+        self.cache.lock().unwrap().extend(ip_table);
+    }
+
+    // every lookup writes its result as soon as it completes, so this already holds the names
+    // of a batch that is still being resolved
     pub fn cache(&mut self) -> IpTable {
         let cache = self.cache.lock().unwrap();
         cache.clone()
     }
+
+    /// How the lookups went since the last call
+    pub fn take_stats(&mut self) -> DnsStats {
+        let durations = self.durations.lock().unwrap().drain(..).collect::<Vec<_>>();
+        DnsStats {
+            lookups: durations.len(),
+            average: if durations.is_empty() {
+                None
+            } else {
+                Some(durations.iter().sum::<Duration>() / durations.len() as u32)
+            },
+            max: durations.iter().copied().max(),
+            pending: self.pending.lock().unwrap().len(),
+        }
+    }
 }
 
 impl Drop for Client {
@@ -0,0 +1,49 @@
+// the public suffixes that take more than one label, the rest are assumed to take only the last
+// one. Not the whole public suffix list, just the ones that are common enough to show up
+const MULTI_LABEL_SUFFIXES: &[&str] = &[
+    "ac.uk",
+    "co.uk",
+    "gov.uk",
+    "org.uk",
+    "com.au",
+    "net.au",
+    "org.au",
+    "co.nz",
+    "co.jp",
+    "ne.jp",
+    "or.jp",
+    "co.kr",
+    "co.in",
+    "co.za",
+    "com.br",
+    "com.cn",
+    "com.hk",
+    "com.mx",
+    "com.sg",
+    "com.tr",
+    "com.tw",
+    "amazonaws.com",
+    "cloudfront.net",
+    "github.io",
+    "herokuapp.com",
+];
+
+/// The part of `host` that was registered, eg. `googlevideo.com` for
+/// `rr1---sn-abc.googlevideo.com` or `bbc.co.uk` for `www.bbc.co.uk`.
+///
+/// Hosts that are no longer than their suffix (eg. `localhost`) are returned as they are.
+pub fn registrable_domain(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_lowercase();
+    let labels: Vec<&str> = host.split('.').collect();
+    let suffix_length = if labels.len() >= 2
+        && MULTI_LABEL_SUFFIXES.contains(&labels[labels.len() - 2..].join(".").as_str())
+    {
+        2
+    } else {
+        1
+    };
+    if labels.len() <= suffix_length + 1 {
+        return host;
+    }
+    labels[labels.len() - suffix_length - 1..].join(".")
+}
@@ -1,9 +1,13 @@
 use std::{collections::HashMap, net::IpAddr};
 
+mod cache_file;
 mod client;
+mod domain;
 mod resolver;
 
+pub use cache_file::*;
 pub use client::*;
+pub use domain::*;
 pub use resolver::*;
 
 pub type IpTable = HashMap<IpAddr, String>;
@@ -1,7 +1,11 @@
 use async_trait::async_trait;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use tokio::runtime::Handle;
-use trust_dns_resolver::{error::ResolveErrorKind, TokioAsyncResolver};
+use trust_dns_resolver::{
+    config::{NameServerConfigGroup, ResolverConfig, ResolverOpts},
+    error::ResolveErrorKind,
+    TokioAsyncResolver,
+};
 
 #[async_trait]
 pub trait Lookup {
@@ -11,8 +15,20 @@ pub trait Lookup {
 pub struct Resolver(TokioAsyncResolver);
 
 impl Resolver {
-    pub async fn new(runtime: Handle) -> Result<Self, failure::Error> {
-        let resolver = TokioAsyncResolver::from_system_conf(runtime).await?;
+    /// Uses `dns_server` if one is given, the system's configuration otherwise
+    pub async fn new(
+        runtime: Handle,
+        dns_server: Option<SocketAddr>,
+    ) -> Result<Self, failure::Error> {
+        let resolver = match dns_server {
+            Some(dns_server) => {
+                let name_servers =
+                    NameServerConfigGroup::from_ips_clear(&[dns_server.ip()], dns_server.port());
+                let config = ResolverConfig::from_parts(None, vec![], name_servers);
+                TokioAsyncResolver::new(config, ResolverOpts::default(), runtime).await?
+            }
+            None => TokioAsyncResolver::from_system_conf(runtime).await?,
+        };
         Ok(Self(resolver))
     }
 }
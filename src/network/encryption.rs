@@ -0,0 +1,87 @@
+use crate::network::{Connection, Protocol};
+
+use Protocol::{Tcp, Udp};
+
+// the ports whose usual protocol sends everything in the clear
+const PLAINTEXT_PORTS: &[(u16, Protocol)] = &[
+    (20, Tcp),
+    (21, Tcp),
+    (23, Tcp),
+    (53, Tcp),
+    (53, Udp),
+    (69, Udp),
+    (80, Tcp),
+    (110, Tcp),
+    (143, Tcp),
+    (161, Udp),
+    (389, Tcp),
+    (514, Udp),
+    (1883, Tcp),
+    (5060, Udp),
+    (8080, Tcp),
+];
+
+// the ports whose usual protocol is always encrypted, the ones that can upgrade (eg. smtp) are in
+// neither list
+const ENCRYPTED_PORTS: &[(u16, Protocol)] = &[
+    (22, Tcp),
+    (443, Tcp),
+    // QUIC, eg. HTTP/3
+    (443, Udp),
+    (465, Tcp),
+    (636, Tcp),
+    (853, Tcp),
+    (993, Tcp),
+    (995, Tcp),
+    (1194, Udp),
+    (8443, Tcp),
+    (51820, Udp),
+];
+
+/// Whether the traffic of a connection looks encrypted, see --security.
+///
+/// This is only a guess from its ports, nothing on a port has to speak what usually does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encryption {
+    Encrypted,
+    Plaintext,
+    Unknown,
+}
+
+impl Encryption {
+    /// The guess for the remote port, or for the local one when this host is the server.
+    /// `plaintext_ports` and `encrypted_ports` (over either protocol) win over the usual ones.
+    pub fn guess(
+        connection: &Connection,
+        plaintext_ports: &[u16],
+        encrypted_ports: &[u16],
+    ) -> Self {
+        let protocol = connection.local_socket.protocol;
+        let guess_port = |port: u16| {
+            if plaintext_ports.contains(&port) {
+                Encryption::Plaintext
+            } else if encrypted_ports.contains(&port) {
+                Encryption::Encrypted
+            } else if PLAINTEXT_PORTS.contains(&(port, protocol)) {
+                Encryption::Plaintext
+            } else if ENCRYPTED_PORTS.contains(&(port, protocol)) {
+                Encryption::Encrypted
+            } else {
+                Encryption::Unknown
+            }
+        };
+        match guess_port(connection.remote_socket.port) {
+            Encryption::Unknown => guess_port(connection.local_socket.port),
+            guess => guess,
+        }
+    }
+
+    /// A lock, an open lock, or nothing when there is no telling
+    pub fn glyph(self) -> &'static str {
+        match self {
+            Encryption::Encrypted => "🔒",
+            Encryption::Plaintext => "🔓",
+            Encryption::Unknown => "",
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use ::ipnetwork::IpNetwork;
+use ::std::net::IpAddr;
+use ::std::str::FromStr;
+
+use crate::network::Connection;
+
+/// Which traffic gets counted at all (see `--filter`), eg. `host 10.0.0.5 or net
+/// 192.168.0.0/16 and port 443`.
+///
+/// Not BPF, just its host, net and port terms joined by `and` and `or`, where `and` binds
+/// tighter. Every term matches either end of a connection.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CaptureFilter {
+    Host(IpAddr),
+    Net(IpNetwork),
+    Port(u16),
+    And(Box<CaptureFilter>, Box<CaptureFilter>),
+    Or(Box<CaptureFilter>, Box<CaptureFilter>),
+}
+
+impl CaptureFilter {
+    pub fn matches(&self, connection: &Connection) -> bool {
+        let local = &connection.local_socket;
+        let remote = &connection.remote_socket;
+        match self {
+            CaptureFilter::Host(ip) => local.ip == *ip || remote.ip == *ip,
+            CaptureFilter::Net(network) => {
+                network.contains(local.ip) || network.contains(remote.ip)
+            }
+            CaptureFilter::Port(port) => local.port == *port || remote.port == *port,
+            CaptureFilter::And(left, right) => {
+                left.matches(connection) && right.matches(connection)
+            }
+            CaptureFilter::Or(left, right) => left.matches(connection) || right.matches(connection),
+        }
+    }
+}
+
+fn parse_term<'a>(words: &mut impl Iterator<Item = &'a str>) -> Result<CaptureFilter, String> {
+    let kind = words
+        .next()
+        .ok_or_else(|| String::from("expected host, net or port at the end"))?;
+    let value = words
+        .next()
+        .ok_or_else(|| format!("expected a value after '{}'", kind))?;
+    match kind {
+        "host" => value
+            .parse()
+            .map(CaptureFilter::Host)
+            .map_err(|_| format!("invalid host '{}', expected an ip address", value)),
+        "net" => value
+            .parse()
+            .map(CaptureFilter::Net)
+            .map_err(|_| format!("invalid net '{}', expected eg. 192.168.0.0/16", value)),
+        "port" => value
+            .parse()
+            .map(CaptureFilter::Port)
+            .map_err(|_| format!("invalid port '{}'", value)),
+        _ => Err(format!(
+            "unknown term '{}', expected host, net or port",
+            kind
+        )),
+    }
+}
+
+impl FromStr for CaptureFilter {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        // the terms joined by `and` so far, and what is joined to them by `or`
+        let mut alternatives = Vec::new();
+        let mut all_of = parse_term(&mut words)?;
+        while let Some(joiner) = words.next() {
+            match joiner {
+                "and" => {
+                    all_of = CaptureFilter::And(Box::new(all_of), Box::new(parse_term(&mut words)?))
+                }
+                "or" => {
+                    alternatives.push(::std::mem::replace(&mut all_of, parse_term(&mut words)?))
+                }
+                _ => return Err(format!("expected 'and' or 'or', found '{}'", joiner)),
+            }
+        }
+        Ok(alternatives
+            .into_iter()
+            .rev()
+            .fold(all_of, |filter, alternative| {
+                CaptureFilter::Or(Box::new(alternative), Box::new(filter))
+            }))
+    }
+}
@@ -0,0 +1,317 @@
+use ::std::convert::TryFrom;
+use ::std::fs;
+use ::std::io::{self, ErrorKind};
+use ::std::net::IpAddr;
+use ::std::path::Path;
+
+use crate::network::dns::IpTable;
+
+// what the metadata at the end of a MaxMind DB file starts after
+const METADATA_MARKER: &[u8] = b"\xab\xcd\xefMaxMind.com";
+// the metadata is in the last 128KiB of the file
+const METADATA_MAX_SIZE: usize = 128 * 1024;
+// the zeros between the search tree and the data section
+const DATA_SECTION_SEPARATOR_SIZE: usize = 16;
+// how deep the data of a record may nest, anything deeper is a corrupt file
+const MAX_DEPTH: usize = 32;
+
+fn invalid_data(reason: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("not a MaxMind DB file ({})", reason),
+    )
+}
+
+// sizes and offsets out of range, that no database a MaxMind DB writer made has
+fn corrupt() -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, "corrupt MaxMind DB file")
+}
+
+// the part of a data section value that the annotations are made of
+#[derive(Debug)]
+enum Value {
+    Map(Vec<(String, Value)>),
+    Text(String),
+    Unsigned(u64),
+    Other,
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries
+                .iter()
+                .find(|(entry_key, _)| entry_key == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+    fn text(&self) -> Option<&str> {
+        match self {
+            Value::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+    fn unsigned(&self) -> Option<u64> {
+        match self {
+            Value::Unsigned(unsigned) => Some(*unsigned),
+            _ => None,
+        }
+    }
+}
+
+// reads the values of a data section, see https://maxmind.github.io/MaxMind-DB/
+struct Decoder<'a> {
+    section: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn bytes(&self, offset: usize, length: usize) -> io::Result<&'a [u8]> {
+        offset
+            .checked_add(length)
+            .and_then(|end| self.section.get(offset..end))
+            .ok_or_else(|| invalid_data("truncated data"))
+    }
+    fn unsigned(&self, offset: usize, length: usize) -> io::Result<u64> {
+        if length > 8 {
+            return Err(invalid_data("oversized integer"));
+        }
+        Ok(self
+            .bytes(offset, length)?
+            .iter()
+            .fold(0, |value, &byte| value << 8 | u64::from(byte)))
+    }
+    // the value at `offset` and the offset after it
+    fn decode(&self, offset: usize, depth: usize) -> io::Result<(Value, usize)> {
+        if depth > MAX_DEPTH {
+            return Err(invalid_data("data nested too deep"));
+        }
+        let control = self.bytes(offset, 1)?[0];
+        let mut offset = offset + 1;
+        let mut data_type = control >> 5;
+        if data_type == 1 {
+            let pointer_size = usize::from((control >> 3) & 0x3) + 1;
+            let low_bits = u64::from(control & 0x7);
+            let pointer = self.unsigned(offset, pointer_size)?;
+            let target = match pointer_size {
+                1 => low_bits << 8 | pointer,
+                2 => (low_bits << 16 | pointer) + 2048,
+                3 => (low_bits << 24 | pointer) + 526_336,
+                _ => pointer,
+            };
+            let target = usize::try_from(target).map_err(|_| corrupt())?;
+            let (value, _) = self.decode(target, depth + 1)?;
+            return Ok((value, offset + pointer_size));
+        }
+        if data_type == 0 {
+            data_type = 7 + self.bytes(offset, 1)?[0];
+            offset += 1;
+        }
+        let size = match control & 0x1f {
+            29 => {
+                offset += 1;
+                29 + self.unsigned(offset - 1, 1)? as usize
+            }
+            30 => {
+                offset += 2;
+                285 + self.unsigned(offset - 2, 2)? as usize
+            }
+            31 => {
+                offset += 3;
+                65_821 + self.unsigned(offset - 3, 3)? as usize
+            }
+            size => usize::from(size),
+        };
+        match data_type {
+            // utf-8 string
+            2 => {
+                let text = String::from_utf8_lossy(self.bytes(offset, size)?).into_owned();
+                Ok((Value::Text(text), offset + size))
+            }
+            // uint16, uint32 and uint64
+            5 | 6 | 9 => Ok((Value::Unsigned(self.unsigned(offset, size)?), offset + size)),
+            // map
+            7 => {
+                let mut entries = Vec::with_capacity(size.min(64));
+                for _ in 0..size {
+                    let (key, next) = self.decode(offset, depth + 1)?;
+                    let (value, next) = self.decode(next, depth + 1)?;
+                    offset = next;
+                    if let Value::Text(key) = key {
+                        entries.push((key, value));
+                    }
+                }
+                Ok((Value::Map(entries), offset))
+            }
+            // array
+            11 => {
+                for _ in 0..size {
+                    offset = self.decode(offset, depth + 1)?.1;
+                }
+                Ok((Value::Other, offset))
+            }
+            // booleans keep their value in the size
+            14 => Ok((Value::Other, offset)),
+            // double, bytes, int32, uint128 and float
+            3 | 4 | 8 | 10 | 15 => {
+                self.bytes(offset, size)?;
+                Ok((Value::Other, offset + size))
+            }
+            _ => Err(invalid_data("unknown data type")),
+        }
+    }
+}
+
+/// A MaxMind DB file (eg. GeoLite2-Country.mmdb or GeoLite2-ASN.mmdb), read into memory whole
+pub struct GeoIpDatabase {
+    contents: Vec<u8>,
+    node_count: usize,
+    record_size: usize,
+    ip_version: u64,
+    data_section_start: usize,
+}
+
+impl GeoIpDatabase {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        GeoIpDatabase::from_bytes(fs::read(path)?)
+    }
+    pub fn from_bytes(contents: Vec<u8>) -> io::Result<Self> {
+        let search_start = contents.len().saturating_sub(METADATA_MAX_SIZE);
+        let metadata_start = contents[search_start..]
+            .windows(METADATA_MARKER.len())
+            .rposition(|window| window == METADATA_MARKER)
+            .map(|position| search_start + position + METADATA_MARKER.len())
+            .ok_or_else(|| invalid_data("no metadata"))?;
+        let metadata = Decoder {
+            section: &contents[metadata_start..],
+        }
+        .decode(0, 0)?
+        .0;
+        let field = |name: &str| {
+            metadata
+                .get(name)
+                .and_then(Value::unsigned)
+                .ok_or_else(|| invalid_data(&format!("no {} in the metadata", name)))
+        };
+        let node_count = usize::try_from(field("node_count")?).map_err(|_| corrupt())?;
+        let record_size = field("record_size")? as usize;
+        let ip_version = field("ip_version")?;
+        if ![24, 28, 32].contains(&record_size) || ![4, 6].contains(&ip_version) {
+            return Err(invalid_data("unsupported record size or ip version"));
+        }
+        // a node holds two records of record_size bits, a corrupt node count overflows this
+        let search_tree_size = node_count
+            .checked_mul(record_size / 4)
+            .ok_or_else(corrupt)?;
+        let data_section_start = search_tree_size
+            .checked_add(DATA_SECTION_SEPARATOR_SIZE)
+            .ok_or_else(corrupt)?;
+        if data_section_start > metadata_start {
+            return Err(invalid_data("truncated search tree"));
+        }
+        Ok(GeoIpDatabase {
+            contents,
+            node_count,
+            record_size,
+            ip_version,
+            data_section_start,
+        })
+    }
+    // the left (0) or right (1) record of a node of the search tree
+    fn record(&self, node: usize, bit: u8) -> usize {
+        let node_size = self.record_size / 4;
+        let bytes = &self.contents[node * node_size..(node + 1) * node_size];
+        let be = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .fold(0, |value, &byte| value << 8 | usize::from(byte))
+        };
+        match (self.record_size, bit) {
+            (28, 0) => usize::from(bytes[3] & 0xf0) << 20 | be(&bytes[..3]),
+            (28, _) => usize::from(bytes[3] & 0x0f) << 24 | be(&bytes[4..]),
+            (_, 0) => be(&bytes[..node_size / 2]),
+            (_, _) => be(&bytes[node_size / 2..]),
+        }
+    }
+    fn lookup(&self, ip: IpAddr) -> Option<Value> {
+        let bits = match (ip, self.ip_version) {
+            (IpAddr::V4(ip), 4) => ip.octets().to_vec(),
+            // in an IPv6 tree the IPv4 addresses are under ::/96
+            (IpAddr::V4(ip), _) => ip.to_ipv6_compatible().octets().to_vec(),
+            (IpAddr::V6(ip), 6) => ip.octets().to_vec(),
+            (IpAddr::V6(_), _) => return None,
+        };
+        let mut node = 0;
+        for index in 0..bits.len() * 8 {
+            if node >= self.node_count {
+                break;
+            }
+            let bit = bits[index / 8] >> (7 - index % 8) & 1;
+            node = self.record(node, bit);
+        }
+        // the node count itself is where the addresses that are in no network end up
+        let offset = node
+            .checked_sub(self.node_count)?
+            .checked_sub(DATA_SECTION_SEPARATOR_SIZE)?;
+        let decoder = Decoder {
+            section: &self.contents[self.data_section_start..],
+        };
+        decoder.decode(offset, 0).ok().map(|(value, _)| value)
+    }
+    /// The country and autonomous system `ip` belongs to, as far as this database knows them,
+    /// eg. `AU` or `AS13335`
+    pub fn annotations(&self, ip: IpAddr) -> Vec<String> {
+        let record = match self.lookup(ip) {
+            Some(record) => record,
+            None => return vec![],
+        };
+        let country = ["country", "registered_country"].iter().find_map(|key| {
+            record
+                .get(key)
+                .and_then(|country| country.get("iso_code"))
+                .and_then(Value::text)
+        });
+        let asn = record
+            .get("autonomous_system_number")
+            .and_then(Value::unsigned);
+        country
+            .map(String::from)
+            .into_iter()
+            .chain(asn.map(|asn| format!("AS{}", asn)))
+            .collect()
+    }
+}
+
+/// Where the remote addresses are, from one or more MaxMind DB files (see --geoip).
+///
+/// Every address is only looked up once, the databases are in memory so that is quick enough to
+/// do right in the tick.
+pub struct GeoIp {
+    databases: Vec<GeoIpDatabase>,
+    cache: IpTable,
+}
+
+impl GeoIp {
+    pub fn new(databases: Vec<GeoIpDatabase>) -> Self {
+        GeoIp {
+            databases,
+            cache: IpTable::new(),
+        }
+    }
+    pub fn look_up(&mut self, ips: impl Iterator<Item = IpAddr>) {
+        for ip in ips {
+            if !self.cache.contains_key(&ip) {
+                let annotations = self
+                    .databases
+                    .iter()
+                    .flat_map(|database| database.annotations(ip))
+                    .collect::<Vec<_>>();
+                self.cache.insert(ip, annotations.join(" "));
+            }
+        }
+    }
+    /// What is known of every address looked up so far, empty for those none of the databases has
+    pub fn cache(&self) -> &IpTable {
+        &self.cache
+    }
+}
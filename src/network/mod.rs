@@ -1,8 +1,18 @@
 mod connection;
 pub mod dns;
+mod encryption;
+mod filter;
+mod geoip;
+mod service;
 mod sniffer;
+mod unix_socket;
 mod utilization;
 
 pub use connection::*;
+pub use encryption::*;
+pub use filter::*;
+pub use geoip::*;
+pub use service::*;
 pub use sniffer::*;
+pub use unix_socket::*;
 pub use utilization::*;
@@ -0,0 +1,57 @@
+use crate::network::Protocol;
+
+use Protocol::{Tcp, Udp};
+
+// the well-known ports people are most likely to see traffic on, named as in /etc/services
+const SERVICES: &[(u16, Protocol, &str)] = &[
+    (20, Tcp, "ftp-data"),
+    (21, Tcp, "ftp"),
+    (22, Tcp, "ssh"),
+    (23, Tcp, "telnet"),
+    (25, Tcp, "smtp"),
+    (53, Tcp, "dns"),
+    (53, Udp, "dns"),
+    (67, Udp, "dhcp"),
+    (68, Udp, "dhcp"),
+    (69, Udp, "tftp"),
+    (80, Tcp, "http"),
+    (110, Tcp, "pop3"),
+    (123, Udp, "ntp"),
+    (143, Tcp, "imap"),
+    (161, Udp, "snmp"),
+    (389, Tcp, "ldap"),
+    (443, Tcp, "https"),
+    // QUIC, eg. HTTP/3
+    (443, Udp, "https"),
+    (445, Tcp, "smb"),
+    (465, Tcp, "smtps"),
+    (514, Udp, "syslog"),
+    (587, Tcp, "submission"),
+    (636, Tcp, "ldaps"),
+    (853, Tcp, "dns-over-tls"),
+    (993, Tcp, "imaps"),
+    (995, Tcp, "pop3s"),
+    (1194, Udp, "openvpn"),
+    (1883, Tcp, "mqtt"),
+    (3306, Tcp, "mysql"),
+    (3389, Tcp, "rdp"),
+    (5060, Udp, "sip"),
+    (5353, Udp, "mdns"),
+    (5432, Tcp, "postgresql"),
+    (5900, Tcp, "vnc"),
+    (6379, Tcp, "redis"),
+    (8080, Tcp, "http-alt"),
+    (8443, Tcp, "https-alt"),
+    (27017, Tcp, "mongodb"),
+    (51820, Udp, "wireguard"),
+];
+
+/// The name of the service that usually listens on this port, eg. https for 443 over tcp
+pub fn service_name(port: u16, protocol: Protocol) -> Option<&'static str> {
+    SERVICES
+        .iter()
+        .find(|&&(service_port, service_protocol, _)| {
+            service_port == port && service_protocol == protocol
+        })
+        .map(|&(_, _, name)| name)
+}
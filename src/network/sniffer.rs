@@ -1,7 +1,11 @@
 use ::std::boxed::Box;
+use ::std::collections::HashMap;
+use ::std::io;
+use ::std::mem;
+use ::std::sync::atomic::{AtomicBool, Ordering};
 
 use ::pnet_bandwhich_fork::datalink::{DataLinkReceiver, NetworkInterface};
-use ::pnet_bandwhich_fork::packet::ethernet::{EtherTypes, EthernetPacket};
+use ::pnet_bandwhich_fork::packet::ethernet::{EtherType, EtherTypes, EthernetPacket};
 use ::pnet_bandwhich_fork::packet::ip::{IpNextHeaderProtocol, IpNextHeaderProtocols};
 use ::pnet_bandwhich_fork::packet::ipv4::Ipv4Packet;
 use ::pnet_bandwhich_fork::packet::ipv6::Ipv6Packet;
@@ -11,8 +15,9 @@ use ::pnet_bandwhich_fork::packet::Packet;
 
 use ::ipnetwork::IpNetwork;
 use ::std::net::{IpAddr, SocketAddr};
+use ::std::str::FromStr;
 
-use crate::network::{Connection, Protocol};
+use crate::network::{CaptureEvent, CaptureEventSender, CaptureFilter, Connection, Protocol};
 
 #[derive(Debug)]
 pub struct Segment {
@@ -20,6 +25,15 @@ pub struct Segment {
     pub connection: Connection,
     pub direction: Direction,
     pub data_length: u128,
+    /// A tcp segment that did not move the sequence number forward, ie. was sent again or came
+    /// out of order. Only told apart with --tcp-stats
+    pub retransmitted: bool,
+}
+
+// the part of the sequence space a tcp segment covers
+struct TcpSequence {
+    first: u32,
+    length: u32,
 }
 
 #[derive(PartialEq, Hash, Eq, Debug, Clone, PartialOrd)]
@@ -29,75 +43,457 @@ pub enum Direction {
 }
 
 impl Direction {
-    pub fn new(network_interface_ips: &[IpNetwork], source: IpAddr) -> Self {
-        if network_interface_ips
-            .iter()
-            .any(|ip_network| ip_network.ip() == source)
-        {
-            Direction::Upload
+    pub fn new(
+        network_interface_ips: &[IpNetwork],
+        source: IpAddr,
+        capture_direction: CaptureDirection,
+    ) -> Self {
+        match capture_direction {
+            // a one-way tap only ever shows us one direction, whatever the addresses look like
+            CaptureDirection::Egress => Direction::Upload,
+            CaptureDirection::Ingress => Direction::Download,
+            CaptureDirection::Both => {
+                if network_interface_ips
+                    .iter()
+                    .any(|ip_network| ip_network.ip() == source)
+                {
+                    Direction::Upload
+                } else {
+                    Direction::Download
+                }
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum CaptureDirection {
+    Egress,
+    Ingress,
+    #[default]
+    Both,
+}
+
+impl CaptureDirection {
+    pub fn bandwidth_label(self) -> &'static str {
+        match self {
+            CaptureDirection::Egress => "Up",
+            CaptureDirection::Ingress => "Down",
+            CaptureDirection::Both => "Up / Down",
+        }
+    }
+}
+
+impl FromStr for CaptureDirection {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "egress" => Ok(CaptureDirection::Egress),
+            "ingress" => Ok(CaptureDirection::Ingress),
+            "both" => Ok(CaptureDirection::Both),
+            _ => Err(format!(
+                "invalid capture direction '{}', expected egress, ingress or both",
+                s
+            )),
+        }
+    }
+}
+
+/// Attribute only one in this many segments to its connection, counted that many times for it
+/// (see --sample). The segments left out still count in the totals, see `SkippedTraffic`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample(u32);
+
+impl Sample {
+    pub fn every(self) -> u32 {
+        self.0
+    }
+}
+
+impl Default for Sample {
+    fn default() -> Self {
+        Sample(1)
+    }
+}
+
+impl FromStr for Sample {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("1/").and_then(|every| every.parse().ok()) {
+            Some(every) if every > 0 => Ok(Sample(every)),
+            _ => Err(format!("invalid sample '{}', expected 1/N, eg. 1/10", s)),
+        }
+    }
+}
+
+/// The traffic of the segments --sample left out, only counted in the totals
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SkippedTraffic {
+    pub bytes_uploaded: u128,
+    pub bytes_downloaded: u128,
+    pub packets_uploaded: u128,
+    pub packets_downloaded: u128,
+}
+
+impl SkippedTraffic {
+    fn add(&mut self, segment: &Segment) {
+        match segment.direction {
+            Direction::Download => {
+                self.bytes_downloaded += segment.data_length;
+                self.packets_downloaded += 1;
+            }
+            Direction::Upload => {
+                self.bytes_uploaded += segment.data_length;
+                self.packets_uploaded += 1;
+            }
+        }
+    }
+}
+
+// the protocol and ports of a tcp or udp header at the start of `payload`
+fn transport_header(
+    protocol: IpNextHeaderProtocol,
+    payload: &[u8],
+) -> Option<(Transport, Option<TcpSequence>)> {
+    match protocol {
+        IpNextHeaderProtocols::Tcp => {
+            let message = TcpPacket::new(payload)?;
+            let sequence = TcpSequence {
+                first: message.get_sequence(),
+                length: message.payload().len() as u32,
+            };
+            Some((
+                Transport {
+                    protocol: Protocol::Tcp,
+                    source_port: message.get_source(),
+                    destination_port: message.get_destination(),
+                },
+                Some(sequence),
+            ))
+        }
+        IpNextHeaderProtocols::Udp => {
+            let datagram = UdpPacket::new(payload)?;
+            Some((
+                Transport {
+                    protocol: Protocol::Udp,
+                    source_port: datagram.get_source(),
+                    destination_port: datagram.get_destination(),
+                },
+                None,
+            ))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Transport {
+    protocol: Protocol,
+    source_port: u16,
+    destination_port: u16,
+}
+
+// a datagram split into fragments, whose fragments all have the same addresses and identification
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct Datagram {
+    source: IpAddr,
+    destination: IpAddr,
+    identification: u32,
+}
+
+/// The transport headers of the datagrams whose first fragment went by, since the other fragments
+/// do not have one. A fragment that comes before the first one of its datagram is not counted
+#[derive(Default)]
+struct FragmentedDatagrams {
+    transports: HashMap<Datagram, Transport>,
+}
+
+impl FragmentedDatagrams {
+    // the fragment at `offset` (in eight byte units, as the ip headers have it) of `datagram`, the
+    // first one has the transport header at the start of `payload`
+    fn fragment(
+        &mut self,
+        datagram: Datagram,
+        protocol: IpNextHeaderProtocol,
+        offset: u16,
+        more_fragments: bool,
+        payload: &[u8],
+    ) -> Option<(Transport, Option<TcpSequence>)> {
+        if offset == 0 {
+            let (transport, tcp_sequence) = transport_header(protocol, payload)?;
+            if more_fragments {
+                if self.transports.len() >= MAX_TRACKED_FRAGMENTED_DATAGRAMS {
+                    self.transports.clear();
+                }
+                self.transports.insert(datagram, transport);
+                // the sequence number spans the whole datagram, which only part of is in here
+                return Some((transport, None));
+            }
+            Some((transport, tcp_sequence))
+        } else if more_fragments {
+            Some((*self.transports.get(&datagram)?, None))
         } else {
-            Direction::Download
+            // the last fragment, though one in the middle may still come after it
+            Some((self.transports.remove(&datagram)?, None))
         }
     }
 }
 
-trait NextLevelProtocol {
-    fn get_next_level_protocol(&self) -> IpNextHeaderProtocol;
+const MAX_CONSECUTIVE_ERRORS: u32 = 10;
+// the outer tag of a QinQ frame as 802.1ad has it, `EtherTypes::QinQ` is the older 0x9100
+const ETHERTYPE_SERVICE_VLAN: EtherType = EtherType(0x88a8);
+// a vlan tag is the tag control information and the ethertype after it
+const VLAN_TAG_LENGTH: usize = 4;
+// version and type, code, session id and length, then the ppp protocol
+const PPPOE_SESSION_HEADER_LENGTH: usize = 8;
+const PPP_PROTOCOL_IPV4: u16 = 0x0021;
+const PPP_PROTOCOL_IPV6: u16 = 0x0057;
+// the sequence numbers are forgotten (and the counting starts over) once there are this many
+const MAX_TRACKED_TCP_STREAMS: usize = 10_000;
+// likewise for the transport headers of the fragmented datagrams
+const MAX_TRACKED_FRAGMENTED_DATAGRAMS: usize = 10_000;
+// the next header of an ipv6 fragment header, and its length
+const IPV6_FRAGMENT_HEADER: IpNextHeaderProtocol = IpNextHeaderProtocol(44);
+const IPV6_FRAGMENT_HEADER_LENGTH: usize = 8;
+// the flag of the fragments that are not the last of their datagram, in ipv4 and ipv6
+const MORE_FRAGMENTS: u16 = 0b1;
+
+/// What a sniffer read since it started (see `--show-stats`). pnet does not tell how many
+/// packets the kernel dropped, so those are not in here.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CaptureStats {
+    pub packets: u64,
+    /// Packets that are not tcp or udp over ip, or too malformed to tell
+    pub unparsed: u64,
+    /// Segments left out because the display thread fell too far behind counting them, see
+    /// `CaptureEventSender`
+    pub overflowed: u64,
 }
 
-impl NextLevelProtocol for Ipv6Packet<'_> {
-    fn get_next_level_protocol(&self) -> IpNextHeaderProtocol {
-        self.get_next_header()
+impl CaptureStats {
+    pub fn add(&mut self, other: CaptureStats) {
+        self.packets += other.packets;
+        self.unparsed += other.unparsed;
+        self.overflowed += other.overflowed;
     }
 }
 
-macro_rules! extract_transport_protocol {
-    (  $ip_packet: ident ) => {{
-        match $ip_packet.get_next_level_protocol() {
-            IpNextHeaderProtocols::Tcp => {
-                let message = TcpPacket::new($ip_packet.payload())?;
-                (
-                    Protocol::Tcp,
-                    message.get_source(),
-                    message.get_destination(),
-                    $ip_packet.payload().len() as u128,
-                )
+// the ethertype and payload of an ethernet frame under its vlan tags (802.1Q, QinQ) and pppoe
+// session header, if it has any
+fn untagged_payload(mut ethertype: EtherType, mut payload: &[u8]) -> Option<(EtherType, &[u8])> {
+    let be_u16 = |bytes: &[u8], offset: usize| -> Option<u16> {
+        let bytes = bytes.get(offset..offset + 2)?;
+        Some(u16::from(bytes[0]) << 8 | u16::from(bytes[1]))
+    };
+    loop {
+        match ethertype {
+            EtherTypes::Vlan | EtherTypes::QinQ | ETHERTYPE_SERVICE_VLAN => {
+                ethertype = EtherType(be_u16(payload, 2)?);
+                payload = payload.get(VLAN_TAG_LENGTH..)?;
             }
-            IpNextHeaderProtocols::Udp => {
-                let datagram = UdpPacket::new($ip_packet.payload())?;
-                (
-                    Protocol::Udp,
-                    datagram.get_source(),
-                    datagram.get_destination(),
-                    $ip_packet.payload().len() as u128,
-                )
+            EtherTypes::PppoeSession => {
+                ethertype = match be_u16(payload, PPPOE_SESSION_HEADER_LENGTH - 2)? {
+                    PPP_PROTOCOL_IPV4 => EtherTypes::Ipv4,
+                    PPP_PROTOCOL_IPV6 => EtherTypes::Ipv6,
+                    _ => return None,
+                };
+                payload = payload.get(PPPOE_SESSION_HEADER_LENGTH..)?;
             }
-            _ => return None,
+            _ => return Some((ethertype, payload)),
         }
-    }};
+    }
 }
 
 pub struct Sniffer {
     network_interface: NetworkInterface,
     network_frames: Box<dyn DataLinkReceiver>,
+    capture_direction: CaptureDirection,
+    filter: Option<CaptureFilter>,
+    consecutive_errors: u32,
+    stats: CaptureStats,
+    /// The end of the highest sequence number seen each way on each tcp connection, only with
+    /// --tcp-stats
+    tcp_sequence_ends: Option<HashMap<(Connection, Direction), u32>>,
+    sample: Sample,
+    // how many segments went by since the last one sampled
+    since_sampled: u32,
+    skipped: SkippedTraffic,
+    fragmented_datagrams: FragmentedDatagrams,
 }
 
 impl Sniffer {
     pub fn new(
         network_interface: NetworkInterface,
         network_frames: Box<dyn DataLinkReceiver>,
+        capture_direction: CaptureDirection,
+        filter: Option<CaptureFilter>,
+        tcp_stats: bool,
     ) -> Self {
         Sniffer {
             network_interface,
             network_frames,
+            capture_direction,
+            filter,
+            consecutive_errors: 0,
+            stats: CaptureStats::default(),
+            tcp_sequence_ends: if tcp_stats {
+                Some(HashMap::new())
+            } else {
+                None
+            },
+            sample: Sample::default(),
+            since_sampled: 0,
+            skipped: SkippedTraffic::default(),
+            fragmented_datagrams: FragmentedDatagrams::default(),
+        }
+    }
+    pub fn set_sample(&mut self, sample: Sample) {
+        self.sample = sample;
+    }
+    pub fn stats(&self) -> CaptureStats {
+        self.stats
+    }
+    /// What to hand over for a segment of `next_segment`, along with the traffic --sample left
+    /// out since the last one
+    pub fn capture_event(&mut self, segment: Segment) -> CaptureEvent {
+        match self.sample.every() {
+            1 => CaptureEvent::Segment(segment),
+            every => CaptureEvent::SampledSegment {
+                segment,
+                every,
+                skipped: mem::take(&mut self.skipped),
+            },
+        }
+    }
+    /// Hands every segment it captures over to be counted until `running` is cleared, along with
+    /// its stats whenever they change. `on_segment` sees each of them first. It stops early with
+    /// the error of `next_segment`, eg. once the interface went down or a replay ran out
+    pub fn capture(
+        &mut self,
+        capture_events: &mut CaptureEventSender,
+        running: &AtomicBool,
+        mut on_segment: impl FnMut(&Segment),
+    ) -> Result<(), io::Error> {
+        let mut sent_stats = CaptureStats::default();
+        while running.load(Ordering::Acquire) {
+            let next = self.next_segment();
+            if self.stats != sent_stats
+                && capture_events.send_stats(&self.network_interface.name, self.stats)
+            {
+                sent_stats = self.stats;
+            }
+            if let Some(segment) = next? {
+                on_segment(&segment);
+                let event = self.capture_event(segment);
+                capture_events.send_segment(event);
+            }
+        }
+        Ok(())
+    }
+    /// The next captured segment, `None` if what was read is not tcp or udp over ip (vlan tagged
+    /// or in a pppoe session is fine too), does not match the `--filter`, is left out by the
+    /// `--sample` (or nothing was read before the timeout). An error means the interface cannot be
+    /// captured from anymore, eg. because it went down.
+    pub fn next_segment(&mut self) -> Result<Option<Segment>, io::Error> {
+        match self.network_frames.next() {
+            Ok(bytes) => {
+                self.consecutive_errors = 0;
+                if bytes.is_empty() {
+                    return Ok(None);
+                }
+                let segment = Self::segment_from(
+                    bytes,
+                    &self.network_interface,
+                    self.capture_direction,
+                    &mut self.fragmented_datagrams,
+                );
+                self.stats.packets += 1;
+                let (mut segment, tcp_sequence) = match segment {
+                    Some(segment) => segment,
+                    None => {
+                        self.stats.unparsed += 1;
+                        return Ok(None);
+                    }
+                };
+                if let Some(filter) = &self.filter {
+                    if !filter.matches(&segment.connection) {
+                        return Ok(None);
+                    }
+                }
+                // the first of every N, so that a connection of a single segment can still show up
+                if self.sample.every() > 1 {
+                    let sampled = self.since_sampled == 0;
+                    self.since_sampled = (self.since_sampled + 1) % self.sample.every();
+                    if !sampled {
+                        self.skipped.add(&segment);
+                        return Ok(None);
+                    }
+                }
+                if let Some(tcp_sequence) = tcp_sequence {
+                    segment.retransmitted = self.is_retransmission(&segment, tcp_sequence);
+                }
+                Ok(Some(segment))
+            }
+            // the read timeout is only there so the capture notices when to stop
+            Err(ref err)
+                if err.kind() == io::ErrorKind::TimedOut
+                    || err.kind() == io::ErrorKind::WouldBlock
+                    || err.kind() == io::ErrorKind::Interrupted =>
+            {
+                Ok(None)
+            }
+            // a single failed read is not reason enough to give up, an interface that is gone
+            // fails every read right away
+            Err(err) => {
+                self.consecutive_errors += 1;
+                if self.consecutive_errors >= MAX_CONSECUTIVE_ERRORS {
+                    Err(err)
+                } else {
+                    Ok(None)
+                }
+            }
         }
     }
-    pub fn next(&mut self) -> Option<Segment> {
-        let bytes = self.network_frames.next().ok()?;
+    fn is_retransmission(&mut self, segment: &Segment, sequence: TcpSequence) -> bool {
+        let tcp_sequence_ends = match self.tcp_sequence_ends.as_mut() {
+            Some(tcp_sequence_ends) => tcp_sequence_ends,
+            None => return false,
+        };
+        // a bare ack does not take up any of the sequence space
+        if sequence.length == 0 {
+            return false;
+        }
+        let end = sequence.first.wrapping_add(sequence.length);
+        let stream = (segment.connection, segment.direction.clone());
+        if tcp_sequence_ends.len() >= MAX_TRACKED_TCP_STREAMS
+            && !tcp_sequence_ends.contains_key(&stream)
+        {
+            tcp_sequence_ends.clear();
+        }
+        match tcp_sequence_ends.get_mut(&stream) {
+            // the sequence numbers wrap around, so whether one is past another is told by their
+            // difference (RFC 1982)
+            Some(highest_end) if (end.wrapping_sub(*highest_end) as i32) <= 0 => true,
+            Some(highest_end) => {
+                *highest_end = end;
+                false
+            }
+            None => {
+                tcp_sequence_ends.insert(stream, end);
+                false
+            }
+        }
+    }
+    fn segment_from(
+        bytes: &[u8],
+        network_interface: &NetworkInterface,
+        capture_direction: CaptureDirection,
+        fragmented_datagrams: &mut FragmentedDatagrams,
+    ) -> Option<(Segment, Option<TcpSequence>)> {
         // See https://github.com/libpnet/libpnet/blob/master/examples/packetdump.rs
         // VPN interfaces (such as utun0, utun1, etc) have POINT_TO_POINT bit set to 1
-        let payload_offset = if (self.network_interface.is_loopback()
-            || self.network_interface.is_point_to_point())
+        let payload_offset = if (network_interface.is_loopback()
+            || network_interface.is_point_to_point())
             && cfg!(target_os = "macos")
         {
             // The pnet code for BPF loopback adds a zero'd out Ethernet header
@@ -109,63 +505,134 @@ impl Sniffer {
         let version = ip_packet.get_version();
 
         match version {
-            4 => Self::handle_v4(ip_packet, &self.network_interface),
+            4 => Self::handle_v4(
+                ip_packet,
+                network_interface,
+                capture_direction,
+                fragmented_datagrams,
+            ),
             6 => Self::handle_v6(
                 Ipv6Packet::new(&bytes[payload_offset..])?,
-                &self.network_interface,
+                network_interface,
+                capture_direction,
+                fragmented_datagrams,
             ),
             _ => {
                 let pkg = EthernetPacket::new(bytes)?;
-                match pkg.get_ethertype() {
-                    EtherTypes::Ipv4 => {
-                        Self::handle_v4(Ipv4Packet::new(pkg.payload())?, &self.network_interface)
-                    }
-                    EtherTypes::Ipv6 => {
-                        Self::handle_v6(Ipv6Packet::new(pkg.payload())?, &self.network_interface)
-                    }
+                let (ethertype, payload) = untagged_payload(pkg.get_ethertype(), pkg.payload())?;
+                match ethertype {
+                    EtherTypes::Ipv4 => Self::handle_v4(
+                        Ipv4Packet::new(payload)?,
+                        network_interface,
+                        capture_direction,
+                        fragmented_datagrams,
+                    ),
+                    EtherTypes::Ipv6 => Self::handle_v6(
+                        Ipv6Packet::new(payload)?,
+                        network_interface,
+                        capture_direction,
+                        fragmented_datagrams,
+                    ),
                     _ => None,
                 }
             }
         }
     }
-    fn handle_v6(ip_packet: Ipv6Packet, network_interface: &NetworkInterface) -> Option<Segment> {
-        let (protocol, source_port, destination_port, data_length) =
-            extract_transport_protocol!(ip_packet);
-
-        let interface_name = network_interface.name.clone();
-        let direction = Direction::new(&network_interface.ips, ip_packet.get_source().into());
-        let from = SocketAddr::new(ip_packet.get_source().into(), source_port);
-        let to = SocketAddr::new(ip_packet.get_destination().into(), destination_port);
-
-        let connection = match direction {
-            Direction::Download => Connection::new(from, to.ip(), destination_port, protocol),
-            Direction::Upload => Connection::new(to, from.ip(), source_port, protocol),
+    fn handle_v6(
+        ip_packet: Ipv6Packet,
+        network_interface: &NetworkInterface,
+        capture_direction: CaptureDirection,
+        fragmented_datagrams: &mut FragmentedDatagrams,
+    ) -> Option<(Segment, Option<TcpSequence>)> {
+        let source = ip_packet.get_source().into();
+        let destination = ip_packet.get_destination().into();
+        let payload = ip_packet.payload();
+        let (transport, tcp_sequence) = if ip_packet.get_next_header() == IPV6_FRAGMENT_HEADER {
+            // the next header, a reserved byte, the offset in eight byte units with the flags in
+            // its last bits, then the identification
+            let header = payload.get(..IPV6_FRAGMENT_HEADER_LENGTH)?;
+            let offset_and_flags = u16::from_be_bytes([header[2], header[3]]);
+            fragmented_datagrams.fragment(
+                Datagram {
+                    source,
+                    destination,
+                    identification: u32::from_be_bytes([
+                        header[4], header[5], header[6], header[7],
+                    ]),
+                },
+                IpNextHeaderProtocol(header[0]),
+                offset_and_flags >> 3,
+                offset_and_flags & MORE_FRAGMENTS != 0,
+                &payload[IPV6_FRAGMENT_HEADER_LENGTH..],
+            )?
+        } else {
+            transport_header(ip_packet.get_next_header(), payload)?
         };
-        Some(Segment {
-            interface_name,
-            connection,
-            data_length,
-            direction,
-        })
+        let segment = Self::segment(
+            network_interface,
+            capture_direction,
+            (source, destination),
+            transport,
+            payload.len() as u128,
+        );
+        Some((segment, tcp_sequence))
     }
-    fn handle_v4(ip_packet: Ipv4Packet, network_interface: &NetworkInterface) -> Option<Segment> {
-        let (protocol, source_port, destination_port, data_length) =
-            extract_transport_protocol!(ip_packet);
-
-        let interface_name = network_interface.name.clone();
-        let direction = Direction::new(&network_interface.ips, ip_packet.get_source().into());
-        let from = SocketAddr::new(ip_packet.get_source().into(), source_port);
-        let to = SocketAddr::new(ip_packet.get_destination().into(), destination_port);
-
+    fn handle_v4(
+        ip_packet: Ipv4Packet,
+        network_interface: &NetworkInterface,
+        capture_direction: CaptureDirection,
+        fragmented_datagrams: &mut FragmentedDatagrams,
+    ) -> Option<(Segment, Option<TcpSequence>)> {
+        let source = ip_packet.get_source().into();
+        let destination = ip_packet.get_destination().into();
+        let (transport, tcp_sequence) = fragmented_datagrams.fragment(
+            Datagram {
+                source,
+                destination,
+                identification: u32::from(ip_packet.get_identification()),
+            },
+            ip_packet.get_next_level_protocol(),
+            ip_packet.get_fragment_offset(),
+            u16::from(ip_packet.get_flags()) & MORE_FRAGMENTS != 0,
+            ip_packet.payload(),
+        )?;
+        let segment = Self::segment(
+            network_interface,
+            capture_direction,
+            (source, destination),
+            transport,
+            ip_packet.payload().len() as u128,
+        );
+        Some((segment, tcp_sequence))
+    }
+    // the segment of `data_length` bytes of ip payload from the first of `addresses` to the other
+    fn segment(
+        network_interface: &NetworkInterface,
+        capture_direction: CaptureDirection,
+        (source, destination): (IpAddr, IpAddr),
+        transport: Transport,
+        data_length: u128,
+    ) -> Segment {
+        let direction = Direction::new(&network_interface.ips, source, capture_direction);
+        let from = SocketAddr::new(source, transport.source_port);
+        let to = SocketAddr::new(destination, transport.destination_port);
         let connection = match direction {
-            Direction::Download => Connection::new(from, to.ip(), destination_port, protocol),
-            Direction::Upload => Connection::new(to, from.ip(), source_port, protocol),
+            Direction::Download => Connection::new(
+                from,
+                destination,
+                transport.destination_port,
+                transport.protocol,
+            ),
+            Direction::Upload => {
+                Connection::new(to, source, transport.source_port, transport.protocol)
+            }
         };
-        Some(Segment {
-            interface_name,
+        Segment {
+            interface_name: network_interface.name.clone(),
             connection,
             data_length,
             direction,
-        })
+            retransmitted: false,
+        }
     }
 }
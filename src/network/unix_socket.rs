@@ -0,0 +1,5 @@
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct UnixSocket {
+    pub path: String,
+    pub process_name: String,
+}
@@ -1,44 +1,248 @@
-use crate::network::{Connection, Direction, Segment};
+use crate::network::{CaptureStats, Connection, Direction, Segment, SkippedTraffic};
 
 use ::std::collections::HashMap;
+use ::std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+
+/// How many events the sniffers can hand over before the display thread counts them, about a
+/// second of a busy link with the display thread redrawing once a second
+pub const CAPTURE_QUEUE_LENGTH: usize = 1 << 17;
+
+/// What a sniffer thread hands over to the display thread, which owns the `Utilization`, so a
+/// sniffer never waits for the display (nor for another sniffer) to count what it captured
+pub enum CaptureEvent {
+    Segment(Segment),
+    /// One segment in `every` with --sample, and what the sniffer left out before it
+    SampledSegment {
+        segment: Segment,
+        every: u32,
+        skipped: SkippedTraffic,
+    },
+    /// What the sniffer of the interface read so far, sent whenever it changed
+    Stats(String, CaptureStats),
+    /// The interface cannot be captured from anymore, its sniffer sends nothing after this
+    InterfaceGone(String),
+}
+
+/// A sniffer's end of the queue to the display thread (see `capture_queue`). What finds it full
+/// is not waited on, so a display thread that falls behind neither holds up the capture nor has
+/// the queue grow without end. The segments left out are counted in the stats instead
+#[derive(Clone)]
+pub struct CaptureEventSender {
+    sender: SyncSender<CaptureEvent>,
+    overflowed: u64,
+}
+
+/// The queue the sniffers hand their events over to the display thread in, `length` long
+pub fn capture_queue(length: usize) -> (CaptureEventSender, Receiver<CaptureEvent>) {
+    let (sender, receiver) = mpsc::sync_channel(length);
+    (
+        CaptureEventSender {
+            sender,
+            overflowed: 0,
+        },
+        receiver,
+    )
+}
+
+impl CaptureEventSender {
+    /// Sends a (sampled) segment, or counts it as overflowed if the queue is full. Nothing is
+    /// counted once the display thread is gone
+    pub fn send_segment(&mut self, event: CaptureEvent) {
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(event) {
+            self.overflowed += 1;
+        }
+    }
+    /// Sends the stats of the interface with the segments that did not fit in the queue, and
+    /// tells whether they got in (they can be sent again with the next segment otherwise)
+    pub fn send_stats(&self, interface_name: &str, stats: CaptureStats) -> bool {
+        let stats = CaptureStats {
+            overflowed: self.overflowed,
+            ..stats
+        };
+        self.sender
+            .try_send(CaptureEvent::Stats(interface_name.to_string(), stats))
+            .is_ok()
+    }
+    /// Waits for room if need be, this is the last the display thread hears of the interface
+    pub fn send_interface_gone(&self, interface_name: &str) {
+        let _ = self
+            .sender
+            .send(CaptureEvent::InterfaceGone(interface_name.to_string()));
+    }
+}
 
 #[derive(Clone)]
 pub struct ConnectionInfo {
     pub interface_name: String,
     pub total_bytes_downloaded: u128,
     pub total_bytes_uploaded: u128,
+    pub total_packets: u128,
+    /// The segments each way, what is counted instead of the bytes with --packets
+    pub packets_downloaded: u128,
+    pub packets_uploaded: u128,
+    /// See `Segment::retransmitted`
+    pub retransmitted_packets: u128,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Utilization {
     pub connections: HashMap<Connection, ConnectionInfo>,
+    /// Everything captured since launch, these are not reset with the connections
+    pub lifetime_bytes_uploaded: u128,
+    pub lifetime_bytes_downloaded: u128,
+    pub lifetime_packets_uploaded: u128,
+    pub lifetime_packets_downloaded: u128,
+    /// What each interface captured since launch, not reset either
+    capture_stats: HashMap<String, CaptureStats>,
 }
 
 impl Utilization {
     pub fn new() -> Self {
         let connections = HashMap::new();
-        Utilization { connections }
+        Utilization {
+            connections,
+            lifetime_bytes_uploaded: 0,
+            lifetime_bytes_downloaded: 0,
+            lifetime_packets_uploaded: 0,
+            lifetime_packets_downloaded: 0,
+            capture_stats: HashMap::new(),
+        }
     }
     pub fn clone_and_reset(&mut self) -> Self {
         let clone = self.clone();
         self.connections.clear();
         clone
     }
+    /// Counts everything the sniffers sent since the last call, in the order they sent it
+    pub fn drain(&mut self, events: &Receiver<CaptureEvent>) {
+        for event in events.try_iter() {
+            match event {
+                CaptureEvent::Segment(segment) => self.update(segment),
+                CaptureEvent::SampledSegment {
+                    segment,
+                    every,
+                    skipped,
+                } => self.update_sampled(segment, every, skipped),
+                CaptureEvent::Stats(interface_name, stats) => {
+                    self.set_capture_stats(&interface_name, stats)
+                }
+                // what it captured last is gone with it rather than shown as a rate
+                CaptureEvent::InterfaceGone(interface_name) => {
+                    self.remove_interface(&interface_name)
+                }
+            }
+        }
+    }
+    /// Adds the traffic of a later tick, eg. to sum up a whole run. The lifetime counters are
+    /// taken from `later` since they already hold everything until then
+    pub fn merge(&mut self, later: Utilization) {
+        for (connection, info) in later.connections {
+            let merged = self
+                .connections
+                .entry(connection)
+                .or_insert_with(|| ConnectionInfo {
+                    interface_name: info.interface_name.clone(),
+                    total_bytes_downloaded: 0,
+                    total_bytes_uploaded: 0,
+                    total_packets: 0,
+                    packets_downloaded: 0,
+                    packets_uploaded: 0,
+                    retransmitted_packets: 0,
+                });
+            merged.total_bytes_downloaded += info.total_bytes_downloaded;
+            merged.total_bytes_uploaded += info.total_bytes_uploaded;
+            merged.total_packets += info.total_packets;
+            merged.packets_downloaded += info.packets_downloaded;
+            merged.packets_uploaded += info.packets_uploaded;
+            merged.retransmitted_packets += info.retransmitted_packets;
+        }
+        self.lifetime_bytes_uploaded = later.lifetime_bytes_uploaded;
+        self.lifetime_bytes_downloaded = later.lifetime_bytes_downloaded;
+        self.lifetime_packets_uploaded = later.lifetime_packets_uploaded;
+        self.lifetime_packets_downloaded = later.lifetime_packets_downloaded;
+        self.capture_stats = later.capture_stats;
+    }
+    /// Starts the lifetime counters over, eg. for a fresh session baseline
+    pub fn reset_lifetime_counters(&mut self) {
+        self.lifetime_bytes_uploaded = 0;
+        self.lifetime_bytes_downloaded = 0;
+        self.lifetime_packets_uploaded = 0;
+        self.lifetime_packets_downloaded = 0;
+    }
+    pub fn total_bytes(&self) -> u128 {
+        self.connections
+            .values()
+            .map(|info| info.total_bytes_downloaded + info.total_bytes_uploaded)
+            .sum()
+    }
+    pub fn set_capture_stats(&mut self, interface_name: &str, stats: CaptureStats) {
+        match self.capture_stats.get_mut(interface_name) {
+            Some(interface_stats) => *interface_stats = stats,
+            None => {
+                self.capture_stats
+                    .insert(String::from(interface_name), stats);
+            }
+        }
+    }
+    /// Summed up over every interface
+    pub fn capture_stats(&self) -> CaptureStats {
+        let mut total = CaptureStats::default();
+        for stats in self.capture_stats.values() {
+            total.add(*stats);
+        }
+        total
+    }
+    pub fn remove_interface(&mut self, interface_name: &str) {
+        self.connections
+            .retain(|_, info| info.interface_name != interface_name);
+    }
     pub fn update(&mut self, seg: Segment) {
-        let total_bandwidth = self
-            .connections
-            .entry(seg.connection)
-            .or_insert(ConnectionInfo {
-                interface_name: seg.interface_name,
-                total_bytes_downloaded: 0,
-                total_bytes_uploaded: 0,
-            });
+        self.count(seg, 1);
+    }
+    /// Counts `seg` `every` times for its connection but once in the lifetime counters, which
+    /// are exact since they get the `skipped` traffic too (see --sample)
+    pub fn update_sampled(&mut self, seg: Segment, every: u32, skipped: SkippedTraffic) {
+        self.lifetime_bytes_uploaded += skipped.bytes_uploaded;
+        self.lifetime_bytes_downloaded += skipped.bytes_downloaded;
+        self.lifetime_packets_uploaded += skipped.packets_uploaded;
+        self.lifetime_packets_downloaded += skipped.packets_downloaded;
+        self.count(seg, u128::from(every));
+    }
+    // `weight` is how many segments like it `seg` stands for on its connection
+    fn count(&mut self, seg: Segment, weight: u128) {
+        let total_bandwidth =
+            self.connections
+                .entry(seg.connection)
+                .or_insert_with(|| ConnectionInfo {
+                    interface_name: seg.interface_name.clone(),
+                    total_bytes_downloaded: 0,
+                    total_bytes_uploaded: 0,
+                    total_packets: 0,
+                    packets_downloaded: 0,
+                    packets_uploaded: 0,
+                    retransmitted_packets: 0,
+                });
+        // the same connection on another interface is the same traffic seen twice, eg. on a
+        // bridge and on the interface it forwards to
+        if total_bandwidth.interface_name != seg.interface_name {
+            return;
+        }
+        total_bandwidth.total_packets += weight;
+        if seg.retransmitted {
+            total_bandwidth.retransmitted_packets += weight;
+        }
         match seg.direction {
             Direction::Download => {
-                total_bandwidth.total_bytes_downloaded += seg.data_length;
+                total_bandwidth.total_bytes_downloaded += seg.data_length * weight;
+                total_bandwidth.packets_downloaded += weight;
+                self.lifetime_bytes_downloaded += seg.data_length;
+                self.lifetime_packets_downloaded += 1;
             }
             Direction::Upload => {
-                total_bandwidth.total_bytes_uploaded += seg.data_length;
+                total_bandwidth.total_bytes_uploaded += seg.data_length * weight;
+                total_bandwidth.packets_uploaded += weight;
+                self.lifetime_bytes_uploaded += seg.data_length;
+                self.lifetime_packets_uploaded += 1;
             }
         }
     }
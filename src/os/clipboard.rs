@@ -0,0 +1,53 @@
+#[cfg(not(target_os = "macos"))]
+use ::std::env;
+use ::std::io::{self, ErrorKind, Write};
+use ::std::process::{Command, Stdio};
+
+// the tools that put their stdin on the clipboard, by what they need to be there
+#[cfg(target_os = "macos")]
+fn clipboard_commands() -> Vec<&'static [&'static str]> {
+    vec![&["pbcopy"]]
+}
+
+#[cfg(not(target_os = "macos"))]
+fn clipboard_commands() -> Vec<&'static [&'static str]> {
+    let is_set = |name: &str| env::var_os(name).is_some_and(|value| !value.is_empty());
+    let mut commands: Vec<&'static [&'static str]> = Vec::new();
+    if is_set("WAYLAND_DISPLAY") {
+        commands.push(&["wl-copy"]);
+    }
+    if is_set("DISPLAY") {
+        commands.push(&["xclip", "-selection", "clipboard"]);
+        commands.push(&["xsel", "--clipboard", "--input"]);
+    }
+    commands
+}
+
+/// Puts `text` on the system clipboard with the first of pbcopy, wl-copy, xclip and xsel that is
+/// there, fails on a headless system where there is no clipboard (see the `y` key)
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    for command in clipboard_commands() {
+        let mut child = match Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        // closed once written, the tools copy what they read until then
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+    Err(io::Error::new(
+        ErrorKind::NotFound,
+        "no clipboard available",
+    ))
+}
@@ -42,3 +42,45 @@ impl fmt::Display for GetInterfaceError {
         fmt::Display::fmt(&self.inner, f)
     }
 }
+
+/// Reasons bandwhich could not start, each with a stable code that scripts can match on
+#[derive(Clone, Eq, PartialEq, Debug, Fail)]
+pub enum StartupError {
+    #[fail(display = "Cannot find interface {}", _0)]
+    InterfaceNotFound(String),
+    #[fail(display = "{}", _0)]
+    PermissionDenied(String),
+    #[fail(display = "{}", _0)]
+    InterfacesUnavailable(String),
+    #[fail(display = "Failed to find any network interface to listen on.")]
+    NoInterfaces,
+    #[fail(display = "Could not initialize the DNS resolver. Are you offline?")]
+    DnsResolver,
+}
+
+impl StartupError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            StartupError::InterfaceNotFound(_) => "interface_not_found",
+            StartupError::PermissionDenied(_) => "permission_denied",
+            StartupError::InterfacesUnavailable(_) => "interfaces_unavailable",
+            StartupError::NoInterfaces => "no_interfaces",
+            StartupError::DnsResolver => "dns_resolver",
+        }
+    }
+    pub fn hint(&self) -> &'static str {
+        match self {
+            StartupError::InterfaceNotFound(_) => {
+                "check the name against the interfaces listed by `ip link` or `ifconfig`"
+            }
+            StartupError::PermissionDenied(_) => {
+                "run with sudo, or grant the binary the cap_net_raw and cap_net_admin capabilities"
+            }
+            StartupError::InterfacesUnavailable(_) => {
+                "pick an interface that can be captured on with --interface"
+            }
+            StartupError::NoInterfaces => "bring up an interface that has an address",
+            StartupError::DnsResolver => "pass --no-resolve to skip resolving hostnames",
+        }
+    }
+}
@@ -1,27 +1,46 @@
 use ::std::collections::HashMap;
+use ::std::fs;
 
+use ::procfs::net::TcpState;
 use ::procfs::process::FDTarget;
 
-use crate::network::{Connection, Protocol};
+use crate::network::{Connection, Protocol, UnixSocket};
+use crate::os::Ancestor;
 use crate::OpenSockets;
 
-pub(crate) fn get_open_sockets() -> OpenSockets {
+pub(crate) fn get_open_sockets(ancestor: Option<&Ancestor>) -> OpenSockets {
     let mut open_sockets = HashMap::new();
+    let mut sockets_to_pids = HashMap::new();
+    let mut sockets_to_users = HashMap::new();
     let mut connections = std::vec::Vec::new();
-    let mut inode_to_procname = HashMap::new();
+    let mut listening_sockets = HashMap::new();
+    let mut pid_to_process = HashMap::new();
+    let mut inode_to_pid = HashMap::new();
+    let mut pid_to_owner = HashMap::new();
 
     if let Ok(all_procs) = procfs::process::all_processes() {
         for process in all_procs {
             if let Ok(fds) = process.fd() {
-                let procname = process.stat.comm;
                 for fd in fds {
                     if let FDTarget::Socket(inode) = fd.target {
-                        inode_to_procname.insert(inode, procname.clone());
+                        inode_to_pid.insert(inode, process.stat.pid);
                     }
                 }
             }
+            pid_to_owner.insert(process.stat.pid, process.owner);
+            pid_to_process.insert(process.stat.pid, (process.stat.ppid, process.stat.comm));
         }
     }
+    let user_names = read_user_names();
+    let user_of = |pid: &i32| {
+        pid_to_owner
+            .get(pid)
+            .map(|uid| user_name(*uid, &user_names))
+    };
+    let inode_to_process: HashMap<_, _> = inode_to_pid
+        .into_iter()
+        .map(|(inode, pid)| (inode, attributed_process(pid, &pid_to_process, ancestor)))
+        .collect();
 
     if let Ok(mut tcp) = ::procfs::net::tcp() {
         if let Ok(mut tcp6) = ::procfs::net::tcp6() {
@@ -30,11 +49,20 @@ pub(crate) fn get_open_sockets() -> OpenSockets {
         for entry in tcp.into_iter() {
             let local_port = entry.local_address.port();
             let local_ip = entry.local_address.ip();
-            if let (connection, Some(procname)) = (
+            if let (connection, Some((pid, procname))) = (
                 Connection::new(entry.remote_address, local_ip, local_port, Protocol::Tcp),
-                inode_to_procname.get(&entry.inode),
+                socket_owner(entry.inode, &inode_to_process),
             ) {
-                open_sockets.insert(connection.local_socket, procname.clone());
+                if entry.state == TcpState::Listen {
+                    listening_sockets.insert(connection.local_socket, procname.clone());
+                }
+                open_sockets.insert(connection.local_socket, procname);
+                if let Some(pid) = pid {
+                    sockets_to_pids.insert(connection.local_socket, pid as u32);
+                    if let Some(user) = user_of(&pid) {
+                        sockets_to_users.insert(connection.local_socket, user);
+                    }
+                }
                 connections.push(connection);
             };
         }
@@ -47,17 +75,269 @@ pub(crate) fn get_open_sockets() -> OpenSockets {
         for entry in udp.into_iter() {
             let local_port = entry.local_address.port();
             let local_ip = entry.local_address.ip();
-            if let (connection, Some(procname)) = (
+            if let (connection, Some((pid, procname))) = (
                 Connection::new(entry.remote_address, local_ip, local_port, Protocol::Udp),
-                inode_to_procname.get(&entry.inode),
+                socket_owner(entry.inode, &inode_to_process),
             ) {
-                open_sockets.insert(connection.local_socket, procname.clone());
+                // an unconnected udp socket takes datagrams from anyone
+                if entry.remote_address.ip().is_unspecified() && entry.remote_address.port() == 0 {
+                    listening_sockets.insert(connection.local_socket, procname.clone());
+                }
+                open_sockets.insert(connection.local_socket, procname);
+                if let Some(pid) = pid {
+                    sockets_to_pids.insert(connection.local_socket, pid as u32);
+                    if let Some(user) = user_of(&pid) {
+                        sockets_to_users.insert(connection.local_socket, user);
+                    }
+                }
                 connections.push(connection);
             };
         }
     }
+    let mut unix_sockets = Vec::new();
+    if let Ok(unix) = ::procfs::net::unix() {
+        for entry in unix.into_iter() {
+            // unnamed sockets (eg. the client side of a connection) cannot be told apart
+            if let (Some(path), Some((_, procname))) =
+                (entry.path, socket_owner(entry.inode, &inode_to_process))
+            {
+                unix_sockets.push(UnixSocket {
+                    path: path.to_string_lossy().into_owned(),
+                    process_name: procname,
+                });
+            }
+        }
+    }
     OpenSockets {
         sockets_to_procs: open_sockets,
+        sockets_to_pids,
+        sockets_to_users,
         connections,
+        listening_sockets,
+        unix_sockets,
+    }
+}
+
+// the pid and name of the process holding the socket, or only its inode when that process could
+// not be read (eg. it belongs to another user and we are not root), so the socket still shows up
+// as more than unknown. Sockets nobody holds anymore (eg. in TIME_WAIT) have the inode 0
+fn socket_owner(
+    inode: u32,
+    inode_to_process: &HashMap<u32, (i32, String)>,
+) -> Option<(Option<i32>, String)> {
+    match inode_to_process.get(&inode) {
+        Some((pid, procname)) => Some((Some(*pid), procname.clone())),
+        None if inode == 0 => None,
+        None => Some((None, format!("[inode:{}]", inode))),
+    }
+}
+
+/// The command line of the process, None for kernel threads and processes that are gone
+pub(crate) fn get_process_cmdline(pid: u32) -> Option<String> {
+    let cmdline = procfs::process::Process::new(pid as i32)
+        .ok()?
+        .cmdline()
+        .ok()?;
+    if cmdline.is_empty() {
+        None
+    } else {
+        Some(cmdline.join(" "))
+    }
+}
+
+/// The container the process runs in, by its name if docker has it on disk and by the first 12
+/// characters of its id otherwise (as `docker ps` shows it), None for a process that is not in one
+pub(crate) fn get_process_container(pid: u32) -> Option<String> {
+    let cgroup = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    let id = container_id(&cgroup)?;
+    Some(docker_container_name(&id).unwrap_or_else(|| String::from(&id[..12])))
+}
+
+// the 64 hex digits of the id in the cgroup path of a container: `/docker/<id>` (cgroup v1),
+// `/system.slice/docker-<id>.scope` (systemd), `/kubepods/.../<id>` or
+// `cri-containerd-<id>.scope` (containerd)
+fn container_id(cgroup: &str) -> Option<String> {
+    cgroup.lines().find_map(|line| {
+        // hierarchy-id:controllers:path
+        let path = line.splitn(3, ':').nth(2)?;
+        path.split('/').rev().find_map(|segment| {
+            let segment = segment.strip_suffix(".scope").unwrap_or(segment);
+            let id = segment.rsplit('-').next()?;
+            if id.len() == 64 && id.chars().all(|c| c.is_ascii_hexdigit()) {
+                Some(String::from(id))
+            } else {
+                None
+            }
+        })
+    })
+}
+
+// docker keeps the name in the config of the container, eg. `"Name":"/web"`
+fn docker_container_name(id: &str) -> Option<String> {
+    let config =
+        fs::read_to_string(format!("/var/lib/docker/containers/{}/config.v2.json", id)).ok()?;
+    let name = config.split("\"Name\":\"").nth(1)?.split('"').next()?;
+    Some(String::from(name.trim_start_matches('/')))
+}
+
+/// The negotiated speed of the link in bits per second, None when the driver does not tell (eg.
+/// for wifi, a virtual interface or a link that is down)
+pub(crate) fn get_link_speed(interface_name: &str) -> Option<u64> {
+    let speed = fs::read_to_string(format!("/sys/class/net/{}/speed", interface_name)).ok()?;
+    // in Mbps, -1 when unknown
+    match speed.trim().parse::<i64>().ok()? {
+        megabits if megabits > 0 => Some(megabits as u64 * 1_000_000),
+        _ => None,
+    }
+}
+
+// the user names by uid, a user that is only in a network directory (eg. LDAP) is not in here
+fn read_user_names() -> HashMap<u32, String> {
+    let passwd = fs::read_to_string("/etc/passwd").unwrap_or_default();
+    parse_passwd(&passwd)
+}
+
+fn parse_passwd(passwd: &str) -> HashMap<u32, String> {
+    passwd
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?.parse().ok()?;
+            Some((uid, String::from(name)))
+        })
+        .collect()
+}
+
+// the numeric uid when it has no name
+fn user_name(uid: u32, user_names: &HashMap<u32, String>) -> String {
+    user_names
+        .get(&uid)
+        .cloned()
+        .unwrap_or_else(|| uid.to_string())
+}
+
+// the pid and name the traffic of this process is shown under
+fn attributed_process(
+    pid: i32,
+    pid_to_process: &HashMap<i32, (i32, String)>,
+    ancestor: Option<&Ancestor>,
+) -> (i32, String) {
+    let procname = pid_to_process
+        .get(&pid)
+        .map(|(_, procname)| procname.clone())
+        .unwrap_or_default();
+    let ancestor = match ancestor {
+        Some(ancestor) => ancestor,
+        None => return (pid, procname),
+    };
+    let mut current_pid = pid;
+    // bounded so that a parent chain that changed while we were reading it cannot loop forever
+    for _ in 0..pid_to_process.len() {
+        let (parent_pid, current_procname) = match pid_to_process.get(&current_pid) {
+            Some(process) => process,
+            None => break,
+        };
+        let is_ancestor = match ancestor {
+            Ancestor::Pid(ancestor_pid) => current_pid == *ancestor_pid,
+            Ancestor::Name(ancestor_name) => current_procname == ancestor_name,
+        };
+        if is_ancestor {
+            return (current_pid, current_procname.clone());
+        }
+        current_pid = *parent_pid;
+    }
+    (pid, procname)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process_tree() -> HashMap<i32, (i32, String)> {
+        let mut pid_to_process = HashMap::new();
+        pid_to_process.insert(1, (0, String::from("init")));
+        pid_to_process.insert(100, (1, String::from("bash")));
+        pid_to_process.insert(200, (100, String::from("curl")));
+        pid_to_process.insert(300, (1, String::from("sshd")));
+        pid_to_process
+    }
+
+    #[test]
+    fn test_no_ancestor_keeps_procname() {
+        assert_eq!(
+            attributed_process(200, &process_tree(), None),
+            (200, String::from("curl"))
+        );
+    }
+
+    #[test]
+    fn test_descendant_is_attributed_to_ancestor_name() {
+        let ancestor = Ancestor::Name(String::from("bash"));
+        assert_eq!(
+            attributed_process(200, &process_tree(), Some(&ancestor)),
+            (100, String::from("bash"))
+        );
+    }
+
+    #[test]
+    fn test_descendant_is_attributed_to_ancestor_pid() {
+        let ancestor = Ancestor::Pid(100);
+        assert_eq!(
+            attributed_process(200, &process_tree(), Some(&ancestor)),
+            (100, String::from("bash"))
+        );
+    }
+
+    #[test]
+    fn test_user_without_a_name_is_shown_by_uid() {
+        let user_names = parse_passwd(
+            "root:x:0:0:root:/root:/bin/bash\n# a comment\nalice:x:1000:1000::/home/alice:/bin/sh\n",
+        );
+        assert_eq!(user_name(0, &user_names), "root");
+        assert_eq!(user_name(1000, &user_names), "alice");
+        assert_eq!(user_name(1001, &user_names), "1001");
+    }
+
+    #[test]
+    fn test_socket_of_an_unreadable_process_is_named_by_inode() {
+        let mut inode_to_process = HashMap::new();
+        inode_to_process.insert(4242, (200, String::from("curl")));
+        assert_eq!(
+            socket_owner(4242, &inode_to_process),
+            Some((Some(200), String::from("curl")))
+        );
+        assert_eq!(
+            socket_owner(12345, &inode_to_process),
+            Some((None, String::from("[inode:12345]")))
+        );
+        assert_eq!(socket_owner(0, &inode_to_process), None);
+    }
+
+    #[test]
+    fn test_container_id_is_read_from_the_cgroup_path() {
+        let id = "4f1c2b0e9a8d7c6b5a4f3e2d1c0b9a8f7e6d5c4b3a2f1e0d9c8b7a6f5e4d3c2b";
+        let docker_v1 = format!("12:memory:/docker/{}\n0::/\n", id);
+        let systemd = format!("0::/system.slice/docker-{}.scope\n", id);
+        let kubernetes = format!(
+            "0::/kubepods.slice/kubepods-pod1234.slice/cri-containerd-{}.scope\n",
+            id
+        );
+        assert_eq!(container_id(&docker_v1), Some(String::from(id)));
+        assert_eq!(container_id(&systemd), Some(String::from(id)));
+        assert_eq!(container_id(&kubernetes), Some(String::from(id)));
+        assert_eq!(
+            container_id("0::/user.slice/user-1000.slice/session-2.scope\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unrelated_process_keeps_procname() {
+        let ancestor = Ancestor::Name(String::from("bash"));
+        assert_eq!(
+            attributed_process(300, &process_tree(), Some(&ancestor)),
+            (300, String::from("sshd"))
+        );
     }
 }
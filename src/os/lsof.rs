@@ -1,6 +1,7 @@
 use ::std::collections::HashMap;
 
 use crate::network::Connection;
+use crate::os::Ancestor;
 use crate::OpenSockets;
 
 use super::lsof_utils;
@@ -15,9 +16,12 @@ struct RawConnection {
     process_name: String,
 }
 
-pub(crate) fn get_open_sockets() -> OpenSockets {
+pub(crate) fn get_open_sockets(_ancestor: Option<&Ancestor>) -> OpenSockets {
     let mut open_sockets = HashMap::new();
+    let mut sockets_to_pids = HashMap::new();
+    let mut sockets_to_users = HashMap::new();
     let mut connections_vec = std::vec::Vec::new();
+    let mut listening_sockets = HashMap::new();
 
     let connections = lsof_utils::get_connections();
 
@@ -31,12 +35,65 @@ pub(crate) fn get_open_sockets() -> OpenSockets {
         let socket_addr = SocketAddr::new(remote_ip, remote_port);
         let connection = Connection::new(socket_addr, local_ip, local_port, protocol);
 
+        // lsof has no remote end for a socket waiting for connections
+        if remote_ip.is_unspecified() && remote_port == 0 {
+            listening_sockets.insert(connection.local_socket, raw_connection.process_name.clone());
+        }
         open_sockets.insert(connection.local_socket, raw_connection.process_name.clone());
+        if let Some(pid) = raw_connection.pid {
+            sockets_to_pids.insert(connection.local_socket, pid);
+        }
+        // lsof shows the uid of an owner that has no name
+        sockets_to_users.insert(connection.local_socket, raw_connection.user.clone());
         connections_vec.push(connection);
     }
 
     OpenSockets {
         sockets_to_procs: open_sockets,
+        sockets_to_pids,
+        sockets_to_users,
         connections: connections_vec,
+        listening_sockets,
+        unix_sockets: Vec::new(),
     }
 }
+
+/// The path of the executable of the process, there is no command line to be had without ps
+#[cfg(target_os = "macos")]
+pub(crate) fn get_process_cmdline(pid: u32) -> Option<String> {
+    use ::std::os::raw::{c_int, c_void};
+
+    // PROC_PIDPATHINFO_MAXSIZE of libproc.h
+    const PROC_PIDPATHINFO_MAXSIZE: usize = 4 * 1024;
+    extern "C" {
+        fn proc_pidpath(pid: c_int, buffer: *mut c_void, buffersize: u32) -> c_int;
+    }
+    let mut buffer = vec![0u8; PROC_PIDPATHINFO_MAXSIZE];
+    let length = unsafe {
+        proc_pidpath(
+            pid as c_int,
+            buffer.as_mut_ptr() as *mut c_void,
+            buffer.len() as u32,
+        )
+    };
+    if length <= 0 {
+        return None;
+    }
+    buffer.truncate(length as usize);
+    String::from_utf8(buffer).ok()
+}
+
+#[cfg(target_os = "freebsd")]
+pub(crate) fn get_process_cmdline(_pid: u32) -> Option<String> {
+    None
+}
+
+/// Not looked up here, containers are only told apart on linux
+pub(crate) fn get_process_container(_pid: u32) -> Option<String> {
+    None
+}
+
+/// Not looked up here, the link speed is only read on linux
+pub(crate) fn get_link_speed(_interface_name: &str) -> Option<u64> {
+    None
+}
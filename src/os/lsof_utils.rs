@@ -13,6 +13,9 @@ pub struct RawConnection {
     remote_port: String,
     protocol: String,
     pub process_name: String,
+    pub pid: Option<u32>,
+    /// The name of the owner of the process, or its uid when it has none
+    pub user: String,
 }
 
 lazy_static! {
@@ -38,9 +41,9 @@ impl RawConnection {
             return None;
         }
         let process_name = columns[0].replace("\\x20", " ");
+        let pid = columns[1].parse::<u32>().ok();
+        let user = String::from(columns[2]);
         // Unneeded
-        // let pid = columns[1];
-        // let username = columns[2];
         // let fd = columns[3];
 
         // IPv4 or IPv6
@@ -72,6 +75,8 @@ impl RawConnection {
                 remote_port,
                 protocol,
                 process_name,
+                pid,
+                user,
             };
             Some(connection)
         } else if let Some(caps) = LISTEN_REGEX.captures(connection_str) {
@@ -95,6 +100,8 @@ impl RawConnection {
                 remote_port,
                 protocol,
                 process_name,
+                pid,
+                user,
             };
             Some(connection)
         } else {
@@ -103,7 +110,7 @@ impl RawConnection {
     }
 
     pub fn get_protocol(&self) -> Protocol {
-        Protocol::from_str(&self.protocol).unwrap()
+        self.protocol.parse().unwrap()
     }
 
     pub fn get_remote_ip(&self) -> IpAddr {
@@ -203,6 +210,18 @@ com.apple   590 etoledom  204u  IPv4 0x28ffb9c04111253f      0t0  TCP 192.168.1.
         assert!(connection.is_none());
     }
 
+    #[test]
+    fn test_raw_connection_parse_pid() {
+        let connection = RawConnection::new(LINE_RAW_OUTPUT).unwrap();
+        assert_eq!(connection.pid, Some(29266));
+    }
+
+    #[test]
+    fn test_raw_connection_parse_user() {
+        let connection = RawConnection::new(LINE_RAW_OUTPUT).unwrap();
+        assert_eq!(connection.user, "user");
+    }
+
     #[test]
     fn test_raw_connection_parse_remote_port_ipv4() {
         test_raw_connection_parse_remote_port(LINE_RAW_OUTPUT);
@@ -7,7 +7,12 @@ pub(self) mod lsof;
 #[cfg(any(target_os = "macos", target_os = "freebsd"))]
 mod lsof_utils;
 
+mod clipboard;
 mod errors;
+mod pcap_file;
 mod shared;
 
+pub use clipboard::copy_to_clipboard;
+pub use errors::StartupError;
+pub use pcap_file::PcapFile;
 pub use shared::*;
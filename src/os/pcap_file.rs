@@ -0,0 +1,146 @@
+use ::pnet_bandwhich_fork::datalink::DataLinkReceiver;
+use ::std::fs::File;
+use ::std::io::{self, BufReader, ErrorKind, Read};
+use ::std::path::Path;
+use ::std::thread;
+use ::std::time::{Duration, Instant};
+
+// the magic numbers of the classic pcap format, with microsecond or nanosecond timestamps
+const MAGIC_MICROSECONDS: u32 = 0xa1b2_c3d4;
+const MAGIC_NANOSECONDS: u32 = 0xa1b2_3c4d;
+// the link types whose frames the sniffer can read, see `Sniffer::segment_from`
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+// the header the "any" interface of tcpdump puts before the ip packet
+const LINUX_SLL_HEADER_LENGTH: usize = 16;
+// anything bigger is a corrupt file rather than a packet
+const MAX_PACKET_LENGTH: usize = 262_144;
+// how long the replay waits for the next packet at a time, like the read timeout of a live capture
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A capture file replayed as if its packets were captured live, as far apart as they were
+/// captured (see `--read-pcap`). Only the classic pcap format is read, not pcapng.
+///
+/// Once the file runs out every read fails with `ErrorKind::UnexpectedEof`.
+pub struct PcapFile {
+    reader: BufReader<File>,
+    big_endian: bool,
+    nanoseconds: bool,
+    link_header_length: usize,
+    packet: Vec<u8>,
+    /// The timestamp and length of the packet read next, once its record header was read
+    pending_record: Option<(Duration, usize)>,
+    /// When the first packet was replayed and its timestamp
+    started: Option<(Instant, Duration)>,
+}
+
+impl PcapFile {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut header = [0; 24];
+        reader.read_exact(&mut header)?;
+        let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let (big_endian, nanoseconds) = match (magic, magic.swap_bytes()) {
+            (MAGIC_MICROSECONDS, _) => (false, false),
+            (MAGIC_NANOSECONDS, _) => (false, true),
+            (_, MAGIC_MICROSECONDS) => (true, false),
+            (_, MAGIC_NANOSECONDS) => (true, true),
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "not a pcap file (pcapng files can be converted with `editcap -F pcap`)",
+                ))
+            }
+        };
+        let mut pcap_file = PcapFile {
+            reader,
+            big_endian,
+            nanoseconds,
+            link_header_length: 0,
+            packet: Vec::new(),
+            pending_record: None,
+            started: None,
+        };
+        pcap_file.link_header_length = match pcap_file.u32_at(&header, 20) {
+            LINKTYPE_ETHERNET | LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => 0,
+            LINKTYPE_LINUX_SLL => LINUX_SLL_HEADER_LENGTH,
+            link_type => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("unsupported link type {} in the pcap file", link_type),
+                ))
+            }
+        };
+        Ok(pcap_file)
+    }
+    fn u32_at(&self, bytes: &[u8], offset: usize) -> u32 {
+        let bytes = [
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ];
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+    fn read_record_header(&mut self) -> io::Result<(Duration, usize)> {
+        let mut header = [0; 16];
+        self.reader.read_exact(&mut header)?;
+        let seconds = self.u32_at(&header, 0);
+        let fraction = self.u32_at(&header, 4);
+        let captured_length = self.u32_at(&header, 8) as usize;
+        if captured_length > MAX_PACKET_LENGTH {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("a packet of {} bytes in the pcap file", captured_length),
+            ));
+        }
+        let nanoseconds = if self.nanoseconds {
+            fraction
+        } else {
+            fraction.saturating_mul(1000)
+        };
+        Ok((
+            Duration::new(u64::from(seconds), nanoseconds),
+            captured_length,
+        ))
+    }
+}
+
+impl DataLinkReceiver for PcapFile {
+    fn next(&mut self) -> io::Result<&[u8]> {
+        let (timestamp, captured_length) = match self.pending_record {
+            Some(record) => record,
+            None => {
+                let record = self.read_record_header()?;
+                self.pending_record = Some(record);
+                record
+            }
+        };
+        match self.started {
+            None => self.started = Some((Instant::now(), timestamp)),
+            Some((started_at, first_timestamp)) => {
+                let due = started_at + timestamp.checked_sub(first_timestamp).unwrap_or_default();
+                let now = Instant::now();
+                // a long gap is waited out a timeout at a time, so the capture can still be stopped
+                if due > now + READ_TIMEOUT {
+                    thread::sleep(READ_TIMEOUT);
+                    return Ok(&[]);
+                } else if due > now {
+                    thread::sleep(due - now);
+                }
+            }
+        }
+        self.pending_record = None;
+        self.packet.resize(captured_length, 0);
+        self.reader.read_exact(&mut self.packet)?;
+        let link_header_length = self.link_header_length.min(self.packet.len());
+        Ok(&self.packet[link_header_length..])
+    }
+}
@@ -1,25 +1,65 @@
 use ::pnet_bandwhich_fork::datalink::Channel::Ethernet;
 use ::pnet_bandwhich_fork::datalink::DataLinkReceiver;
 use ::pnet_bandwhich_fork::datalink::{self, Config, NetworkInterface};
+use ::std::convert::Infallible;
 use ::std::io::{self, stdin, ErrorKind, Write};
+use ::std::net::SocketAddr;
+use ::std::path::Path;
+use ::std::str::FromStr;
 use ::termion::event::Event;
 use ::termion::input::TermRead;
 use ::tokio::runtime::Runtime;
 
 use ::std::time;
 
-use crate::os::errors::GetInterfaceErrorKind;
+#[cfg(target_os = "linux")]
+use ::std::fs;
+
+const IFF_PROMISC: u32 = 0x100;
+
+/// Passed as the interface name to capture from every interface but the loopback ones
+pub const ANY_INTERFACE: &str = "any";
+
+use crate::os::errors::{GetInterfaceErrorKind, StartupError};
 use signal_hook::iterator::Signals;
 
 #[cfg(target_os = "linux")]
-use crate::os::linux::get_open_sockets;
+use crate::os::linux::{
+    get_link_speed, get_open_sockets, get_process_cmdline, get_process_container,
+};
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+use crate::os::lsof::{
+    get_link_speed, get_open_sockets, get_process_cmdline, get_process_container,
+};
+
+/// Where the sockets of the processes are looked up, see --print-capabilities
+#[cfg(target_os = "linux")]
+pub const OPEN_SOCKETS_BACKEND: &str = "procfs";
 #[cfg(any(target_os = "macos", target_os = "freebsd"))]
-use crate::os::lsof::get_open_sockets;
-use crate::{network::dns, OsInputOutput};
+pub const OPEN_SOCKETS_BACKEND: &str = "lsof";
+use crate::os::{copy_to_clipboard, PcapFile};
+use crate::{enumerate_sockets, network::dns, OpenSockets, OsInputOutput};
 
 pub type OnSigWinch = dyn Fn(Box<dyn Fn()>) + Send;
+pub type OnSigTerm = dyn Fn(Box<dyn Fn()>) + Send;
 pub type SigCleanup = dyn Fn() + Send;
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ancestor {
+    Pid(i32),
+    Name(String),
+}
+
+impl FromStr for Ancestor {
+    type Err = Infallible;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse() {
+            Ok(pid) => Ancestor::Pid(pid),
+            Err(_) => Ancestor::Name(String::from(s)),
+        })
+    }
+}
+
 pub struct KeyboardEvents;
 
 impl Iterator for KeyboardEvents {
@@ -57,16 +97,38 @@ fn get_datalink_channel(
     }
 }
 
+// the capture asks for promiscuous mode, but some (mostly virtualized) NICs quietly do not honor it
+#[cfg(target_os = "linux")]
+fn is_promiscuous(interface: &NetworkInterface) -> bool {
+    // unlike the flags from getifaddrs, these include promiscuity asked for by packet sockets
+    let flags = fs::read_to_string(format!("/sys/class/net/{}/flags", interface.name))
+        .ok()
+        .and_then(|flags| u32::from_str_radix(flags.trim().trim_start_matches("0x"), 16).ok());
+    match flags {
+        Some(flags) => flags & IFF_PROMISC != 0,
+        None => true, // nothing to go by, so nothing to warn about
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+fn is_promiscuous(interface: &NetworkInterface) -> bool {
+    match get_interface(&interface.name) {
+        Some(interface) => interface.flags & IFF_PROMISC != 0,
+        None => true,
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
 fn get_interface(interface_name: &str) -> Option<NetworkInterface> {
     datalink::interfaces()
         .into_iter()
         .find(|iface| iface.name == interface_name)
 }
 
-fn sigwinch() -> (Box<OnSigWinch>, Box<SigCleanup>) {
-    let signals = Signals::new(&[signal_hook::SIGWINCH]).unwrap();
+fn signals() -> io::Result<(Box<OnSigWinch>, Box<OnSigTerm>, Box<SigCleanup>)> {
+    let winch_signals = Signals::new(&[signal_hook::SIGWINCH])?;
     let on_winch = {
-        let signals = signals.clone();
+        let signals = winch_signals.clone();
         move |cb: Box<dyn Fn()>| {
             for signal in signals.forever() {
                 match signal {
@@ -76,10 +138,28 @@ fn sigwinch() -> (Box<OnSigWinch>, Box<SigCleanup>) {
             }
         }
     };
+    // once these are handled they no longer kill the process, so the terminal gets restored
+    let term_signals = Signals::new([signal_hook::SIGTERM, signal_hook::SIGINT])?;
+    let on_terminate = {
+        let signals = term_signals.clone();
+        move |cb: Box<dyn Fn()>| {
+            for signal in signals.forever() {
+                match signal {
+                    signal_hook::SIGTERM | signal_hook::SIGINT => cb(),
+                    _ => unreachable!(),
+                }
+            }
+        }
+    };
     let cleanup = move || {
-        signals.close();
+        winch_signals.close();
+        term_signals.close();
     };
-    (Box::new(on_winch), Box::new(cleanup))
+    Ok((
+        Box::new(on_winch),
+        Box::new(on_terminate),
+        Box::new(cleanup),
+    ))
 }
 
 fn create_write_to_stdout() -> Box<dyn FnMut(String) + Send> {
@@ -97,7 +177,7 @@ pub struct UserErrors {
     other: Option<String>,
 }
 
-pub fn collect_errors<'a, I>(network_frames: I) -> String
+pub fn collect_errors<'a, I>(network_frames: I) -> Option<StartupError>
 where
     I: Iterator<
         Item = (
@@ -147,79 +227,224 @@ where
     );
     if let Some(interface_name) = errors.permission {
         if let Some(other_errors) = errors.other {
-            format!(
+            Some(StartupError::PermissionDenied(format!(
                 "\n\n{}: {} \nAdditional Errors: \n {}",
                 interface_name,
                 eperm_message(),
                 other_errors
-            )
+            )))
         } else {
-            format!("\n\n{}: {}", interface_name, eperm_message())
+            Some(StartupError::PermissionDenied(format!(
+                "\n\n{}: {}",
+                interface_name,
+                eperm_message()
+            )))
         }
     } else {
-        let other_errors = errors
-            .other
-            .expect("asked to collect errors but found no errors");
-        format!("\n\n {}", other_errors)
+        errors.other.map(|other_errors| {
+            StartupError::InterfacesUnavailable(format!("\n\n {}", other_errors))
+        })
     }
 }
 
-pub fn get_input(
-    interface_name: &Option<String>,
-    resolve: bool,
-) -> Result<OsInputOutput, failure::Error> {
-    let network_interfaces = if let Some(name) = interface_name {
-        match get_interface(&name) {
-            Some(interface) => vec![interface],
+// what the traffic is read from, live interfaces or a capture file
+struct CaptureInput {
+    network_interfaces: Vec<NetworkInterface>,
+    network_frames: Vec<Box<dyn DataLinkReceiver>>,
+    host_only_interfaces: Vec<String>,
+    uncaptured_interfaces: Vec<(String, String)>,
+}
+
+// the packets of a capture file cannot be told apart by process
+fn get_no_open_sockets(_ancestor: Option<&Ancestor>) -> OpenSockets {
+    OpenSockets::default()
+}
+
+/// The sockets open right now and the processes they belong to, without capturing anything (see
+/// --once)
+pub fn get_current_sockets(
+    attribute_to_ancestor: Option<&Ancestor>,
+    full_cmdline: bool,
+    containers: bool,
+    show_pid: bool,
+) -> OpenSockets {
+    let get_process_cmdline = if full_cmdline {
+        Some(get_process_cmdline as _)
+    } else {
+        None
+    };
+    let get_process_container = if containers {
+        Some(get_process_container as _)
+    } else {
+        None
+    };
+    enumerate_sockets(
+        get_open_sockets,
+        get_process_cmdline,
+        get_process_container,
+        attribute_to_ancestor,
+        show_pid,
+    )
+}
+
+fn read_capture_file(path: &Path) -> Result<CaptureInput, failure::Error> {
+    let pcap_file = match PcapFile::open(path) {
+        Ok(pcap_file) => pcap_file,
+        Err(err) => failure::bail!("Failed to read {}: {}", path.display(), err),
+    };
+    // the traffic is told apart by the addresses of this host, where it was most likely captured
+    let network_interface = NetworkInterface {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string()),
+        index: 0,
+        mac: None,
+        ips: datalink::interfaces()
+            .into_iter()
+            .flat_map(|iface| iface.ips)
+            .collect(),
+        flags: 0,
+    };
+    Ok(CaptureInput {
+        network_interfaces: vec![network_interface],
+        network_frames: vec![Box::new(pcap_file)],
+        host_only_interfaces: Vec::new(),
+        uncaptured_interfaces: Vec::new(),
+    })
+}
+
+/// The interfaces named in `names`, separated by commas (eg. `eth0,wg0`), in that order and each
+/// once. Fails on the first name not among `interfaces`
+pub fn select_interfaces(
+    names: &str,
+    interfaces: Vec<NetworkInterface>,
+) -> Result<Vec<NetworkInterface>, StartupError> {
+    let mut selected: Vec<NetworkInterface> = Vec::new();
+    for name in names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        if selected.iter().any(|iface| iface.name == name) {
+            continue;
+        }
+        match interfaces.iter().find(|iface| iface.name == name) {
+            Some(interface) => selected.push(interface.clone()),
             None => {
-                failure::bail!("Cannot find interface {}", name);
+                return Err(StartupError::InterfaceNotFound(name.to_owned()));
                 // the homebrew formula relies on this wording, please be careful when changing
             }
         }
-    } else {
-        datalink::interfaces()
+    }
+    Ok(selected)
+}
+
+fn capture_interfaces(
+    interface_name: &Option<String>,
+    excluded_interfaces: &[String],
+) -> Result<CaptureInput, failure::Error> {
+    let network_interfaces = match interface_name.as_deref() {
+        Some(ANY_INTERFACE) => datalink::interfaces()
+            .into_iter()
+            .filter(|iface| !iface.is_loopback())
+            .collect(),
+        Some(names) => select_interfaces(names, datalink::interfaces())?,
+        None => datalink::interfaces(),
     };
+    let network_interfaces: Vec<NetworkInterface> = network_interfaces
+        .into_iter()
+        .filter(|iface| !excluded_interfaces.contains(&iface.name))
+        .collect();
 
     let network_frames = network_interfaces
         .iter()
         .filter(|iface| iface.is_up() && !iface.ips.is_empty())
         .map(|iface| (iface, get_datalink_channel(iface)));
 
+    let mut uncaptured_interfaces: Vec<(String, String)> = network_interfaces
+        .iter()
+        .filter(|iface| !iface.is_up() || iface.ips.is_empty())
+        .map(|iface| {
+            (
+                iface.name.clone(),
+                String::from("down or without an address"),
+            )
+        })
+        .collect();
     let (available_network_frames, network_interfaces) = {
         let network_frames = network_frames.clone();
         let mut available_network_frames = Vec::new();
         let mut available_interfaces: Vec<NetworkInterface> = Vec::new();
-        for (iface, rx) in network_frames.filter_map(|(iface, channel)| {
-            if let Ok(rx) = channel {
-                Some((iface, rx))
-            } else {
-                None
+        for (iface, channel) in network_frames {
+            match channel {
+                Ok(rx) => {
+                    available_interfaces.push(iface.clone());
+                    available_network_frames.push(rx);
+                }
+                Err(GetInterfaceErrorKind::PermissionError(_)) => uncaptured_interfaces
+                    .push((iface.name.clone(), String::from("permission denied"))),
+                Err(GetInterfaceErrorKind::OtherError(message)) => {
+                    uncaptured_interfaces.push((iface.name.clone(), message))
+                }
             }
-        }) {
-            available_interfaces.push(iface.clone());
-            available_network_frames.push(rx);
         }
         (available_network_frames, available_interfaces)
     };
 
     if available_network_frames.is_empty() {
-        let all_errors = collect_errors(network_frames.clone());
-        if !all_errors.is_empty() {
-            failure::bail!(all_errors);
+        if let Some(all_errors) = collect_errors(network_frames.clone()) {
+            return Err(all_errors.into());
         }
 
-        failure::bail!("Failed to find any network interface to listen on.");
+        return Err(StartupError::NoInterfaces.into());
     }
 
+    let host_only_interfaces = network_interfaces
+        .iter()
+        .filter(|iface| !iface.is_loopback() && !is_promiscuous(iface))
+        .map(|iface| iface.name.clone())
+        .collect();
+    Ok(CaptureInput {
+        network_interfaces,
+        network_frames: available_network_frames,
+        host_only_interfaces,
+        uncaptured_interfaces,
+    })
+}
+
+/// Reads the traffic from the capture file at `read_pcap` if given, from the network interfaces
+/// otherwise
+pub fn get_input(
+    interface_name: &Option<String>,
+    excluded_interfaces: &[String],
+    read_pcap: Option<&Path>,
+    resolve: bool,
+    dns_server: Option<SocketAddr>,
+) -> Result<OsInputOutput, failure::Error> {
+    let (capture_input, get_open_sockets) = match read_pcap {
+        Some(path) => (read_capture_file(path)?, get_no_open_sockets as _),
+        None => (
+            capture_interfaces(interface_name, excluded_interfaces)?,
+            get_open_sockets as _,
+        ),
+    };
+
+    let link_speeds = capture_input
+        .network_interfaces
+        .iter()
+        .filter_map(|iface| Some((iface.name.clone(), get_link_speed(&iface.name)?)))
+        .collect();
     let keyboard_events = Box::new(KeyboardEvents);
     let write_to_stdout = create_write_to_stdout();
-    let (on_winch, cleanup) = sigwinch();
+    let (on_winch, on_terminate, cleanup) = signals()?;
     let dns_client = if resolve {
         let mut runtime = Runtime::new()?;
-        let resolver = match runtime.block_on(dns::Resolver::new(runtime.handle().clone())) {
-            Ok(resolver) => resolver,
-            Err(_) => failure::bail!("Could not initialize the DNS resolver. Are you offline?"),
-        };
+        let resolver =
+            match runtime.block_on(dns::Resolver::new(runtime.handle().clone(), dns_server)) {
+                Ok(resolver) => resolver,
+                Err(_) => return Err(StartupError::DnsResolver.into()),
+            };
         let dns_client = dns::Client::new(resolver, runtime)?;
         Some(dns_client)
     } else {
@@ -227,12 +452,19 @@ pub fn get_input(
     };
 
     Ok(OsInputOutput {
-        network_interfaces,
-        network_frames: available_network_frames,
+        network_interfaces: capture_input.network_interfaces,
+        network_frames: capture_input.network_frames,
+        host_only_interfaces: capture_input.host_only_interfaces,
+        uncaptured_interfaces: capture_input.uncaptured_interfaces,
+        link_speeds,
         get_open_sockets,
+        get_process_cmdline,
+        get_process_container,
+        copy_to_clipboard,
         keyboard_events,
         dns_client,
         on_winch,
+        on_terminate,
         cleanup,
         write_to_stdout,
     })
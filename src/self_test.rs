@@ -0,0 +1,189 @@
+use ::std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use ::std::thread;
+use ::std::time::{Duration, Instant};
+
+use crate::network::{Connection, Direction, Protocol, Segment, Utilization};
+
+/// How long a --self-test runs without --duration
+pub const SELF_TEST_SECONDS: u64 = 10;
+// how many connections the synthetic segments are spread over, about what a busy host has open
+const SELF_TEST_CONNECTIONS: u32 = 1024;
+// the payload of a full ethernet frame of tcp
+const SELF_TEST_SEGMENT_LENGTH: u128 = 1460;
+// the display thread takes what was counted and starts over every tick, as with --interval 1
+const SELF_TEST_TICK: Duration = Duration::from_secs(1);
+// how long the loop waits once it counted everything due
+const PACING_GRAIN: Duration = Duration::from_millis(1);
+// how far behind the counting can fall before the capture drops what comes due, the kernel
+// buffers about that much of a busy link
+const CAPTURE_BUFFER: Duration = Duration::from_millis(100);
+// how many segments are counted between two looks at the clock
+const SELF_TEST_BATCH: u64 = 256;
+
+pub fn parse_self_test_rate(rate: &str) -> Result<u64, String> {
+    match rate.parse::<u64>() {
+        Ok(packets_per_second) if packets_per_second > 0 => Ok(packets_per_second),
+        _ => Err(format!(
+            "invalid rate '{}', expected a number of packets per second above 0",
+            rate
+        )),
+    }
+}
+
+// the `index`th segment, round robin over the connections and both ways. The remote addresses
+// are in 198.18.0.0/15, which is set aside for benchmarks
+fn synthetic_segment(index: u64) -> Segment {
+    let connection_index = (index % u64::from(SELF_TEST_CONNECTIONS)) as u32;
+    let [_, _, high, low] = connection_index.to_be_bytes();
+    Segment {
+        interface_name: String::from("self-test"),
+        connection: Connection::new(
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(198, 18, high, low)), 443),
+            IpAddr::V4(Ipv4Addr::new(198, 19, 0, 1)),
+            40000 + connection_index as u16,
+            Protocol::Tcp,
+        ),
+        direction: if index % 2 == 0 {
+            Direction::Download
+        } else {
+            Direction::Upload
+        },
+        data_length: SELF_TEST_SEGMENT_LENGTH,
+        retransmitted: false,
+    }
+}
+
+/// How the counting of the captured traffic kept up with a --self-test
+#[derive(Clone, Debug)]
+pub struct SelfTestReport {
+    pub target_packets_per_second: u64,
+    pub elapsed: Duration,
+    /// The segments that came due at the target rate until the end
+    pub due: u64,
+    pub counted: u64,
+    /// The segments that came due while a `CAPTURE_BUFFER` of them was waiting to be counted
+    pub dropped: u64,
+    /// How long counting them took, the rest of the run went to waiting for more to come due
+    pub busy: Duration,
+}
+
+impl SelfTestReport {
+    pub fn achieved_packets_per_second(&self) -> u64 {
+        (self.counted as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)) as u64
+    }
+    /// What could be counted flat out, from the time it took to count what was
+    pub fn capacity_packets_per_second(&self) -> u64 {
+        (self.counted as f64 / self.busy.as_secs_f64().max(f64::EPSILON)) as u64
+    }
+    pub fn kept_up(&self) -> bool {
+        self.dropped == 0
+    }
+    pub fn lines(&self, raw: bool, json: bool) -> Vec<String> {
+        if json {
+            vec![format!(
+                "{{\"self_test\":{{\"target_pps\":{},\"achieved_pps\":{},\"capacity_pps\":{},\"due\":{},\"counted\":{},\"dropped\":{},\"seconds\":{:.2},\"kept_up\":{}}}}}",
+                self.target_packets_per_second,
+                self.achieved_packets_per_second(),
+                self.capacity_packets_per_second(),
+                self.due,
+                self.counted,
+                self.dropped,
+                self.elapsed.as_secs_f64(),
+                self.kept_up(),
+            )]
+        } else if raw {
+            vec![format!(
+                "self_test: target_pps: {} achieved_pps: {} capacity_pps: {} due: {} counted: {} dropped: {} seconds: {:.2}",
+                self.target_packets_per_second,
+                self.achieved_packets_per_second(),
+                self.capacity_packets_per_second(),
+                self.due,
+                self.counted,
+                self.dropped,
+                self.elapsed.as_secs_f64(),
+            )]
+        } else {
+            vec![
+                format!(
+                    "Self-test: {} packets/s of synthetic traffic for {:.2}s over {} connections",
+                    self.target_packets_per_second,
+                    self.elapsed.as_secs_f64(),
+                    SELF_TEST_CONNECTIONS
+                ),
+                format!(
+                    "Counted: {} of {} packets, {} packets/s",
+                    self.counted,
+                    self.due,
+                    self.achieved_packets_per_second()
+                ),
+                format!(
+                    "Capacity: about {} packets/s flat out",
+                    self.capacity_packets_per_second()
+                ),
+                if self.kept_up() {
+                    String::from("Dropped: none, it kept up")
+                } else {
+                    format!(
+                        "Dropped: about {} packets, it fell behind (--sample can help)",
+                        self.dropped
+                    )
+                },
+            ]
+        }
+    }
+}
+
+/// Counts synthetic segments at `packets_per_second` for `duration` the way the captured ones
+/// are, without capturing anything, to tell how fast this machine counts them (see --self-test)
+pub fn run_self_test(packets_per_second: u64, duration: Duration) -> SelfTestReport {
+    run_self_test_with(packets_per_second, duration, Instant::now, thread::sleep)
+}
+
+/// `run_self_test` on the clock of `now`, waiting for more to come due with `sleep`
+pub fn run_self_test_with(
+    packets_per_second: u64,
+    duration: Duration,
+    mut now: impl FnMut() -> Instant,
+    mut sleep: impl FnMut(Duration),
+) -> SelfTestReport {
+    let mut utilization = Utilization::new();
+    let buffer = (packets_per_second as f64 * CAPTURE_BUFFER.as_secs_f64()).ceil() as u64;
+    let mut counted = 0;
+    let mut dropped = 0;
+    let mut busy = Duration::from_secs(0);
+    let start_time = now();
+    let mut last_tick = start_time;
+    loop {
+        let elapsed = now().duration_since(start_time).min(duration);
+        let due = (packets_per_second as f64 * elapsed.as_secs_f64()) as u64;
+        // what is still waiting at the end would be counted after it, so it is not dropped
+        dropped += due.saturating_sub(counted + dropped + buffer);
+        if elapsed >= duration {
+            return SelfTestReport {
+                target_packets_per_second: packets_per_second,
+                elapsed,
+                due,
+                counted,
+                dropped,
+                busy,
+            };
+        }
+        let next = counted + dropped;
+        if next >= due {
+            sleep(PACING_GRAIN);
+            continue;
+        }
+        let batch_start = now();
+        let batch_end = due.min(next + SELF_TEST_BATCH);
+        for index in next..batch_end {
+            utilization.update(synthetic_segment(index));
+        }
+        counted += batch_end - next;
+        let batch_end_time = now();
+        if batch_end_time.duration_since(last_tick) >= SELF_TEST_TICK {
+            last_tick = batch_end_time;
+            utilization.clone_and_reset();
+        }
+        busy += batch_end_time.duration_since(batch_start);
+    }
+}
@@ -0,0 +1,77 @@
+use ::std::collections::HashMap;
+
+use chrono::prelude::*;
+use structopt::StructOpt;
+
+use crate::display::{ProcessEntry, RateAlert, Snapshot, UnitPrefix};
+use crate::Opt;
+
+fn snapshot_of_rates(rates: &[(&str, u128)], cumulative: bool) -> Snapshot {
+    Snapshot {
+        timestamp: Local.timestamp_opt(1_600_000_000, 0).unwrap(),
+        cumulative,
+        total_bytes_uploaded: 0,
+        total_bytes_downloaded: rates.iter().map(|&(_, rate)| rate).sum(),
+        processes: rates
+            .iter()
+            .map(|&(name, rate)| ProcessEntry {
+                name: String::from(name),
+                connection_count: 1,
+                total_bytes_uploaded: 0,
+                total_bytes_downloaded: rate,
+                pid_count: 0,
+            })
+            .collect(),
+        remote_addresses: Vec::new(),
+        connections: Vec::new(),
+        ip_to_host: HashMap::new(),
+    }
+}
+
+#[test]
+fn alerts_once_per_crossing_of_the_threshold() {
+    let mut rate_alert = RateAlert::new("5MB".parse().unwrap(), None, UnitPrefix::Si);
+    let mut check = |rate: u128| rate_alert.check(&snapshot_of_rates(&[("curl", rate)], false));
+
+    assert_eq!(
+        check(6_000_000),
+        vec![String::from(
+            "alert: <1600000000> \"curl\" went above 5.00MBps with 6.00MBps"
+        )]
+    );
+    assert!(check(7_000_000).is_empty());
+    assert!(check(1_000).is_empty());
+    assert_eq!(check(6_000_000).len(), 1);
+}
+
+#[test]
+fn alerts_only_on_the_watched_processes() {
+    let mut rate_alert = RateAlert::new(
+        "1KBps".parse().unwrap(),
+        Some(String::from("Fire")),
+        UnitPrefix::Binary,
+    );
+    let rates = [("firefox", 2_000), ("curl", 2_000)];
+
+    let alerts = rate_alert.check(&snapshot_of_rates(&rates, false));
+    assert_eq!(alerts.len(), 1);
+    assert!(alerts[0].contains("\"firefox\""));
+    // totals since launch are no rates
+    let mut rate_alert = RateAlert::new("1KBps".parse().unwrap(), None, UnitPrefix::Binary);
+    assert!(rate_alert
+        .check(&snapshot_of_rates(&rates, true))
+        .is_empty());
+}
+
+#[test]
+fn alert_process_needs_a_threshold() {
+    assert!(Opt::from_iter_safe(&["bandwhich", "--alert-process", "curl"]).is_err());
+    assert!(Opt::from_iter_safe(&[
+        "bandwhich",
+        "--alert-process",
+        "curl",
+        "--alert-threshold",
+        "5MB"
+    ])
+    .is_ok());
+}
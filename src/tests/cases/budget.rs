@@ -0,0 +1,112 @@
+use ::std::fs;
+use ::std::path::PathBuf;
+
+use ::chrono::prelude::*;
+
+use crate::display::{parse_budget, Budget};
+use crate::start;
+use crate::tests::cases::test_utils::{
+    opts_ui, os_input_output, sample_frames, test_backend_factory,
+};
+
+fn budget_path(name: &str) -> PathBuf {
+    ::std::env::temp_dir()
+        .join(format!(
+            "bandwhich-budget-{}-{}",
+            name,
+            ::std::process::id()
+        ))
+        .join("budget")
+}
+
+fn at(seconds: i64) -> DateTime<Local> {
+    Local.timestamp_opt(seconds, 0).single().unwrap()
+}
+
+const DAY: i64 = 24 * 60 * 60;
+
+#[test]
+fn budgets_are_parsed_in_powers_of_1000() {
+    assert_eq!(parse_budget("50GB"), Ok(50_000_000_000));
+    assert_eq!(parse_budget("1.5M"), Ok(1_500_000));
+    assert_eq!(parse_budget("2048"), Ok(2048));
+    assert!(parse_budget("50GiB").is_err());
+    assert!(parse_budget("0GB").is_err());
+}
+
+#[test]
+fn what_was_used_is_kept_until_the_period_is_over() {
+    let path = budget_path("period");
+    let start_time = at(1_588_000_000);
+    let mut budget = Budget::load(path.clone(), 1000, 30, start_time).unwrap();
+    budget.count(400, start_time + chrono::Duration::seconds(DAY));
+    budget.save().unwrap();
+
+    let budget = Budget::load(
+        path.clone(),
+        1000,
+        30,
+        start_time + chrono::Duration::seconds(29 * DAY),
+    )
+    .unwrap();
+    assert_eq!(budget.used, 400);
+    assert_eq!(budget.period_start, start_time);
+    // a period and a bit later, the next period started where the last one ended
+    let budget = Budget::load(
+        path.clone(),
+        1000,
+        30,
+        start_time + chrono::Duration::seconds(31 * DAY),
+    )
+    .unwrap();
+    assert_eq!(budget.used, 0);
+    assert_eq!(
+        budget.period_start,
+        start_time + chrono::Duration::seconds(30 * DAY)
+    );
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn counting_goes_on_over_a_session_reset_and_projects_the_pace() {
+    let start_time = at(1_588_000_000);
+    let mut budget = Budget::load(budget_path("unused"), 1000, 30, start_time).unwrap();
+    budget.count(100, start_time + chrono::Duration::seconds(DAY));
+    budget.count(300, start_time + chrono::Duration::seconds(2 * DAY));
+    // the lifetime counters started over with `z`
+    budget.restart_count();
+    budget.count(50, start_time + chrono::Duration::seconds(2 * DAY));
+    assert_eq!(budget.used, 350);
+    assert_eq!(budget.remaining(), 650);
+    assert!(!budget.running_low());
+
+    // 350 bytes in 2 days, so the other 650 last about 3.7 more days
+    let now = start_time + chrono::Duration::seconds(2 * DAY);
+    let exhaustion = budget.projected_exhaustion(now).unwrap();
+    assert_eq!((exhaustion - now).num_hours(), 89);
+
+    budget.count(950, now);
+    assert!(budget.running_low());
+    assert_eq!(budget.remaining(), 0);
+}
+
+#[test]
+fn the_budget_is_shown_and_saved_on_exit() {
+    let path = budget_path("run");
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(sample_frames(), 2);
+    let mut opts = opts_ui();
+    opts.budget = Some(parse_budget("50GB").unwrap());
+    opts.budget_period = 30;
+    opts.budget_file = Some(path.clone());
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror
+        .iter()
+        .any(|draw| draw.contains("Budget: ") && draw.contains("of 50.00GB used")));
+    let saved = fs::read_to_string(&path).unwrap();
+    assert!(saved.starts_with("period_start "));
+    assert!(!saved.contains("\nused 0\n"));
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
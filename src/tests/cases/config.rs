@@ -0,0 +1,103 @@
+use ::std::ffi::OsString;
+use ::std::fs;
+use ::std::path::PathBuf;
+
+use structopt::StructOpt;
+
+use crate::config::args_with_config;
+use crate::Opt;
+
+fn config_file(name: &str, contents: &str) -> PathBuf {
+    let path =
+        ::std::env::temp_dir().join(format!("bandwhich-{}-{}.toml", name, ::std::process::id()));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn args(args: &[&str]) -> Vec<OsString> {
+    args.iter().map(OsString::from).collect()
+}
+
+#[test]
+fn config_file_fills_in_what_the_command_line_leaves_out() {
+    let path = config_file(
+        "filled-in",
+        "# the defaults\nprocesses = true\ninterface = \"eth0\" # wired\nport = [80, 443]\nraw = false\nlog_max_size = 1024\n",
+    );
+    let (args, warnings) = args_with_config(
+        args(&[
+            "bandwhich",
+            "--config",
+            path.to_str().unwrap(),
+            "-i",
+            "wlan0",
+        ]),
+        None,
+    )
+    .unwrap();
+    fs::remove_file(&path).unwrap();
+    let opts = Opt::from_iter_safe(args).unwrap();
+
+    assert!(warnings.is_empty());
+    assert!(opts.render_opts.processes);
+    assert_eq!(opts.interface.as_deref(), Some("wlan0"));
+    assert_eq!(opts.render_opts.port, vec![80, 443]);
+    assert!(!opts.raw);
+    assert_eq!(opts.log_max_size, 1024);
+}
+
+#[test]
+fn unknown_keys_are_warned_about_and_ignored() {
+    let path = config_file("unknown-keys", "colour = 'always'\nprocesses = true\n");
+    let (args, warnings) = args_with_config(args(&["bandwhich"]), Some(path.clone())).unwrap();
+    let opts = Opt::from_iter_safe(args).unwrap();
+
+    assert_eq!(
+        warnings,
+        vec![format!(
+            "warning: unknown key 'colour' in {}, ignored",
+            path.display()
+        )]
+    );
+    assert!(opts.render_opts.processes);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn no_config_ignores_the_file() {
+    let path = config_file("ignored", "processes = true\n");
+    let (args, _) =
+        args_with_config(args(&["bandwhich", "--no-config"]), Some(path.clone())).unwrap();
+    fs::remove_file(&path).unwrap();
+    let opts = Opt::from_iter_safe(args).unwrap();
+
+    assert!(!opts.render_opts.processes);
+}
+
+#[test]
+fn only_a_missing_default_config_file_is_fine() {
+    let missing = ::std::env::temp_dir().join("bandwhich-there-is-no-such-config.toml");
+
+    assert!(args_with_config(args(&["bandwhich"]), Some(missing.clone())).is_ok());
+    assert!(args_with_config(
+        args(&["bandwhich", "--config", missing.to_str().unwrap()]),
+        None
+    )
+    .is_err());
+}
+
+#[test]
+fn invalid_config_files_are_refused() {
+    for &(name, contents) in &[
+        ("no-value", "processes\n"),
+        ("table", "[render]\nprocesses = true\n"),
+        ("unterminated", "interface = \"eth0\n"),
+        ("trailing", "interface = \"eth0\" eth1\n"),
+    ] {
+        let path = config_file(name, contents);
+        let result = args_with_config(args(&["bandwhich"]), Some(path.clone()));
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err(), "{}", name);
+    }
+}
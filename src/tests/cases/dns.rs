@@ -0,0 +1,110 @@
+use ::async_trait::async_trait;
+use ::std::net::IpAddr;
+use ::std::sync::Mutex;
+use ::std::{thread, time};
+use ::tokio::runtime::Runtime;
+use ::tokio::sync::oneshot;
+
+use crate::network::dns::{Client, DnsStats, IpTable, Lookup};
+use crate::parse_dns_server;
+
+struct GatedResolver {
+    slow_ip: IpAddr,
+    gate: Mutex<Option<oneshot::Receiver<()>>>,
+}
+
+#[async_trait]
+impl Lookup for GatedResolver {
+    async fn lookup(&self, ip: IpAddr) -> Option<String> {
+        if ip == self.slow_ip {
+            let gate = self.gate.lock().unwrap().take().unwrap();
+            gate.await.unwrap();
+            Some(String::from("slow.host"))
+        } else {
+            Some(String::from("fast.host"))
+        }
+    }
+}
+
+fn wait_for_cache(client: &mut Client, ready: impl Fn(&IpTable) -> bool) -> IpTable {
+    for _ in 0..50 {
+        let cache = client.cache();
+        if ready(&cache) {
+            return cache;
+        }
+        thread::sleep(time::Duration::from_millis(100));
+    }
+    panic!("dns cache was never updated");
+}
+
+#[test]
+fn names_are_cached_as_each_lookup_completes() {
+    let fast_ip: IpAddr = "1.1.1.1".parse().unwrap();
+    let slow_ip: IpAddr = "2.2.2.2".parse().unwrap();
+    let (open_gate, gate) = oneshot::channel();
+    let resolver = GatedResolver {
+        slow_ip,
+        gate: Mutex::new(Some(gate)),
+    };
+    let mut client = Client::new(resolver, Runtime::new().unwrap()).unwrap();
+
+    client.resolve(vec![slow_ip, fast_ip]);
+    let cache = wait_for_cache(&mut client, |cache| cache.contains_key(&fast_ip));
+    assert_eq!(cache.get(&fast_ip).unwrap(), "fast.host");
+    assert!(!cache.contains_key(&slow_ip));
+
+    open_gate.send(()).unwrap();
+    let cache = wait_for_cache(&mut client, |cache| cache.contains_key(&slow_ip));
+    assert_eq!(cache.get(&slow_ip).unwrap(), "slow.host");
+}
+
+#[test]
+fn stats_count_the_lookups_since_they_were_last_taken() {
+    let fast_ip: IpAddr = "1.1.1.1".parse().unwrap();
+    let slow_ip: IpAddr = "2.2.2.2".parse().unwrap();
+    let (open_gate, gate) = oneshot::channel();
+    let resolver = GatedResolver {
+        slow_ip,
+        gate: Mutex::new(Some(gate)),
+    };
+    let mut client = Client::new(resolver, Runtime::new().unwrap()).unwrap();
+
+    client.resolve(vec![slow_ip, fast_ip]);
+    wait_for_cache(&mut client, |cache| cache.contains_key(&fast_ip));
+    let stats = client.take_stats();
+    assert_eq!(stats.lookups, 1);
+    assert_eq!(stats.pending, 1);
+    assert!(stats.average.is_some());
+
+    thread::sleep(time::Duration::from_millis(100));
+    open_gate.send(()).unwrap();
+    wait_for_cache(&mut client, |cache| cache.contains_key(&slow_ip));
+    let stats = client.take_stats();
+    assert_eq!(stats.lookups, 1);
+    assert_eq!(stats.pending, 0);
+    assert!(stats.max.unwrap() >= time::Duration::from_millis(100));
+
+    assert_eq!(
+        client.take_stats(),
+        DnsStats {
+            lookups: 0,
+            average: None,
+            max: None,
+            pending: 0,
+        }
+    );
+}
+
+#[test]
+fn dns_server_defaults_to_port_53() {
+    assert_eq!(
+        parse_dns_server("10.0.0.1"),
+        Ok("10.0.0.1:53".parse().unwrap())
+    );
+    assert_eq!(
+        parse_dns_server("[::1]:5353"),
+        Ok("[::1]:5353".parse().unwrap())
+    );
+    assert!(parse_dns_server("dns.example.com").is_err());
+    assert!(parse_dns_server("10.0.0.1:dns").is_err());
+}
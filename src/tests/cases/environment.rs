@@ -0,0 +1,67 @@
+use ::std::collections::HashMap;
+
+use structopt::StructOpt;
+
+use crate::config::apply_env_flags;
+use crate::Opt;
+
+fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<String, String> = pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    move |name: &str| vars.get(name).cloned()
+}
+
+#[test]
+fn env_flags_turn_on_switches() {
+    let mut opts = Opt::default();
+    apply_env_flags(
+        &mut opts,
+        vars(&[("BANDWHICH_RAW", "1"), ("BANDWHICH_NO_RESOLVE", "True")]),
+    );
+
+    assert!(opts.raw);
+    assert!(opts.no_resolve);
+}
+
+#[test]
+fn env_flags_ignore_other_values() {
+    let mut opts = Opt::default();
+    apply_env_flags(
+        &mut opts,
+        vars(&[("BANDWHICH_RAW", "0"), ("BANDWHICH_NO_RESOLVE", "")]),
+    );
+
+    assert!(!opts.raw);
+    assert!(!opts.no_resolve);
+}
+
+#[test]
+fn no_color_turns_colors_off_unless_empty() {
+    let mut opts = Opt::default();
+    apply_env_flags(&mut opts, vars(&[("NO_COLOR", "")]));
+    assert!(!opts.render_opts.no_color);
+
+    apply_env_flags(&mut opts, vars(&[("NO_COLOR", "1")]));
+    assert!(opts.render_opts.no_color);
+}
+
+#[test]
+fn env_flags_do_not_override_the_command_line() {
+    let mut opts = Opt::from_iter(&["bandwhich", "--raw"]);
+    apply_env_flags(&mut opts, vars(&[("BANDWHICH_RAW", "false")]));
+
+    assert!(opts.raw);
+}
+
+#[test]
+fn interface_is_read_from_the_environment_unless_given() {
+    let mut from_env = Opt::from_iter(&["bandwhich"]);
+    apply_env_flags(&mut from_env, vars(&[("BANDWHICH_INTERFACE", "eth9")]));
+    let mut from_flag = Opt::from_iter(&["bandwhich", "--interface", "wlan0"]);
+    apply_env_flags(&mut from_flag, vars(&[("BANDWHICH_INTERFACE", "eth9")]));
+
+    assert_eq!(from_env.interface, Some(String::from("eth9")));
+    assert_eq!(from_flag.interface, Some(String::from("wlan0")));
+}
@@ -0,0 +1,108 @@
+use ::std::net::SocketAddr;
+use ::std::sync::{Arc, Mutex};
+
+use pnet_bandwhich_fork::datalink::DataLinkReceiver;
+use structopt::StructOpt;
+
+use crate::network::{CaptureFilter, Connection, Protocol};
+use crate::start;
+use crate::tests::cases::test_utils::{
+    build_tcp_packet, opts_raw, os_input_output_stdout, test_backend_factory,
+};
+use crate::tests::fakes::NetworkFrames;
+use crate::Opt;
+
+fn connection(remote: &str, local: &str) -> Connection {
+    let remote: SocketAddr = remote.parse().unwrap();
+    let local: SocketAddr = local.parse().unwrap();
+    Connection::new(remote, local.ip(), local.port(), Protocol::Tcp)
+}
+
+#[test]
+fn filter_terms_match_either_end_of_a_connection() {
+    let to_one = connection("1.1.1.1:443", "10.0.0.2:12345");
+    let to_lan = connection("192.168.1.7:22", "10.0.0.2:54321");
+
+    for &(expression, matches_one, matches_lan) in &[
+        ("host 1.1.1.1", true, false),
+        ("host 10.0.0.2", true, true),
+        ("net 192.168.0.0/16", false, true),
+        ("port 443", true, false),
+        ("port 54321", false, true),
+        ("host 10.0.0.2 and port 22", false, true),
+        ("port 443 or net 192.168.0.0/16", true, true),
+    ] {
+        let filter: CaptureFilter = expression.parse().unwrap();
+        assert_eq!(filter.matches(&to_one), matches_one, "{}", expression);
+        assert_eq!(filter.matches(&to_lan), matches_lan, "{}", expression);
+    }
+}
+
+#[test]
+fn and_goes_before_or() {
+    let to_one = connection("1.1.1.1:443", "10.0.0.2:12345");
+    let filter: CaptureFilter = "port 80 and host 1.1.1.1 or port 443".parse().unwrap();
+
+    assert!(filter.matches(&to_one));
+    assert_eq!(
+        filter,
+        CaptureFilter::Or(
+            Box::new(CaptureFilter::And(
+                Box::new(CaptureFilter::Port(80)),
+                Box::new(CaptureFilter::Host("1.1.1.1".parse().unwrap()))
+            )),
+            Box::new(CaptureFilter::Port(443))
+        )
+    );
+}
+
+#[test]
+fn invalid_filters_are_refused_at_startup() {
+    for &expression in &[
+        "",
+        "host",
+        "host example.com",
+        "net 10.0.0.0/33",
+        "port 70000",
+        "proto tcp",
+        "port 443 and",
+        "port 443 port 80",
+    ] {
+        assert!(
+            Opt::from_iter_safe(&["bandwhich", "--filter", expression]).is_err(),
+            "{}",
+            expression
+        );
+    }
+}
+
+#[test]
+fn traffic_not_matching_the_filter_is_not_counted() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2",
+            "10.0.0.2",
+            54321,
+            4434,
+            b"I am left out",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.filter = Some("host 1.1.1.1".parse().unwrap());
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let output = String::from_utf8(stdout).unwrap();
+
+    assert!(output.contains("1.1.1.1:12345"));
+    assert!(!output.contains("2.2.2.2"));
+}
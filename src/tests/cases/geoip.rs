@@ -0,0 +1,104 @@
+use ::std::fs;
+use ::std::net::IpAddr;
+
+use crate::network::GeoIpDatabase;
+use crate::start;
+use crate::tests::cases::test_utils::{
+    opts_ui, os_input_output, sample_frames, test_backend_factory,
+};
+
+fn text(text: &str) -> Vec<u8> {
+    let mut bytes = vec![2 << 5 | text.len() as u8];
+    bytes.extend_from_slice(text.as_bytes());
+    bytes
+}
+
+fn unsigned(data_type: u8, value: u32) -> Vec<u8> {
+    let mut bytes = vec![data_type << 5 | 4];
+    bytes.extend_from_slice(&value.to_be_bytes());
+    bytes
+}
+
+// an unsigned 64 bit integer, which takes the extended type
+fn unsigned_64(value: u64) -> Vec<u8> {
+    let mut bytes = vec![8, 9 - 7];
+    bytes.extend_from_slice(&value.to_be_bytes());
+    bytes
+}
+
+fn map(entries: Vec<(&str, Vec<u8>)>) -> Vec<u8> {
+    let mut bytes = vec![7 << 5 | entries.len() as u8];
+    for (key, value) in entries {
+        bytes.extend(text(key));
+        bytes.extend(value);
+    }
+    bytes
+}
+
+// an IPv4 database with 24 bit records where 0.0.0.0/1 is in Australia and AS13335, and the rest
+// is in no network
+fn country_and_asn_database() -> Vec<u8> {
+    let node_count = 1;
+    // the first record points at the start of the data section, the second one at the node count
+    let mut bytes = vec![0, 0, 17, 0, 0, 1];
+    bytes.extend_from_slice(&[0; 16]);
+    bytes.extend(map(vec![
+        ("country", map(vec![("iso_code", text("AU"))])),
+        ("autonomous_system_number", unsigned(6, 13335)),
+    ]));
+    bytes.extend_from_slice(b"\xab\xcd\xefMaxMind.com");
+    bytes.extend(map(vec![
+        ("node_count", unsigned(6, node_count)),
+        ("record_size", unsigned(5, 24)),
+        ("ip_version", unsigned(5, 4)),
+    ]));
+    bytes
+}
+
+#[test]
+fn annotations_of_an_address_in_a_network_of_the_database() {
+    let database = GeoIpDatabase::from_bytes(country_and_asn_database()).unwrap();
+    let ip = |ip: &str| ip.parse::<IpAddr>().unwrap();
+
+    assert_eq!(database.annotations(ip("1.1.1.1")), ["AU", "AS13335"]);
+    assert!(database.annotations(ip("200.0.0.1")).is_empty());
+    assert!(database.annotations(ip("::1")).is_empty());
+}
+
+#[test]
+fn not_a_maxmind_database() {
+    let err = GeoIpDatabase::from_bytes(b"I am not a database".to_vec())
+        .err()
+        .unwrap();
+    assert_eq!(err.to_string(), "not a MaxMind DB file (no metadata)");
+}
+
+#[test]
+fn a_node_count_out_of_range_is_a_corrupt_database() {
+    let mut bytes = b"\xab\xcd\xefMaxMind.com".to_vec();
+    bytes.extend(map(vec![
+        ("node_count", unsigned_64(u64::MAX / 2)),
+        ("record_size", unsigned(5, 24)),
+        ("ip_version", unsigned(5, 4)),
+    ]));
+    let err = GeoIpDatabase::from_bytes(bytes).err().unwrap();
+    assert_eq!(err.to_string(), "corrupt MaxMind DB file");
+}
+
+#[test]
+fn geo_adds_where_the_remote_addresses_are_to_their_table() {
+    let path =
+        ::std::env::temp_dir().join(format!("bandwhich-geoip-{}.mmdb", ::std::process::id()));
+    fs::write(&path, country_and_asn_database()).unwrap();
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(sample_frames(), 2);
+    let mut opts = opts_ui();
+    opts.geoip = vec![path.clone()];
+    opts.render_opts.geo = true;
+    start(backend, os_input, opts).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+    assert!(terminal_draw_events_mirror[0].contains("Location"));
+    assert!(terminal_draw_events_mirror[1].contains("AU AS13335"));
+}
@@ -0,0 +1,257 @@
+use ::std::collections::HashMap;
+use ::std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use ::std::sync::atomic::{AtomicUsize, Ordering};
+use ::std::sync::{Arc, Mutex};
+use ::std::time::{Duration, Instant};
+
+use ::termion::event::{Event, Key};
+use pnet_bandwhich_fork::datalink::DataLinkReceiver;
+
+use crate::display::UIState;
+use crate::network::{Connection, Direction, Protocol, Segment, Utilization};
+use crate::os::Ancestor;
+use crate::tests::cases::test_utils::{
+    build_tcp_packet, opts_raw, os_input_output_factory, os_input_output_stdout,
+    test_backend_factory,
+};
+use crate::tests::fakes::{
+    create_fake_dns_client, get_open_sockets, KeyboardEvents, NetworkFrames,
+};
+use crate::tick::{adaptive_tick_length, measured_window};
+use crate::{parse_interval, start, OpenSockets};
+
+fn state_after_window(bytes: u128, window: Duration) -> UIState {
+    let connection = Connection::new(
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)), 443),
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+        12345,
+        Protocol::Tcp,
+    );
+    let mut utilization = Utilization::new();
+    utilization.update(Segment {
+        interface_name: String::from("interface_name"),
+        connection,
+        direction: Direction::Download,
+        data_length: bytes,
+        retransmitted: false,
+    });
+    let mut state = UIState::default();
+    state.update(HashMap::new(), utilization, window);
+    state
+}
+
+#[test]
+fn rates_are_per_second_whatever_the_window() {
+    assert_eq!(
+        state_after_window(500, Duration::from_millis(500)).total_bytes_downloaded,
+        1000
+    );
+    assert_eq!(
+        state_after_window(500, Duration::from_secs(2)).total_bytes_downloaded,
+        250
+    );
+}
+
+#[test]
+fn a_long_rate_window_still_recalls_a_bounded_number_of_windows() {
+    let mut state = state_after_window(1_000_000, Duration::from_secs(1));
+    state.rate_window = Some(Duration::from_secs(24 * 60 * 60));
+    for _ in 0..1200 {
+        state.update(HashMap::new(), Utilization::new(), Duration::from_secs(1));
+    }
+    // the window with the traffic is the 1201st from the end, out of recall
+    assert_eq!(state.total_bytes_downloaded, 0);
+}
+
+#[test]
+fn interval_accepts_fractional_seconds() {
+    assert_eq!(parse_interval("0.5"), Ok(Duration::from_millis(500)));
+    assert_eq!(parse_interval("2"), Ok(Duration::from_secs(2)));
+    assert!(parse_interval("0").is_err());
+    assert!(parse_interval("-1").is_err());
+    assert!(parse_interval("soon").is_err());
+}
+
+#[test]
+fn windows_ignore_timer_jitter() {
+    let interval = Duration::from_millis(500);
+    assert_eq!(
+        measured_window(Duration::from_millis(508), interval),
+        interval
+    );
+    assert_eq!(
+        measured_window(Duration::from_millis(493), interval),
+        interval
+    );
+    assert_eq!(
+        measured_window(Duration::from_millis(2), interval),
+        Duration::from_millis(0)
+    );
+    assert_eq!(
+        measured_window(Duration::from_millis(260), interval),
+        Duration::from_millis(250)
+    );
+}
+
+#[test]
+fn adaptive_ticks_follow_the_traffic() {
+    let interval = Duration::from_secs(1);
+    assert_eq!(adaptive_tick_length(0, interval), Duration::from_secs(4));
+    assert_eq!(adaptive_tick_length(1500, interval), interval);
+    assert_eq!(
+        adaptive_tick_length(5_000_000, interval),
+        Duration::from_millis(250)
+    );
+    // the rates of a shortened tick are still per second
+    assert_eq!(
+        measured_window(Duration::from_millis(252), interval),
+        Duration::from_millis(250)
+    );
+}
+
+#[test]
+fn shorter_interval_ticks_more_often() {
+    let network_frames = vec![NetworkFrames::new(vec![Some(build_tcp_packet(
+        "10.0.0.2",
+        "1.1.1.1",
+        443,
+        12345,
+        b"I am a fake tcp packet",
+    ))]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.interval = Some(Duration::from_millis(250));
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let stdout = String::from_utf8(stdout).unwrap();
+
+    // the same two seconds hold about eight ticks instead of two
+    let ticks = stdout
+        .lines()
+        .filter(|line| line.starts_with("process:"))
+        .count();
+    assert!(ticks > 2, "only {} ticks in:\n{}", ticks, stdout);
+}
+
+#[test]
+fn traffic_while_paused_is_not_shown_after_resuming() {
+    // the packet arrives after half a second, while paused
+    let network_frames = vec![NetworkFrames::new(vec![Some(build_tcp_packet(
+        "10.0.0.2",
+        "1.1.1.1",
+        443,
+        12345,
+        b"I am a fake tcp packet",
+    ))]) as Box<dyn DataLinkReceiver>];
+    // pause right away, resume after about two seconds and quit two seconds later
+    let events = Box::new(KeyboardEvents::new(vec![
+        Some(Event::Key(Key::Char(' '))),
+        None,
+        None,
+        Some(Event::Key(Key::Char(' '))),
+        None,
+        None,
+        Some(Event::Key(Key::Ctrl('c'))),
+    ]));
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_factory(
+        network_frames,
+        Some(stdout.clone()),
+        create_fake_dns_client(HashMap::new()),
+        events,
+    );
+    start(backend, os_input, opts_raw()).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let stdout = String::from_utf8(stdout).unwrap();
+
+    assert!(
+        !stdout.lines().any(|line| line.starts_with("process:")),
+        "traffic from while paused in:\n{}",
+        stdout
+    );
+}
+
+static SOCKET_ENUMERATIONS: AtomicUsize = AtomicUsize::new(0);
+
+fn counted_get_open_sockets(ancestor: Option<&Ancestor>) -> OpenSockets {
+    SOCKET_ENUMERATIONS.fetch_add(1, Ordering::SeqCst);
+    get_open_sockets(ancestor)
+}
+
+#[test]
+fn sockets_are_looked_up_at_their_own_interval() {
+    let network_frames = vec![NetworkFrames::new(vec![Some(build_tcp_packet(
+        "10.0.0.2",
+        "1.1.1.1",
+        443,
+        12345,
+        b"I am a fake tcp packet",
+    ))]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let mut os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    os_input.get_open_sockets = counted_get_open_sockets;
+    let mut opts = opts_raw();
+    opts.interval = Some(Duration::from_millis(250));
+    opts.sockets_interval = Some(Duration::from_secs(60));
+    let start_time = Instant::now();
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let stdout = String::from_utf8(stdout).unwrap();
+
+    // every tick attributes its traffic with the sockets looked up before the first one
+    assert_eq!(SOCKET_ENUMERATIONS.load(Ordering::SeqCst), 1);
+    assert!(stdout.contains(" \"1\" up/down"), "{}", stdout);
+    // the lookup does not hold up exiting until its next turn
+    assert!(start_time.elapsed() < Duration::from_secs(30));
+}
+
+#[test]
+fn z_key_starts_the_session_over() {
+    let network_frames = vec![NetworkFrames::new(vec![Some(build_tcp_packet(
+        "10.0.0.2",
+        "1.1.1.1",
+        443,
+        12345,
+        b"I am a fake tcp packet",
+    ))]) as Box<dyn DataLinkReceiver>];
+    // reset after about two seconds and quit two seconds later
+    let events = Box::new(KeyboardEvents::new(vec![
+        None,
+        None,
+        Some(Event::Key(Key::Char('z'))),
+        None,
+        None,
+        Some(Event::Key(Key::Ctrl('c'))),
+    ]));
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_factory(
+        network_frames,
+        Some(stdout.clone()),
+        create_fake_dns_client(HashMap::new()),
+        events,
+    );
+    start(backend, os_input, opts_raw()).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let stdout = String::from_utf8(stdout).unwrap();
+    let last_line = |prefix: &str| stdout.lines().rev().find(|line| line.starts_with(prefix));
+
+    assert!(stdout.contains("up/down bytes: 42/0"), "{}", stdout);
+    assert!(
+        last_line("session:")
+            .unwrap()
+            .contains("up/down bytes: 0/0"),
+        "{}",
+        stdout
+    );
+    // the rates still average over the windows from before the reset
+    assert!(
+        !last_line("tick:").unwrap().contains("Bps: 0/0"),
+        "{}",
+        stdout
+    );
+}
@@ -0,0 +1,289 @@
+use crate::tests::fakes::{get_open_sockets, NetworkFrames};
+
+use ::insta::assert_snapshot;
+use ::std::collections::HashMap;
+use ::std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use ::std::sync::atomic::{AtomicBool, Ordering};
+use ::std::sync::{Arc, Mutex};
+use ::std::time::Duration;
+
+use chrono::Local;
+use pnet_bandwhich_fork::datalink::DataLinkReceiver;
+
+use crate::display::{
+    display_json_error, json_line, DataUnit, JsonTick, SessionTotals, Snapshot, UIState,
+    JSON_SCHEMA_VERSION,
+};
+use crate::network::{Connection, Direction, LocalSocket, Protocol, Segment, Utilization};
+use crate::os::StartupError;
+use crate::tests::cases::test_utils::{
+    build_tcp_packet, opts_raw, os_input_output_stdout, test_backend_factory,
+};
+use crate::{print_capabilities, print_open_connections, start, Opt};
+
+fn opts_json() -> Opt {
+    let mut opts = opts_raw();
+    opts.raw = false;
+    opts.json = true;
+    opts
+}
+
+fn format_json_output(output: Vec<u8>) -> String {
+    let stdout_utf8 = String::from_utf8(output).unwrap();
+    use regex::Regex;
+    let elapsed = Regex::new(r#""elapsed_seconds":\d+"#).unwrap();
+    let replaced = elapsed.replace_all(&stdout_utf8, r#""elapsed_seconds":ELAPSED_REMOVED"#);
+    format!("{}", replaced)
+}
+
+#[test]
+fn no_traffic_keeps_every_key() {
+    let network_frames = vec![NetworkFrames::new(vec![None]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 1, Some(stdout.clone()));
+    start(backend, os_input, opts_json()).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_json_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn one_packet_of_traffic() {
+    let network_frames = vec![NetworkFrames::new(vec![Some(build_tcp_packet(
+        "10.0.0.2",
+        "1.1.1.1",
+        443,
+        12345,
+        b"I am a fake tcp packet",
+    ))]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    start(backend, os_input, opts_json()).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_json_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn no_tables_only_has_the_totals() {
+    let network_frames = vec![NetworkFrames::new(vec![Some(build_tcp_packet(
+        "10.0.0.2",
+        "1.1.1.1",
+        443,
+        12345,
+        b"I am a fake tcp packet",
+    ))]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_json();
+    opts.render_opts.no_tables = true;
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_json_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn one_packet_of_traffic_in_bits() {
+    let network_frames = vec![NetworkFrames::new(vec![Some(build_tcp_packet(
+        "10.0.0.2",
+        "1.1.1.1",
+        443,
+        12345,
+        b"I am a fake tcp packet",
+    ))]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_json();
+    opts.render_opts.bits = true;
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_json_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn strings_are_escaped() {
+    assert_eq!(json_line(&"plain"), r#""plain""#);
+    assert_eq!(
+        json_line(&"say \"hi\"\\\n\u{1}"),
+        r#""say \"hi\"\\\n\u0001""#
+    );
+}
+
+#[test]
+fn startup_error_is_a_json_object() {
+    let err: failure::Error = StartupError::DnsResolver.into();
+    assert_eq!(
+        display_json_error(&err),
+        r#"{"schema_version":1,"error":{"code":"dns_resolver","message":"Could not initialize the DNS resolver. Are you offline?","hint":"pass --no-resolve to skip resolving hostnames"}}"#
+    );
+}
+
+#[test]
+fn once_lists_the_connections_as_the_ticks_do() {
+    let mut lines = Vec::new();
+    let mut opts = opts_json();
+    opts.render_opts.process = Some(String::from("1"));
+    print_open_connections(&get_open_sockets(None), &opts, &mut |line| lines.push(line));
+
+    assert_eq!(
+        lines,
+        vec![concat!(
+            r#"{"schema_version":1,"connections":["#,
+            r#"{"interface":null,"local_socket":{"ip":"10.0.0.2","port":443,"protocol":"tcp"},"remote_socket":{"ip":"1.1.1.1","port":12345,"host":null},"process":"1"},"#,
+            r#"{"interface":null,"local_socket":{"ip":"10.0.0.2","port":443,"protocol":"tcp"},"remote_socket":{"ip":"1.1.1.1","port":12346,"host":null},"process":"1"}],"#,
+            r#""listening_sockets":[{"ip":"0.0.0.0","port":443,"protocol":"tcp","process":"1"}]}"#
+        )]
+    );
+}
+
+#[test]
+fn capabilities_are_versioned_like_the_ticks() {
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let mut os_input = os_input_output_stdout(vec![], 1, Some(stdout.clone()));
+    os_input.uncaptured_interfaces = vec![(
+        String::from("docker0"),
+        String::from("down or without an address"),
+    )];
+    print_capabilities(&mut os_input, &opts_json());
+    drop(os_input);
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let output = String::from_utf8(stdout).unwrap();
+
+    // the version and the platform change with the build, the rest is up to the input
+    assert!(output.starts_with(r#"{"schema_version":1,"version":""#));
+    assert!(output.ends_with(concat!(
+        r#""dns":true,"interfaces":["#,
+        r#"{"name":"interface_name","captured":true,"promiscuous":true,"reason":null},"#,
+        r#"{"name":"docker0","captured":false,"promiscuous":false,"reason":"down or without an address"}]}"#,
+        "\n"
+    )));
+}
+
+#[test]
+fn the_schema_is_that_of_its_version() {
+    let local_ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+    let connection = Connection::new(
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)), 12345),
+        local_ip,
+        443,
+        Protocol::Tcp,
+    );
+    let mut utilization = Utilization::new();
+    utilization.update(Segment {
+        interface_name: String::from("eth0"),
+        connection,
+        direction: Direction::Upload,
+        data_length: 42,
+        retransmitted: false,
+    });
+    let mut connections_to_procs = HashMap::new();
+    connections_to_procs.insert(
+        LocalSocket {
+            ip: local_ip,
+            port: 443,
+            protocol: Protocol::Tcp,
+        },
+        String::from("curl"),
+    );
+    let mut state = UIState::default();
+    state.update(connections_to_procs, utilization, Duration::from_secs(1));
+    let mut ip_to_host = HashMap::new();
+    ip_to_host.insert(connection.remote_socket.ip, String::from("one.one.one.one"));
+    let snapshot = Snapshot::new(&state, &ip_to_host, Local::now());
+    let session_totals = SessionTotals {
+        bytes_uploaded: 63,
+        bytes_downloaded: 3072,
+        elapsed: Duration::from_secs(3),
+    };
+    let json = JsonTick {
+        snapshot: &snapshot,
+        session_totals: &session_totals,
+        elapsed_seconds: 3,
+        unit: DataUnit::Bytes,
+        count: usize::MAX,
+        totals_only: false,
+    }
+    .to_json();
+
+    // a field of a version is never renamed nor removed, a change of this file takes a new one
+    assert_eq!(JSON_SCHEMA_VERSION, 1);
+    assert_eq!(json, include_str!("golden/json_schema_v1.json").trim_end());
+}
+
+#[test]
+fn process_names_are_escaped_in_every_table() {
+    let local_ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+    let connection = Connection::new(
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)), 12345),
+        local_ip,
+        443,
+        Protocol::Tcp,
+    );
+    let mut utilization = Utilization::new();
+    utilization.update(Segment {
+        interface_name: String::from("eth0"),
+        connection,
+        direction: Direction::Upload,
+        data_length: 42,
+        retransmitted: false,
+    });
+    let mut connections_to_procs = HashMap::new();
+    // process names are whatever the executable was called, nothing keeps them printable
+    connections_to_procs.insert(
+        LocalSocket {
+            ip: local_ip,
+            port: 443,
+            protocol: Protocol::Tcp,
+        },
+        String::from("say \"hi\" C:\\tmp\tnow\u{7}\u{1f}"),
+    );
+    let mut state = UIState::default();
+    state.update(connections_to_procs, utilization, Duration::from_secs(1));
+    let snapshot = Snapshot::new(&state, &HashMap::new(), Local::now());
+    let session_totals = SessionTotals {
+        bytes_uploaded: 42,
+        bytes_downloaded: 0,
+        elapsed: Duration::from_secs(1),
+    };
+    let json = JsonTick {
+        snapshot: &snapshot,
+        session_totals: &session_totals,
+        elapsed_seconds: 1,
+        unit: DataUnit::Bytes,
+        count: usize::MAX,
+        totals_only: false,
+    }
+    .to_json();
+
+    let escaped = r#""say \"hi\" C:\\tmp\tnow\u0007\u001f""#;
+    assert!(json.contains(&format!("\"name\":{}", escaped)));
+    assert!(json.contains(&format!("\"process\":{}", escaped)));
+    // nothing is left unescaped that would end the string or the line early
+    assert!(!json.contains('\t'));
+    assert!(!json.contains('\u{7}'));
+    assert!(!json.contains('\u{1f}'));
+}
+
+#[test]
+fn a_panic_in_the_display_thread_still_cleans_up() {
+    let network_frames = vec![NetworkFrames::new(vec![None]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let mut os_input = os_input_output_stdout(network_frames, 1, None);
+    // panics while the display thread holds the locks of the ui and stdout
+    os_input.write_to_stdout = Box::new(|_| panic!("stdout went away"));
+    let cleaned_up = Arc::new(AtomicBool::new(false));
+    os_input.cleanup = Box::new({
+        let cleaned_up = cleaned_up.clone();
+        move || cleaned_up.store(true, Ordering::SeqCst)
+    });
+    let err = start(backend, os_input, opts_json()).unwrap_err();
+
+    assert_eq!(err.to_string(), "The display_handler thread panicked");
+    assert!(cleaned_up.load(Ordering::SeqCst));
+}
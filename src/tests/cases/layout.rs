@@ -0,0 +1,102 @@
+use ::std::fs;
+use ::std::iter;
+use ::std::path::PathBuf;
+
+use ::termion::event::{Event, Key};
+
+use crate::display::{LayoutState, SortBy};
+use crate::start;
+use crate::tests::cases::test_utils::{
+    opts_ui, os_input_output, os_input_output_factory, sample_frames, test_backend_factory,
+};
+use crate::tests::fakes::KeyboardEvents;
+
+fn layout_path(name: &str) -> PathBuf {
+    ::std::env::temp_dir()
+        .join(format!(
+            "bandwhich-layout-{}-{}",
+            name,
+            ::std::process::id()
+        ))
+        .join("layout")
+}
+
+fn default_layout() -> LayoutState {
+    LayoutState {
+        sort_by: SortBy::Total,
+        focused_table: 0,
+        session_bytes: false,
+        connection_history: false,
+    }
+}
+
+#[test]
+fn layout_changed_with_keys_is_saved_on_quit() {
+    let path = layout_path("saved");
+    // sleep for 1s, then sort by download, focus the second table, toggle the session column
+    // and the connection history, then quit
+    let mut events: Vec<Option<Event>> = iter::repeat(None).take(1).collect();
+    for key in &['s', '\t', 't', 'h'] {
+        events.push(Some(Event::Key(Key::Char(*key))));
+    }
+    events.push(None);
+    events.push(Some(Event::Key(Key::Ctrl('c'))));
+    let events = Box::new(KeyboardEvents::new(events));
+    let os_input = os_input_output_factory(sample_frames(), None, None, events);
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let mut opts = opts_ui();
+    opts.layout_file = Some(path.clone());
+    start(backend, os_input, opts).unwrap();
+
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "sort_by download\nfocused_table 1\nsession_bytes true\nconnection_history true\n"
+    );
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn saved_layout_is_restored_with_a_stale_focus_left_out() {
+    let path = layout_path("restored");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    // only the processes table is shown, so there is no seventh one to focus anymore
+    fs::write(&path, "sort_by upload\nfocused_table 6\nunknown line\n").unwrap();
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(sample_frames(), 2);
+    let mut opts = opts_ui();
+    opts.render_opts.processes = true;
+    opts.layout_file = Some(path.clone());
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror[0].contains("Up ▾ / Down"));
+    assert_eq!(
+        LayoutState::load(&path, default_layout()).unwrap(),
+        LayoutState {
+            sort_by: SortBy::Upload,
+            ..default_layout()
+        }
+    );
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn reset_layout_starts_from_the_options() {
+    let path = layout_path("reset");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, "sort_by upload\n").unwrap();
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(sample_frames(), 2);
+    let mut opts = opts_ui();
+    opts.layout_file = Some(path.clone());
+    opts.reset_layout = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(!terminal_draw_events_mirror[0].contains('▾'));
+    assert_eq!(
+        LayoutState::load(&path, default_layout()).unwrap(),
+        default_layout()
+    );
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
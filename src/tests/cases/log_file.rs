@@ -0,0 +1,208 @@
+use ::std::collections::HashMap;
+use ::std::fs;
+use ::std::path::PathBuf;
+
+use chrono::Local;
+
+use pnet_bandwhich_fork::datalink::DataLinkReceiver;
+
+use crate::display::{CsvHistory, LogFile, OutputTarget, ProcessEntry, Snapshot};
+use crate::start;
+use crate::tests::cases::test_utils::{
+    build_tcp_packet, opts_ui, os_input_output, test_backend_factory,
+};
+use crate::tests::fakes::NetworkFrames;
+
+fn log_path(name: &str) -> PathBuf {
+    ::std::env::temp_dir().join(format!("bandwhich-{}-{}", name, ::std::process::id()))
+}
+
+fn read_and_remove(path: &PathBuf) -> String {
+    let contents = fs::read_to_string(path).unwrap();
+    fs::remove_file(path).unwrap();
+    contents
+}
+
+#[test]
+fn log_file_is_rotated_once_it_grows_too_big() {
+    let path = log_path("rotated-log");
+    let rotated = |number: usize| PathBuf::from(format!("{}.{}", path.display(), number));
+    fs::write(&path, "from an earlier run\n").unwrap();
+
+    let mut log_file = LogFile::open(path.clone(), 30).unwrap();
+    log_file.write_line("first").unwrap();
+    log_file.write_line("second").unwrap();
+    log_file.write_line("third").unwrap();
+    log_file
+        .write_line("a line longer than the whole file may be")
+        .unwrap();
+    log_file.write_line("fourth").unwrap();
+
+    assert_eq!(read_and_remove(&rotated(3)), "from an earlier run\nfirst\n");
+    assert_eq!(read_and_remove(&rotated(2)), "second\nthird\n");
+    assert_eq!(
+        read_and_remove(&rotated(1)),
+        "a line longer than the whole file may be\n"
+    );
+    assert_eq!(read_and_remove(&path), "fourth\n");
+    assert!(!rotated(4).exists());
+}
+
+#[test]
+fn ui_mode_logs_every_tick_alongside_the_tables() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        None, // sleep
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"Same here, but one second later",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let path = log_path("ui-mode-log");
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 3);
+    let mut opts = opts_ui();
+    opts.log_file = Some(path.clone());
+    opts.log_max_size = 1024 * 1024;
+    start(backend, os_input, opts).unwrap();
+    let log = read_and_remove(&path);
+
+    assert_eq!(terminal_draw_events.lock().unwrap().len(), 3);
+    assert_eq!(log.matches("process: <").count(), 2);
+    assert!(log.contains(" \"1\" up/down Bps: 0/"));
+    assert!(log.contains("connection: <"));
+}
+
+#[test]
+fn each_output_file_gets_every_tick_in_its_own_format() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        None, // sleep
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"Same here, but one second later",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let raw_path = log_path("output-raw");
+    let json_path = PathBuf::from(format!("{}.json", log_path("output-json").display()));
+    let dropped_path = log_path("output-dropped");
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 3);
+    let mut opts = opts_ui();
+    opts.log_max_size = 1024 * 1024;
+    opts.output = vec![
+        OutputTarget::File(dropped_path.clone()),
+        OutputTarget::None,
+        OutputTarget::File(raw_path.clone()),
+        OutputTarget::File(json_path.clone()),
+    ];
+    start(backend, os_input, opts).unwrap();
+    let raw = read_and_remove(&raw_path);
+    let json = read_and_remove(&json_path);
+
+    assert_eq!(terminal_draw_events.lock().unwrap().len(), 3);
+    assert!(raw.contains("connection: <"));
+    assert_eq!(json.lines().count(), 3);
+    assert!(json
+        .lines()
+        .all(|line| line.starts_with("{\"schema_version\":1,\"elapsed_seconds\":")));
+    assert!(!dropped_path.exists());
+}
+
+#[test]
+fn outputs_are_parsed_by_kind() {
+    assert_eq!("stdout-json".parse(), Ok(OutputTarget::StdoutJson));
+    assert_eq!(
+        "socket:/run/bandwhich.sock".parse(),
+        Ok(OutputTarget::Socket(PathBuf::from("/run/bandwhich.sock")))
+    );
+    assert!("file:".parse::<OutputTarget>().is_err());
+    assert!("stderr".parse::<OutputTarget>().is_err());
+}
+
+#[test]
+fn csv_history_has_a_row_of_totals_per_tick() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        None, // sleep
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"Same here, but one second later",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let path = log_path("csv-history");
+    fs::write(&path, "from an earlier run\n").unwrap();
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 3);
+    let mut opts = opts_ui();
+    opts.csv_history = Some(path.clone());
+    start(backend, os_input, opts).unwrap();
+    let csv = read_and_remove(&path);
+    let lines: Vec<&str> = csv.lines().collect();
+
+    assert_eq!(lines[0], "timestamp,up,down");
+    assert_eq!(lines.len(), 4);
+    let row = regex::Regex::new(r"^\d{4}-\d{2}-\d{2}T[^,]+,\d+,\d+$").unwrap();
+    assert!(lines[1..].iter().all(|line| row.is_match(line)));
+}
+
+#[test]
+fn csv_history_per_process_quotes_the_names_that_need_it() {
+    let path = log_path("csv-history-per-process");
+    let process = |name: &str, uploaded: u128| ProcessEntry {
+        name: String::from(name),
+        connection_count: 1,
+        total_bytes_uploaded: uploaded,
+        total_bytes_downloaded: 0,
+        pid_count: 0,
+    };
+    let timestamp = Local::now();
+    let snapshot = Snapshot {
+        timestamp,
+        cumulative: false,
+        total_bytes_uploaded: 30,
+        total_bytes_downloaded: 0,
+        processes: vec![process("firefox", 10), process("sh -c \"a, b\"", 20)],
+        remote_addresses: vec![],
+        connections: vec![],
+        ip_to_host: HashMap::new(),
+    };
+    let mut csv_history = CsvHistory::create(path.clone(), true).unwrap();
+    csv_history.write_tick(&snapshot).unwrap();
+    drop(csv_history);
+
+    assert_eq!(
+        read_and_remove(&path),
+        format!(
+            "timestamp,process,up,down\n{0},firefox,10,0\n{0},\"sh -c \"\"a, b\"\"\",20,0\n",
+            timestamp.to_rfc3339()
+        )
+    );
+}
@@ -0,0 +1,122 @@
+use ::std::collections::HashMap;
+use ::std::io::{Read, Write};
+use ::std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use ::std::sync::atomic::{AtomicBool, Ordering};
+use ::std::sync::Arc;
+use ::std::thread;
+use ::std::time::Duration;
+
+use chrono::prelude::*;
+
+use crate::display::{prometheus_metrics, MetricsServer, SessionTotals, Snapshot, UIState};
+use crate::network::{Connection, Direction, LocalSocket, Protocol, Segment, Utilization};
+
+fn traffic() -> (HashMap<LocalSocket, String>, Utilization) {
+    let connection = Connection::new(
+        SocketAddr::new("1.1.1.1".parse().unwrap(), 443),
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+        12345,
+        Protocol::Tcp,
+    );
+    let mut utilization = Utilization::new();
+    utilization.update(Segment {
+        interface_name: String::from("interface_name"),
+        connection,
+        direction: Direction::Download,
+        data_length: 30,
+        retransmitted: false,
+    });
+    let mut connections_to_procs = HashMap::new();
+    connections_to_procs.insert(connection.local_socket, String::from("fire\"fox"));
+    (connections_to_procs, utilization)
+}
+
+fn ip_to_host() -> HashMap<IpAddr, String> {
+    let mut ip_to_host = HashMap::new();
+    ip_to_host.insert("1.1.1.1".parse().unwrap(), String::from("one.one.one.one"));
+    ip_to_host
+}
+
+#[test]
+fn metrics_are_labeled_by_process_host_and_direction() {
+    let mut state = UIState::default();
+    let (connections_to_procs, utilization) = traffic();
+    state.update(connections_to_procs, utilization, Duration::from_secs(1));
+    let snapshot = Snapshot::new(&state, &ip_to_host(), Local::now());
+    let session_totals = SessionTotals {
+        bytes_uploaded: 5,
+        bytes_downloaded: 90,
+        elapsed: Duration::from_secs(3),
+    };
+    let metrics = prometheus_metrics(
+        Some(&snapshot),
+        &state.launch_totals,
+        &ip_to_host(),
+        &session_totals,
+    );
+
+    assert!(metrics.contains("# TYPE bandwhich_process_bytes_total counter\n"));
+    assert!(metrics.contains(
+        "bandwhich_process_bytes_total{process=\"fire\\\"fox\",direction=\"down\"} 30\n"
+    ));
+    assert!(metrics.contains("# TYPE bandwhich_process_bytes_per_second gauge\n"));
+    assert!(metrics.contains(
+        "bandwhich_process_bytes_per_second{process=\"fire\\\"fox\",direction=\"down\"} 30\n"
+    ));
+    assert!(metrics.contains(
+        "bandwhich_connection_bytes_per_second{process=\"fire\\\"fox\",remote_host=\"one.one.one.one\",remote_port=\"443\",local_port=\"12345\",protocol=\"tcp\",direction=\"up\"} 0\n"
+    ));
+    assert!(metrics.contains("# TYPE bandwhich_bytes_total counter\n"));
+    assert!(metrics.contains("bandwhich_bytes_total{direction=\"down\"} 90\n"));
+}
+
+#[test]
+fn counters_only_go_up_whatever_the_tables_show() {
+    let mut state = UIState::default();
+    state.cumulative_mode = true;
+    for _ in 0..2 {
+        let (connections_to_procs, utilization) = traffic();
+        state.update(connections_to_procs, utilization, Duration::from_secs(1));
+    }
+    // a `z` starts the totals of the tables over, not the counters
+    state.reset_session();
+    let metrics = prometheus_metrics(
+        None,
+        &state.launch_totals,
+        &ip_to_host(),
+        &SessionTotals::default(),
+    );
+
+    assert!(metrics.contains(
+        "bandwhich_connection_bytes_total{process=\"fire\\\"fox\",remote_host=\"one.one.one.one\",remote_port=\"443\",local_port=\"12345\",protocol=\"tcp\",direction=\"down\"} 60\n"
+    ));
+    // the tables hold totals rather than rates
+    assert!(!metrics.contains("_bytes_per_second"));
+}
+
+#[test]
+fn metrics_server_answers_until_it_is_stopped() {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let address = listener.local_addr().unwrap();
+    let metrics_server = MetricsServer::new(listener).unwrap();
+    let running = Arc::new(AtomicBool::new(true));
+    let server = thread::spawn({
+        let running = running.clone();
+        move || metrics_server.serve(&running, || String::from("bandwhich_up 1\n"))
+    });
+    let get = |path: &str| {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+
+    let response = get("/metrics");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\nbandwhich_up 1\n"));
+    assert!(get("/").starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+    running.store(false, Ordering::Release);
+    server.join().unwrap();
+}
@@ -1,3 +1,23 @@
+pub mod alert;
+pub mod budget;
+pub mod config;
+pub mod dns;
+pub mod environment;
+pub mod filter;
+pub mod geoip;
+pub mod interval;
+pub mod json;
+pub mod layout;
+pub mod log_file;
+pub mod metrics;
+pub mod monitor;
+pub mod pcap;
 pub mod raw_mode;
+pub mod self_test;
+pub mod snapshot;
+pub mod sniffer;
+pub mod socket;
 pub mod test_utils;
+pub mod theme;
+pub mod tick;
 pub mod ui;
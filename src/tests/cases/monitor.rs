@@ -0,0 +1,42 @@
+use ::std::thread;
+use ::std::time::{Duration, Instant};
+
+use bandwhich::Monitor;
+use pnet_bandwhich_fork::datalink::DataLinkReceiver;
+
+use crate::tests::cases::test_utils::build_tcp_packet;
+use crate::tests::fakes::{get_interfaces, NetworkFrames};
+
+#[test]
+fn monitor_is_polled_for_what_was_captured_since() {
+    let network_frames = NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(build_tcp_packet(
+            "10.0.0.2",
+            "2.2.2.2",
+            443,
+            54321,
+            b"I am going to 2.2.2.2",
+        )),
+    ]) as Box<dyn DataLinkReceiver>;
+    let mut monitor = Monitor::from_frames(vec![(get_interfaces().remove(0), network_frames)]);
+
+    // the fake frames only come in after a while
+    let started = Instant::now();
+    let mut snapshot = monitor.poll();
+    while snapshot.connections.len() < 2 && started.elapsed() < Duration::from_secs(5) {
+        thread::sleep(Duration::from_millis(100));
+        snapshot = monitor.poll();
+    }
+
+    assert_eq!(snapshot.connections.len(), 2);
+    assert_eq!(snapshot.processes[0].name, "<UNKNOWN>");
+    assert!(snapshot.total_bytes_uploaded > 0);
+    assert!(snapshot.total_bytes_downloaded > 0);
+}
@@ -0,0 +1,111 @@
+use ::std::fs;
+use ::std::io::ErrorKind;
+use ::std::path::PathBuf;
+use ::std::sync::{Arc, Mutex};
+
+use pnet_bandwhich_fork::datalink::DataLinkReceiver;
+
+use crate::os::PcapFile;
+use crate::start;
+use crate::tests::cases::test_utils::{
+    build_tcp_packet, opts_raw, os_input_output_factory, test_backend_factory,
+};
+use crate::tests::fakes::{create_fake_dns_client, KeyboardEvents};
+
+fn pcap_path(name: &str) -> PathBuf {
+    ::std::env::temp_dir().join(format!("bandwhich-{}-{}.pcap", name, ::std::process::id()))
+}
+
+// a little endian pcap of ethernet frames, all captured at the same moment
+fn write_pcap(path: &PathBuf, packets: &[Vec<u8>]) {
+    let mut bytes = vec![];
+    bytes.extend_from_slice(&0xa1b2_c3d4u32.to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&4u16.to_le_bytes());
+    bytes.extend_from_slice(&[0; 8]);
+    bytes.extend_from_slice(&65535u32.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    for packet in packets {
+        bytes.extend_from_slice(&1_600_000_000u32.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(packet);
+    }
+    fs::write(path, bytes).unwrap();
+}
+
+#[test]
+fn pcap_file_is_read_packet_by_packet_until_its_end() {
+    let path = pcap_path("read");
+    let packets = vec![
+        build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        ),
+        build_tcp_packet("10.0.0.2", "2.2.2.2", 443, 54321, b"I am going to 2.2.2.2"),
+    ];
+    write_pcap(&path, &packets);
+
+    let mut pcap_file = PcapFile::open(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(pcap_file.next().unwrap(), &packets[0][..]);
+    assert_eq!(pcap_file.next().unwrap(), &packets[1][..]);
+    assert_eq!(
+        pcap_file.next().unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+fn a_file_that_is_not_a_pcap_is_refused() {
+    let path = pcap_path("not-a-pcap");
+    fs::write(&path, "a text file, long enough for a pcap header").unwrap();
+
+    let err = PcapFile::open(&path).err().unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn replaying_a_pcap_file_ends_at_its_end_with_a_summary() {
+    let path = pcap_path("replayed");
+    write_pcap(
+        &path,
+        &[
+            build_tcp_packet(
+                "1.1.1.1",
+                "10.0.0.2",
+                12345,
+                443,
+                b"I have come from 1.1.1.1",
+            ),
+            build_tcp_packet("10.0.0.2", "2.2.2.2", 443, 54321, b"I am going to 2.2.2.2"),
+        ],
+    );
+    let network_frames =
+        vec![Box::new(PcapFile::open(&path).unwrap()) as Box<dyn DataLinkReceiver>];
+    fs::remove_file(&path).unwrap();
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    // nobody quits, the end of the file does
+    let os_input = os_input_output_factory(
+        network_frames,
+        Some(stdout.clone()),
+        create_fake_dns_client(Default::default()),
+        Box::new(KeyboardEvents::new(vec![])),
+    );
+    let mut opts = opts_raw();
+    opts.read_pcap = Some(path);
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let output = String::from_utf8(stdout).unwrap();
+
+    // the whole file at once, as a --duration run would sum it up
+    assert_eq!(output.matches("tick: ").count(), 1);
+    assert!(output.contains("up/down Bps: 41/44 connections: 2"));
+    assert!(output.contains("session: "));
+}
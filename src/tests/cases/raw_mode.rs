@@ -1,21 +1,30 @@
-use crate::tests::fakes::{create_fake_dns_client, NetworkFrames};
+use crate::tests::fakes::{
+    create_fake_dns_client, get_interfaces, get_open_sockets, NetworkFrames,
+};
 
 use ::insta::assert_snapshot;
 use ::std::sync::{Arc, Mutex};
 
-use ::std::collections::HashMap;
-use ::std::net::IpAddr;
+use ::std::collections::{HashMap, HashSet};
+use ::std::net::{IpAddr, SocketAddr};
+use ::std::time::Duration;
 
 use packet_builder::payload::PayloadData;
 use packet_builder::*;
-use pnet_bandwhich_fork::datalink::DataLinkReceiver;
+use pnet_bandwhich_fork::datalink::{DataLinkReceiver, NetworkInterface};
 use pnet_bandwhich_fork::packet::Packet;
 
 use crate::tests::cases::test_utils::{
-    build_tcp_packet, opts_raw, os_input_output_dns, os_input_output_stdout, test_backend_factory,
+    build_tcp_packet, opts_raw, os_input_output_dns, os_input_output_stdout, sample_frames,
+    test_backend_factory,
 };
 
-use crate::{start, Opt, RenderOpts};
+use crate::display::{ConnectionDirection, Ui};
+use crate::network::{Connection, Direction, Protocol, Segment, Utilization};
+use crate::os::{select_interfaces, StartupError};
+use crate::{
+    display_raw_error, print_capabilities, print_open_connections, start, Opt, RenderOpts,
+};
 
 fn build_ip_tcp_packet(
     source_ip: &str,
@@ -55,7 +64,7 @@ fn one_ip_packet_of_traffic() {
     let stdout = Arc::new(Mutex::new(Vec::new()));
     let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
     let opts = opts_raw();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
     let formatted = format_raw_output(stdout);
     assert_snapshot!(formatted);
@@ -74,7 +83,7 @@ fn one_packet_of_traffic() {
     let stdout = Arc::new(Mutex::new(Vec::new()));
     let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
     let opts = opts_raw();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
     let formatted = format_raw_output(stdout);
     assert_snapshot!(formatted);
@@ -102,7 +111,7 @@ fn bi_directional_traffic() {
     let stdout = Arc::new(Mutex::new(Vec::new()));
     let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
     let opts = opts_raw();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
     let formatted = format_raw_output(stdout);
     assert_snapshot!(formatted);
@@ -130,7 +139,7 @@ fn multiple_packets_of_traffic_from_different_connections() {
     let stdout = Arc::new(Mutex::new(Vec::new()));
     let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
     let opts = opts_raw();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
     let formatted = format_raw_output(stdout);
     assert_snapshot!(formatted);
@@ -158,7 +167,7 @@ fn multiple_packets_of_traffic_from_single_connection() {
     let stdout = Arc::new(Mutex::new(Vec::new()));
     let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
     let opts = opts_raw();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
     let formatted = format_raw_output(stdout);
     assert_snapshot!(formatted);
@@ -186,7 +195,7 @@ fn one_process_with_multiple_connections() {
     let stdout = Arc::new(Mutex::new(Vec::new()));
     let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
     let opts = opts_raw();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
     let formatted = format_raw_output(stdout);
     assert_snapshot!(formatted);
@@ -228,7 +237,7 @@ fn multiple_processes_with_multiple_connections() {
     let stdout = Arc::new(Mutex::new(Vec::new()));
     let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
     let opts = opts_raw();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
     let formatted = format_raw_output(stdout);
     assert_snapshot!(formatted);
@@ -257,7 +266,7 @@ fn multiple_connections_from_remote_address() {
     let stdout = Arc::new(Mutex::new(Vec::new()));
     let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
     let opts = opts_raw();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
     let formatted = format_raw_output(stdout);
     assert_snapshot!(formatted);
@@ -287,7 +296,7 @@ fn sustained_traffic_from_one_process() {
     let stdout = Arc::new(Mutex::new(Vec::new()));
     let os_input = os_input_output_stdout(network_frames, 3, Some(stdout.clone()));
     let opts = opts_raw();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
     let formatted = format_raw_output(stdout);
     assert_snapshot!(formatted);
@@ -331,7 +340,7 @@ fn sustained_traffic_from_multiple_processes() {
     let stdout = Arc::new(Mutex::new(Vec::new()));
     let os_input = os_input_output_stdout(network_frames, 3, Some(stdout.clone()));
     let opts = opts_raw();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
     let formatted = format_raw_output(stdout);
     assert_snapshot!(formatted);
@@ -403,7 +412,7 @@ fn sustained_traffic_from_multiple_processes_bi_directional() {
     let os_input = os_input_output_stdout(network_frames, 3, Some(stdout.clone()));
 
     let opts = opts_raw();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
     let formatted = format_raw_output(stdout);
     assert_snapshot!(formatted);
@@ -488,7 +497,7 @@ fn traffic_with_host_names() {
     let stdout = Arc::new(Mutex::new(Vec::new()));
     let os_input = os_input_output_dns(network_frames, 3, Some(stdout.clone()), dns_client);
     let opts = opts_raw();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
     let formatted = format_raw_output(stdout);
     assert_snapshot!(formatted);
@@ -581,10 +590,856 @@ fn no_resolve_mode() {
             connections: false,
             processes: false,
             total_utilization: false,
+            ..Default::default()
         },
+        ..Default::default()
     };
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn exact_bytes_mode() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            &[b'a'; 1400],
+        )),
+        None, // sleep
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            &[b'b'; 1400],
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 3, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.total_utilization = true;
+    opts.render_opts.exact_bytes = true;
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn group_by_mode() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(build_tcp_packet(
+            "3.3.3.3",
+            "10.0.0.2",
+            1337,
+            4435,
+            b"Awesome, I'm from 3.3.3.3 and I have quite a bit more to say",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.group_by = Some("protocol".parse().unwrap());
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn process_filter_mode() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(build_tcp_packet(
+            "3.3.3.3",
+            "10.0.0.2",
+            1337,
+            4435,
+            b"Awesome, I'm from 3.3.3.3",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.process = Some(String::from("5"));
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn port_filter_mode() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2",
+            "10.0.0.2",
+            54321,
+            4434,
+            b"I have come from 2.2.2.2 to say a bit more",
+        )),
+        Some(build_tcp_packet(
+            "3.3.3.3",
+            "10.0.0.2",
+            1337,
+            4435,
+            b"Awesome, I'm from 3.3.3.3",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    // one matches a local port, the other a remote one
+    opts.render_opts.port = vec![443, 54321];
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn bits_mode() {
+    let network_frames = vec![NetworkFrames::new(vec![Some(build_tcp_packet(
+        "10.0.0.2",
+        "1.1.1.1",
+        443,
+        12345,
+        b"I am a fake tcp packet",
+    ))]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.bits = true;
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn packets_mode_counts_and_sorts_by_packets() {
+    // 1.1.1.1 sends the most bytes, 2.2.2.2 the most packets
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I am one big packet, bigger than the three small ones put together",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2", "10.0.0.2", 54321, 443, b"small",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2", "10.0.0.2", 54321, 443, b"small",
+        )),
+        Some(build_tcp_packet(
+            "10.0.0.2", "2.2.2.2", 443, 54321, b"small",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.packets = true;
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn packet_stats_mode() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "10.0.0.2",
+            "1.1.1.1",
+            443,
+            12345,
+            b"I am a fake tcp upload packet",
+        )),
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I am a fake tcp download packet",
+        )),
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"And so am I",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.packet_stats = true;
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn startup_error_is_machine_readable() {
+    let err: failure::Error = StartupError::InterfaceNotFound(String::from("eth9")).into();
+    assert_eq!(
+        display_raw_error(&err),
+        "error: code: interface_not_found message: \"Cannot find interface eth9\" hint: \"check the name against the interfaces listed by `ip link` or `ifconfig`\""
+    );
+}
+
+#[test]
+fn interface_can_be_a_comma_separated_list() {
+    let interfaces = ["eth0", "wg0", "docker0"]
+        .iter()
+        .map(|name| NetworkInterface {
+            name: name.to_string(),
+            ..get_interfaces().remove(0)
+        })
+        .collect::<Vec<_>>();
+    let names = |selected: Vec<NetworkInterface>| {
+        selected
+            .into_iter()
+            .map(|interface| interface.name)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names(select_interfaces("wg0,eth0", interfaces.clone()).unwrap()),
+        vec!["wg0", "eth0"]
+    );
+    assert_eq!(
+        names(select_interfaces("eth0, eth0,", interfaces.clone()).unwrap()),
+        vec!["eth0"]
+    );
+    assert_eq!(
+        select_interfaces("eth0,wg1", interfaces).unwrap_err(),
+        StartupError::InterfaceNotFound(String::from("wg1"))
+    );
+}
+
+#[test]
+fn unexpected_error_is_machine_readable() {
+    let err = failure::format_err!("\n\n something\n went wrong ");
+    assert_eq!(
+        display_raw_error(&err),
+        "error: code: other message: \"something\\n went wrong\""
+    );
+}
+
+#[test]
+fn dns_cache_is_loaded_and_saved() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(build_tcp_packet(
+            "3.3.3.3",
+            "10.0.0.2",
+            1337,
+            4435,
+            b"Awesome, I'm from 3.3.3.3",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let path = ::std::env::temp_dir().join(format!(
+        "bandwhich-dns-cache-raw-mode-{}",
+        ::std::process::id()
+    ));
+    ::std::fs::write(&path, "1.1.1.1 99999999999 cached.one\n").unwrap();
+    let mut ips_to_hosts = HashMap::new();
+    ips_to_hosts.insert(
+        "3.3.3.3".parse::<IpAddr>().unwrap(),
+        String::from("resolved.three"),
+    );
+    let dns_client = create_fake_dns_client(ips_to_hosts);
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_dns(network_frames, 3, Some(stdout.clone()), dns_client);
+    let opts = Opt {
+        raw: true,
+        dns_cache: Some(path.clone()),
+        dns_cache_ttl: 60,
+        ..Default::default()
+    };
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let stdout = String::from_utf8(stdout).unwrap();
+    let cache = ::std::fs::read_to_string(&path).unwrap();
+    ::std::fs::remove_file(&path).unwrap();
+
+    assert!(stdout.contains("remote_address: <"));
+    assert!(stdout.contains(" cached.one up/down"));
+    assert!(cache.starts_with("1.1.1.1 99999999999 cached.one\n3.3.3.3 "));
+    assert!(cache.ends_with(" resolved.three\n"));
+}
+
+#[test]
+fn duration_mode_prints_one_summary() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "10.0.0.2",
+            "1.1.1.1",
+            443,
+            12345,
+            b"I am a fake tcp upload packet",
+        )),
+        None, // sleep
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I am a fake tcp download packet",
+        )),
+        Some(build_tcp_packet(
+            "10.0.0.2",
+            "1.1.1.1",
+            443,
+            12345,
+            b"I am a fake tcp upload packet",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 3, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.duration = Some(2);
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn interface_breakdown_mode() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "10.0.0.2",
+            "1.1.1.1",
+            443,
+            12345,
+            b"I am a fake tcp upload packet",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2",
+            "10.0.0.2",
+            54321,
+            4434,
+            b"I am a fake tcp download packet",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.interface_breakdown = true;
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn interface_breakdown_shows_the_share_of_the_link_speed() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "10.0.0.2",
+            "1.1.1.1",
+            443,
+            12345,
+            b"I am a fake tcp upload packet",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2",
+            "10.0.0.2",
+            54321,
+            4434,
+            b"I am a fake tcp download packet",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let mut os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    // 1 kbps, so 49/51 Bps are 39.2%/40.8% of it
+    os_input
+        .link_speeds
+        .insert(String::from("interface_name"), 1_000);
+    let mut opts = opts_raw();
+    opts.render_opts.interface_breakdown = true;
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert!(formatted.contains("up/down Bps: 49/51 connections: 2 link: 39.2%/40.8%"));
+}
+
+#[test]
+fn alias_shows_an_interface_by_another_name() {
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(sample_frames(), 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.interface_breakdown = true;
+    opts.render_opts.alias = vec!["interface_name=lan".parse().unwrap()];
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert!(formatted.contains("interface: <TIMESTAMP_REMOVED> \"lan\""));
+    assert!(formatted.contains("connection: <TIMESTAMP_REMOVED> <lan>:443 => 1.1.1.1:12345"));
+    assert!(!formatted.contains("interface_name"));
+}
+
+#[test]
+fn no_tables_only_prints_the_totals() {
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(sample_frames(), 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.no_tables = true;
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn count_caps_the_rows_of_each_table() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "10.0.0.2",
+            "1.1.1.1",
+            443,
+            12345,
+            b"I am a fake tcp upload packet",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2",
+            "10.0.0.2",
+            54321,
+            4434,
+            b"I am a fake tcp download packet, a bit longer",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.count = 1;
+    start(backend, os_input, opts).unwrap();
     let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
     let formatted = format_raw_output(stdout);
     assert_snapshot!(formatted);
 }
+
+#[test]
+fn merge_connections_mode() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            4434,
+            b"So have I, to another process",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2",
+            "10.0.0.2",
+            54321,
+            4435,
+            b"I am left as I am",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.merge_connections = true;
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn listening_sockets_count_the_connections_they_accepted() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have been accepted",
+        )),
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12346,
+            443,
+            b"So have I, on the same port",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2",
+            "10.0.0.2",
+            54321,
+            4434,
+            b"I was not, nothing listens on my port",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.listening = true;
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn compact_output_is_one_line_per_tick() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(build_tcp_packet(
+            "10.0.0.2",
+            "2.2.2.2",
+            4434,
+            54321,
+            b"I am going to 2.2.2.2",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.compact = true;
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn tcp_segments_sent_again_are_counted_with_tcp_stats() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I might get lost on the way",
+        )),
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I might get lost on the way",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2",
+            "10.0.0.2",
+            54321,
+            4434,
+            b"I made it the first time",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.tcp_stats = true;
+    opts.render_opts.packet_stats = true;
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let formatted = format_raw_output(stdout);
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn capabilities_tell_which_interfaces_are_captured() {
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let mut os_input = os_input_output_stdout(vec![], 1, Some(stdout.clone()));
+    os_input.uncaptured_interfaces = vec![(
+        String::from("docker0"),
+        String::from("down or without an address"),
+    )];
+    print_capabilities(&mut os_input, &opts_raw());
+    drop(os_input);
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let output = String::from_utf8(stdout).unwrap();
+
+    // the version and the platform change with the build, the rest is up to the input
+    assert!(output.starts_with("capabilities: version: "));
+    assert!(output.contains(" dns: true\n"));
+    assert!(output.contains("interface: \"interface_name\" captured: true promiscuous: true\n"));
+    assert!(output.contains(
+        "interface: \"docker0\" captured: false promiscuous: false reason: \"down or without an address\"\n"
+    ));
+}
+
+#[test]
+fn direction_tells_connections_accepted_here_from_those_opened_from_here() {
+    let connections_opened = |direction: ConnectionDirection| {
+        let network_frames = vec![NetworkFrames::new(vec![
+            // to the socket listening on port 443
+            Some(build_tcp_packet(
+                "1.1.1.1",
+                "10.0.0.2",
+                12345,
+                443,
+                b"I have come from 1.1.1.1",
+            )),
+            Some(build_tcp_packet(
+                "10.0.0.2",
+                "2.2.2.2",
+                4434,
+                54321,
+                b"I am going to 2.2.2.2",
+            )),
+        ]) as Box<dyn DataLinkReceiver>];
+        let (_, _, backend) = test_backend_factory(190, 50);
+        let stdout = Arc::new(Mutex::new(Vec::new()));
+        let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+        let mut opts = opts_raw();
+        opts.render_opts.direction = direction;
+        start(backend, os_input, opts).unwrap();
+        let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+        String::from_utf8(stdout)
+            .unwrap()
+            .lines()
+            .filter(|line| line.starts_with("connection: "))
+            .map(String::from)
+            .collect::<Vec<String>>()
+    };
+
+    let inbound = connections_opened(ConnectionDirection::In);
+    assert_eq!(inbound.len(), 1);
+    assert!(inbound[0].contains(":443 => 1.1.1.1:12345"));
+    let outbound = connections_opened(ConnectionDirection::Out);
+    assert_eq!(outbound.len(), 1);
+    assert!(outbound[0].contains(":4434 => 2.2.2.2:54321"));
+    assert_eq!(connections_opened(ConnectionDirection::Both).len(), 2);
+}
+
+#[test]
+fn every_tick_starts_with_its_wall_clock_time() {
+    let time_lines = |epoch: bool| {
+        let network_frames = vec![NetworkFrames::new(vec![Some(build_tcp_packet(
+            "10.0.0.2",
+            "1.1.1.1",
+            443,
+            12345,
+            b"I am a fake tcp packet",
+        ))]) as Box<dyn DataLinkReceiver>];
+        let (_, _, backend) = test_backend_factory(190, 50);
+        let stdout = Arc::new(Mutex::new(Vec::new()));
+        let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+        let mut opts = opts_raw();
+        opts.render_opts.timestamp = true;
+        opts.render_opts.epoch = epoch;
+        start(backend, os_input, opts).unwrap();
+        let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+        let stdout = String::from_utf8(stdout).unwrap();
+        let lines: Vec<&str> = stdout.lines().collect();
+        // right after the format version, and before every tick
+        lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.starts_with("tick: "))
+            .map(|(index, _)| String::from(lines[index - 1]))
+            .collect::<Vec<String>>()
+    };
+
+    let rfc3339 =
+        regex::Regex::new(r"^time: \d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}[+-]\d{2}:\d{2}$").unwrap();
+    let rfc3339_lines = time_lines(false);
+    assert!(!rfc3339_lines.is_empty());
+    assert!(rfc3339_lines.iter().all(|line| rfc3339.is_match(line)));
+    let epoch = regex::Regex::new(r"^time: \d+$").unwrap();
+    let epoch_lines = time_lines(true);
+    assert!(!epoch_lines.is_empty());
+    assert!(epoch_lines.iter().all(|line| epoch.is_match(line)));
+}
+
+#[test]
+fn containers_go_after_the_names_of_the_processes_in_them() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I am not in a container",
+        )),
+        Some(build_tcp_packet(
+            "4.4.4.4",
+            "10.0.0.2",
+            1337,
+            4432,
+            b"I am in the web container",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(network_frames, 2, Some(stdout.clone()));
+    let mut opts = opts_raw();
+    opts.render_opts.containers = true;
+    start(backend, os_input, opts).unwrap();
+    let stdout = Arc::try_unwrap(stdout).unwrap().into_inner().unwrap();
+    let output = String::from_utf8(stdout).unwrap();
+
+    assert!(output.contains(" \"2 [web]\" up/down Bps: "));
+    assert!(output.contains(" \"1\" up/down Bps: "));
+    assert!(!output.contains("\"1 ["));
+}
+
+#[test]
+fn once_lists_the_open_connections_of_the_processes_let_through() {
+    let mut lines = Vec::new();
+    let mut opts = opts_raw();
+    opts.render_opts.process = Some(String::from("1"));
+    print_open_connections(&get_open_sockets(None), &opts, &mut |line| lines.push(line));
+
+    assert_eq!(
+        lines,
+        vec![
+            "connection: 10.0.0.2:443 => 1.1.1.1:12345 (tcp) process: \"1\"",
+            "connection: 10.0.0.2:443 => 1.1.1.1:12346 (tcp) process: \"1\"",
+            "listening: 0.0.0.0:443 (tcp) process: \"1\"",
+        ]
+    );
+}
+
+#[test]
+fn connections_carry_their_whole_five_tuple() {
+    let connections = vec![
+        Connection::new(
+            "1.1.1.1:12345".parse().unwrap(),
+            "10.0.0.2".parse().unwrap(),
+            443,
+            Protocol::Tcp,
+        ),
+        Connection::new(
+            "8.8.8.8:53".parse().unwrap(),
+            "10.0.0.2".parse().unwrap(),
+            5353,
+            Protocol::Udp,
+        ),
+        Connection::new(
+            "[2606:4700::1111]:443".parse().unwrap(),
+            "fd00::2".parse().unwrap(),
+            51000,
+            Protocol::Tcp,
+        ),
+        Connection::new(
+            "[2001:4860:4860::8888]:53".parse().unwrap(),
+            "fd00::2".parse().unwrap(),
+            5353,
+            Protocol::Udp,
+        ),
+    ];
+    let mut utilization = Utilization::new();
+    for connection in &connections {
+        utilization.update(Segment {
+            interface_name: String::from("interface_name"),
+            connection: *connection,
+            direction: Direction::Download,
+            data_length: 100,
+            retransmitted: false,
+        });
+    }
+    // the hostnames stand in for the addresses in the tables, not in the 5-tuples
+    let ip_to_host = connections
+        .iter()
+        .map(|connection| {
+            (
+                connection.remote_socket.ip,
+                String::from("resolved.example"),
+            )
+        })
+        .collect::<HashMap<IpAddr, String>>();
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let mut ui = Ui::new(backend, opts_raw().render_opts, HashSet::new());
+    ui.update_state(
+        HashMap::new(),
+        utilization,
+        ip_to_host,
+        Duration::from_secs(1),
+    );
+    let mut lines = Vec::new();
+    ui.output_text(&mut |line| lines.push(line));
+    ui.output_json(&mut |line| lines.push(line));
+    let (json, raw) = lines.split_last().unwrap();
+
+    for connection in &connections {
+        let local = &connection.local_socket;
+        let remote = &connection.remote_socket;
+        let raw_line = raw
+            .iter()
+            .find(|line| {
+                line.starts_with("connection: ")
+                    && line.contains(&format!(
+                        " local: {} remote: {}",
+                        SocketAddr::new(local.ip, local.port),
+                        SocketAddr::new(remote.ip, remote.port)
+                    ))
+            })
+            .unwrap();
+        assert!(raw_line.contains(&format!("({})", local.protocol)));
+        assert!(json.contains(&format!(
+            "\"local_socket\":{{\"ip\":\"{}\",\"port\":{},\"protocol\":\"{}\"}},\"remote_socket\":{{\"ip\":\"{}\",\"port\":{},",
+            local.ip, local.port, local.protocol, remote.ip, remote.port
+        )));
+    }
+}
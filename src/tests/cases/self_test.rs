@@ -0,0 +1,89 @@
+use ::std::cell::Cell;
+use ::std::time::{Duration, Instant};
+
+use structopt::StructOpt;
+
+use crate::self_test::{parse_self_test_rate, run_self_test_with, SelfTestReport};
+use crate::Opt;
+
+// a self-test on a clock that only moves by `cost` whenever it is looked at and by what is slept,
+// so it takes as long on any machine
+fn run_self_test_on_fake_clock(
+    packets_per_second: u64,
+    duration: Duration,
+    cost: Duration,
+) -> SelfTestReport {
+    let clock = Cell::new(Instant::now());
+    run_self_test_with(
+        packets_per_second,
+        duration,
+        || {
+            clock.set(clock.get() + cost);
+            clock.get()
+        },
+        |sleep| clock.set(clock.get() + sleep),
+    )
+}
+
+#[test]
+fn self_test_is_a_hidden_option_that_takes_a_rate() {
+    let opts = Opt::from_iter(vec!["bandwhich", "--self-test", "1000"]);
+    assert_eq!(opts.self_test, Some(1000));
+    assert!(parse_self_test_rate("0").is_err());
+    assert!(parse_self_test_rate("fast").is_err());
+    assert!(Opt::from_iter_safe(vec!["bandwhich", "--self-test", "1000", "--once"]).is_err());
+}
+
+#[test]
+fn a_rate_the_machine_keeps_up_with_is_counted_whole() {
+    let report =
+        run_self_test_on_fake_clock(1000, Duration::from_millis(200), Duration::from_micros(1));
+
+    assert_eq!(report.elapsed, Duration::from_millis(200));
+    assert_eq!(report.due, 200);
+    assert!(report.kept_up(), "{:?}", report);
+    assert!(report.counted >= 190, "{:?}", report);
+    assert!((900..=1000).contains(&report.achieved_packets_per_second()));
+    assert!(report.capacity_packets_per_second() >= report.achieved_packets_per_second());
+}
+
+#[test]
+fn a_rate_too_fast_to_count_drops_what_the_capture_cannot_buffer() {
+    // every batch takes a couple of milliseconds to count
+    let report = run_self_test_on_fake_clock(
+        10_000_000_000,
+        Duration::from_millis(300),
+        Duration::from_millis(1),
+    );
+
+    assert!(!report.kept_up());
+    // a tenth of a second of them is still buffered at the end
+    assert_eq!(report.due - report.counted - report.dropped, 1_000_000_000);
+    assert!(report.capacity_packets_per_second() < 10_000_000_000);
+}
+
+#[test]
+fn what_fell_further_behind_than_the_capture_buffer_is_reported_dropped() {
+    let report = SelfTestReport {
+        target_packets_per_second: 1000,
+        elapsed: Duration::from_secs(1),
+        due: 1000,
+        counted: 500,
+        dropped: 400,
+        busy: Duration::from_millis(500),
+    };
+
+    assert!(!report.kept_up());
+    assert_eq!(report.achieved_packets_per_second(), 500);
+    assert_eq!(report.capacity_packets_per_second(), 1000);
+    assert_eq!(
+        report.lines(true, false),
+        vec!["self_test: target_pps: 1000 achieved_pps: 500 capacity_pps: 1000 due: 1000 counted: 500 dropped: 400 seconds: 1.00"]
+    );
+    assert!(report.lines(false, true)[0]
+        .ends_with("\"dropped\":400,\"seconds\":1.00,\"kept_up\":false}}"));
+    assert_eq!(
+        report.lines(false, false).last().unwrap(),
+        "Dropped: about 400 packets, it fell behind (--sample can help)"
+    );
+}
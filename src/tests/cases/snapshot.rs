@@ -0,0 +1,752 @@
+use ::std::collections::HashMap;
+use ::std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use ::std::sync::mpsc;
+use ::std::time::Duration;
+
+use chrono::prelude::*;
+
+use crate::display::{
+    GroupBy, GroupEntry, ProcessEntry, SessionTotals, Snapshot, SortBy, UIState, UNKNOWN_PROCESS,
+};
+use crate::network::dns::registrable_domain;
+use crate::network::{
+    CaptureEvent, CaptureStats, Connection, Direction, Protocol, Segment, Utilization,
+};
+
+fn local_ip() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2))
+}
+
+fn connection(remote_ip: &str, local_port: u16) -> Connection {
+    Connection::new(
+        SocketAddr::new(remote_ip.parse().unwrap(), 443),
+        local_ip(),
+        local_port,
+        Protocol::Tcp,
+    )
+}
+
+fn segment(connection: Connection, direction: Direction, data_length: u128) -> Segment {
+    Segment {
+        interface_name: String::from("interface_name"),
+        connection,
+        direction,
+        data_length,
+        retransmitted: false,
+    }
+}
+
+fn state_with_traffic(cumulative_mode: bool) -> UIState {
+    let mut utilization = Utilization::new();
+    utilization.update(segment(connection("1.1.1.1", 12345), Direction::Upload, 10));
+    utilization.update(segment(
+        connection("1.1.1.1", 12345),
+        Direction::Download,
+        30,
+    ));
+    utilization.update(segment(
+        connection("2.2.2.2", 54321),
+        Direction::Download,
+        100,
+    ));
+    let mut connections_to_procs = HashMap::new();
+    connections_to_procs.insert(
+        connection("1.1.1.1", 12345).local_socket,
+        String::from("curl"),
+    );
+    connections_to_procs.insert(
+        connection("2.2.2.2", 54321).local_socket,
+        String::from("curl"),
+    );
+    let mut state = UIState::default();
+    state.cumulative_mode = cumulative_mode;
+    state.update(connections_to_procs, utilization, Duration::from_secs(1));
+    state
+}
+
+#[test]
+fn snapshot_holds_totals_and_entries_of_the_tick() {
+    let state = state_with_traffic(false);
+    let timestamp = Local::now();
+    let snapshot = Snapshot::new(&state, &HashMap::new(), timestamp);
+
+    assert_eq!(snapshot.timestamp, timestamp);
+    assert!(!snapshot.cumulative);
+    assert_eq!(snapshot.total_bytes_uploaded, 10);
+    assert_eq!(snapshot.total_bytes_downloaded, 130);
+    assert_eq!(
+        snapshot.processes,
+        vec![ProcessEntry {
+            name: String::from("curl"),
+            connection_count: 2,
+            total_bytes_uploaded: 10,
+            total_bytes_downloaded: 130,
+            pid_count: 0,
+        }]
+    );
+    assert_eq!(snapshot.remote_addresses.len(), 2);
+    assert_eq!(snapshot.connections.len(), 2);
+}
+
+#[test]
+fn traffic_of_connections_missing_from_the_socket_table_is_counted_as_unknown() {
+    let mut utilization = Utilization::new();
+    utilization.update(segment(
+        connection("1.1.1.1", 12345),
+        Direction::Download,
+        30,
+    ));
+    utilization.update(segment(connection("2.2.2.2", 54321), Direction::Upload, 70));
+    utilization.update(segment(
+        connection("2.2.2.2", 54321),
+        Direction::Download,
+        100,
+    ));
+    let mut connections_to_procs = HashMap::new();
+    connections_to_procs.insert(
+        connection("1.1.1.1", 12345).local_socket,
+        String::from("curl"),
+    );
+    let mut state = UIState::default();
+    state.update(connections_to_procs, utilization, Duration::from_secs(1));
+    let snapshot = Snapshot::new(&state, &HashMap::new(), Local::now());
+
+    assert_eq!(
+        snapshot.processes,
+        vec![
+            ProcessEntry {
+                name: String::from(UNKNOWN_PROCESS),
+                connection_count: 1,
+                total_bytes_uploaded: 70,
+                total_bytes_downloaded: 100,
+                pid_count: 0,
+            },
+            ProcessEntry {
+                name: String::from("curl"),
+                connection_count: 1,
+                total_bytes_uploaded: 0,
+                total_bytes_downloaded: 30,
+                pid_count: 0,
+            }
+        ]
+    );
+    let process_totals = snapshot.processes.iter().fold((0, 0), |(up, down), entry| {
+        (
+            up + entry.total_bytes_uploaded,
+            down + entry.total_bytes_downloaded,
+        )
+    });
+    assert_eq!(
+        process_totals,
+        (
+            snapshot.total_bytes_uploaded,
+            snapshot.total_bytes_downloaded
+        )
+    );
+}
+
+#[test]
+fn snapshot_entries_are_sorted_by_bandwidth() {
+    let snapshot = Snapshot::new(&state_with_traffic(false), &HashMap::new(), Local::now());
+
+    let remote_ips: Vec<IpAddr> = snapshot
+        .remote_addresses
+        .iter()
+        .map(|entry| entry.ip)
+        .collect();
+    assert_eq!(
+        remote_ips,
+        vec![
+            "2.2.2.2".parse::<IpAddr>().unwrap(),
+            "1.1.1.1".parse::<IpAddr>().unwrap()
+        ]
+    );
+    assert_eq!(
+        snapshot.connections[0].connection,
+        connection("2.2.2.2", 54321)
+    );
+    assert_eq!(snapshot.connections[0].process_name, "curl");
+    assert_eq!(snapshot.connections[0].interface_name, "interface_name");
+    assert_eq!(snapshot.connections[0].total_packets, 1);
+    assert_eq!(snapshot.connections[1].total_packets, 2);
+}
+
+#[test]
+fn snapshot_only_keeps_hostnames_of_its_own_addresses() {
+    let mut ip_to_host = HashMap::new();
+    ip_to_host.insert(
+        "1.1.1.1".parse::<IpAddr>().unwrap(),
+        String::from("one.one.one.one"),
+    );
+    ip_to_host.insert(
+        "3.3.3.3".parse::<IpAddr>().unwrap(),
+        String::from("not.in.this.tick"),
+    );
+    let snapshot = Snapshot::new(&state_with_traffic(false), &ip_to_host, Local::now());
+
+    let mut expected_ip_to_host = HashMap::new();
+    expected_ip_to_host.insert(
+        "1.1.1.1".parse::<IpAddr>().unwrap(),
+        String::from("one.one.one.one"),
+    );
+    assert_eq!(snapshot.ip_to_host, expected_ip_to_host);
+}
+
+#[test]
+fn snapshot_carries_cumulative_mode() {
+    let snapshot = Snapshot::new(&state_with_traffic(true), &HashMap::new(), Local::now());
+
+    assert!(snapshot.cumulative);
+    assert_eq!(snapshot.total_bytes_downloaded, 130);
+}
+
+#[test]
+fn group_by_sums_the_connections_sharing_a_key() {
+    let snapshot = Snapshot::new(&state_with_traffic(false), &HashMap::new(), Local::now());
+    let group_by: GroupBy = "interface,process".parse().unwrap();
+
+    assert_eq!(
+        group_by.group(&snapshot, SortBy::default()),
+        vec![GroupEntry {
+            key: vec![String::from("interface_name"), String::from("curl")],
+            connection_count: 2,
+            total_bytes_uploaded: 10,
+            total_bytes_downloaded: 130,
+        }]
+    );
+}
+
+#[test]
+fn group_by_keeps_distinct_keys_apart() {
+    let mut ip_to_host = HashMap::new();
+    ip_to_host.insert(
+        "1.1.1.1".parse::<IpAddr>().unwrap(),
+        String::from("one.one.one.one"),
+    );
+    let snapshot = Snapshot::new(&state_with_traffic(false), &ip_to_host, Local::now());
+    let group_by: GroupBy = "remote-address,protocol".parse().unwrap();
+
+    let keys: Vec<Vec<String>> = group_by
+        .group(&snapshot, SortBy::default())
+        .into_iter()
+        .map(|group| group.key)
+        .collect();
+    assert_eq!(
+        keys,
+        vec![
+            vec![String::from("2.2.2.2"), String::from("tcp")],
+            vec![String::from("one.one.one.one"), String::from("tcp")],
+        ]
+    );
+}
+
+#[test]
+fn group_by_user_groups_by_who_the_sockets_belong_to() {
+    let mut state = state_with_traffic(false);
+    let mut sockets_to_procs = HashMap::new();
+    sockets_to_procs.insert(
+        connection("1.1.1.1", 12345).local_socket,
+        String::from("curl"),
+    );
+    let mut sockets_to_users = HashMap::new();
+    sockets_to_users.insert(
+        connection("1.1.1.1", 12345).local_socket,
+        String::from("alice"),
+    );
+    state.update_process_users(&sockets_to_procs, &sockets_to_users);
+    let snapshot = Snapshot::new(&state, &HashMap::new(), Local::now());
+    let group_by: GroupBy = "interface,user".parse().unwrap();
+
+    assert_eq!(
+        group_by.group(&snapshot, SortBy::default()),
+        vec![
+            GroupEntry {
+                key: vec![String::from("interface_name"), String::from("<UNKNOWN>")],
+                connection_count: 1,
+                total_bytes_uploaded: 0,
+                total_bytes_downloaded: 100,
+            },
+            GroupEntry {
+                key: vec![String::from("interface_name"), String::from("alice")],
+                connection_count: 1,
+                total_bytes_uploaded: 10,
+                total_bytes_downloaded: 30,
+            },
+        ]
+    );
+}
+
+#[test]
+fn group_by_rejects_unknown_or_repeated_keys() {
+    assert!("process,colour".parse::<GroupBy>().is_err());
+    assert!("process,process".parse::<GroupBy>().is_err());
+}
+
+#[test]
+fn processes_sharing_a_name_are_one_row_with_their_pid_count() {
+    let mut utilization = Utilization::new();
+    for (remote_ip, local_port) in &[("1.1.1.1", 12345), ("2.2.2.2", 12346), ("3.3.3.3", 12347)] {
+        utilization.update(segment(
+            connection(remote_ip, *local_port),
+            Direction::Download,
+            30,
+        ));
+    }
+    let mut sockets_to_procs = HashMap::new();
+    let mut sockets_to_pids = HashMap::new();
+    for (local_port, process_name, pid) in &[
+        (12345, "firefox", 100),
+        (12346, "firefox", 101),
+        (12347, "curl", 200),
+    ] {
+        let local_socket = connection("1.1.1.1", *local_port).local_socket;
+        sockets_to_procs.insert(local_socket, String::from(*process_name));
+        sockets_to_pids.insert(local_socket, *pid);
+    }
+    let mut state = UIState::default();
+    state.update_process_pid_counts(&sockets_to_procs, &sockets_to_pids);
+    state.update(sockets_to_procs, utilization, Duration::from_secs(1));
+    let snapshot = Snapshot::new(&state, &HashMap::new(), Local::now());
+
+    assert_eq!(snapshot.total_bytes_downloaded, 90);
+    let rows: Vec<(String, u128)> = snapshot
+        .processes
+        .iter()
+        .map(|entry| (entry.display_name(), entry.total_bytes_downloaded))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (String::from("firefox (×2)"), 60),
+            (String::from("curl (×1)"), 30)
+        ]
+    );
+}
+
+#[test]
+fn process_filter_leaves_out_the_traffic_of_other_processes() {
+    let mut utilization = Utilization::new();
+    utilization.update(segment(
+        connection("1.1.1.1", 12345),
+        Direction::Download,
+        30,
+    ));
+    utilization.update(segment(
+        connection("2.2.2.2", 54321),
+        Direction::Download,
+        100,
+    ));
+    let mut connections_to_procs = HashMap::new();
+    connections_to_procs.insert(
+        connection("1.1.1.1", 12345).local_socket,
+        String::from("Firefox"),
+    );
+    connections_to_procs.insert(
+        connection("2.2.2.2", 54321).local_socket,
+        String::from("curl"),
+    );
+    let mut state = UIState::default();
+    state.process_filter = Some(String::from("fire"));
+    state.update(connections_to_procs, utilization, Duration::from_secs(1));
+    let snapshot = Snapshot::new(&state, &HashMap::new(), Local::now());
+
+    assert_eq!(snapshot.total_bytes_downloaded, 30);
+    assert_eq!(
+        snapshot.processes,
+        vec![ProcessEntry {
+            name: String::from("Firefox"),
+            connection_count: 1,
+            total_bytes_uploaded: 0,
+            total_bytes_downloaded: 30,
+            pid_count: 0,
+        }]
+    );
+    assert_eq!(snapshot.remote_addresses.len(), 1);
+    assert_eq!(
+        snapshot.remote_addresses[0].ip,
+        connection("1.1.1.1", 0).remote_socket.ip
+    );
+    assert_eq!(snapshot.connections.len(), 1);
+}
+
+#[test]
+fn excluded_processes_are_left_out_even_if_they_match_the_process_filter() {
+    let mut utilization = Utilization::new();
+    for &(remote_ip, local_port, data_length) in &[
+        ("1.1.1.1", 12345, 30),
+        ("2.2.2.2", 54321, 100),
+        ("3.3.3.3", 4434, 7),
+    ] {
+        utilization.update(segment(
+            connection(remote_ip, local_port),
+            Direction::Download,
+            data_length,
+        ));
+    }
+    let mut connections_to_procs = HashMap::new();
+    for &(remote_ip, local_port, process_name) in &[
+        ("1.1.1.1", 12345, "firefox"),
+        ("2.2.2.2", 54321, "NetworkManager"),
+        ("3.3.3.3", 4434, "firefox-updater"),
+    ] {
+        connections_to_procs.insert(
+            connection(remote_ip, local_port).local_socket,
+            String::from(process_name),
+        );
+    }
+    let mut state = UIState::default();
+    state.process_filter = Some(String::from("fire"));
+    state.process_excludes = vec![String::from("networkmanager"), String::from("updater")];
+    state.update(connections_to_procs, utilization, Duration::from_secs(1));
+    let snapshot = Snapshot::new(&state, &HashMap::new(), Local::now());
+
+    assert_eq!(snapshot.total_bytes_downloaded, 30);
+    assert_eq!(snapshot.processes.len(), 1);
+    assert_eq!(snapshot.processes[0].name, "firefox");
+    assert_eq!(snapshot.connections.len(), 1);
+}
+
+#[test]
+fn sort_by_orders_every_table() {
+    let mut state = state_with_traffic(false);
+    state.sort_by = SortBy::Upload;
+    let mut utilization = Utilization::new();
+    utilization.update(segment(connection("1.1.1.1", 12345), Direction::Upload, 10));
+    utilization.update(segment(
+        connection("2.2.2.2", 54321),
+        Direction::Download,
+        100,
+    ));
+    state.update(HashMap::new(), utilization, Duration::from_secs(1));
+    let snapshot = Snapshot::new(&state, &HashMap::new(), Local::now());
+    assert_eq!(
+        snapshot.connections[0].connection,
+        connection("1.1.1.1", 12345)
+    );
+
+    state.sort_by = SortBy::Name;
+    state.update(HashMap::new(), Utilization::new(), Duration::from_secs(1));
+    let snapshot = Snapshot::new(&state, &HashMap::new(), Local::now());
+    let remote_ips: Vec<IpAddr> = snapshot
+        .remote_addresses
+        .iter()
+        .map(|entry| entry.ip)
+        .collect();
+    assert_eq!(
+        remote_ips,
+        vec![
+            "1.1.1.1".parse::<IpAddr>().unwrap(),
+            "2.2.2.2".parse::<IpAddr>().unwrap()
+        ]
+    );
+}
+
+#[test]
+fn sort_by_parses_and_cycles() {
+    assert_eq!("download".parse::<SortBy>(), Ok(SortBy::Download));
+    assert!("colour".parse::<SortBy>().is_err());
+    let mut sort_by = SortBy::default();
+    for _ in 0..5 {
+        sort_by = sort_by.next();
+    }
+    assert_eq!(sort_by, SortBy::default());
+}
+
+#[test]
+fn matching_keeps_the_rows_that_contain_the_query() {
+    let mut ip_to_host = HashMap::new();
+    ip_to_host.insert(
+        "1.1.1.1".parse::<IpAddr>().unwrap(),
+        String::from("one.one.one.one"),
+    );
+    let snapshot = Snapshot::new(&state_with_traffic(false), &ip_to_host, Local::now());
+
+    let matching = snapshot.matching("ONE.one");
+    assert!(matching.processes.is_empty());
+    assert_eq!(matching.remote_addresses.len(), 1);
+    assert_eq!(
+        matching.connections[0].connection,
+        connection("1.1.1.1", 12345)
+    );
+    assert_eq!(matching.total_bytes_downloaded, 130);
+
+    let matching = snapshot.matching("curl");
+    assert_eq!(matching.processes.len(), 1);
+    assert!(matching.remote_addresses.is_empty());
+    assert_eq!(matching.connections.len(), 2);
+}
+
+#[test]
+fn top_processes_keep_the_rest_in_an_others_row() {
+    let process = |name: &str, uploaded: u128, downloaded: u128| ProcessEntry {
+        name: String::from(name),
+        connection_count: 1,
+        total_bytes_uploaded: uploaded,
+        total_bytes_downloaded: downloaded,
+        pid_count: 0,
+    };
+    let mut snapshot = Snapshot::new(&UIState::default(), &HashMap::new(), Local::now());
+    snapshot.processes = vec![
+        process("firefox", 100, 1000),
+        process("curl", 10, 500),
+        process("ssh", 20, 30),
+        process("dig", 1, 2),
+    ];
+
+    let top = snapshot.with_top_processes(2);
+    assert_eq!(
+        top.processes,
+        vec![
+            process("firefox", 100, 1000),
+            process("curl", 10, 500),
+            ProcessEntry {
+                connection_count: 2,
+                ..process("others (2)", 21, 32)
+            },
+        ]
+    );
+    // nothing to sum up
+    assert_eq!(snapshot.with_top_processes(4).processes, snapshot.processes);
+}
+
+#[test]
+fn connection_seen_on_two_interfaces_is_counted_once() {
+    let mut utilization = Utilization::new();
+    utilization.update(segment(
+        connection("1.1.1.1", 12345),
+        Direction::Download,
+        30,
+    ));
+    utilization.update(Segment {
+        interface_name: String::from("docker0"),
+        ..segment(connection("1.1.1.1", 12345), Direction::Download, 30)
+    });
+    let mut state = UIState::default();
+    state.update(HashMap::new(), utilization, Duration::from_secs(1));
+    let snapshot = Snapshot::new(&state, &HashMap::new(), Local::now());
+
+    assert_eq!(snapshot.total_bytes_downloaded, 30);
+    assert_eq!(snapshot.connections[0].interface_name, "interface_name");
+}
+
+#[test]
+fn capture_events_are_counted_in_the_order_they_were_sent() {
+    let (capture_events, capture_events_receiver) = mpsc::channel();
+    let download = |interface_name: &str, local_port: u16| {
+        CaptureEvent::Segment(Segment {
+            interface_name: String::from(interface_name),
+            ..segment(connection("1.1.1.1", local_port), Direction::Download, 30)
+        })
+    };
+    capture_events.send(download("docker0", 12345)).unwrap();
+    capture_events
+        .send(download("interface_name", 12345))
+        .unwrap();
+    capture_events
+        .send(download("interface_name", 12346))
+        .unwrap();
+    capture_events.send(download("eth1", 12347)).unwrap();
+    capture_events
+        .send(CaptureEvent::InterfaceGone(String::from("eth1")))
+        .unwrap();
+    let stats = CaptureStats {
+        packets: 3,
+        unparsed: 1,
+        overflowed: 0,
+    };
+    capture_events
+        .send(CaptureEvent::Stats(String::from("interface_name"), stats))
+        .unwrap();
+    let mut utilization = Utilization::new();
+    utilization.drain(&capture_events_receiver);
+    let utilization = utilization.clone_and_reset();
+
+    // the interface that sent a connection first keeps it, like with updates in that order
+    assert_eq!(utilization.connections.len(), 2);
+    assert_eq!(
+        utilization.connections[&connection("1.1.1.1", 12345)].interface_name,
+        "docker0"
+    );
+    assert_eq!(utilization.total_bytes(), 60);
+    assert_eq!(utilization.capture_stats(), stats);
+}
+
+#[test]
+fn session_totals_outlive_the_reset_of_each_tick() {
+    let mut utilization = Utilization::new();
+    utilization.update(segment(connection("1.1.1.1", 12345), Direction::Upload, 10));
+    utilization.clone_and_reset();
+    utilization.update(segment(
+        connection("1.1.1.1", 12345),
+        Direction::Download,
+        30,
+    ));
+    let totals = SessionTotals {
+        bytes_uploaded: utilization.lifetime_bytes_uploaded,
+        bytes_downloaded: utilization.lifetime_bytes_downloaded,
+        elapsed: Duration::from_secs(2),
+    };
+
+    assert_eq!(totals.bytes_uploaded, 10);
+    assert_eq!(totals.average(totals.bytes_downloaded), 15);
+    assert_eq!(SessionTotals::default().average(100), 0);
+}
+
+#[test]
+fn loopback_connections_are_left_out_unless_asked_for() {
+    let utilization = || {
+        let mut utilization = Utilization::new();
+        utilization.update(segment(connection("::1", 12345), Direction::Download, 30));
+        utilization.update(segment(
+            connection("127.0.0.1", 12346),
+            Direction::Download,
+            20,
+        ));
+        utilization.update(segment(
+            connection("2.2.2.2", 54321),
+            Direction::Download,
+            100,
+        ));
+        utilization
+    };
+    let mut state = UIState::default();
+    state.update(HashMap::new(), utilization(), Duration::from_secs(1));
+    let snapshot = Snapshot::new(&state, &HashMap::new(), Local::now());
+    assert_eq!(snapshot.total_bytes_downloaded, 100);
+    assert_eq!(snapshot.connections.len(), 1);
+
+    let mut state = UIState::default();
+    state.show_loopback = true;
+    state.update(HashMap::new(), utilization(), Duration::from_secs(1));
+    let snapshot = Snapshot::new(&state, &HashMap::new(), Local::now());
+    assert_eq!(snapshot.total_bytes_downloaded, 150);
+    assert_eq!(snapshot.remote_addresses.len(), 3);
+}
+
+#[test]
+fn protocol_filter_leaves_out_the_other_protocol() {
+    let dns_query = Connection::new(
+        SocketAddr::new("1.1.1.1".parse().unwrap(), 53),
+        local_ip(),
+        12347,
+        Protocol::Udp,
+    );
+    let mut utilization = Utilization::new();
+    utilization.update(segment(dns_query, Direction::Download, 30));
+    utilization.update(segment(
+        connection("2.2.2.2", 54321),
+        Direction::Download,
+        100,
+    ));
+    let mut connections_to_procs = HashMap::new();
+    connections_to_procs.insert(dns_query.local_socket, String::from("resolver"));
+    let mut state = UIState::default();
+    state.protocol_filter = Some(Protocol::Udp);
+    state.update(connections_to_procs, utilization, Duration::from_secs(1));
+    let snapshot = Snapshot::new(&state, &HashMap::new(), Local::now());
+
+    assert_eq!(snapshot.total_bytes_downloaded, 30);
+    assert_eq!(snapshot.connections.len(), 1);
+    assert_eq!(snapshot.connections[0].connection, dns_query);
+    assert_eq!(snapshot.processes.len(), 1);
+    assert_eq!(snapshot.processes[0].name, "resolver");
+}
+
+#[test]
+fn connections_age_until_they_drop_out_of_the_table() {
+    let old = connection("1.1.1.1", 12345);
+    let new = connection("2.2.2.2", 12346);
+    let traffic = |connections: &[Connection]| {
+        let mut utilization = Utilization::new();
+        for connection in connections {
+            utilization.update(segment(*connection, Direction::Download, 100));
+        }
+        utilization
+    };
+    let age_of = |snapshot: &Snapshot, connection: Connection| {
+        snapshot
+            .connections
+            .iter()
+            .find(|entry| entry.connection == connection)
+            .map(|entry| entry.age)
+    };
+    let mut state = UIState::default();
+    state.sort_by = SortBy::Age;
+    state.update(HashMap::new(), traffic(&[old]), Duration::from_secs(1));
+    state.update(HashMap::new(), traffic(&[old]), Duration::from_secs(2));
+    // only quiet for a while, so it is still in the table
+    state.update(HashMap::new(), traffic(&[new]), Duration::from_secs(1));
+    let snapshot = Snapshot::new(&state, &HashMap::new(), Local::now());
+    assert_eq!(age_of(&snapshot, old), Some(Duration::from_secs(3)));
+    assert_eq!(age_of(&snapshot, new), Some(Duration::from_secs(0)));
+    assert_eq!(snapshot.connections[0].connection, old);
+
+    for _ in 0..5 {
+        state.update(HashMap::new(), traffic(&[]), Duration::from_secs(1));
+    }
+    state.update(HashMap::new(), traffic(&[old]), Duration::from_secs(1));
+    let snapshot = Snapshot::new(&state, &HashMap::new(), Local::now());
+    assert_eq!(age_of(&snapshot, old), Some(Duration::from_secs(0)));
+    assert_eq!(age_of(&snapshot, new), None);
+}
+
+#[test]
+fn registrable_domain_keeps_the_label_before_the_public_suffix() {
+    assert_eq!(
+        registrable_domain("rr1---sn-abc.googlevideo.com"),
+        "googlevideo.com"
+    );
+    assert_eq!(registrable_domain("www.BBC.co.uk."), "bbc.co.uk");
+    assert_eq!(registrable_domain("example.com"), "example.com");
+    assert_eq!(registrable_domain("co.uk"), "co.uk");
+    assert_eq!(registrable_domain("localhost"), "localhost");
+}
+
+#[test]
+fn group_by_remote_domain_rolls_up_hosts_and_unresolved_addresses() {
+    let mut utilization = Utilization::new();
+    for &(remote_ip, local_port, data_length) in &[
+        ("1.1.1.1", 12345, 10),
+        ("3.3.3.3", 12346, 20),
+        ("4.4.4.4", 12347, 40),
+        ("2.2.2.2", 12348, 100),
+    ] {
+        utilization.update(segment(
+            connection(remote_ip, local_port),
+            Direction::Download,
+            data_length,
+        ));
+    }
+    let mut state = UIState::default();
+    state.update(HashMap::new(), utilization, Duration::from_secs(1));
+    let mut ip_to_host = HashMap::new();
+    for &(ip, host) in &[
+        ("1.1.1.1", "rr1.googlevideo.com"),
+        ("3.3.3.3", "rr2.googlevideo.com"),
+        ("4.4.4.4", "www.bbc.co.uk"),
+    ] {
+        ip_to_host.insert(ip.parse::<IpAddr>().unwrap(), String::from(host));
+    }
+    let snapshot = Snapshot::new(&state, &ip_to_host, Local::now());
+    let group_by: GroupBy = "remote-domain".parse().unwrap();
+
+    let groups: Vec<(Vec<String>, u128, u128)> = group_by
+        .group(&snapshot, SortBy::default())
+        .into_iter()
+        .map(|group| {
+            (
+                group.key,
+                group.connection_count,
+                group.total_bytes_downloaded,
+            )
+        })
+        .collect();
+    assert_eq!(
+        groups,
+        vec![
+            (vec![String::from("unresolved")], 1, 100),
+            (vec![String::from("bbc.co.uk")], 1, 40),
+            (vec![String::from("googlevideo.com")], 2, 30),
+        ]
+    );
+}
@@ -0,0 +1,284 @@
+use ::std::net::{Ipv6Addr, SocketAddr};
+
+use pnet_bandwhich_fork::datalink::DataLinkReceiver;
+
+use crate::network::{
+    capture_queue, CaptureDirection, CaptureEvent, CaptureStats, Connection, Direction, Protocol,
+    Sample, Segment, Sniffer, Utilization,
+};
+use crate::tests::cases::test_utils::build_tcp_packet;
+use crate::tests::fakes::{get_interfaces, NetworkFrames};
+
+fn download_frame() -> Vec<u8> {
+    build_tcp_packet(
+        "1.1.1.1",
+        "10.0.0.2",
+        12345,
+        443,
+        b"I have come from 1.1.1.1",
+    )
+}
+
+// the frame with `headers` between its mac addresses and its ethertype
+fn with_headers(frame: Vec<u8>, headers: &[u8]) -> Vec<u8> {
+    let mut tagged = frame[..12].to_vec();
+    tagged.extend_from_slice(headers);
+    tagged.extend_from_slice(&frame[12..]);
+    tagged
+}
+
+// the ip packet of the frame in a pppoe session, which has its own protocol numbers
+fn in_pppoe_session(frame: Vec<u8>) -> Vec<u8> {
+    let ip_packet = &frame[14..];
+    let length = (ip_packet.len() + 2) as u16;
+    let mut pppoe = frame[..12].to_vec();
+    pppoe.extend_from_slice(&[0x88, 0x64, 0x11, 0x00, 0x12, 0x34]);
+    pppoe.extend_from_slice(&length.to_be_bytes());
+    pppoe.extend_from_slice(&[0x00, 0x21]);
+    pppoe.extend_from_slice(ip_packet);
+    pppoe
+}
+
+fn sniff(frame: Vec<u8>) -> Option<Segment> {
+    let frames = NetworkFrames::new(vec![Some(frame)]) as Box<dyn DataLinkReceiver>;
+    let mut sniffer = Sniffer::new(
+        get_interfaces().remove(0),
+        frames,
+        CaptureDirection::Both,
+        None,
+        false,
+    );
+    sniffer.next_segment().unwrap()
+}
+
+fn assert_is_the_download(segment: Option<Segment>) {
+    let segment = segment.expect("the frame was not parsed");
+    let untagged = sniff(download_frame()).unwrap();
+    assert_eq!(
+        segment.connection,
+        Connection::new(
+            "1.1.1.1:12345".parse::<SocketAddr>().unwrap(),
+            "10.0.0.2".parse().unwrap(),
+            443,
+            Protocol::Tcp,
+        )
+    );
+    assert_eq!(segment.direction, Direction::Download);
+    assert_eq!(segment.data_length, untagged.data_length);
+}
+
+#[test]
+fn vlan_tagged_frames_are_counted_like_untagged_ones() {
+    // 802.1Q, vlan 42
+    assert_is_the_download(sniff(with_headers(
+        download_frame(),
+        &[0x81, 0x00, 0x00, 0x2a],
+    )));
+}
+
+#[test]
+fn qinq_tagged_frames_are_counted_like_untagged_ones() {
+    // 802.1ad outer vlan 100 then inner vlan 42, and the same with the older outer ethertype
+    assert_is_the_download(sniff(with_headers(
+        download_frame(),
+        &[0x88, 0xa8, 0x00, 0x64, 0x81, 0x00, 0x00, 0x2a],
+    )));
+    assert_is_the_download(sniff(with_headers(
+        download_frame(),
+        &[0x91, 0x00, 0x00, 0x64, 0x81, 0x00, 0x00, 0x2a],
+    )));
+}
+
+#[test]
+fn frames_of_a_pppoe_session_are_counted_like_untagged_ones() {
+    assert_is_the_download(sniff(in_pppoe_session(download_frame())));
+}
+
+#[test]
+fn truncated_vlan_tag_is_not_counted() {
+    let frame = download_frame();
+    let mut truncated = frame[..12].to_vec();
+    truncated.extend_from_slice(&[0x81, 0x00, 0x00]);
+    assert!(sniff(truncated).is_none());
+}
+
+#[test]
+fn sampled_segments_stand_for_the_ones_left_out() {
+    let frames = NetworkFrames::new(vec![Some(download_frame()); 4]) as Box<dyn DataLinkReceiver>;
+    let mut sniffer = Sniffer::new(
+        get_interfaces().remove(0),
+        frames,
+        CaptureDirection::Both,
+        None,
+        false,
+    );
+    sniffer.set_sample("1/2".parse().unwrap());
+    let mut utilization = Utilization::new();
+    for _ in 0..4 {
+        if let Some(segment) = sniffer.next_segment().unwrap() {
+            match sniffer.capture_event(segment) {
+                CaptureEvent::SampledSegment {
+                    segment,
+                    every,
+                    skipped,
+                } => utilization.update_sampled(segment, every, skipped),
+                _ => panic!("the segment was not sampled"),
+            }
+        }
+    }
+
+    let data_length = sniff(download_frame()).unwrap().data_length;
+    // the last segment was left out after the last one sampled, so it is not in yet
+    assert_eq!(utilization.lifetime_bytes_downloaded, 3 * data_length);
+    assert_eq!(utilization.total_bytes(), 4 * data_length);
+    assert_eq!("1/1".parse::<Sample>().unwrap(), Sample::default());
+    assert!("2/3".parse::<Sample>().is_err());
+    assert!("1/0".parse::<Sample>().is_err());
+}
+
+// a tcp segment of 40 bytes from 1.1.1.1:12345 to 10.0.0.2:443, without the ip header
+fn tcp_segment() -> Vec<u8> {
+    let mut segment = vec![
+        0x30, 0x39, 0x01, 0xbb, 0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x18, 0xff, 0xff,
+    ];
+    segment.extend_from_slice(&[0; 4]);
+    segment.extend_from_slice(&[b'x'; 20]);
+    segment
+}
+
+// the bare ipv4 packet of a fragment of the datagram `identification`, `offset` in eight byte
+// units
+fn ipv4_fragment(
+    identification: u16,
+    offset: u16,
+    more_fragments: bool,
+    payload: &[u8],
+) -> Vec<u8> {
+    let total_length = (20 + payload.len()) as u16;
+    let flags_and_offset = offset | if more_fragments { 0x2000 } else { 0 };
+    let mut packet = vec![0x45, 0];
+    packet.extend_from_slice(&total_length.to_be_bytes());
+    packet.extend_from_slice(&identification.to_be_bytes());
+    packet.extend_from_slice(&flags_and_offset.to_be_bytes());
+    packet.extend_from_slice(&[64, 6, 0, 0, 1, 1, 1, 1, 10, 0, 0, 2]);
+    packet.extend_from_slice(payload);
+    packet
+}
+
+fn sniff_all(frames: Vec<Vec<u8>>) -> Vec<Option<Segment>> {
+    let count = frames.len();
+    let frames =
+        NetworkFrames::new(frames.into_iter().map(Some).collect()) as Box<dyn DataLinkReceiver>;
+    let mut sniffer = Sniffer::new(
+        get_interfaces().remove(0),
+        frames,
+        CaptureDirection::Both,
+        None,
+        false,
+    );
+    (0..count)
+        .map(|_| sniffer.next_segment().unwrap())
+        .collect()
+}
+
+#[test]
+fn every_fragment_of_an_ipv4_datagram_is_counted_to_its_connection() {
+    let segment = tcp_segment();
+    let whole = sniff(ipv4_fragment(7, 0, false, &segment)).unwrap();
+    let fragments = sniff_all(vec![
+        ipv4_fragment(7, 0, true, &segment[..24]),
+        ipv4_fragment(7, 3, false, &segment[24..]),
+        // the first fragment of this one never came
+        ipv4_fragment(8, 3, false, &segment[24..]),
+    ]);
+
+    let counted = fragments[..2]
+        .iter()
+        .map(|fragment| fragment.as_ref().expect("the fragment was not counted"))
+        .collect::<Vec<_>>();
+    for fragment in &counted {
+        assert_eq!(fragment.connection, whole.connection);
+        assert_eq!(fragment.direction, Direction::Download);
+    }
+    assert_eq!(
+        counted
+            .iter()
+            .map(|fragment| fragment.data_length)
+            .sum::<u128>(),
+        whole.data_length
+    );
+    assert!(fragments[2].is_none());
+}
+
+#[test]
+fn every_fragment_of_an_ipv6_datagram_is_counted_to_its_connection() {
+    // a udp datagram of 32 bytes from [2001:db8::1]:53 to [fd00::2]:5353
+    let mut datagram = vec![0x00, 0x35, 0x14, 0xe9, 0x00, 0x20, 0, 0];
+    datagram.extend_from_slice(&[b'x'; 24]);
+    let fragment = |offset: u16, more_fragments: bool, payload: &[u8]| {
+        let offset_and_flags = (offset << 3) | if more_fragments { 1 } else { 0 };
+        let mut packet = vec![0x60, 0, 0, 0];
+        packet.extend_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+        packet.extend_from_slice(&[44, 64]);
+        packet.extend_from_slice(&"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        packet.extend_from_slice(&"fd00::2".parse::<Ipv6Addr>().unwrap().octets());
+        packet.extend_from_slice(&[17, 0]);
+        packet.extend_from_slice(&offset_and_flags.to_be_bytes());
+        packet.extend_from_slice(&42u32.to_be_bytes());
+        packet.extend_from_slice(payload);
+        packet
+    };
+    let fragments = sniff_all(vec![
+        fragment(0, true, &datagram[..16]),
+        fragment(2, false, &datagram[16..]),
+    ]);
+
+    let connection = Connection::new(
+        "[2001:db8::1]:53".parse::<SocketAddr>().unwrap(),
+        "fd00::2".parse().unwrap(),
+        5353,
+        Protocol::Udp,
+    );
+    let mut data_length = 0;
+    for fragment in fragments {
+        let fragment = fragment.expect("the fragment was not counted");
+        assert_eq!(fragment.connection, connection);
+        data_length += fragment.data_length;
+    }
+    // the fragment headers are part of what the ip packets carried
+    assert_eq!(data_length, datagram.len() as u128 + 2 * 8);
+}
+
+#[test]
+fn segments_that_find_the_queue_full_are_counted_in_the_stats() {
+    let frames = vec![Some(download_frame()), Some(download_frame())];
+    let mut sniffer = Sniffer::new(
+        get_interfaces().remove(0),
+        NetworkFrames::new(frames) as Box<dyn DataLinkReceiver>,
+        CaptureDirection::Both,
+        None,
+        false,
+    );
+    let (mut capture_events, capture_events_receiver) = capture_queue(1);
+    for _ in 0..2 {
+        let segment = sniffer.next_segment().unwrap().unwrap();
+        capture_events.send_segment(sniffer.capture_event(segment));
+    }
+    // no room for them either until the display thread counted the first segment
+    assert!(!capture_events.send_stats("interface_name", sniffer.stats()));
+    let mut utilization = Utilization::new();
+    utilization.drain(&capture_events_receiver);
+    assert!(capture_events.send_stats("interface_name", sniffer.stats()));
+    utilization.drain(&capture_events_receiver);
+
+    let data_length = sniff(download_frame()).unwrap().data_length;
+    assert_eq!(utilization.lifetime_bytes_downloaded, data_length);
+    assert_eq!(
+        utilization.capture_stats(),
+        CaptureStats {
+            packets: 2,
+            unparsed: 0,
+            overflowed: 1,
+        }
+    );
+}
@@ -0,0 +1,89 @@
+use ::std::io::{BufRead, BufReader};
+use ::std::os::unix::net::{UnixListener, UnixStream};
+use ::std::path::PathBuf;
+
+use pnet_bandwhich_fork::datalink::DataLinkReceiver;
+
+use crate::display::SocketSink;
+use crate::start;
+use crate::tests::cases::test_utils::{
+    build_tcp_packet, opts_ui, os_input_output, test_backend_factory,
+};
+use crate::tests::fakes::NetworkFrames;
+
+fn socket_path(name: &str) -> PathBuf {
+    ::std::env::temp_dir().join(format!("bandwhich-{}-{}.sock", name, ::std::process::id()))
+}
+
+fn read_line(reader: &mut BufReader<UnixStream>) -> String {
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    line
+}
+
+#[test]
+fn ui_mode_streams_every_tick_to_a_listening_socket() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        None, // sleep
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"Same here, but one second later",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let path = socket_path("listening");
+    let _ = ::std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 3);
+    let mut opts = opts_ui();
+    opts.socket = Some(path.clone());
+    start(backend, os_input, opts).unwrap();
+    let (stream, _) = listener.accept().unwrap();
+    // the stream is closed once bandwhich is done
+    let lines = BufReader::new(stream)
+        .lines()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    ::std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(terminal_draw_events.lock().unwrap().len(), 3);
+    assert_eq!(lines.len(), 3);
+    assert!(lines
+        .iter()
+        .all(|line| line.starts_with("{\"schema_version\":1,\"elapsed_seconds\":")));
+    assert!(lines[1].contains("\"remote_socket\":{\"ip\":\"1.1.1.1\",\"port\":12345"));
+}
+
+#[test]
+fn a_consumer_that_goes_away_is_dropped_and_the_next_one_gets_the_lines() {
+    let path = socket_path("created");
+    let _ = ::std::fs::remove_file(&path);
+    let mut socket_sink = SocketSink::open(path.clone()).unwrap();
+    // nobody is there yet, the line is lost
+    socket_sink.write_line("nobody");
+    let first_consumer = UnixStream::connect(&path).unwrap();
+    socket_sink.write_line("first");
+    let mut first_reader = BufReader::new(first_consumer);
+    assert_eq!(read_line(&mut first_reader), "first\n");
+    drop(first_reader);
+
+    let second_consumer = UnixStream::connect(&path).unwrap();
+    // written to the consumer that went away, which is then dropped
+    socket_sink.write_line("gone");
+    socket_sink.write_line("second");
+    let mut second_reader = BufReader::new(second_consumer);
+    assert_eq!(read_line(&mut second_reader), "second\n");
+
+    drop(socket_sink);
+    assert!(!path.exists());
+}
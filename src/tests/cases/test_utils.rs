@@ -1,5 +1,6 @@
 use crate::tests::fakes::{
-    create_fake_dns_client, create_fake_on_winch, get_interfaces, get_open_sockets, KeyboardEvents,
+    copy_to_clipboard, create_fake_dns_client, create_fake_on_terminate, create_fake_on_winch,
+    get_interfaces, get_open_sockets, get_process_cmdline, get_process_container, KeyboardEvents,
     NetworkFrames, TerminalEvent, TestBackend,
 };
 use std::iter;
@@ -105,6 +106,7 @@ pub fn os_input_output_factory(
     keyboard_events: Box<dyn Iterator<Item = Event> + Send>,
 ) -> OsInputOutput {
     let on_winch = create_fake_on_winch(false);
+    let on_terminate = create_fake_on_terminate(false);
     let cleanup = Box::new(|| {});
 
     let write_to_stdout: Box<dyn FnMut(String) + Send> = match stdout {
@@ -120,10 +122,17 @@ pub fn os_input_output_factory(
     OsInputOutput {
         network_interfaces: get_interfaces(),
         network_frames,
+        host_only_interfaces: Vec::new(),
+        uncaptured_interfaces: Vec::new(),
+        link_speeds: HashMap::new(),
         get_open_sockets,
+        get_process_cmdline,
+        get_process_container,
+        copy_to_clipboard,
         keyboard_events,
         dns_client,
         on_winch,
+        on_terminate,
         cleanup,
         write_to_stdout,
     }
@@ -147,7 +156,9 @@ fn opts_factory(raw: bool) -> Opt {
             connections: false,
             processes: false,
             total_utilization: false,
+            ..Default::default()
         },
+        ..Default::default()
     }
 }
 type BackendWithStreams = (
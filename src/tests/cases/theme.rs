@@ -0,0 +1,98 @@
+use ::tui::style::Color;
+
+use pnet_bandwhich_fork::datalink::DataLinkReceiver;
+
+use crate::display::{builtin_theme, parse_color, parse_theme, FrameExportBackend, Theme};
+use crate::start;
+use crate::tests::cases::test_utils::{
+    build_tcp_packet, opts_ui, os_input_output, test_backend_factory,
+};
+use crate::tests::fakes::NetworkFrames;
+
+#[test]
+fn colors_are_given_by_name_hex_or_index() {
+    assert_eq!(parse_color("yellow"), Ok(Color::Yellow));
+    assert_eq!(parse_color("light-blue"), Ok(Color::LightBlue));
+    assert_eq!(parse_color("default"), Ok(Color::Reset));
+    assert_eq!(parse_color("#586e75"), Ok(Color::Rgb(0x58, 0x6e, 0x75)));
+    assert_eq!(parse_color("236"), Ok(Color::Indexed(236)));
+    assert!(parse_color("#586e7").is_err());
+    assert!(parse_color("256").is_err());
+    assert!(parse_color("chartreuse").is_err());
+}
+
+#[test]
+fn a_theme_file_changes_the_colors_of_its_base() {
+    let theme = parse_theme(
+        "# for a white background\n\
+         base = \"light\"\n\
+         header = \"cyan\"  # rather than blue\n\
+         selected = \"236\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        theme,
+        Theme {
+            header: Color::Cyan,
+            selected: Some(Color::Indexed(236)),
+            ..builtin_theme("light").unwrap()
+        }
+    );
+    assert_eq!(
+        parse_theme("selected = \"reverse\"\n"),
+        Ok(Theme::default())
+    );
+    // the strings are TOML's, single quotes included
+    assert_eq!(
+        parse_theme("header = 'cyan'\n"),
+        Ok(Theme {
+            header: Color::Cyan,
+            ..Theme::default()
+        })
+    );
+    assert!(parse_theme("header = blue\n").is_err());
+    assert!(parse_theme("header = [\"blue\"]\n").is_err());
+    assert!(parse_theme("headers = \"blue\"\n").is_err());
+    assert!(parse_theme("base = \"sepia\"\n").is_err());
+    assert!(parse_theme("high = \"bright red\"\n").is_err());
+}
+
+#[test]
+fn the_tables_are_drawn_in_the_colors_of_the_theme() {
+    let network_frames = vec![NetworkFrames::new(vec![Some(build_tcp_packet(
+        "1.1.1.1",
+        "10.0.0.2",
+        12345,
+        443,
+        b"I have come from 1.1.1.1",
+    ))]) as Box<dyn DataLinkReceiver>];
+    let path = std::env::temp_dir().join("bandwhich_theme_light.txt");
+    let backend = FrameExportBackend::new(path.clone(), 190, 50).unwrap();
+    let os_input = os_input_output(network_frames, 2);
+    let mut opts = opts_ui();
+    opts.render_opts.theme = Some(String::from("light"));
+    start(backend, os_input, opts).unwrap();
+    let exported = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // blue headers, and nothing in the yellow of the default theme
+    assert!(exported.contains("\x1b[34mProcess"));
+    assert!(!exported.contains("\x1b[33m"));
+}
+
+#[test]
+fn a_theme_that_cannot_be_read_falls_back_to_the_default_colors() {
+    let path = std::env::temp_dir().join("bandwhich_theme_invalid.toml");
+    std::fs::write(&path, "header = \"bright red\"\n").unwrap();
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let network_frames = vec![NetworkFrames::new(vec![None]) as Box<dyn DataLinkReceiver>];
+    let os_input = os_input_output(network_frames, 1);
+    let mut opts = opts_ui();
+    opts.render_opts.theme = Some(path.to_string_lossy().into_owned());
+    start(backend, os_input, opts).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror[0].contains("warning: could not read the theme"));
+    assert!(terminal_draw_events_mirror[0].contains("line 1: invalid color 'bright red'"));
+}
@@ -0,0 +1,283 @@
+use ::std::collections::HashSet;
+use ::std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use ::std::sync::atomic::{AtomicBool, Ordering};
+use ::std::sync::mpsc;
+use ::std::sync::{Arc, Mutex};
+use ::std::time::{Duration, Instant};
+
+use ::chrono::prelude::*;
+
+use crate::display::{Budget, Ui};
+use crate::network::{CaptureEvent, Connection, Direction, Protocol, Segment};
+use crate::tests::cases::test_utils::{opts_raw, test_backend_factory};
+use crate::tests::fakes::{get_open_sockets, TestBackend};
+use crate::tick::{run_tick, DisplayState, SharedState, Tick, TickOptions};
+use crate::RenderOpts;
+
+fn raw_tick_options() -> TickOptions {
+    TickOptions {
+        interval: Duration::from_secs(1),
+        duration: None,
+        idle_timeout: None,
+        fail_if_above: None,
+        quiet_until_traffic: false,
+        raw_mode: true,
+        json_mode: false,
+        summarize_at_end: false,
+        summary: false,
+        group_processes: false,
+        show_user: false,
+        dns_stats: false,
+    }
+}
+
+fn shared_state(backend: TestBackend, stdout: Arc<Mutex<Vec<String>>>) -> SharedState<TestBackend> {
+    shared_state_with(backend, stdout, opts_raw().render_opts)
+}
+
+fn shared_state_with(
+    backend: TestBackend,
+    stdout: Arc<Mutex<Vec<String>>>,
+    render_opts: RenderOpts,
+) -> SharedState<TestBackend> {
+    let ui = Ui::new(backend, render_opts, HashSet::new());
+    let write_to_stdout: Box<dyn FnMut(String) + Send> =
+        Box::new(move |line: String| stdout.lock().unwrap().push(line));
+    SharedState {
+        ui: Arc::new(Mutex::new(ui)),
+        open_sockets: Arc::new(Mutex::new(get_open_sockets(None))),
+        write_to_stdout: Arc::new(Mutex::new(write_to_stdout)),
+        session_report: Arc::new(Mutex::new(None)),
+        paused: Arc::new(AtomicBool::new(false)),
+        resolving: Arc::new(AtomicBool::new(true)),
+        reset_session: Arc::new(AtomicBool::new(false)),
+        replay_finished: Arc::new(AtomicBool::new(false)),
+        threshold_breached: Arc::new(AtomicBool::new(false)),
+        nothing_captured: Arc::new(AtomicBool::new(false)),
+        adaptive_refresh: None,
+    }
+}
+
+fn upload(data_length: u128) -> CaptureEvent {
+    CaptureEvent::Segment(Segment {
+        interface_name: String::from("interface_name"),
+        connection: Connection::new(
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)), 12345),
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+            443,
+            Protocol::Tcp,
+        ),
+        direction: Direction::Upload,
+        data_length,
+        retransmitted: false,
+    })
+}
+
+#[test]
+fn a_tick_counts_what_was_captured_since_the_last_one() {
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let shared = shared_state(backend, stdout.clone());
+    let (capture_events, capture_events_receiver) = mpsc::channel();
+    let start_time = Instant::now();
+    let mut state = DisplayState::new(raw_tick_options(), capture_events_receiver, start_time);
+
+    capture_events.send(upload(100)).unwrap();
+    let tick = run_tick(&shared, &mut state, start_time + Duration::from_secs(1));
+    assert_eq!(tick, Tick::Continue);
+    capture_events.send(upload(50)).unwrap();
+    run_tick(&shared, &mut state, start_time + Duration::from_secs(2));
+
+    let stdout = stdout.lock().unwrap();
+    let processes = stdout
+        .iter()
+        .filter(|line| line.starts_with("process:"))
+        .collect::<Vec<_>>();
+    assert_eq!(processes.len(), 2);
+    assert!(processes[0].ends_with("\"1\" up/down Bps: 100/0 connections: 1"));
+    // the rates are averaged over the recent ticks
+    assert!(processes[1].ends_with("\"1\" up/down Bps: 75/0 connections: 1"));
+}
+
+#[test]
+fn the_rates_are_averaged_over_the_rate_window_whatever_the_interval() {
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let mut render_opts = opts_raw().render_opts;
+    render_opts.rate_window = Some(Duration::from_secs(1));
+    let shared = shared_state_with(backend, stdout.clone(), render_opts);
+    let (capture_events, capture_events_receiver) = mpsc::channel();
+    let start_time = Instant::now();
+    let options = TickOptions {
+        interval: Duration::from_millis(500),
+        ..raw_tick_options()
+    };
+    let mut state = DisplayState::new(options, capture_events_receiver, start_time);
+
+    for (tick, data_length) in [100, 50, 30].iter().enumerate() {
+        capture_events.send(upload(*data_length)).unwrap();
+        run_tick(
+            &shared,
+            &mut state,
+            start_time + Duration::from_millis(500 * (tick as u64 + 1)),
+        );
+    }
+
+    let stdout = stdout.lock().unwrap();
+    let processes = stdout
+        .iter()
+        .filter(|line| line.starts_with("process:"))
+        .collect::<Vec<_>>();
+    assert_eq!(processes.len(), 3);
+    assert!(processes[0].ends_with("\"1\" up/down Bps: 200/0 connections: 1"));
+    assert!(processes[1].ends_with("\"1\" up/down Bps: 150/0 connections: 1"));
+    // the first tick is out of the last second by then
+    assert!(processes[2].ends_with("\"1\" up/down Bps: 80/0 connections: 1"));
+}
+
+#[test]
+fn a_suspend_between_two_ticks_spreads_what_came_in_over_it() {
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let shared = shared_state(backend, stdout.clone());
+    let (capture_events, capture_events_receiver) = mpsc::channel();
+    let start_time = Instant::now();
+    let mut state = DisplayState::new(raw_tick_options(), capture_events_receiver, start_time);
+
+    capture_events.send(upload(100)).unwrap();
+    run_tick(&shared, &mut state, start_time + Duration::from_secs(1));
+    // the machine slept for an hour, the monotonic clock jumps ahead by as much on resume
+    capture_events.send(upload(7200)).unwrap();
+    let tick = run_tick(&shared, &mut state, start_time + Duration::from_secs(3601));
+    assert_eq!(tick, Tick::Continue);
+    capture_events.send(upload(100)).unwrap();
+    run_tick(&shared, &mut state, start_time + Duration::from_secs(3602));
+
+    let stdout = stdout.lock().unwrap();
+    let upload_rates = stdout
+        .iter()
+        .filter(|line| line.starts_with("process:"))
+        .map(|line| {
+            let rates = &line[line.find("Bps: ").unwrap() + 5..];
+            rates[..rates.find('/').unwrap()].parse::<f64>().unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(upload_rates.len(), 3);
+    // no spike of what came in over the hour as if it came in a second, nor a rate of nothing
+    assert!(upload_rates[1] > 0.0 && upload_rates[1] < 100.0);
+    assert!(upload_rates[2] > 0.0 && upload_rates[2] < 100.0);
+}
+
+#[test]
+fn the_tick_after_a_replay_ran_out_is_the_last() {
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let shared = shared_state(backend, Arc::new(Mutex::new(Vec::new())));
+    let (_capture_events, capture_events_receiver) = mpsc::channel();
+    let start_time = Instant::now();
+    let mut state = DisplayState::new(raw_tick_options(), capture_events_receiver, start_time);
+
+    shared.replay_finished.store(true, Ordering::SeqCst);
+    let tick = run_tick(&shared, &mut state, start_time + Duration::from_secs(1));
+    assert_eq!(tick, Tick::Last);
+    assert!(shared.nothing_captured.load(Ordering::SeqCst));
+}
+
+#[test]
+fn the_idle_timeout_counts_from_the_last_tick_with_traffic() {
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let shared = shared_state(backend, Arc::new(Mutex::new(Vec::new())));
+    let (capture_events, capture_events_receiver) = mpsc::channel();
+    let start_time = Instant::now();
+    let options = TickOptions {
+        idle_timeout: Some(Duration::from_secs(3)),
+        ..raw_tick_options()
+    };
+    let mut state = DisplayState::new(options, capture_events_receiver, start_time);
+
+    let mut ticks = vec![];
+    for seconds in 1..=6 {
+        if seconds == 2 {
+            capture_events.send(upload(1000)).unwrap();
+        } else {
+            // below the idle rate
+            capture_events.send(upload(10)).unwrap();
+        }
+        ticks.push(run_tick(
+            &shared,
+            &mut state,
+            start_time + Duration::from_secs(seconds),
+        ));
+    }
+    assert_eq!(
+        ticks,
+        vec![
+            Tick::Continue,
+            Tick::Continue,
+            Tick::Continue,
+            Tick::Continue,
+            Tick::Last,
+            Tick::Last
+        ]
+    );
+    assert!(!shared.nothing_captured.load(Ordering::SeqCst));
+}
+
+#[test]
+fn nothing_captured_while_there_are_connections_is_warned_of_once() {
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let shared = shared_state(backend, Arc::new(Mutex::new(Vec::new())));
+    let (_capture_events, capture_events_receiver) = mpsc::channel();
+    let start_time = Instant::now();
+    let options = TickOptions {
+        raw_mode: false,
+        ..raw_tick_options()
+    };
+    let mut state = DisplayState::new(options, capture_events_receiver, start_time);
+
+    for seconds in 1..=7 {
+        run_tick(
+            &shared,
+            &mut state,
+            start_time + Duration::from_secs(seconds),
+        );
+    }
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+    let warned = |draw: &String| draw.contains("the capture may lack permissions");
+
+    assert!(!terminal_draw_events_mirror[..4].iter().any(warned));
+    assert!(terminal_draw_events_mirror[4..].iter().all(warned));
+    assert_eq!(
+        terminal_draw_events_mirror[6]
+            .matches("the capture may lack permissions")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn the_budget_keeps_counting_over_a_session_reset() {
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let shared = shared_state(backend, Arc::new(Mutex::new(Vec::new())));
+    let (capture_events, capture_events_receiver) = mpsc::channel();
+    let start_time = Instant::now();
+    let mut state = DisplayState::new(raw_tick_options(), capture_events_receiver, start_time);
+    let budget_path = ::std::env::temp_dir()
+        .join(format!("bandwhich-tick-budget-{}", ::std::process::id()))
+        .join("budget");
+    state.budget = Some(Budget::load(budget_path, 1_000_000, 30, Local::now()).unwrap());
+
+    for (seconds, data_length) in [100, 200, 300, 700].iter().enumerate() {
+        if seconds == 2 {
+            // `z` is pressed
+            shared.reset_session.store(true, Ordering::SeqCst);
+        }
+        capture_events.send(upload(*data_length)).unwrap();
+        run_tick(
+            &shared,
+            &mut state,
+            start_time + Duration::from_secs(seconds as u64 + 1),
+        );
+    }
+
+    assert_eq!(state.budget.as_ref().unwrap().used, 1300);
+}
@@ -1,16 +1,22 @@
 use crate::tests::fakes::TerminalEvent::*;
 use crate::tests::fakes::{
-    create_fake_dns_client, create_fake_on_winch, get_interfaces, get_open_sockets, NetworkFrames,
+    copy_to_clipboard, create_fake_dns_client, create_fake_on_terminate, create_fake_on_winch,
+    get_interfaces, get_open_sockets, get_process_cmdline, get_process_container,
+    LostInterfaceFrames, NetworkFrames, TestBackend,
 };
 
 use ::insta::assert_snapshot;
+use ::regex::Regex;
 
-use ::std::collections::HashMap;
-use ::std::net::IpAddr;
+use ::std::collections::{HashMap, HashSet};
+use ::std::io;
+use ::std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use ::std::sync::{Arc, Mutex};
+use ::std::time::Duration;
 
 use crate::tests::cases::test_utils::{
-    build_tcp_packet, opts_ui, os_input_output, os_input_output_factory, sample_frames,
-    sleep_and_quit_events, test_backend_factory,
+    build_tcp_packet, opts_ui, os_input_output, os_input_output_factory, os_input_output_stdout,
+    sample_frames, sleep_and_quit_events, test_backend_factory,
 };
 use ::termion::event::{Event, Key};
 use pnet_bandwhich_fork::datalink::DataLinkReceiver;
@@ -18,7 +24,11 @@ use std::iter;
 
 use crate::tests::fakes::KeyboardEvents;
 
-use crate::{start, Opt, OsInputOutput, RenderOpts};
+use crate::display::{
+    BorderStyle, ClickAction, FrameExportBackend, NetworkTag, QuitKeys, SortBy, Ui,
+};
+use crate::network::{service_name, Connection, Direction, Protocol, Segment, Utilization};
+use crate::{start, ExitStatus, Opt, OsInputOutput, RenderOpts, SCROLL_PAGE_ROWS};
 
 #[test]
 fn basic_startup() {
@@ -29,7 +39,7 @@ fn basic_startup() {
     let (terminal_events, terminal_draw_events, backend) = test_backend_factory(190, 50);
     let os_input = os_input_output(network_frames, 1);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![Clear, HideCursor, Draw, Flush, Clear, ShowCursor];
@@ -76,7 +86,7 @@ fn pause_by_space() {
     let os_input = os_input_output_factory(network_frames, None, None, events);
     let (terminal_events, terminal_draw_events, backend) = test_backend_factory(190, 50);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
     let expected_terminal_events = vec![
         Clear, HideCursor, Draw, Flush, Draw, Flush, Draw, Flush, Clear, ShowCursor,
@@ -91,6 +101,98 @@ fn pause_by_space() {
     assert_snapshot!(&terminal_draw_events_mirror[2]);
 }
 
+#[test]
+fn event_log_pane() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        None, // sleep
+    ]) as Box<dyn DataLinkReceiver>];
+
+    // sleep for 1s, then press l, sleep for 1s, then quit
+    let mut events: Vec<Option<Event>> = iter::repeat(None).take(1).collect();
+    events.push(Some(Event::Key(Key::Char('l'))));
+    events.push(None);
+    events.push(Some(Event::Key(Key::Ctrl('c'))));
+
+    let events = Box::new(KeyboardEvents::new(events));
+    let os_input = os_input_output_factory(network_frames, None, None, events);
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let opts = opts_ui();
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    use regex::Regex;
+    let timestamp = Regex::new(r"\d{2}:\d{2}:\d{2}").unwrap();
+    let last_draw = terminal_draw_events_mirror.last().unwrap();
+    let formatted = timestamp.replace_all(last_draw, "<TIME>").to_string();
+    assert_snapshot!(formatted);
+}
+
+#[test]
+fn r_key_turns_resolving_hostnames_off_and_on() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        None, // sleep
+    ]) as Box<dyn DataLinkReceiver>];
+
+    // sleep for 1s, then press r, sleep for 1s, press r again, then quit
+    let mut events: Vec<Option<Event>> = iter::repeat(None).take(1).collect();
+    events.push(Some(Event::Key(Key::Char('r'))));
+    events.push(None);
+    events.push(Some(Event::Key(Key::Char('r'))));
+    events.push(Some(Event::Key(Key::Ctrl('c'))));
+
+    let events = Box::new(KeyboardEvents::new(events));
+    let dns_client = create_fake_dns_client(HashMap::new());
+    let os_input = os_input_output_factory(network_frames, None, dns_client, events);
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let opts = opts_ui();
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    let turned_off = terminal_draw_events_mirror
+        .iter()
+        .position(|draw| draw.contains("[not resolving hostnames]"))
+        .unwrap();
+    // pressing r again draws the header without it
+    assert!(terminal_draw_events_mirror.len() > turned_off + 1);
+}
+
+#[test]
+fn r_key_does_nothing_without_resolving() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        None, // sleep
+    ]) as Box<dyn DataLinkReceiver>];
+
+    // sleep for 1s, then press r, then quit
+    let mut events: Vec<Option<Event>> = iter::repeat(None).take(1).collect();
+    events.push(Some(Event::Key(Key::Char('r'))));
+    events.push(Some(Event::Key(Key::Ctrl('c'))));
+
+    let events = Box::new(KeyboardEvents::new(events));
+    let os_input = os_input_output_factory(network_frames, None, None, events);
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let opts = opts_ui();
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(!terminal_draw_events_mirror
+        .iter()
+        .any(|draw| draw.contains("[not resolving hostnames]")));
+}
+
 #[test]
 fn basic_only_processes() {
     let network_frames = vec![NetworkFrames::new(vec![
@@ -108,10 +210,12 @@ fn basic_only_processes() {
             connections: false,
             processes: true,
             total_utilization: false,
+            ..Default::default()
         },
+        ..Default::default()
     };
 
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
     assert_snapshot!(&terminal_draw_events_mirror[0]);
 }
@@ -133,10 +237,12 @@ fn basic_only_connections() {
             connections: true,
             processes: false,
             total_utilization: false,
+            ..Default::default()
         },
+        ..Default::default()
     };
 
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
     assert_snapshot!(&terminal_draw_events_mirror[0]);
 }
@@ -158,10 +264,52 @@ fn basic_only_addresses() {
             connections: false,
             processes: false,
             total_utilization: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+    assert_snapshot!(&terminal_draw_events_mirror[0]);
+}
+
+#[test]
+fn basic_with_unix_sockets() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        None, // sleep
+    ]) as Box<dyn DataLinkReceiver>];
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 1);
+    let opts = Opt {
+        interface: Some(String::from("interface_name")),
+        raw: false,
+        no_resolve: false,
+        render_opts: RenderOpts {
+            unix_sockets: true,
+            ..Default::default()
         },
+        ..Default::default()
     };
 
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+    assert_snapshot!(&terminal_draw_events_mirror[0]);
+}
+
+#[test]
+fn only_listening_sockets() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        None, // sleep
+    ]) as Box<dyn DataLinkReceiver>];
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 1);
+    let mut opts = opts_ui();
+    opts.render_opts.listening = true;
+
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
     assert_snapshot!(&terminal_draw_events_mirror[0]);
 }
@@ -181,10 +329,12 @@ fn two_packets_only_processes() {
             connections: false,
             processes: true,
             total_utilization: false,
+            ..Default::default()
         },
+        ..Default::default()
     };
 
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
     assert_snapshot!(&terminal_draw_events_mirror[0]);
     assert_snapshot!(&terminal_draw_events_mirror[1]);
@@ -205,10 +355,12 @@ fn two_packets_only_connections() {
             connections: true,
             processes: false,
             total_utilization: false,
+            ..Default::default()
         },
+        ..Default::default()
     };
 
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
     assert_snapshot!(&terminal_draw_events_mirror[0]);
     assert_snapshot!(&terminal_draw_events_mirror[1]);
@@ -229,10 +381,12 @@ fn two_packets_only_addresses() {
             connections: false,
             processes: false,
             total_utilization: false,
+            ..Default::default()
         },
+        ..Default::default()
     };
 
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
     assert_snapshot!(&terminal_draw_events_mirror[0]);
     assert_snapshot!(&terminal_draw_events_mirror[1]);
@@ -255,10 +409,12 @@ fn two_windows_split_horizontally() {
             connections: true,
             processes: false,
             total_utilization: false,
+            ..Default::default()
         },
+        ..Default::default()
     };
 
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
     assert_snapshot!(&terminal_draw_events_mirror[0]);
 }
@@ -280,10 +436,12 @@ fn two_windows_split_vertically() {
             connections: true,
             processes: false,
             total_utilization: false,
+            ..Default::default()
         },
+        ..Default::default()
     };
 
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
     assert_snapshot!(&terminal_draw_events_mirror[0]);
 }
@@ -300,7 +458,7 @@ fn one_packet_of_traffic() {
     let (terminal_events, terminal_draw_events, backend) = test_backend_factory(190, 50);
     let os_input = os_input_output(network_frames, 2);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -323,7 +481,7 @@ fn bi_directional_traffic() {
     let (terminal_events, terminal_draw_events, backend) = test_backend_factory(190, 50);
     let os_input = os_input_output(network_frames, 2);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -361,7 +519,7 @@ fn multiple_packets_of_traffic_from_different_connections() {
     let (terminal_events, terminal_draw_events, backend) = test_backend_factory(190, 50);
     let os_input = os_input_output(network_frames, 2);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -399,7 +557,7 @@ fn multiple_packets_of_traffic_from_single_connection() {
     let (terminal_events, terminal_draw_events, backend) = test_backend_factory(190, 50);
     let os_input = os_input_output(network_frames, 2);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -437,7 +595,7 @@ fn one_process_with_multiple_connections() {
     let (terminal_events, terminal_draw_events, backend) = test_backend_factory(190, 50);
     let os_input = os_input_output(network_frames, 2);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -489,7 +647,7 @@ fn multiple_processes_with_multiple_connections() {
     let (terminal_events, terminal_draw_events, backend) = test_backend_factory(190, 50);
     let os_input = os_input_output(network_frames, 2);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -527,7 +685,7 @@ fn multiple_connections_from_remote_address() {
     let (terminal_events, terminal_draw_events, backend) = test_backend_factory(190, 50);
     let os_input = os_input_output(network_frames, 2);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -567,7 +725,7 @@ fn sustained_traffic_from_one_process() {
 
     let os_input = os_input_output(network_frames, 3);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -608,7 +766,7 @@ fn sustained_traffic_from_one_process_total() {
     let os_input = os_input_output(network_frames, 3);
     let mut opts = opts_ui();
     opts.render_opts.total_utilization = true;
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -662,7 +820,7 @@ fn sustained_traffic_from_multiple_processes() {
 
     let os_input = os_input_output(network_frames, 3);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -717,7 +875,7 @@ fn sustained_traffic_from_multiple_processes_total() {
     let os_input = os_input_output(network_frames, 3);
     let mut opts = opts_ui();
     opts.render_opts.total_utilization = true;
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -799,7 +957,7 @@ fn sustained_traffic_from_multiple_processes_bi_directional() {
 
     let os_input = os_input_output(network_frames, 3);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -882,7 +1040,7 @@ fn sustained_traffic_from_multiple_processes_bi_directional_total() {
     let os_input = os_input_output(network_frames, 3);
     let mut opts = opts_ui();
     opts.render_opts.total_utilization = true;
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -977,21 +1135,29 @@ fn traffic_with_host_names() {
     );
     let dns_client = create_fake_dns_client(ips_to_hostnames);
     let on_winch = create_fake_on_winch(false);
+    let on_terminate = create_fake_on_terminate(false);
     let cleanup = Box::new(|| {});
     let write_to_stdout = Box::new({ move |_output: String| {} });
 
     let os_input = OsInputOutput {
         network_interfaces: get_interfaces(),
         network_frames,
+        host_only_interfaces: Vec::new(),
+        uncaptured_interfaces: Vec::new(),
+        link_speeds: HashMap::new(),
         get_open_sockets,
+        get_process_cmdline,
+        get_process_container,
+        copy_to_clipboard,
         keyboard_events: sleep_and_quit_events(3),
         dns_client,
         on_winch,
+        on_terminate,
         cleanup,
         write_to_stdout,
     };
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -1007,6 +1173,68 @@ fn traffic_with_host_names() {
     assert_snapshot!(&terminal_draw_events_mirror[2]);
 }
 
+#[test]
+fn remote_addresses_grouped_by_domain() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(build_tcp_packet(
+            "3.3.3.3",
+            "10.0.0.2",
+            1337,
+            4435,
+            b"Same domain, other host",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2",
+            "10.0.0.2",
+            54321,
+            4434,
+            b"Nobody knows my name",
+        )),
+        None, // sleep
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"Back again",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let mut ips_to_hostnames = HashMap::new();
+    ips_to_hostnames.insert(
+        IpAddr::V4("1.1.1.1".parse().unwrap()),
+        String::from("one.example.co.uk"),
+    );
+    ips_to_hostnames.insert(
+        IpAddr::V4("3.3.3.3".parse().unwrap()),
+        String::from("three.example.co.uk"),
+    );
+    let os_input = os_input_output_factory(
+        network_frames,
+        None,
+        create_fake_dns_client(ips_to_hostnames),
+        sleep_and_quit_events(3),
+    );
+    let mut opts = opts_ui();
+    opts.render_opts.addresses = true;
+    opts.render_opts.total_utilization = true;
+    opts.render_opts.group_by_domain = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 3);
+    assert_snapshot!(&terminal_draw_events_mirror[1]);
+    assert_snapshot!(&terminal_draw_events_mirror[2]);
+}
+
 #[test]
 fn truncate_long_hostnames() {
     let network_frames = vec![NetworkFrames::new(vec![
@@ -1086,21 +1314,29 @@ fn truncate_long_hostnames() {
     );
     let dns_client = create_fake_dns_client(ips_to_hostnames);
     let on_winch = create_fake_on_winch(false);
+    let on_terminate = create_fake_on_terminate(false);
     let cleanup = Box::new(|| {});
     let write_to_stdout = Box::new({ move |_output: String| {} });
 
     let os_input = OsInputOutput {
         network_interfaces: get_interfaces(),
         network_frames,
+        host_only_interfaces: Vec::new(),
+        uncaptured_interfaces: Vec::new(),
+        link_speeds: HashMap::new(),
         get_open_sockets,
+        get_process_cmdline,
+        get_process_container,
+        copy_to_clipboard,
         keyboard_events: sleep_and_quit_events(3),
         dns_client,
         on_winch,
+        on_terminate,
         cleanup,
         write_to_stdout,
     };
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -1194,21 +1430,29 @@ fn no_resolve_mode() {
     );
     let dns_client = None;
     let on_winch = create_fake_on_winch(false);
+    let on_terminate = create_fake_on_terminate(false);
     let cleanup = Box::new(|| {});
     let write_to_stdout = Box::new({ move |_output: String| {} });
 
     let os_input = OsInputOutput {
         network_interfaces: get_interfaces(),
         network_frames,
+        host_only_interfaces: Vec::new(),
+        uncaptured_interfaces: Vec::new(),
+        link_speeds: HashMap::new(),
         get_open_sockets,
+        get_process_cmdline,
+        get_process_container,
+        copy_to_clipboard,
         keyboard_events: sleep_and_quit_events(3),
         dns_client,
         on_winch,
+        on_terminate,
         cleanup,
         write_to_stdout,
     };
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -1238,21 +1482,29 @@ fn traffic_with_winch_event() {
 
     let dns_client = create_fake_dns_client(HashMap::new());
     let on_winch = create_fake_on_winch(true);
+    let on_terminate = create_fake_on_terminate(false);
     let cleanup = Box::new(|| {});
     let write_to_stdout = Box::new({ move |_output: String| {} });
 
     let os_input = OsInputOutput {
         network_interfaces: get_interfaces(),
         network_frames,
+        host_only_interfaces: Vec::new(),
+        uncaptured_interfaces: Vec::new(),
+        link_speeds: HashMap::new(),
         get_open_sockets,
+        get_process_cmdline,
+        get_process_container,
+        copy_to_clipboard,
         keyboard_events: sleep_and_quit_events(2),
         dns_client,
         on_winch,
+        on_terminate,
         cleanup,
         write_to_stdout,
     };
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -1269,6 +1521,37 @@ fn traffic_with_winch_event() {
     assert_snapshot!(&terminal_draw_events_mirror[2]);
 }
 
+#[test]
+fn terminating_signal_restores_the_terminal_without_a_key_press() {
+    let network_frames = vec![NetworkFrames::new(vec![Some(build_tcp_packet(
+        "10.0.0.2",
+        "1.1.1.1",
+        443,
+        12345,
+        b"I am a fake tcp packet",
+    ))]) as Box<dyn DataLinkReceiver>];
+
+    let (terminal_events, _, backend) = test_backend_factory(190, 50);
+    let mut os_input = os_input_output_factory(
+        network_frames,
+        None,
+        create_fake_dns_client(HashMap::new()),
+        // nobody is there to press a key, so this waits a good while longer than the test takes
+        Box::new(KeyboardEvents::new(vec![None; 100])),
+    );
+    os_input.on_terminate = create_fake_on_terminate(true);
+    let opts = opts_ui();
+    start(backend, os_input, opts).unwrap();
+    let terminal_events = terminal_events.lock().unwrap();
+
+    assert_eq!(&terminal_events[..2], &[Clear, HideCursor][..]);
+    assert!(terminal_events.contains(&Draw));
+    assert_eq!(
+        &terminal_events[terminal_events.len() - 2..],
+        &[Clear, ShowCursor][..]
+    );
+}
+
 #[test]
 fn layout_full_width_under_30_height() {
     let network_frames = vec![NetworkFrames::new(vec![
@@ -1306,7 +1589,7 @@ fn layout_full_width_under_30_height() {
 
     let os_input = os_input_output(network_frames, 2);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -1358,7 +1641,7 @@ fn layout_under_120_width_full_height() {
 
     let os_input = os_input_output(network_frames, 2);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -1409,7 +1692,7 @@ fn layout_under_120_width_under_30_height() {
     let (terminal_events, terminal_draw_events, backend) = test_backend_factory(119, 29);
     let os_input = os_input_output(network_frames, 2);
     let opts = opts_ui();
-    start(backend, os_input, opts);
+    start(backend, os_input, opts).unwrap();
     let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
 
     let expected_terminal_events = vec![
@@ -1424,3 +1707,1741 @@ fn layout_under_120_width_under_30_height() {
     assert_snapshot!(&terminal_draw_events_mirror[0]);
     assert_snapshot!(&terminal_draw_events_mirror[1]);
 }
+
+#[test]
+fn sustained_traffic_exact_bytes() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            &[b'a'; 1400],
+        )),
+        None, // sleep
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            &[b'b'; 1400],
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+
+    let os_input = os_input_output(network_frames, 3);
+    let mut opts = opts_ui();
+    opts.render_opts.total_utilization = true;
+    opts.render_opts.exact_bytes = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 3);
+    assert_snapshot!(&terminal_draw_events_mirror[2]);
+}
+
+#[test]
+fn connections_with_up_down_ratio() {
+    let network_frames = sample_frames();
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 2);
+    let mut opts = opts_ui();
+    opts.render_opts.connections = true;
+    opts.render_opts.up_down_ratio = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 2);
+    assert_snapshot!(&terminal_draw_events_mirror[0]);
+    assert_snapshot!(&terminal_draw_events_mirror[1]);
+}
+
+#[test]
+fn percent_of_the_table_traffic_for_each_row() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1, which is three times as far as 2.2.2.2 from here",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2",
+            "10.0.0.2",
+            54321,
+            443,
+            b"I have come from 2.2.2.2",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 2);
+    let mut opts = opts_ui();
+    opts.render_opts.percent = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 2);
+    assert_snapshot!(&terminal_draw_events_mirror[0]);
+    assert_snapshot!(&terminal_draw_events_mirror[1]);
+}
+
+#[test]
+fn bars_of_the_rates_against_the_busiest_row() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1, which is three times as far as 2.2.2.2 from here",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2",
+            "10.0.0.2",
+            54321,
+            443,
+            b"I have come from 2.2.2.2",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 2);
+    let mut opts = opts_ui();
+    opts.render_opts.addresses = true;
+    opts.render_opts.bars = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    let bars = terminal_draw_events_mirror[1]
+        .lines()
+        .map(|line| line.matches('█').count())
+        .filter(|&blocks| blocks > 0)
+        .collect::<Vec<_>>();
+    assert_eq!(bars.len(), 2);
+    // the busiest row takes all of the room left, the other about a third of it
+    assert!(bars[0] > 2 * bars[1]);
+}
+
+#[test]
+fn connections_with_session_bytes() {
+    let network_frames = sample_frames();
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 2);
+    let mut opts = opts_ui();
+    opts.render_opts.connections = true;
+    opts.render_opts.session_bytes = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 2);
+    assert_snapshot!(&terminal_draw_events_mirror[0]);
+    assert_snapshot!(&terminal_draw_events_mirror[1]);
+}
+
+#[test]
+fn t_key_toggles_the_session_bytes_column() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        None, // sleep
+    ]) as Box<dyn DataLinkReceiver>];
+
+    // sleep for 2s, then press t, then quit
+    let mut events: Vec<Option<Event>> = vec![None, None];
+    events.push(Some(Event::Key(Key::Char('t'))));
+    events.push(Some(Event::Key(Key::Ctrl('c'))));
+
+    let events = Box::new(KeyboardEvents::new(events));
+    let os_input = os_input_output_factory(network_frames, None, None, events);
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let mut opts = opts_ui();
+    opts.render_opts.connections = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 3);
+    assert_snapshot!(&terminal_draw_events_mirror[2]);
+}
+
+#[test]
+fn connections_filtered_by_protocol_lose_the_protocol_column() {
+    let network_frames = sample_frames();
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 2);
+    let mut opts = opts_ui();
+    opts.render_opts.connections = true;
+    opts.render_opts.protocol = Some(Protocol::Tcp);
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 2);
+    assert_snapshot!(&terminal_draw_events_mirror[1]);
+}
+
+#[test]
+fn egress_capture_direction() {
+    let network_frames = vec![NetworkFrames::new(vec![Some(build_tcp_packet(
+        "10.0.0.2",
+        "1.1.1.1",
+        443,
+        12345,
+        b"I am leaving through a one-way tap",
+    ))]) as Box<dyn DataLinkReceiver>];
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 2);
+    let mut opts = opts_ui();
+    opts.render_opts.total_utilization = true;
+    opts.render_opts.capture_direction = "egress".parse().unwrap();
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 2);
+    assert_snapshot!(&terminal_draw_events_mirror[1]);
+}
+
+#[test]
+fn group_by_interface_and_process() {
+    let network_frames = sample_frames();
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 2);
+    let mut opts = opts_ui();
+    opts.render_opts.group_by = Some("interface,process".parse().unwrap());
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 2);
+    assert_snapshot!(&terminal_draw_events_mirror[0]);
+    assert_snapshot!(&terminal_draw_events_mirror[1]);
+}
+
+#[test]
+fn processes_with_their_pids() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(build_tcp_packet(
+            "3.3.3.3",
+            "10.0.0.2",
+            1337,
+            4435,
+            b"Mine has gone away by now",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 2);
+    let mut opts = opts_ui();
+    opts.render_opts.processes = true;
+    opts.render_opts.show_pid = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 2);
+    assert_snapshot!(&terminal_draw_events_mirror[1]);
+}
+
+#[test]
+fn only_the_quit_keys_given_quit() {
+    // sleep for 1s, press `first`, sleep for 1s, then press `second`
+    let draws = |quit_keys: QuitKeys, first: Key, second: Key| {
+        let events = vec![
+            None,
+            Some(Event::Key(first)),
+            None,
+            Some(Event::Key(second)),
+        ];
+        let events = Box::new(KeyboardEvents::new(events));
+        let os_input = os_input_output_factory(sample_frames(), None, None, events);
+        let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+        let mut opts = opts_ui();
+        opts.render_opts.quit_key = quit_keys;
+        start(backend, os_input, opts).unwrap();
+        let draws = terminal_draw_events.lock().unwrap().len();
+        draws
+    };
+
+    let (ctrl_c, q) = (Key::Ctrl('c'), Key::Char('q'));
+    assert!(draws(QuitKeys::Q, ctrl_c, q) > draws(QuitKeys::Both, ctrl_c, q));
+    assert!(draws(QuitKeys::CtrlC, q, ctrl_c) > draws(QuitKeys::Both, q, ctrl_c));
+}
+
+#[test]
+fn processes_with_their_users() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2",
+            "10.0.0.2",
+            54321,
+            4434,
+            b"My owner has no name",
+        )),
+        Some(build_tcp_packet(
+            "3.3.3.3",
+            "10.0.0.2",
+            1337,
+            4435,
+            b"Mine has gone away by now",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 2);
+    let mut opts = opts_ui();
+    opts.render_opts.processes = true;
+    opts.render_opts.show_user = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 2);
+    let processes = &terminal_draw_events_mirror[1];
+    assert!(processes.contains("User"));
+    assert!(processes.contains("root"));
+    assert!(processes.contains("1001"));
+    assert!(!processes.contains("alice"));
+}
+
+#[test]
+fn full_command_lines_are_cut_on_the_left() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(build_tcp_packet(
+            "2.2.2.2",
+            "10.0.0.2",
+            54321,
+            4434,
+            b"My command line cannot be read",
+        )),
+        Some(build_tcp_packet(
+            "3.3.3.3",
+            "10.0.0.2",
+            1337,
+            4435,
+            b"Mine has gone away by now",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 2);
+    let mut opts = opts_ui();
+    opts.render_opts.processes = true;
+    opts.render_opts.connections = true;
+    opts.render_opts.full_cmdline = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 2);
+    assert_snapshot!(&terminal_draw_events_mirror[1]);
+}
+
+#[test]
+fn interface_breakdown_is_a_table_of_its_own() {
+    let network_frames = sample_frames();
+
+    // sleep for 1s, then move the focus to the last of the four tables, then quit
+    let mut events: Vec<Option<Event>> = iter::repeat(None).take(1).collect();
+    for _ in 0..3 {
+        events.push(Some(Event::Key(Key::Char('\t'))));
+    }
+    events.push(None);
+    events.push(Some(Event::Key(Key::Ctrl('c'))));
+
+    let events = Box::new(KeyboardEvents::new(events));
+    let os_input = os_input_output_factory(network_frames, None, None, events);
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let mut opts = opts_ui();
+    opts.render_opts.interface_breakdown = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror[0].contains("Utilization by interface"));
+    assert_snapshot!(terminal_draw_events_mirror.last().unwrap());
+}
+
+#[test]
+fn capture_stats_below_the_tables() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(vec![0; 20]), // neither ip nor ethernet
+        Some(build_tcp_packet(
+            "3.3.3.3",
+            "10.0.0.2",
+            1337,
+            4435,
+            b"Mine has gone away by now",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 2);
+    let mut opts = opts_ui();
+    opts.render_opts.processes = true;
+    opts.render_opts.show_stats = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 2);
+    assert_snapshot!(&terminal_draw_events_mirror[0]);
+    assert_snapshot!(&terminal_draw_events_mirror[1]);
+}
+
+#[test]
+fn dns_stats_are_shown_below_the_tables() {
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(sample_frames(), 2);
+    let mut opts = opts_ui();
+    opts.render_opts.dns_stats = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    // how long the lookups take varies, so only the line itself is checked
+    let footer = terminal_draw_events_mirror[0]
+        .lines()
+        .find(|line| line.contains("still pending"))
+        .expect("no dns stats below the tables");
+    assert!(footer.starts_with(" Resolved "));
+}
+
+#[test]
+fn connections_with_packet_stats_and_up_down_ratio() {
+    let network_frames = sample_frames();
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(network_frames, 2);
+    let mut opts = opts_ui();
+    opts.render_opts.connections = true;
+    opts.render_opts.up_down_ratio = true;
+    opts.render_opts.packet_stats = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 2);
+    assert_snapshot!(&terminal_draw_events_mirror[0]);
+    assert_snapshot!(&terminal_draw_events_mirror[1]);
+}
+
+#[test]
+fn host_only_capture_is_shown_in_the_header() {
+    let network_frames = sample_frames();
+
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let mut os_input = os_input_output(network_frames, 2);
+    os_input.host_only_interfaces = vec![String::from("interface_name")];
+    let opts = opts_ui();
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 2);
+    assert!(terminal_draw_events_mirror[0]
+        .lines()
+        .next()
+        .unwrap()
+        .contains("[host traffic only: interface_name]"));
+}
+
+fn sustained_traffic_frames() -> Vec<Box<dyn DataLinkReceiver>> {
+    vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        None, // sleep
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"Same here, but one second later",
+        )),
+    ]) as Box<dyn DataLinkReceiver>]
+}
+
+#[test]
+fn fail_if_above_threshold_breached() {
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(sustained_traffic_frames(), 3);
+    let mut opts = opts_ui();
+    opts.fail_if_above = Some("1Bps".parse().unwrap());
+    let exit_status = start(backend, os_input, opts).unwrap();
+
+    assert_eq!(exit_status, ExitStatus::ThresholdBreached);
+    assert_eq!(exit_status.code(), 3);
+}
+
+#[test]
+fn fail_if_above_threshold_not_breached() {
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(sustained_traffic_frames(), 3);
+    let mut opts = opts_ui();
+    opts.fail_if_above = Some("1Gbps".parse().unwrap());
+    let exit_status = start(backend, os_input, opts).unwrap();
+
+    assert_eq!(exit_status, ExitStatus::Clean);
+    assert_eq!(exit_status.code(), 0);
+}
+
+#[test]
+fn duration_ends_the_run_without_quitting() {
+    let (terminal_events, _, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output_factory(
+        sustained_traffic_frames(),
+        None,
+        create_fake_dns_client(HashMap::new()),
+        Box::new(KeyboardEvents::new(vec![])),
+    );
+    let mut opts = opts_ui();
+    opts.duration = Some(2);
+    let exit_status = start(backend, os_input, opts).unwrap();
+
+    assert_eq!(exit_status, ExitStatus::Clean);
+    let terminal_events = terminal_events.lock().unwrap();
+    assert_eq!(
+        &terminal_events[terminal_events.len() - 2..],
+        &[Clear, ShowCursor]
+    );
+}
+
+#[test]
+fn duration_without_any_traffic_is_told_apart() {
+    let network_frames =
+        vec![NetworkFrames::new(vec![None, None, None]) as Box<dyn DataLinkReceiver>];
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output_factory(
+        network_frames,
+        None,
+        create_fake_dns_client(HashMap::new()),
+        Box::new(KeyboardEvents::new(vec![])),
+    );
+    let mut opts = opts_ui();
+    opts.duration = Some(1);
+    let exit_status = start(backend, os_input, opts).unwrap();
+
+    assert_eq!(exit_status, ExitStatus::NothingCaptured);
+    assert_eq!(exit_status.code(), 4);
+}
+
+#[test]
+fn quiet_until_traffic_then_capture_for_the_duration() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        None, // sleep
+        None, // sleep
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output_factory(
+        network_frames,
+        None,
+        create_fake_dns_client(HashMap::new()),
+        Box::new(KeyboardEvents::new(vec![])),
+    );
+    let mut opts = opts_ui();
+    opts.quiet_until_traffic = true;
+    opts.duration = Some(1);
+    let exit_status = start(backend, os_input, opts).unwrap();
+
+    assert_eq!(exit_status, ExitStatus::Clean);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+    // the duration only starts counting once the traffic shows up
+    assert!(terminal_draw_events_mirror.len() > 2);
+    assert!(terminal_draw_events_mirror[0].contains("Waiting for traffic..."));
+    assert!(!terminal_draw_events_mirror[0].contains("Utilization by"));
+    assert!(terminal_draw_events_mirror
+        .iter()
+        .any(|draw| draw.contains("1.1.1.1")));
+}
+
+#[test]
+fn summary_of_the_session_printed_on_quit() {
+    let (_, _, backend) = test_backend_factory(190, 50);
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let os_input = os_input_output_stdout(sustained_traffic_frames(), 3, Some(stdout.clone()));
+    let mut opts = opts_ui();
+    opts.summary = true;
+    let exit_status = start(backend, os_input, opts).unwrap();
+
+    assert_eq!(exit_status, ExitStatus::Clean);
+    let stdout = String::from_utf8(stdout.lock().unwrap().clone()).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert!(lines[0].starts_with("Session: 00:00:0"));
+    assert_eq!(
+        lines[1..],
+        [
+            "Total up/down: 0B / 95B",
+            "Top processes:",
+            "  1 0B / 95B",
+            "Top remote hosts:",
+            "  1.1.1.1 0B / 95B",
+        ]
+    );
+}
+
+#[test]
+fn graph_of_the_total_throughput_above_the_tables() {
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let os_input = os_input_output(sustained_traffic_frames(), 3);
+    let mut opts = opts_ui();
+    opts.render_opts.graph = true;
+    start(backend, os_input, opts).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_eq!(terminal_draw_events_mirror.len(), 3);
+    assert_snapshot!(&terminal_draw_events_mirror[0]);
+    assert_snapshot!(&terminal_draw_events_mirror[2]);
+}
+
+fn export_frame_with(file_name: &str, opts: Opt) -> String {
+    let path = std::env::temp_dir().join(file_name);
+    let backend = FrameExportBackend::new(path.clone(), 190, 50).unwrap();
+    let os_input = os_input_output(sustained_traffic_frames(), 3);
+    start(backend, os_input, opts).unwrap();
+    let exported = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    exported
+}
+
+fn export_frame(file_name: &str) -> String {
+    export_frame_with(file_name, opts_ui())
+}
+
+#[test]
+fn export_frame_as_svg() {
+    let exported = export_frame("bandwhich_export_frame_as_svg.svg");
+
+    assert!(exported.starts_with("<svg "));
+    assert!(exported.trim_end().ends_with("</svg>"));
+    assert_eq!(exported.matches("<text ").count(), 50);
+    assert!(exported.contains("Utilization by process name"));
+    assert!(exported.contains("1.1.1.1:12345"));
+}
+
+#[test]
+fn export_frame_as_ansi() {
+    let exported = export_frame("bandwhich_export_frame_as_ansi.txt");
+
+    assert_eq!(exported.lines().count(), 50);
+    assert!(exported.contains("\x1b[1;32m Total Up / Down: "));
+    assert!(exported.contains("Utilization by process name"));
+    assert!(exported.contains("1.1.1.1:12345"));
+}
+
+#[test]
+fn no_color_leaves_the_text_plain() {
+    let mut opts = opts_ui();
+    opts.render_opts.no_color = true;
+    let exported = export_frame_with("bandwhich_no_color.txt", opts);
+
+    // bold is not a color, it stays
+    assert!(exported.contains("\x1b[1m Total Up / Down: "));
+    assert!(!exported.contains(";3"));
+    assert!(!exported.contains("\x1b[3"));
+    assert!(exported.contains("1.1.1.1:12345"));
+}
+
+#[test]
+fn highlight_host_addresses() {
+    let highlighted_addresses = |file_name: &str, no_color: bool| {
+        let network_frames = vec![NetworkFrames::new(vec![
+            Some(build_tcp_packet(
+                "10.0.0.2",
+                "10.0.0.2",
+                443,
+                5555,
+                b"I am talking to myself",
+            )),
+            Some(build_tcp_packet(
+                "1.1.1.1",
+                "10.0.0.2",
+                12345,
+                443,
+                b"I have come from 1.1.1.1",
+            )),
+        ]) as Box<dyn DataLinkReceiver>];
+        let path = std::env::temp_dir().join(file_name);
+        let backend = FrameExportBackend::new(path.clone(), 190, 50).unwrap();
+        let os_input = os_input_output(network_frames, 3);
+        let mut opts = opts_ui();
+        opts.render_opts.addresses = true;
+        opts.render_opts.highlight_host = true;
+        opts.render_opts.no_color = no_color;
+        start(backend, os_input, opts).unwrap();
+        let exported = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        exported
+    };
+
+    let colored = highlighted_addresses("bandwhich_highlight_host_colored.txt", false);
+    assert!(colored.contains("\x1b[1;36m10.0.0.2\x1b[0m"));
+    assert!(!colored.contains("\x1b[1;36m1.1.1.1"));
+    assert!(!colored.contains("(this host)"));
+
+    let annotated = highlighted_addresses("bandwhich_highlight_host_annotated.txt", true);
+    assert!(annotated.contains("10.0.0.2 (this host)"));
+    assert!(!annotated.contains("1.1.1.1 (this host)"));
+    assert!(!annotated.contains("\x1b[1;36m"));
+}
+
+#[test]
+fn tagged_networks_are_labeled_in_their_own_color() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(build_tcp_packet(
+            "3.3.3.3",
+            "10.0.0.2",
+            1337,
+            4435,
+            b"Same here, but one of a kind",
+        )),
+    ]) as Box<dyn DataLinkReceiver>];
+    let path = std::env::temp_dir().join("bandwhich_tagged_networks.txt");
+    let backend = FrameExportBackend::new(path.clone(), 190, 50).unwrap();
+    let os_input = os_input_output(network_frames, 3);
+    let mut opts = opts_ui();
+    opts.render_opts.tag = vec![
+        "1.0.0.0/8=cloud".parse().unwrap(),
+        "1.1.1.0/24=dns".parse().unwrap(),
+    ];
+    start(backend, os_input, opts).unwrap();
+    let exported = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // the most specific network wins, in the color of the second tag
+    assert!(exported.contains("\x1b[34m1.1.1.1 [dns]"));
+    assert!(exported
+        .lines()
+        .any(|line| line.contains("=> 1.1.1.1:") && line.contains("(tcp) [dns]")));
+    assert!(!exported.contains("[cloud]"));
+    assert!(exported.contains("3.3.3.3"));
+    assert!(!exported.contains("3.3.3.3 ["));
+    assert!("1.1.1.0/24".parse::<NetworkTag>().is_err());
+    assert!("nas=nas".parse::<NetworkTag>().is_err());
+}
+
+#[test]
+fn sort_key_is_cycled_and_marked() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        None, // sleep
+    ]) as Box<dyn DataLinkReceiver>];
+
+    // sleep for 1s, then press s twice to sort by upload, sleep for 1s, then quit
+    let mut events: Vec<Option<Event>> = iter::repeat(None).take(1).collect();
+    events.push(Some(Event::Key(Key::Char('s'))));
+    events.push(Some(Event::Key(Key::Char('s'))));
+    events.push(None);
+    events.push(Some(Event::Key(Key::Ctrl('c'))));
+
+    let events = Box::new(KeyboardEvents::new(events));
+    let os_input = os_input_output_factory(network_frames, None, None, events);
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    start(backend, os_input, opts_ui()).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(!terminal_draw_events_mirror[0].contains('▾'));
+    // draws only hold the cells that changed, so the unchanged "Up" is blank
+    assert!(terminal_draw_events_mirror
+        .iter()
+        .any(|draw| draw.contains("▾ / Down")));
+}
+
+#[test]
+fn help_shows_the_keys_until_any_key_is_pressed() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        None, // sleep
+    ]) as Box<dyn DataLinkReceiver>];
+
+    // sleep for 1s, show the help and close it with q rather than quitting, then show it again
+    let mut events: Vec<Option<Event>> = iter::repeat(None).take(1).collect();
+    events.push(Some(Event::Key(Key::Char('?'))));
+    events.push(Some(Event::Key(Key::Char('q'))));
+    events.push(Some(Event::Key(Key::Char('?'))));
+    events.push(None);
+    events.push(Some(Event::Key(Key::Ctrl('c'))));
+
+    let events = Box::new(KeyboardEvents::new(events));
+    let os_input = os_input_output_factory(network_frames, None, None, events);
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    start(backend, os_input, opts_ui()).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    let help_draws: Vec<&String> = terminal_draw_events_mirror
+        .iter()
+        .filter(|draw| draw.contains("Press any key to close this."))
+        .collect();
+    assert_eq!(help_draws.len(), 2);
+    assert!(help_draws[0].contains("reset the session counters"));
+}
+
+#[test]
+fn search_filters_the_tables() {
+    let network_frames = vec![NetworkFrames::new(vec![
+        Some(build_tcp_packet(
+            "1.1.1.1",
+            "10.0.0.2",
+            12345,
+            443,
+            b"I have come from 1.1.1.1",
+        )),
+        Some(build_tcp_packet(
+            "3.3.3.3",
+            "10.0.0.2",
+            1337,
+            4435,
+            b"Awesome, I'm from 3.3.3.3",
+        )),
+        None, // sleep
+    ]) as Box<dyn DataLinkReceiver>];
+
+    // sleep for 1s, then search for 3.3.3, sleep for 1s, then quit
+    let mut events: Vec<Option<Event>> = iter::repeat(None).take(1).collect();
+    events.push(Some(Event::Key(Key::Char('/'))));
+    for character in "3.3.3".chars() {
+        events.push(Some(Event::Key(Key::Char(character))));
+    }
+    events.push(Some(Event::Key(Key::Char('\n'))));
+    events.push(None);
+    events.push(Some(Event::Key(Key::Ctrl('c'))));
+
+    let events = Box::new(KeyboardEvents::new(events));
+    let os_input = os_input_output_factory(network_frames, None, None, events);
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    start(backend, os_input, opts_ui()).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror
+        .iter()
+        .any(|draw| draw.contains("Search: _")));
+    assert_snapshot!(terminal_draw_events_mirror.last().unwrap());
+}
+
+#[test]
+fn lost_interface_is_logged() {
+    let network_frames = vec![LostInterfaceFrames::new(vec![Some(build_tcp_packet(
+        "1.1.1.1",
+        "10.0.0.2",
+        12345,
+        443,
+        b"I have come from 1.1.1.1",
+    ))]) as Box<dyn DataLinkReceiver>];
+
+    // sleep for 1s, then press l, sleep for 1s, then quit
+    let mut events: Vec<Option<Event>> = iter::repeat(None).take(1).collect();
+    events.push(Some(Event::Key(Key::Char('l'))));
+    events.push(None);
+    events.push(Some(Event::Key(Key::Ctrl('c'))));
+
+    let events = Box::new(KeyboardEvents::new(events));
+    let os_input = os_input_output_factory(network_frames, None, None, events);
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    start(backend, os_input, opts_ui()).unwrap();
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror.iter().any(|draw| draw
+        .contains("interface interface_name is gone and no longer captured: No such device")));
+}
+
+// a connection from a local port of its own for every download
+fn downloads_of_connections(downloads: impl Iterator<Item = u128>) -> Utilization {
+    let mut utilization = Utilization::new();
+    for (index, download) in downloads.enumerate() {
+        utilization.update(Segment {
+            interface_name: String::from("interface_name"),
+            connection: Connection::new(
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)), 443),
+                IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+                10000 + index as u16,
+                Protocol::Tcp,
+            ),
+            direction: Direction::Download,
+            data_length: download,
+            retransmitted: false,
+        });
+    }
+    utilization
+}
+
+#[test]
+fn the_same_state_is_always_written_out_the_same_way() {
+    // every map the rows are counted in iterates in an order of its own
+    let outputs = (0..2)
+        .map(|_| {
+            let (_, _, backend) = test_backend_factory(190, 50);
+            let mut ui = Ui::new(backend, RenderOpts::default(), HashSet::new());
+            ui.update_state(
+                HashMap::new(),
+                downloads_of_connections((1..=20).map(|index| 100 * (index % 3))),
+                HashMap::new(),
+                Duration::from_secs(1),
+            );
+            let mut output = String::new();
+            ui.output_text(&mut |line| output.push_str(&format!("{}\n", line)));
+            ui.output_json(&mut |line| output.push_str(&line));
+            // the clock may move on between the two
+            let timestamps = Regex::new(r#"<\d+>|"elapsed_seconds":\d+"#).unwrap();
+            timestamps.replace_all(&output, "").into_owned()
+        })
+        .collect::<Vec<_>>();
+
+    // the rows just as busy as each other are in the order of their connections
+    assert!(outputs[0].find(":10001 => ").unwrap() < outputs[0].find(":10004 => ").unwrap());
+    assert_eq!(outputs[0], outputs[1]);
+}
+
+#[test]
+fn focused_table_scrolls_no_further_than_its_last_row() {
+    let render_opts = RenderOpts {
+        connections: true,
+        ..Default::default()
+    };
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 20);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    // the first tick only tells that the rates are being collected
+    for _ in 0..2 {
+        ui.update_state(
+            HashMap::new(),
+            downloads_of_connections((1..=20).map(|index| 100 * index)),
+            HashMap::new(),
+            Duration::from_secs(1),
+        );
+    }
+
+    // 14 of the 20 rows fit, so a page down stops at the 7th
+    ui.scroll_down(SCROLL_PAGE_ROWS);
+    ui.draw(false);
+    ui.scroll_up(1);
+    ui.draw(false);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror[0].contains("[7-20 of 20]"));
+    assert_snapshot!(&terminal_draw_events_mirror[1]);
+}
+
+#[test]
+fn jumping_to_the_top_talker_pins_it_above_the_rows_sorted_before_it() {
+    let render_opts = RenderOpts {
+        connections: true,
+        sort_by: SortBy::Name,
+        ..Default::default()
+    };
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 20);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    // the first tick only tells that the rates are being collected
+    for _ in 0..2 {
+        ui.update_state(
+            HashMap::new(),
+            downloads_of_connections((1..=20).map(|index| 100 * index)),
+            HashMap::new(),
+            Duration::from_secs(1),
+        );
+    }
+
+    // the busiest connection is the last by name, below the 14 rows that fit
+    ui.scroll_down(SCROLL_PAGE_ROWS);
+    ui.jump_to_top_talker();
+    ui.draw(false);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror[0].contains("[1-14 of 20]"));
+    assert_snapshot!(&terminal_draw_events_mirror[0]);
+}
+
+#[test]
+fn clicking_a_column_header_sorts_by_it() {
+    let render_opts = RenderOpts {
+        connections: true,
+        ..Default::default()
+    };
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 20);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    ui.update_state(
+        HashMap::new(),
+        downloads_of_connections((1..=20).map(|index| 100 * index)),
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+    ui.draw(false);
+
+    // the headers are on the third line, below the total and the top border
+    assert_eq!(ui.click(1, 2), ClickAction::Sorted(SortBy::Name));
+    ui.update_state(
+        HashMap::new(),
+        downloads_of_connections((1..=20).map(|index| 100 * index)),
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+    ui.draw(false);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    // only what changed is drawn the second time
+    assert!(!terminal_draw_events_mirror[0].contains('▾'));
+    assert!(terminal_draw_events_mirror[1].contains('▾'));
+}
+
+#[test]
+fn pinned_row_stays_on_top_once_idle() {
+    let render_opts = RenderOpts {
+        connections: true,
+        ..Default::default()
+    };
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 20);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    ui.update_state(
+        HashMap::new(),
+        downloads_of_connections((1..=20).map(|index| 100 * index)),
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+    ui.draw(false);
+    // the second busiest connection, below the busiest one
+    ui.click(20, 5);
+    ui.toggle_pin();
+    // it went quiet, so it is gone from the rates once they no longer recall it
+    for _ in 0..6 {
+        ui.update_state(
+            HashMap::new(),
+            downloads_of_connections((1..=3).map(|index| 100 * index)),
+            HashMap::new(),
+            Duration::from_secs(1),
+        );
+    }
+    ui.draw(false);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+    let rows = terminal_draw_events_mirror[1].lines().collect::<Vec<_>>();
+
+    // the first row, then the blank one that sets it apart from the rest
+    assert!(rows[4].contains("<interface_name>:10018 => 1.1.1.1:https (tcp)"));
+    assert!(rows[4].contains("0Bps / 0Bps"));
+    assert!(!rows[5].contains("1.1.1.1"));
+}
+
+#[test]
+fn clicking_a_row_selects_it_until_clicked_again() {
+    let utilization = downloads_of_connections((1..=20).map(|index| 100 * index));
+    let render_opts = RenderOpts {
+        connections: true,
+        ..Default::default()
+    };
+    let (_, _, backend) = test_backend_factory(190, 20);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    ui.update_state(
+        HashMap::new(),
+        utilization,
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+    ui.draw(false);
+
+    // the busiest connection is on top, below the headers and a gap
+    let busiest_connection = String::from("<interface_name>:10019 => 1.1.1.1:https (tcp)");
+    assert_eq!(
+        ui.click(20, 4),
+        ClickAction::Selected(Some(busiest_connection))
+    );
+    ui.draw(false);
+    assert_eq!(ui.click(20, 4), ClickAction::Selected(None));
+    assert_eq!(ui.click(20, 0), ClickAction::Missed);
+}
+
+#[test]
+fn copying_the_selected_row_puts_its_connection_on_the_clipboard() {
+    fn copy_busiest_connection(text: &str) -> io::Result<()> {
+        assert!(text.starts_with("<interface_name>:10019 => 1.1.1.1:https (tcp)  "));
+        Ok(())
+    }
+    let render_opts = RenderOpts {
+        connections: true,
+        ..Default::default()
+    };
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 20);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    ui.update_state(
+        HashMap::new(),
+        downloads_of_connections((1..=20).map(|index| 100 * index)),
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+    ui.draw(false);
+    ui.click(20, 4);
+    ui.copy_selected_row(copy_busiest_connection);
+    ui.draw(false);
+    // without a clipboard, eg. over ssh, the footer only says so
+    ui.copy_selected_row(|_| Err(io::Error::new(io::ErrorKind::NotFound, "no clipboard")));
+    ui.draw(false);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(!terminal_draw_events_mirror[0].contains("[copied]"));
+    assert!(terminal_draw_events_mirror[1].contains("[copied]"));
+    assert!(terminal_draw_events_mirror[2].contains("[no clipboard to copy to]"));
+}
+
+#[test]
+fn connections_holding_a_round_rate_are_marked_as_capped() {
+    let render_opts = RenderOpts {
+        connections: true,
+        detect_caps: true,
+        cap_ticks: 3,
+        cap_tolerance: 2.0,
+        ..Default::default()
+    };
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 20);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    // the first one holds 128KiBps, the second one jumps around
+    for jumpy_download in [100_000, 300_000, 100_000].iter() {
+        ui.update_state(
+            HashMap::new(),
+            downloads_of_connections([131_072, *jumpy_download].iter().copied()),
+            HashMap::new(),
+            Duration::from_secs(1),
+        );
+    }
+    ui.draw(false);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror[0]
+        .contains("<interface_name>:10000 => 1.1.1.1:https (tcp) [capped?]"));
+    assert!(!terminal_draw_events_mirror[0].contains(":10001 => 1.1.1.1:https (tcp) [capped?]"));
+}
+
+#[test]
+fn smooth_averages_the_rates_of_the_tables_over_the_last_ticks() {
+    let render_opts = RenderOpts {
+        connections: true,
+        smooth: 2,
+        ..Default::default()
+    };
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 20);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    for download in [100, 300].iter() {
+        ui.update_state(
+            HashMap::new(),
+            downloads_of_connections(iter::once(*download)),
+            HashMap::new(),
+            Duration::from_secs(1),
+        );
+    }
+    ui.draw(false);
+    let mut raw_output = String::new();
+    ui.output_text(&mut |line| raw_output.push_str(&line));
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    // the ticks are averaged over 100Bps and then 200Bps (the two windows so far)
+    assert!(terminal_draw_events_mirror[0].contains("0Bps / 150Bps"));
+    assert!(raw_output.contains("up/down Bps: 0/200 connections: 1"));
+}
+
+#[test]
+fn si_units_scale_the_rates_in_powers_of_1000() {
+    let render_opts = RenderOpts {
+        connections: true,
+        si_units: true,
+        ..Default::default()
+    };
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 20);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    for _ in 0..2 {
+        ui.update_state(
+            HashMap::new(),
+            downloads_of_connections(iter::once(1_500)),
+            HashMap::new(),
+            Duration::from_secs(1),
+        );
+    }
+    ui.draw(false);
+    let mut raw_output = String::new();
+    ui.output_text(&mut |line| raw_output.push_str(&line));
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror[0].contains("0Bps / 1.50kBps"));
+    assert!(!terminal_draw_events_mirror[0].contains("KiB"));
+    // the raw output has the exact counts either way
+    assert!(raw_output.contains("up/down Bps: 0/1500 connections: 1"));
+}
+
+#[test]
+fn churn_counts_the_connections_opened_and_closed_since_the_last_tick() {
+    let render_opts = RenderOpts {
+        connections: true,
+        churn: true,
+        ..Default::default()
+    };
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 20);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    ui.update_state(
+        HashMap::new(),
+        downloads_of_connections((1..=3).map(|index| 100 * index)),
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+    // one of the three goes on, the other two stay in the table for the ticks the rates are
+    // averaged over
+    for _ in 0..4 {
+        ui.update_state(
+            HashMap::new(),
+            downloads_of_connections(iter::once(100)),
+            HashMap::new(),
+            Duration::from_secs(1),
+        );
+    }
+    // then they are gone as a new one shows up
+    let mut utilization = downloads_of_connections(iter::once(100));
+    utilization.update(Segment {
+        interface_name: String::from("interface_name"),
+        connection: Connection::new(
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(2, 2, 2, 2)), 443),
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+            20000,
+            Protocol::Tcp,
+        ),
+        direction: Direction::Download,
+        data_length: 100,
+        retransmitted: false,
+    });
+    ui.update_state(
+        HashMap::new(),
+        utilization,
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+    ui.draw(false);
+    let mut raw_output = String::new();
+    ui.output_text(&mut |line| raw_output.push_str(&line));
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror[0]
+        .contains("1 connections opened and 2 closed since the last tick"));
+    assert!(raw_output.contains("opened: 1 closed: 2"));
+}
+
+#[test]
+fn frozen_tables_stay_as_they_were_while_the_state_goes_on() {
+    let render_opts = RenderOpts {
+        connections: true,
+        ..Default::default()
+    };
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 20);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    ui.update_state(
+        HashMap::new(),
+        downloads_of_connections(iter::once(100)),
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+    ui.toggle_frozen();
+    ui.update_state(
+        HashMap::new(),
+        downloads_of_connections(vec![100, 200].into_iter()),
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+    ui.draw(false);
+    let mut raw_output = String::new();
+    ui.output_text(&mut |line| raw_output.push_str(&line));
+    ui.toggle_frozen();
+    ui.draw(false);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror[0].contains("[FROZEN]"));
+    assert!(!terminal_draw_events_mirror[0].contains(":10001"));
+    assert!(raw_output.contains(":10001"));
+    assert!(!terminal_draw_events_mirror[1].contains("[FROZEN]"));
+    assert!(terminal_draw_events_mirror[1].contains(":10001"));
+}
+
+#[test]
+fn highlight_changes_marks_the_rows_new_or_changed_since_the_last_tick() {
+    let path = std::env::temp_dir().join("bandwhich_highlight_changes.txt");
+    let backend = FrameExportBackend::new(path.clone(), 190, 20).unwrap();
+    let render_opts = RenderOpts {
+        connections: true,
+        no_color: true,
+        highlight_changes: true,
+        ..Default::default()
+    };
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    for downloads in [vec![100, 200], vec![100, 0, 300]].iter() {
+        ui.update_state(
+            HashMap::new(),
+            downloads_of_connections(downloads.iter().copied()),
+            HashMap::new(),
+            Duration::from_secs(1),
+        );
+    }
+    ui.draw(false);
+    drop(ui);
+    let exported = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // the rates are averaged over both ticks, so only the second one is down to 100Bps
+    assert!(exported.contains("│<interface_name>:10000 "));
+    assert!(exported.contains("\x1b[1m<interface_name>:10001 "));
+    assert!(exported.contains("\x1b[1;4m<interface_name>:10002 "));
+}
+
+#[test]
+fn columns_are_fitted_to_the_terminal_size_of_every_draw() {
+    let terminal_width = Arc::new(Mutex::new(190));
+    let terminal_draw_events = Arc::new(Mutex::new(Vec::new()));
+    let backend = TestBackend::new(
+        Arc::new(Mutex::new(Vec::new())),
+        terminal_draw_events.clone(),
+        terminal_width.clone(),
+        Arc::new(Mutex::new(20)),
+    );
+    let render_opts = RenderOpts {
+        connections: true,
+        ..Default::default()
+    };
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    ui.update_state(
+        HashMap::new(),
+        downloads_of_connections(iter::once(100)),
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+    ui.draw(false);
+    *terminal_width.lock().unwrap() = 60;
+    ui.draw(false);
+    *terminal_width.lock().unwrap() = 190;
+    ui.draw(false);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    // a resize starts over from a blank screen, so every draw here holds the whole table
+    let header = |draw: &String| draw.lines().nth(2).unwrap().to_string();
+    let wide = header(&terminal_draw_events_mirror[0]);
+    assert_eq!(wide.chars().count(), 190);
+    assert!(wide.contains("Process") && wide.contains("Protocol"));
+    let narrow = header(&terminal_draw_events_mirror[1]);
+    assert_eq!(narrow.chars().count(), 60);
+    assert!(narrow.contains("Connection") && narrow.contains("Up / Down"));
+    assert!(!narrow.contains("Process") && !narrow.contains("Protocol"));
+    assert_eq!(header(&terminal_draw_events_mirror[2]), wide);
+}
+
+#[test]
+fn wide_puts_processes_and_connections_side_by_side_while_the_terminal_is_wide_enough() {
+    let terminal_width = Arc::new(Mutex::new(190));
+    let terminal_draw_events = Arc::new(Mutex::new(Vec::new()));
+    let backend = TestBackend::new(
+        Arc::new(Mutex::new(Vec::new())),
+        terminal_draw_events.clone(),
+        terminal_width.clone(),
+        Arc::new(Mutex::new(50)),
+    );
+    let render_opts = RenderOpts {
+        wide: true,
+        ..Default::default()
+    };
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    ui.update_state(
+        HashMap::new(),
+        downloads_of_connections(iter::once(100)),
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+    ui.draw(false);
+    *terminal_width.lock().unwrap() = 150;
+    ui.draw(false);
+    *terminal_width.lock().unwrap() = 190;
+    ui.draw(false);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    let title_line =
+        |draw: &String, title: &str| draw.lines().position(|line| line.contains(title));
+    let side_by_side = |draw: &String| {
+        let processes = title_line(draw, "Utilization by process name").unwrap();
+        let connections = title_line(draw, "Utilization by connection").unwrap();
+        let addresses = title_line(draw, "Utilization by remote address").unwrap();
+        (processes == connections, processes < addresses)
+    };
+    assert_eq!(side_by_side(&terminal_draw_events_mirror[0]), (true, true));
+    // too narrow for both, so the default layout with the remote addresses next to the processes
+    let narrow = &terminal_draw_events_mirror[1];
+    assert_eq!(
+        title_line(narrow, "Utilization by process name"),
+        title_line(narrow, "Utilization by remote address")
+    );
+    assert!(!side_by_side(narrow).0);
+    assert_eq!(side_by_side(&terminal_draw_events_mirror[2]), (true, true));
+}
+
+#[test]
+fn min_rate_hides_the_slower_rows_and_tells_how_many() {
+    let render_opts = RenderOpts {
+        connections: true,
+        min_rate: "1KBps".parse().unwrap(),
+        ..Default::default()
+    };
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 20);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    ui.update_state(
+        HashMap::new(),
+        downloads_of_connections(vec![100, 2_000].into_iter()),
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+    ui.draw(false);
+    let mut raw_output = String::new();
+    ui.output_text(&mut |line| raw_output.push_str(&line));
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror[0].contains(":10001 => "));
+    assert!(!terminal_draw_events_mirror[0].contains(":10000 => "));
+    // only the slower connection goes, its process and remote address are the faster one's too
+    assert!(terminal_draw_events_mirror[0].contains("[rows hidden below --min-rate: 1]"));
+    // the raw output still has everything
+    assert_eq!(raw_output.matches("connection: ").count(), 2);
+}
+
+#[test]
+fn tab_wraps_around_to_the_first_table() {
+    let utilization = downloads_of_connections((1..=30).map(|index| 100 * index));
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let mut ui = Ui::new(backend, RenderOpts::default(), HashSet::new());
+    ui.update_state(
+        HashMap::new(),
+        utilization,
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+
+    // twice around the three tables, then on to the connections table
+    for _ in 0..8 {
+        ui.focus_next_table();
+    }
+    ui.scroll_down(SCROLL_PAGE_ROWS);
+    ui.draw(false);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror[0].contains("Utilization by connection [11-30 of 30]"));
+}
+
+#[test]
+fn shift_tab_wraps_around_to_the_last_table() {
+    let utilization = downloads_of_connections((1..=30).map(|index| 100 * index));
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let mut ui = Ui::new(backend, RenderOpts::default(), HashSet::new());
+    ui.update_state(
+        HashMap::new(),
+        utilization,
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+
+    // back to the connections table, then once around the three tables
+    for _ in 0..4 {
+        ui.focus_previous_table();
+    }
+    ui.scroll_down(SCROLL_PAGE_ROWS);
+    ui.draw(false);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert!(terminal_draw_events_mirror[0].contains("Utilization by connection [11-30 of 30]"));
+}
+
+#[test]
+fn count_keeps_the_busiest_rows_and_tells_how_many_are_left_out() {
+    let render_opts = RenderOpts {
+        connections: true,
+        count: 3,
+        ..Default::default()
+    };
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    // the first tick only tells that the rates are being collected
+    for _ in 0..2 {
+        ui.update_state(
+            HashMap::new(),
+            downloads_of_connections((1..=30).map(|index| 100 * index)),
+            HashMap::new(),
+            Duration::from_secs(1),
+        );
+    }
+    ui.draw(false);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    assert_snapshot!(&terminal_draw_events_mirror[0]);
+}
+
+#[test]
+fn the_first_tick_shows_the_rows_collecting_their_rates() {
+    let render_opts = RenderOpts {
+        connections: true,
+        count: 3,
+        ..Default::default()
+    };
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    ui.update_state(
+        HashMap::new(),
+        downloads_of_connections((1..=30).map(|index| 100 * index)),
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+    ui.draw(false);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    // the rows are still sorted by what they have sent so far
+    assert_snapshot!(&terminal_draw_events_mirror[0]);
+}
+
+#[test]
+fn tables_are_drawn_with_the_border_asked_for() {
+    let connections_table = |border: BorderStyle| {
+        let render_opts = RenderOpts {
+            connections: true,
+            border,
+            ..Default::default()
+        };
+        let (_, terminal_draw_events, backend) = test_backend_factory(190, 20);
+        let mut ui = Ui::new(backend, render_opts, HashSet::new());
+        ui.update_state(
+            HashMap::new(),
+            downloads_of_connections((1..=30).map(|index| 100 * index)),
+            HashMap::new(),
+            Duration::from_secs(1),
+        );
+        ui.draw(false);
+        let draw = terminal_draw_events.lock().unwrap()[0].clone();
+        draw
+    };
+    let shown_rows = |draw: &str| {
+        let title = Regex::new(r"Utilization by connection \[1-(\d+) of 30\]").unwrap();
+        title.captures(draw).unwrap()[1].parse::<usize>().unwrap()
+    };
+
+    let plain = connections_table(BorderStyle::Plain);
+    let ascii = connections_table(BorderStyle::Ascii);
+    let rounded = connections_table(BorderStyle::Rounded);
+    let none = connections_table(BorderStyle::None);
+    assert!(plain.contains('┌') && plain.contains('│'));
+    assert!(ascii.contains('+') && ascii.contains('|'));
+    assert!(!ascii.contains('┌') && !ascii.contains('│'));
+    assert!(rounded.contains('╭') && rounded.contains('╯') && !rounded.contains('┌'));
+    assert!(!none.contains('┌') && !none.contains('│'));
+    // the bottom border goes, and its row shows one more connection
+    assert_eq!(shown_rows(&none), shown_rows(&plain) + 1);
+}
+
+#[test]
+fn well_known_remote_ports_are_shown_as_services() {
+    let connections_table = |numeric_ports: bool| {
+        let render_opts = RenderOpts {
+            connections: true,
+            numeric_ports,
+            ..Default::default()
+        };
+        let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+        let mut ui = Ui::new(backend, render_opts, HashSet::new());
+        ui.update_state(
+            HashMap::new(),
+            downloads_of_connections(vec![1000].into_iter()),
+            HashMap::new(),
+            Duration::from_secs(1),
+        );
+        ui.draw(false);
+        let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+        terminal_draw_events_mirror[0].clone()
+    };
+
+    assert!(connections_table(false).contains("<interface_name>:10000 => 1.1.1.1:https (tcp)"));
+    assert!(connections_table(true).contains("<interface_name>:10000 => 1.1.1.1:443 (tcp)"));
+    assert_eq!(service_name(53, Protocol::Udp), Some("dns"));
+    assert_eq!(service_name(22, Protocol::Udp), None);
+    assert_eq!(service_name(12345, Protocol::Tcp), None);
+}
+
+#[test]
+fn every_optional_connection_column_is_shown_at_once() {
+    let render_opts = RenderOpts {
+        connections: true,
+        session_bytes: true,
+        up_down_ratio: true,
+        packet_stats: true,
+        tcp_stats: true,
+        security: true,
+        percent: true,
+        ..Default::default()
+    };
+    let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+    let mut ui = Ui::new(backend, render_opts, HashSet::new());
+    ui.update_state(
+        HashMap::new(),
+        downloads_of_connections(vec![1000].into_iter()),
+        HashMap::new(),
+        Duration::from_secs(1),
+    );
+    ui.draw(false);
+    let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+
+    let header = terminal_draw_events_mirror[0]
+        .lines()
+        .find(|line| line.contains("Connection") && line.contains("Retrans"))
+        .unwrap();
+    // the last two of the twelve columns
+    assert!(header.contains(" Enc "));
+    assert!(header.contains(" % "));
+}
+
+#[test]
+fn connections_are_guessed_encrypted_or_plaintext_by_their_ports() {
+    let connections_table = |plaintext_port: Vec<u16>| {
+        let render_opts = RenderOpts {
+            connections: true,
+            security: true,
+            plaintext_port,
+            ..Default::default()
+        };
+        let (_, terminal_draw_events, backend) = test_backend_factory(190, 50);
+        let mut ui = Ui::new(backend, render_opts, HashSet::new());
+        ui.update_state(
+            HashMap::new(),
+            downloads_of_connections(vec![1000].into_iter()),
+            HashMap::new(),
+            Duration::from_secs(1),
+        );
+        ui.draw(false);
+        let terminal_draw_events_mirror = terminal_draw_events.lock().unwrap();
+        terminal_draw_events_mirror[0].clone()
+    };
+
+    assert!(connections_table(vec![]).contains("🔒"));
+    // the ports given win over the usual ones
+    let overridden = connections_table(vec![443]);
+    assert!(overridden.contains("🔓"));
+    assert!(!overridden.contains("🔒"));
+}
+
+#[test]
+fn rows_are_colored_by_their_share_of_the_busiest_one() {
+    let colored_rows = |file_name: &str, no_color: bool| {
+        let path = std::env::temp_dir().join(file_name);
+        let backend = FrameExportBackend::new(path.clone(), 190, 50).unwrap();
+        let render_opts = RenderOpts {
+            connections: true,
+            no_color,
+            ..Default::default()
+        };
+        let mut ui = Ui::new(backend, render_opts, HashSet::new());
+        ui.update_state(
+            HashMap::new(),
+            downloads_of_connections(vec![1000, 500, 100].into_iter()),
+            HashMap::new(),
+            Duration::from_secs(1),
+        );
+        ui.draw(false);
+        let exported = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        exported
+    };
+
+    let colored = colored_rows("bandwhich_colored_rows.txt", false);
+    assert!(colored.contains("\x1b[31m<interface_name>:10000"));
+    assert!(colored.contains("\x1b[33m<interface_name>:10001"));
+    assert!(colored.contains("\x1b[32m<interface_name>:10002"));
+    let plain = colored_rows("bandwhich_plain_rows.txt", true);
+    assert!(plain.contains("\n│<interface_name>:10000"));
+}
+
+#[test]
+fn ipv6_addresses_take_the_spare_width_and_keep_their_port() {
+    let draw_connections = |width: u16| {
+        let mut utilization = Utilization::new();
+        utilization.update(Segment {
+            interface_name: String::from("interface_name"),
+            connection: Connection::new(
+                SocketAddr::new("2001:db8:85a3::8a2e:370:7334".parse().unwrap(), 12345),
+                "2001:db8::2".parse().unwrap(),
+                443,
+                Protocol::Tcp,
+            ),
+            direction: Direction::Download,
+            data_length: 100,
+            retransmitted: false,
+        });
+        let render_opts = RenderOpts {
+            connections: true,
+            ..Default::default()
+        };
+        let (_, terminal_draw_events, backend) = test_backend_factory(width, 20);
+        let mut ui = Ui::new(backend, render_opts, HashSet::new());
+        ui.update_state(
+            HashMap::new(),
+            utilization,
+            HashMap::new(),
+            Duration::from_secs(1),
+        );
+        ui.draw(false);
+        let draw = terminal_draw_events.lock().unwrap()[0].clone();
+        draw
+    };
+
+    let full = "<interface_name>:443 => [2001:db8:85a3::8a2e:370:7334]:12345 (tcp)";
+    assert!(draw_connections(120).contains(full));
+    let narrow = draw_connections(60);
+    assert!(!narrow.contains(full));
+    assert!(narrow.contains("[..]"));
+    assert!(narrow.contains(":12345 (tcp)"));
+}
@@ -3,7 +3,8 @@ use ::ipnetwork::IpNetwork;
 use ::pnet_bandwhich_fork::datalink::DataLinkReceiver;
 use ::pnet_bandwhich_fork::datalink::NetworkInterface;
 use ::std::collections::HashMap;
-use ::std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use ::std::io;
+use ::std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use ::std::{thread, time};
 use ::termion::event::Event;
 use ::tokio::runtime::Runtime;
@@ -11,9 +12,9 @@ use ::tokio::runtime::Runtime;
 use crate::{
     network::{
         dns::{self, Lookup},
-        Connection, Protocol,
+        Connection, LocalSocket, Protocol, UnixSocket,
     },
-    os::OnSigWinch,
+    os::{Ancestor, OnSigTerm, OnSigWinch},
     OpenSockets,
 };
 
@@ -84,7 +85,30 @@ impl DataLinkReceiver for NetworkFrames {
     }
 }
 
-pub fn get_open_sockets() -> OpenSockets {
+/// Frames of an interface that goes down once its packets are read
+pub struct LostInterfaceFrames {
+    pub frames: Box<NetworkFrames>,
+}
+
+impl LostInterfaceFrames {
+    pub fn new(packets: Vec<Option<Vec<u8>>>) -> Box<Self> {
+        Box::new(LostInterfaceFrames {
+            frames: NetworkFrames::new(packets),
+        })
+    }
+}
+
+impl DataLinkReceiver for LostInterfaceFrames {
+    fn next(&mut self) -> Result<&[u8], std::io::Error> {
+        if self.frames.current_index < self.frames.packets.len() {
+            self.frames.next()
+        } else {
+            Err(std::io::Error::other("No such device"))
+        }
+    }
+}
+
+pub fn get_open_sockets(_ancestor: Option<&Ancestor>) -> OpenSockets {
     let mut open_sockets = HashMap::new();
     let local_ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
     open_sockets.insert(
@@ -133,15 +157,87 @@ pub fn get_open_sockets() -> OpenSockets {
         String::from("1"),
     );
     let mut local_socket_to_procs = HashMap::new();
+    let mut local_socket_to_pids = HashMap::new();
+    let mut local_socket_to_users = HashMap::new();
     let mut connections = std::vec::Vec::new();
     for (connection, process_name) in open_sockets {
+        // the pid of "5" cannot be found, like that of a process that is already gone
+        if process_name != "5" {
+            let pid = process_name.parse::<u32>().unwrap() * 1000;
+            local_socket_to_pids.insert(connection.local_socket, pid);
+            // the owner of "4" has no name, so it shows up by uid
+            let user = match process_name.as_str() {
+                "1" => "root",
+                "4" => "1001",
+                _ => "alice",
+            };
+            local_socket_to_users.insert(connection.local_socket, String::from(user));
+        }
         local_socket_to_procs.insert(connection.local_socket, process_name);
         connections.push(connection);
     }
 
+    let mut listening_sockets = HashMap::new();
+    listening_sockets.insert(
+        LocalSocket {
+            ip: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            port: 443,
+            protocol: Protocol::Tcp,
+        },
+        String::from("1"),
+    );
+    listening_sockets.insert(
+        LocalSocket {
+            ip: IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            port: 5353,
+            protocol: Protocol::Udp,
+        },
+        String::from("4"),
+    );
+
+    let unix_sockets = vec![
+        UnixSocket {
+            path: String::from("/run/dbus/system_bus_socket"),
+            process_name: String::from("1"),
+        },
+        UnixSocket {
+            path: String::from("/run/dbus/system_bus_socket"),
+            process_name: String::from("5"),
+        },
+        UnixSocket {
+            path: String::from("@/tmp/.X11-unix/X0"),
+            process_name: String::from("4"),
+        },
+    ];
+
     OpenSockets {
         sockets_to_procs: local_socket_to_procs,
+        sockets_to_pids: local_socket_to_pids,
+        sockets_to_users: local_socket_to_users,
         connections,
+        listening_sockets,
+        unix_sockets,
+    }
+}
+
+pub fn get_process_cmdline(pid: u32) -> Option<String> {
+    match pid {
+        // the command line of "4" cannot be read, like that of a kernel thread
+        4000 => None,
+        _ => Some(format!(
+            "/usr/lib/fake-processes/bin/process-{} --config /etc/fake-processes/{}.conf",
+            pid / 1000,
+            pid / 1000
+        )),
+    }
+}
+
+pub fn get_process_container(pid: u32) -> Option<String> {
+    match pid {
+        // "2" runs in a container docker knows the name of, "4" in one it does not
+        2000 => Some(String::from("web")),
+        4000 => Some(String::from("0123456789ab")),
+        _ => None,
     }
 }
 
@@ -167,6 +263,15 @@ pub fn create_fake_on_winch(should_send_winch_event: bool) -> Box<OnSigWinch> {
     })
 }
 
+pub fn create_fake_on_terminate(should_send_terminate_event: bool) -> Box<OnSigTerm> {
+    Box::new(move |cb| {
+        if should_send_terminate_event {
+            thread::sleep(time::Duration::from_millis(2500));
+            cb()
+        }
+    })
+}
+
 pub fn create_fake_dns_client(ips_to_hosts: HashMap<IpAddr, String>) -> Option<dns::Client> {
     let runtime = Runtime::new().unwrap();
     let dns_client = dns::Client::new(FakeResolver(ips_to_hosts), runtime).unwrap();
@@ -181,3 +286,8 @@ impl Lookup for FakeResolver {
         self.0.get(&ip).cloned()
     }
 }
+
+// as if it went on the clipboard, whatever the machine running the tests has
+pub fn copy_to_clipboard(_text: &str) -> io::Result<()> {
+    Ok(())
+}
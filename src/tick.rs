@@ -0,0 +1,448 @@
+//! What the display thread does once per interval, taken out of `start` so that it can be driven
+//! one tick at a time (see `run_tick`).
+use ::chrono::prelude::*;
+use ::std::collections::HashMap;
+use ::std::io;
+use ::std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use ::std::sync::mpsc::Receiver;
+use ::std::sync::{Arc, Mutex};
+use ::std::thread::{park_timeout, Thread};
+use ::std::time::{Duration, Instant};
+use ::tui::backend::Backend;
+
+use crate::display::{BandwidthLimit, Budget, OutputSink, RateAlert, Ui};
+use crate::network::{
+    dns::{self, IpTable},
+    CaptureEvent, GeoIp, LocalSocket, Utilization,
+};
+use crate::{lock, write_output_sinks, OpenSockets};
+
+// how long nothing at all may be captured while there are connections before a capture without
+// the permissions for it is suspected
+const SILENT_CAPTURE_TIMEOUT: Duration = Duration::from_secs(5);
+// the rate (in bytes per second, both ways) below which a tick counts as idle for --idle-timeout
+const IDLE_RATE: u128 = 100;
+// how often what the --budget used is written down, besides once the run is over
+const BUDGET_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+// how much faster or slower than the --interval the ticks can come with --adaptive
+const ADAPTIVE_FACTOR: u32 = 4;
+// what has to come in for a tick to be drawn early with --adaptive
+const ADAPTIVE_BURST_BYTES: u64 = 1_000_000;
+
+/// What the display thread shares with the other threads of `start`
+pub struct SharedState<B: Backend> {
+    pub ui: Arc<Mutex<Ui<B>>>,
+    /// The latest the sockets thread found
+    pub open_sockets: Arc<Mutex<OpenSockets>>,
+    pub write_to_stdout: Arc<Mutex<Box<dyn FnMut(String) + Send>>>,
+    /// Printed once the terminal is given back, see --summary
+    pub session_report: Arc<Mutex<Option<String>>>,
+    pub paused: Arc<AtomicBool>,
+    pub resolving: Arc<AtomicBool>,
+    /// The `z` key was pressed, the session totals start over
+    pub reset_session: Arc<AtomicBool>,
+    /// The capture file ran out, see --read-pcap
+    pub replay_finished: Arc<AtomicBool>,
+    pub threshold_breached: Arc<AtomicBool>,
+    pub nothing_captured: Arc<AtomicBool>,
+    pub adaptive_refresh: Option<Arc<AdaptiveRefresh>>,
+}
+
+/// How the ticks go, as the command line has it
+#[derive(Clone, Copy)]
+pub struct TickOptions {
+    pub interval: Duration,
+    pub duration: Option<Duration>,
+    /// Stop once the traffic stayed below `IDLE_RATE` for this long
+    pub idle_timeout: Option<Duration>,
+    pub fail_if_above: Option<BandwidthLimit>,
+    pub quiet_until_traffic: bool,
+    pub raw_mode: bool,
+    pub json_mode: bool,
+    /// The raw and JSON output only sum up the whole run once it is over
+    pub summarize_at_end: bool,
+    pub summary: bool,
+    pub group_processes: bool,
+    pub show_user: bool,
+    pub dns_stats: bool,
+}
+
+/// Whether another tick is to come
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tick {
+    Continue,
+    /// The --duration or the --idle-timeout is over or the capture file ran out
+    Last,
+}
+
+/// What the display thread keeps to itself from one tick to the next
+pub struct DisplayState {
+    pub options: TickOptions,
+    pub capture_events: Receiver<CaptureEvent>,
+    pub dns_client: Option<dns::Client>,
+    pub geoip: Option<GeoIp>,
+    pub rate_alert: Option<RateAlert>,
+    pub output_sinks: Vec<OutputSink>,
+    pub budget: Option<Budget>,
+    capture_start_time: Instant,
+    // where the session totals are counted from, launch until the `z` key is pressed
+    session_start_time: Instant,
+    // what the summary at the end is made of, see summarize_at_end and --summary
+    run_utilization: Utilization,
+    run_sockets_to_procs: HashMap<LocalSocket, String>,
+    network_utilization: Utilization,
+    last_reset_time: Option<Instant>,
+    captured_traffic: bool,
+    traffic_start_time: Option<Instant>,
+    // since when every tick was idle, see --idle-timeout
+    idle_start_time: Instant,
+    // the hint that the capture may lack permissions is only given once
+    warned_of_silent_capture: bool,
+    budget_saved_at: Instant,
+    warned_of_budget: bool,
+}
+
+impl DisplayState {
+    /// Without hostnames, locations, alerts, outputs nor budget, which can be set on it
+    pub fn new(
+        options: TickOptions,
+        capture_events: Receiver<CaptureEvent>,
+        capture_start_time: Instant,
+    ) -> Self {
+        DisplayState {
+            options,
+            capture_events,
+            dns_client: None,
+            geoip: None,
+            rate_alert: None,
+            output_sinks: Vec::new(),
+            budget: None,
+            capture_start_time,
+            session_start_time: capture_start_time,
+            run_utilization: Utilization::new(),
+            run_sockets_to_procs: HashMap::new(),
+            network_utilization: Utilization::new(),
+            last_reset_time: None,
+            captured_traffic: false,
+            traffic_start_time: if options.quiet_until_traffic {
+                None
+            } else {
+                Some(capture_start_time)
+            },
+            idle_start_time: capture_start_time,
+            warned_of_silent_capture: false,
+            budget_saved_at: capture_start_time,
+            warned_of_budget: false,
+        }
+    }
+}
+
+// adds what was captured since the last count to the --budget, warns once it runs low and
+// writes it down every `BUDGET_SAVE_INTERVAL`
+fn count_budget<B: Backend>(
+    shared: &SharedState<B>,
+    state: &mut DisplayState,
+    tick_start_time: Instant,
+) {
+    let options = state.options;
+    let utilization = &state.network_utilization;
+    let lifetime_bytes =
+        utilization.lifetime_bytes_uploaded + utilization.lifetime_bytes_downloaded;
+    if let Some(budget) = state.budget.as_mut() {
+        budget.count(lifetime_bytes, Local::now());
+        if !state.warned_of_budget && budget.running_low() {
+            state.warned_of_budget = true;
+            let warning = format!(
+                "{}% of the budget is used, {} bytes are left until {}",
+                budget.used * 100 / budget.limit.max(1),
+                budget.remaining(),
+                budget.period_end().format("%Y-%m-%d")
+            );
+            if options.raw_mode {
+                eprintln!("warning: {}", warning);
+            } else {
+                lock(&shared.ui).log_warning(&warning);
+            }
+        }
+        if tick_start_time.duration_since(state.budget_saved_at) >= BUDGET_SAVE_INTERVAL {
+            state.budget_saved_at = tick_start_time;
+            if let Err(err) = budget.save() {
+                let warning = format!(
+                    "failed to write the budget {}: {}",
+                    budget.path().display(),
+                    err
+                );
+                if options.raw_mode {
+                    eprintln!("warning: {}", warning);
+                } else {
+                    lock(&shared.ui).log_warning(&warning);
+                }
+            }
+        }
+        if !options.raw_mode {
+            lock(&shared.ui).update_budget(budget.clone());
+        }
+    }
+}
+
+/// Counts what the sniffers sent since the last tick, attributes it to the processes, resolves
+/// the remote addresses and updates and renders the tables (or the raw or JSON output).
+///
+/// The display thread calls this once per interval, starting at `tick_start_time`
+pub fn run_tick<B: Backend>(
+    shared: &SharedState<B>,
+    state: &mut DisplayState,
+    tick_start_time: Instant,
+) -> Tick {
+    let options = state.options;
+    // read before what was captured is, so the last packets make it into this tick
+    let last_tick = shared.replay_finished.load(Ordering::SeqCst);
+    // what this tick captured came before the reset, so it only goes into the rates
+    let reset = shared.reset_session.swap(false, Ordering::SeqCst);
+    if reset {
+        state.session_start_time = tick_start_time;
+        state.run_utilization = Utilization::new();
+        lock(&shared.ui).reset_session();
+    }
+    if let Some(adaptive_refresh) = &shared.adaptive_refresh {
+        adaptive_refresh.start_tick();
+    }
+    // this also runs while paused, so what arrived in the meantime is dropped here
+    // instead of showing up as a spike once resumed
+    state.network_utilization.drain(&state.capture_events);
+    if reset {
+        // the budget is counted up to the reset from the counters that start over
+        count_budget(shared, state, tick_start_time);
+        state.network_utilization.reset_lifetime_counters();
+        if let Some(budget) = state.budget.as_mut() {
+            budget.restart_count();
+        }
+    }
+    let utilization = state.network_utilization.clone_and_reset();
+    count_budget(shared, state, tick_start_time);
+    if let (Some(limit), Some(last_reset_time)) = (options.fail_if_above, state.last_reset_time) {
+        // the first window only covers the startup, so it is never held against the limit
+        let window = tick_start_time.duration_since(last_reset_time);
+        if limit.is_exceeded_by(utilization.total_bytes(), window) {
+            shared.threshold_breached.store(true, Ordering::SeqCst);
+        }
+    }
+    let window = measured_window(
+        tick_start_time.duration_since(state.last_reset_time.unwrap_or(state.capture_start_time)),
+        options.interval,
+    );
+    state.last_reset_time = Some(tick_start_time);
+    if utilization.total_bytes() * 1000 / window.as_millis().max(1) >= IDLE_RATE {
+        state.idle_start_time = tick_start_time;
+    }
+    state.captured_traffic |= !utilization.connections.is_empty();
+    if state.traffic_start_time.is_none() && !utilization.connections.is_empty() {
+        state.traffic_start_time = Some(tick_start_time);
+        lock(&shared.ui).set_waiting_for_traffic(false);
+    }
+    let OpenSockets {
+        sockets_to_procs,
+        sockets_to_pids,
+        sockets_to_users,
+        connections,
+        listening_sockets,
+        unix_sockets,
+        ..
+    } = lock(&shared.open_sockets).clone();
+    // some setups open the interfaces fine and then never hand over a single packet
+    if !state.warned_of_silent_capture
+        && state.network_utilization.capture_stats().packets == 0
+        && !connections.is_empty()
+        && tick_start_time.duration_since(state.capture_start_time) >= SILENT_CAPTURE_TIMEOUT
+    {
+        state.warned_of_silent_capture = true;
+        let warning = format!(
+            "nothing was captured in {} seconds while there are open connections, the capture may lack permissions (run with sudo, or grant the binary the cap_net_raw and cap_net_admin capabilities)",
+            SILENT_CAPTURE_TIMEOUT.as_secs()
+        );
+        if options.raw_mode {
+            eprintln!("warning: {}", warning);
+        } else {
+            lock(&shared.ui).log_warning(&warning);
+        }
+    }
+    let mut ip_to_host = IpTable::new();
+    if let Some(dns_client) = state.dns_client.as_mut() {
+        ip_to_host = dns_client.cache();
+        // what was resolved before it was turned off with `r` is still shown
+        if shared.resolving.load(Ordering::SeqCst) {
+            let unresolved_ips = connections
+                .iter()
+                .filter(|conn| !ip_to_host.contains_key(&conn.remote_socket.ip))
+                .map(|conn| conn.remote_socket.ip)
+                .collect::<Vec<_>>();
+
+            dns_client.resolve(unresolved_ips);
+        }
+        if options.dns_stats {
+            lock(&shared.ui).update_dns_stats(dns_client.take_stats());
+        }
+    }
+    if let Some(geoip) = state.geoip.as_mut() {
+        geoip.look_up(connections.iter().map(|conn| conn.remote_socket.ip));
+        lock(&shared.ui).update_ip_to_geo(geoip.cache().clone());
+    }
+    {
+        let mut ui = lock(&shared.ui);
+        let paused = shared.paused.load(Ordering::SeqCst);
+        ui.update_session_totals(
+            &utilization,
+            measured_window(
+                tick_start_time.duration_since(state.session_start_time),
+                options.interval,
+            ),
+        );
+        if !paused && state.traffic_start_time.is_some() {
+            // before the update, --direction tells connections apart by them
+            ui.update_listening_sockets(listening_sockets);
+            if options.group_processes {
+                ui.update_process_pid_counts(&sockets_to_procs, &sockets_to_pids);
+            }
+            if options.show_user {
+                ui.update_process_users(&sockets_to_procs, &sockets_to_users);
+            }
+            if options.summary {
+                state.run_utilization.merge(utilization.clone());
+                state.run_sockets_to_procs.extend(sockets_to_procs.clone());
+            }
+            if options.summarize_at_end {
+                state.run_utilization.merge(utilization);
+                state.run_sockets_to_procs.extend(sockets_to_procs);
+            } else {
+                ui.update_state(sockets_to_procs, utilization, ip_to_host, window);
+                write_output_sinks(&mut ui, &mut state.output_sinks, options.raw_mode);
+                if let Some(rate_alert) = state.rate_alert.as_mut() {
+                    // a line on the terminal the tables are drawn on would garble them
+                    let to_stderr = options.raw_mode || !termion::is_tty(&io::stderr());
+                    for alert in ui.check_alert(rate_alert) {
+                        if to_stderr {
+                            eprintln!("{}", alert);
+                        }
+                    }
+                }
+            }
+            ui.update_unix_sockets(unix_sockets);
+        }
+        if !options.raw_mode {
+            ui.draw(paused);
+        } else if !options.summarize_at_end {
+            if options.json_mode {
+                ui.output_json(&mut **lock(&shared.write_to_stdout));
+            } else {
+                ui.output_text(&mut **lock(&shared.write_to_stdout));
+            }
+        }
+    }
+    let duration_over = match (options.duration, state.traffic_start_time) {
+        (Some(duration), Some(traffic_start_time)) => traffic_start_time.elapsed() >= duration,
+        _ => false,
+    };
+    let idled_out = match options.idle_timeout {
+        Some(idle_timeout) => tick_start_time.duration_since(state.idle_start_time) >= idle_timeout,
+        None => false,
+    };
+    if duration_over || idled_out || last_tick {
+        shared
+            .nothing_captured
+            .store(!state.captured_traffic, Ordering::SeqCst);
+        Tick::Last
+    } else {
+        Tick::Continue
+    }
+}
+
+/// Sums up the whole run once the last tick is over, in the raw or JSON output (see
+/// `TickOptions::summarize_at_end`) or for --summary
+pub fn finish_run<B: Backend>(shared: &SharedState<B>, state: &mut DisplayState) {
+    let run_utilization = std::mem::replace(&mut state.run_utilization, Utilization::new());
+    let run_sockets_to_procs = std::mem::take(&mut state.run_sockets_to_procs);
+    if state.options.summarize_at_end {
+        let ip_to_host = state
+            .dns_client
+            .as_mut()
+            .map(|dns_client| dns_client.cache())
+            .unwrap_or_default();
+        let mut ui = lock(&shared.ui);
+        // a single cumulative update, so every byte of the run is counted exactly once
+        ui.update_state(
+            run_sockets_to_procs,
+            run_utilization,
+            ip_to_host,
+            state.capture_start_time.elapsed(),
+        );
+        write_output_sinks(&mut ui, &mut state.output_sinks, state.options.raw_mode);
+        if state.options.json_mode {
+            ui.output_json(&mut **lock(&shared.write_to_stdout));
+        } else {
+            ui.output_text(&mut **lock(&shared.write_to_stdout));
+        }
+    } else if state.options.summary {
+        let report = lock(&shared.ui).session_report(run_sockets_to_procs, run_utilization);
+        *lock(&shared.session_report) = Some(report);
+    }
+}
+
+/// How long a tick lasts with --adaptive, by what was captured in it so far: a quarter of the
+/// interval once there is a burst of traffic, four times it while there is none
+pub fn adaptive_tick_length(captured_bytes: u64, interval: Duration) -> Duration {
+    if captured_bytes >= ADAPTIVE_BURST_BYTES {
+        interval / ADAPTIVE_FACTOR
+    } else if captured_bytes > 0 {
+        interval
+    } else {
+        interval * ADAPTIVE_FACTOR
+    }
+}
+
+/// The sniffers count what they capture in here, so the display thread can wake up as soon as a
+/// tick has to be cut short (see --adaptive)
+#[derive(Default)]
+pub struct AdaptiveRefresh {
+    captured_bytes: AtomicU64,
+    woken_by_capture: AtomicBool,
+}
+
+impl AdaptiveRefresh {
+    pub fn count(&self, bytes: u128, display_handler: &Thread) {
+        let bytes = bytes as u64;
+        let before = self.captured_bytes.fetch_add(bytes, Ordering::Relaxed);
+        // the tick gets shorter when the first traffic comes in and again when a burst does
+        if before == 0 || (before < ADAPTIVE_BURST_BYTES && before + bytes >= ADAPTIVE_BURST_BYTES)
+        {
+            self.woken_by_capture.store(true, Ordering::SeqCst);
+            display_handler.unpark();
+        }
+    }
+    fn start_tick(&self) {
+        self.captured_bytes.store(0, Ordering::Relaxed);
+    }
+    pub fn park_until_next_tick(&self, tick_start: Instant, interval: Duration) {
+        loop {
+            let tick_length =
+                adaptive_tick_length(self.captured_bytes.load(Ordering::Relaxed), interval);
+            let elapsed = tick_start.elapsed();
+            if elapsed >= tick_length {
+                return;
+            }
+            park_timeout(tick_length - elapsed);
+            // whatever else wakes the display thread (eg. a key press) wants the next tick now
+            if !self.woken_by_capture.swap(false, Ordering::SeqCst) {
+                return;
+            }
+        }
+    }
+}
+
+// windows are only counted in twentieths of the interval, so a wakeup that is a few milliseconds
+// late does not make steady traffic flicker, while the ones that are clearly off (eg. the one
+// before the first tick) still count with about their real length
+pub fn measured_window(elapsed: Duration, interval: Duration) -> Duration {
+    let step = interval / 20;
+    let steps = (elapsed.as_secs_f64() / step.as_secs_f64()).round();
+    step * steps as u32
+}